@@ -1,8 +1,10 @@
 use btmesh_common::{NetworkId, Uuid};
+use btmesh_pdu::provisioned::beacon::SecureNetworkBeacon;
 
 #[derive(Copy, Clone)]
 pub enum Beacon {
     Unprovisioned(Uuid),
     Provisioned(NetworkId),
-    Secure, /* (NetworkId?) */
+    NodeIdentity { hash: [u8; 8], random: [u8; 8] },
+    Secure(SecureNetworkBeacon),
 }
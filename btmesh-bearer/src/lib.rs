@@ -30,6 +30,18 @@ pub trait AdvertisingBearer {
 pub trait GattBearer<const MTU: usize> {
     fn reset(&self);
 
+    /// Identifies the current connection, if any.
+    ///
+    /// Every new connection must be identified differently from the previous one,
+    /// so that state kept for a connection is not carried over to the next.
+    fn connection_id(&self) -> Option<u32>;
+
+    /// Maximum number of bytes transmitted at once on the current connection,
+    /// that is the negotiated ATT MTU less the 3 bytes of ATT header.
+    ///
+    /// Larger proxy PDUs are segmented to fit.
+    fn mtu(&self) -> usize;
+
     type RunFuture<'m>: Future<Output = Result<(), BearerError>> + 'm
     where
         Self: 'm;
@@ -1,4 +1,3 @@
-use crate::crypto::network::PrivacyKey;
 use crate::IvIndex;
 use aes::cipher::Block;
 use aes::{Aes128, BlockEncrypt, NewBlockCipher};
@@ -12,6 +11,8 @@ use cmac::crypto_mac::{InvalidKeyLength, Key, Output};
 use cmac::{Cmac, Mac, NewMac};
 use core::convert::TryInto;
 use heapless::Vec;
+use hmac::Hmac;
+use sha2::Sha256;
 
 pub mod application;
 pub mod device;
@@ -25,6 +26,16 @@ pub fn s1(input: &[u8]) -> Result<Output<Cmac<Aes128>>, InvalidKeyLength> {
     aes_cmac(&ZERO, input)
 }
 
+pub fn s2(input: &[u8]) -> Result<[u8; 32], InvalidKeyLength> {
+    hmac_sha256(&[0; 32], input)
+}
+
+pub fn hmac_sha256(key: &[u8], input: &[u8]) -> Result<[u8; 32], InvalidKeyLength> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key)?;
+    mac.update(input);
+    Ok(mac.finalize().into_bytes().into())
+}
+
 pub fn aes_cmac(key: &[u8], input: &[u8]) -> Result<Output<Cmac<Aes128>>, InvalidKeyLength> {
     let key = Key::<Cmac<Aes128>>::from_slice(key);
     let mut mac = Cmac::<Aes128>::new(key);
@@ -38,6 +49,11 @@ pub fn k1(n: &[u8], salt: &[u8], p: &[u8]) -> Result<Output<Cmac<Aes128>>, Inval
     aes_cmac(&t, p)
 }
 
+pub fn k5(n: &[u8], salt: &[u8], p: &[u8]) -> Result<[u8; 32], InvalidKeyLength> {
+    let t = hmac_sha256(salt, n)?;
+    hmac_sha256(&t, p)
+}
+
 pub fn k2(n: &[u8], p: &[u8]) -> Result<(u8, [u8; 16], [u8; 16]), InvalidKeyLength> {
     let salt = s1(b"smk2")?;
     let t = &aes_cmac(&salt.into_bytes(), n)?.into_bytes();
@@ -73,7 +89,7 @@ pub fn k2(n: &[u8], p: &[u8]) -> Result<(u8, [u8; 16], [u8; 16]), InvalidKeyLeng
     ))
 }
 
-pub fn e(key: &PrivacyKey, mut data: [u8; 16]) -> Result<[u8; 16], InvalidKeyLength> {
+pub fn e(key: &[u8; 16], mut data: [u8; 16]) -> Result<[u8; 16], InvalidKeyLength> {
     let key = GenericArray::<u8, <Aes128 as NewBlockCipher>::KeySize>::from_slice(key.as_ref());
     let cipher = Aes128::new_from_slice(key).map_err(|_| InvalidKeyLength)?;
 
@@ -229,6 +245,76 @@ mod tests {
         );
     }
 
+    #[test]
+    pub fn hmac_sha256() {
+        // RFC 4231 test case 2
+        let result = super::hmac_sha256(b"Jefe", b"what do ya want for nothing?").unwrap();
+
+        assert_eq!(
+            result,
+            [
+                0x5b, 0xdc, 0xc1, 0x46, 0xbf, 0x60, 0x75, 0x4e, 0x6a, 0x04, 0x24, 0x26, 0x08, 0x95,
+                0x75, 0xc7, 0x5a, 0x00, 0x3f, 0x08, 0x9d, 0x27, 0x39, 0x83, 0x9d, 0xec, 0x58, 0xb9,
+                0x64, 0xec, 0x38, 0x43,
+            ]
+        );
+    }
+
+    // Mesh Protocol 1.1 provisioning sample data, HMAC-SHA256 algorithm.
+    const CONFIRMATION_INPUTS: [u8; 145] = [
+        0x00, 0x01, 0x00, 0x03, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01,
+        0x00, 0x00, 0x2c, 0x31, 0xa4, 0x7b, 0x57, 0x79, 0x80, 0x9e, 0xf4, 0x4c, 0xb5, 0xea, 0xaf,
+        0x5c, 0x3e, 0x43, 0xd5, 0xf8, 0xfa, 0xad, 0x4a, 0x87, 0x94, 0xcb, 0x98, 0x7e, 0x9b, 0x03,
+        0x74, 0x5c, 0x78, 0xdd, 0x91, 0x95, 0x12, 0x18, 0x38, 0x98, 0xdf, 0xbe, 0xcd, 0x52, 0xe2,
+        0x40, 0x8e, 0x43, 0x87, 0x1f, 0xd0, 0x21, 0x10, 0x91, 0x17, 0xbd, 0x3e, 0xd4, 0xea, 0xf8,
+        0x43, 0x77, 0x43, 0x71, 0x5d, 0x4f, 0xf4, 0x65, 0xe4, 0x3f, 0xf2, 0x3d, 0x3f, 0x1b, 0x9d,
+        0xc7, 0xdf, 0xc0, 0x4d, 0xa8, 0x75, 0x81, 0x84, 0xdb, 0xc9, 0x66, 0x20, 0x47, 0x96, 0xec,
+        0xcf, 0x0d, 0x6c, 0xf5, 0xe1, 0x65, 0x00, 0xcc, 0x02, 0x01, 0xd0, 0x48, 0xbc, 0xbb, 0xd8,
+        0x99, 0xee, 0xef, 0xc4, 0x24, 0x16, 0x4e, 0x33, 0xc2, 0x01, 0xc2, 0xb0, 0x10, 0xca, 0x6b,
+        0x4d, 0x43, 0xa8, 0xa1, 0x55, 0xca, 0xd8, 0xec, 0xb2, 0x79,
+    ];
+
+    const CONFIRMATION_SALT: [u8; 32] = [
+        0xa7, 0x11, 0x41, 0xba, 0x8c, 0xb6, 0xb4, 0x0f, 0x4f, 0x52, 0xb6, 0x22, 0xe1, 0xc0, 0x91,
+        0x61, 0x4c, 0x73, 0xfc, 0x30, 0x8f, 0x87, 0x1b, 0x78, 0xca, 0x77, 0x5e, 0x76, 0x9b, 0xc3,
+        0xae, 0x69,
+    ];
+
+    #[test]
+    pub fn s2() {
+        // ConfirmationSalt
+        let result = super::s2(&CONFIRMATION_INPUTS).unwrap();
+
+        assert_eq!(result, CONFIRMATION_SALT);
+    }
+
+    #[test]
+    pub fn k5() {
+        // ConfirmationKey, of the ECDHSecret and the static OOB AuthValue
+        let mut n = [0; 64];
+        n[0..32].copy_from_slice(&[
+            0xab, 0x85, 0x84, 0x3a, 0x2f, 0x6d, 0x88, 0x3f, 0x62, 0xe5, 0x68, 0x4b, 0x38, 0xe3,
+            0x07, 0x33, 0x5f, 0xe6, 0xe1, 0x94, 0x5e, 0xcd, 0x19, 0x60, 0x41, 0x05, 0xc6, 0xf2,
+            0x32, 0x21, 0xeb, 0x69,
+        ]);
+        n[32..64].copy_from_slice(&[
+            0x90, 0x6d, 0x73, 0xa3, 0xc7, 0xa7, 0xcb, 0x3f, 0xf7, 0x30, 0xdc, 0xa6, 0x8a, 0x46,
+            0xb9, 0xc1, 0x8d, 0x67, 0x3f, 0x50, 0xe0, 0x78, 0x20, 0x23, 0x11, 0x47, 0x3e, 0xbb,
+            0xe2, 0x53, 0x66, 0x9f,
+        ]);
+
+        let result = super::k5(&n, &CONFIRMATION_SALT, b"prck256").unwrap();
+
+        assert_eq!(
+            result,
+            [
+                0x21, 0x0c, 0x3c, 0x44, 0x81, 0x52, 0xe8, 0xd5, 0x9e, 0xf7, 0x42, 0xaa, 0x7d, 0x22,
+                0xee, 0x5b, 0xa5, 0x9a, 0x38, 0x64, 0x8b, 0xda, 0x6b, 0xf0, 0x5c, 0x74, 0xf3, 0xe4,
+                0x6f, 0xc2, 0xc0, 0xbb,
+            ]
+        );
+    }
+
     #[test]
     pub fn k1() {
         // 8.1.2 k1 function
@@ -1,7 +1,7 @@
-use crate::crypto::nonce::NetworkNonce;
+use crate::address::UnicastAddress;
 use crate::crypto::{aes_ccm_decrypt_detached, aes_ccm_encrypt_detached};
 use crate::mic::InvalidLength;
-use crate::{crypto, NetworkId, ParseError};
+use crate::{crypto, IvIndex, NetworkId, ParseError};
 use ccm::aead::Error;
 use cmac::crypto_mac::InvalidKeyLength;
 use core::ops::Deref;
@@ -148,7 +148,80 @@ impl Deref for PrivacyKey {
     }
 }
 
-#[derive(Copy, Clone, Debug, Hash)]
+/// Key used to compute the Node Identity hash advertised by a proxy server.
+#[derive(Default, Eq, PartialEq, Copy, Clone, Debug, Hash)]
+#[cfg_attr(feature = "defmt", derive(::defmt::Format))]
+pub struct IdentityKey([u8; 16]);
+
+impl IdentityKey {
+    pub fn new(bytes: [u8; 16]) -> Self {
+        Self(bytes)
+    }
+
+    /// Node Identity hash for the given random value and unicast address.
+    pub fn node_identity_hash(
+        &self,
+        random: &[u8; 8],
+        address: UnicastAddress,
+    ) -> Result<[u8; 8], InvalidKeyLength> {
+        // the padding is zeroed, as in the specification's sample data.
+        let mut data = [0; 16];
+        data[6..14].copy_from_slice(random);
+        data[14..16].copy_from_slice(&address.as_bytes());
+
+        let result = crypto::e(&self.0, data)?;
+        let mut hash = [0; 8];
+        hash.copy_from_slice(&result[8..16]);
+        Ok(hash)
+    }
+}
+
+impl Deref for IdentityKey {
+    type Target = [u8; 16];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Key used to authenticate secure network beacons.
+#[derive(Default, Eq, PartialEq, Copy, Clone, Debug, Hash)]
+#[cfg_attr(feature = "defmt", derive(::defmt::Format))]
+pub struct BeaconKey([u8; 16]);
+
+impl BeaconKey {
+    pub fn new(bytes: [u8; 16]) -> Self {
+        Self(bytes)
+    }
+
+    /// Authentication value of a secure network beacon with the given contents.
+    pub fn authentication_value(
+        &self,
+        flags: u8,
+        network_id: &NetworkId,
+        iv_index: IvIndex,
+    ) -> Result<[u8; 8], InvalidKeyLength> {
+        let mut data = [0; 13];
+        data[0] = flags;
+        data[1..9].copy_from_slice(network_id);
+        data[9..13].copy_from_slice(&iv_index.to_be_bytes());
+
+        let result = crypto::aes_cmac(&self.0, &data)?.into_bytes();
+        let mut authentication_value = [0; 8];
+        authentication_value.copy_from_slice(&result[0..8]);
+        Ok(authentication_value)
+    }
+}
+
+impl Deref for BeaconKey {
+    type Target = [u8; 16];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct NetworkKey {
     network_key: [u8; 16],
@@ -198,6 +271,11 @@ impl NetworkKey {
         })
     }
 
+    /// The key itself, as distributed to other nodes.
+    pub fn to_bytes(&self) -> [u8; 16] {
+        self.network_key
+    }
+
     pub fn network_id(&self) -> NetworkId {
         self.network_id
     }
@@ -213,18 +291,67 @@ impl NetworkKey {
     pub fn nid(&self) -> Nid {
         self.nid
     }
+
+    /// Derive the identity key, which is not kept around as it is only
+    /// needed while advertising Node Identity.
+    pub fn identity_key(&self) -> Result<IdentityKey, InvalidKeyLength> {
+        let salt = crypto::s1(b"nkik")?;
+        let key = crypto::k1(&self.network_key, &salt.into_bytes(), b"id128\x01")?;
+        let mut identity_key = [0; 16];
+        identity_key.copy_from_slice(&key.into_bytes());
+        Ok(IdentityKey(identity_key))
+    }
+
+    /// Derive the beacon key, which is not kept around either so that
+    /// stored network keys remain compatible.
+    pub fn beacon_key(&self) -> Result<BeaconKey, InvalidKeyLength> {
+        let salt = crypto::s1(b"nkbk")?;
+        let key = crypto::k1(&self.network_key, &salt.into_bytes(), b"id128\x01")?;
+        let mut beacon_key = [0; 16];
+        beacon_key.copy_from_slice(&key.into_bytes());
+        Ok(BeaconKey(beacon_key))
+    }
+
+    /// Derive the friendship credentials securing the messages exchanged between
+    /// a Low Power node and its friend.
+    ///
+    /// The network key and network ID are retained, so the credentials stand in
+    /// for the master credentials within the friendship.
+    pub fn friendship_credentials(
+        &self,
+        lpn_address: UnicastAddress,
+        friend_address: UnicastAddress,
+        lpn_counter: u16,
+        friend_counter: u16,
+    ) -> Result<Self, InvalidKeyLength> {
+        let mut p = [0; 9];
+        p[0] = 0x01;
+        p[1..3].copy_from_slice(&lpn_address.as_bytes());
+        p[3..5].copy_from_slice(&friend_address.as_bytes());
+        p[5..7].copy_from_slice(&lpn_counter.to_be_bytes());
+        p[7..9].copy_from_slice(&friend_counter.to_be_bytes());
+
+        let (nid, encryption_key, privacy_key) = crypto::k2(&self.network_key, &p)?;
+
+        Ok(Self {
+            privacy_key: PrivacyKey(privacy_key),
+            encryption_key: EncryptionKey(encryption_key),
+            nid: Nid::new(nid),
+            ..*self
+        })
+    }
 }
 
 #[allow(clippy::explicit_auto_deref)]
 pub fn try_decrypt_network(
     network_key: &NetworkKey,
-    nonce: &NetworkNonce,
+    nonce: &[u8; 13],
     payload: &mut [u8],
     mic: &NetMic,
 ) -> Result<(), Error> {
     aes_ccm_decrypt_detached(
         &*network_key.encryption_key,
-        &**nonce,
+        nonce,
         payload,
         mic.as_ref(),
         None,
@@ -234,13 +361,13 @@ pub fn try_decrypt_network(
 #[allow(clippy::explicit_auto_deref)]
 pub fn encrypt_network(
     network_key: &NetworkKey,
-    nonce: &NetworkNonce,
+    nonce: &[u8; 13],
     payload: &mut [u8],
     mic: &mut NetMic,
 ) -> Result<(), Error> {
     aes_ccm_encrypt_detached(
         &*network_key.encryption_key,
-        &**nonce,
+        nonce,
         payload,
         mic.as_mut(),
         None,
@@ -249,7 +376,11 @@ pub fn encrypt_network(
 
 #[cfg(test)]
 mod test {
-    use crate::crypto::network::{EncryptionKey, NetworkKey, Nid, PrivacyKey};
+    use crate::address::UnicastAddress;
+    use crate::crypto::network::{
+        BeaconKey, EncryptionKey, IdentityKey, NetworkKey, Nid, PrivacyKey,
+    };
+    use crate::{IvIndex, NetworkId};
 
     #[test]
     fn network_key_derivation() {
@@ -274,4 +405,91 @@ mod test {
         assert_eq!(privacy_key, network_key.privacy_key());
         assert_eq!(encryption_key, network_key.encryption_key());
     }
+
+    #[test]
+    fn friendship_credentials() {
+        // 8.1.4 k2 function (Friendship)
+        let network_key = NetworkKey::new([
+            0xf7, 0xa2, 0xa4, 0x4f, 0x8e, 0x8a, 0x80, 0x29, 0x06, 0x4f, 0x17, 0x3d, 0xdc, 0x1e,
+            0x2b, 0x00,
+        ])
+        .unwrap();
+
+        let credentials = network_key
+            .friendship_credentials(
+                UnicastAddress::new(0x0203).unwrap(),
+                UnicastAddress::new(0x0405).unwrap(),
+                0x0607,
+                0x0809,
+            )
+            .unwrap();
+
+        assert_eq!(Nid::new(0x73), credentials.nid());
+        assert_eq!(
+            EncryptionKey::new([
+                0x11, 0xef, 0xec, 0x06, 0x42, 0x77, 0x49, 0x92, 0x51, 0x0f, 0xb5, 0x92, 0x96, 0x46,
+                0xdf, 0x49,
+            ]),
+            credentials.encryption_key()
+        );
+        assert_eq!(
+            PrivacyKey::new([
+                0xd4, 0xd7, 0xcc, 0x0d, 0xfa, 0x77, 0x2d, 0x83, 0x6a, 0x8d, 0xf9, 0xdf, 0x55, 0x10,
+                0xd7, 0xa7,
+            ]),
+            credentials.privacy_key()
+        );
+        assert_eq!(network_key.network_id(), credentials.network_id());
+    }
+
+    #[test]
+    fn node_identity_hash() {
+        // 8.9.1 Identity key and 8.10 Node Identity hash
+        let network_key = NetworkKey::new([
+            0x7d, 0xd7, 0x36, 0x4c, 0xd8, 0x42, 0xad, 0x18, 0xc1, 0x7c, 0x2b, 0x82, 0x0c, 0x84,
+            0xc3, 0xd6,
+        ])
+        .unwrap();
+
+        let identity_key = IdentityKey::new([
+            0x84, 0x39, 0x6c, 0x43, 0x5a, 0xc4, 0x85, 0x60, 0xb5, 0x96, 0x53, 0x85, 0x25, 0x3e,
+            0x21, 0x0c,
+        ]);
+        assert_eq!(identity_key, network_key.identity_key().unwrap());
+
+        let hash = identity_key
+            .node_identity_hash(
+                &[0x34, 0xae, 0x60, 0x8f, 0xbb, 0xc1, 0xf2, 0xc6],
+                UnicastAddress::new(0x1201).unwrap(),
+            )
+            .unwrap();
+        assert_eq!([0x00, 0x86, 0x17, 0x65, 0xae, 0xfc, 0xc5, 0x7b], hash);
+    }
+
+    #[test]
+    fn secure_network_beacon_authentication() {
+        // 8.4.1 Secure Network beacon
+        let network_key = NetworkKey::new([
+            0x7d, 0xd7, 0x36, 0x4c, 0xd8, 0x42, 0xad, 0x18, 0xc1, 0x7c, 0x2b, 0x82, 0x0c, 0x84,
+            0xc3, 0xd6,
+        ])
+        .unwrap();
+
+        let beacon_key = BeaconKey::new([
+            0x54, 0x23, 0xd9, 0x67, 0xda, 0x63, 0x9a, 0x99, 0xcb, 0x02, 0x23, 0x1a, 0x83, 0xf7,
+            0xd2, 0x54,
+        ]);
+        assert_eq!(beacon_key, network_key.beacon_key().unwrap());
+
+        let network_id = NetworkId::new([0x3e, 0xca, 0xff, 0x67, 0x2f, 0x67, 0x33, 0x70]);
+        assert_eq!(network_id, network_key.network_id());
+
+        let authentication_value = beacon_key
+            .authentication_value(0x00, &network_id, IvIndex::new(0x12345678))
+            .unwrap();
+        assert_eq!(
+            [0x8e, 0xa2, 0x61, 0x58, 0x2f, 0x36, 0x4f, 0x6f],
+            authentication_value
+        );
+    }
 }
@@ -164,10 +164,52 @@ impl Deref for DeviceNonce {
 
 pub struct ProxyNonce([u8; 13]);
 
+impl Deref for ProxyNonce {
+    type Target = [u8; 13];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl ProxyNonce {
+    const NONCE_TYPE: NonceType = NonceType(0x03);
+
+    pub fn new(seq: Seq, src: UnicastAddress, iv_index: IvIndex) -> Self {
+        let mut nonce = [0; 13];
+        nonce[0] = Self::NONCE_TYPE.0;
+        nonce[1] = 0x00;
+
+        let seq = seq.to_be_bytes();
+        nonce[2] = seq[1];
+        nonce[3] = seq[2];
+        nonce[4] = seq[3];
+
+        let src = src.as_bytes();
+        nonce[5] = src[0];
+        nonce[6] = src[1];
+
+        nonce[7] = 0x00;
+        nonce[8] = 0x00;
+
+        let iv_index = iv_index.to_be_bytes();
+        nonce[9] = iv_index[0];
+        nonce[10] = iv_index[1];
+        nonce[11] = iv_index[2];
+        nonce[12] = iv_index[3];
+
+        Self(nonce)
+    }
+
+    pub fn into_bytes(self) -> [u8; 13] {
+        self.0
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::address::UnicastAddress;
-    use crate::crypto::nonce::NetworkNonce;
+    use crate::crypto::nonce::{NetworkNonce, ProxyNonce};
     use crate::{IvIndex, Seq};
 
     #[test]
@@ -186,4 +228,19 @@ mod test {
 
         assert_eq!(expected, result.into_bytes())
     }
+
+    #[test]
+    fn proxy_nonce() {
+        let expected = [
+            0x03, 0x00, 0x00, 0x00, 0x01, 0x12, 0x01, 0x00, 0x00, 0x12, 0x34, 0x56, 0x78,
+        ];
+
+        let seq = Seq::parse(0x000001).unwrap();
+        let src = UnicastAddress::parse([0x12, 0x01]).unwrap();
+        let iv_index = IvIndex::parse(&[0x12, 0x34, 0x56, 0x78]).unwrap();
+
+        let result = ProxyNonce::new(seq, src, iv_index);
+
+        assert_eq!(expected, result.into_bytes())
+    }
 }
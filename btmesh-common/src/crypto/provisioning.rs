@@ -34,6 +34,12 @@ pub fn prck(secret: &[u8], salt: &[u8]) -> Result<Output<Cmac<Aes128>>, InvalidK
     crypto::k1(secret, salt, b"prck")
 }
 
+/// Confirmation key of BTM_ECDH_P256_HMAC_SHA256_AES_CCM, with `secret` being
+/// the ECDH secret followed by the AuthValue.
+pub fn prck256(secret: &[u8], salt: &[u8]) -> Result<[u8; 32], InvalidKeyLength> {
+    crypto::k5(secret, salt, b"prck256")
+}
+
 pub fn prdk(secret: &[u8], salt: &[u8]) -> Result<Output<Cmac<Aes128>>, InvalidKeyLength> {
     crypto::k1(secret, salt, b"prdk")
 }
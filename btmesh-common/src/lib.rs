@@ -2,6 +2,7 @@
 
 use core::array::TryFromSliceError;
 use core::ops::{Add, BitAnd, Deref, Index, IndexMut, Sub};
+use hash32_derive::Hash32;
 use heapless::Vec;
 use rand_core::RngCore;
 
@@ -232,6 +233,8 @@ impl Ttl {
     }
 }
 
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct SeqRolloverError;
 
 #[derive(Default, Copy, Clone, Eq, PartialEq, PartialOrd)]
@@ -269,11 +272,12 @@ impl Seq {
 
 impl From<Seq> for SeqZero {
     fn from(seq: Seq) -> Self {
-        Self(seq.0 as u16)
+        // the least significant 13 bits.
+        Self((seq.0 & 0x1FFF) as u16)
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, PartialOrd)]
+#[derive(Copy, Clone, Eq, PartialEq, PartialOrd, Hash, Hash32)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct SeqZero(u16);
 
@@ -380,6 +384,7 @@ impl Deref for Uuid {
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct NetworkId([u8; 8]);
 
 impl NetworkId {
@@ -556,6 +561,10 @@ impl<X: Default> Composition<X> {
         self.crpl
     }
 
+    pub fn set_crpl(&mut self, crpl: u16) {
+        self.crpl = crpl;
+    }
+
     pub fn features(&self) -> Features {
         self.features
     }
@@ -641,8 +650,9 @@ pub struct ModelDescriptor<X = ()> {
     pub extra: X,
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Features {
     pub relay: bool,
     pub proxy: bool,
@@ -651,8 +661,23 @@ pub struct Features {
 }
 
 impl Features {
-    pub fn emit<const N: usize>(&self, xmit: &mut Vec<u8, N>) -> Result<(), InsufficientBuffer> {
-        // bits 15-8 RFU
+    /// No features at all, regardless of what the node supports.
+    pub fn none() -> Self {
+        Self::from_bits(0)
+    }
+
+    /// Construct from the 16-bit features bitfield.
+    pub fn from_bits(bits: u16) -> Self {
+        Self {
+            relay: bits & 0b0001 != 0,
+            proxy: bits & 0b0010 != 0,
+            friend: bits & 0b0100 != 0,
+            low_power: bits & 0b1000 != 0,
+        }
+    }
+
+    /// The 16-bit features bitfield, bits 15-4 RFU.
+    pub fn bits(&self) -> u16 {
         let mut val = 0;
         if self.relay {
             val |= 0b0001;
@@ -666,8 +691,12 @@ impl Features {
         if self.low_power {
             val |= 0b1000;
         }
-        xmit.push(val).map_err(|_| InsufficientBuffer)?;
-        xmit.push(0).map_err(|_| InsufficientBuffer)?;
+        val
+    }
+
+    pub fn emit<const N: usize>(&self, xmit: &mut Vec<u8, N>) -> Result<(), InsufficientBuffer> {
+        xmit.extend_from_slice(&self.bits().to_le_bytes())
+            .map_err(|_| InsufficientBuffer)?;
         Ok(())
     }
 }
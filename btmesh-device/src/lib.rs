@@ -11,6 +11,7 @@ use btmesh_common::address::{Address, LabelUuid, UnicastAddress};
 use btmesh_common::crypto::application::Aid;
 use btmesh_common::crypto::network::Nid;
 pub use btmesh_common::location;
+use btmesh_common::mic::SzMic;
 use btmesh_common::opcode::Opcode;
 pub use btmesh_common::ElementDescriptor;
 pub use btmesh_common::{
@@ -304,6 +305,10 @@ impl InboundMetadata {
         self.ttl
     }
 
+    pub fn network_key_handle(&self) -> NetworkKeyHandle {
+        self.network_key_handle
+    }
+
     pub fn reply(&self) -> OutboundMetadata {
         OutboundMetadata {
             dst: self.src.into(),
@@ -312,6 +317,7 @@ impl InboundMetadata {
             key_handle: self.key_handle,
             label_uuid: self.label_uuid,
             ttl: None,
+            szmic: None,
         }
     }
 }
@@ -326,6 +332,7 @@ pub struct OutboundMetadata {
     key_handle: KeyHandle,
     label_uuid: Option<LabelUuid>,
     ttl: Option<Ttl>,
+    szmic: Option<SzMic>,
 }
 
 impl OutboundMetadata {
@@ -334,6 +341,13 @@ impl OutboundMetadata {
         self
     }
 
+    /// Send with a TransMIC of the given size, rather than choosing one automatically.
+    /// A 64-bit TransMIC always results in a segmented message.
+    pub fn with_szmic(mut self, szmic: SzMic) -> Self {
+        self.szmic.replace(szmic);
+        self
+    }
+
     pub fn dst(&self) -> Address {
         self.dst
     }
@@ -357,6 +371,10 @@ impl OutboundMetadata {
     pub fn ttl(&self) -> Option<Ttl> {
         self.ttl
     }
+
+    pub fn szmic(&self) -> Option<SzMic> {
+        self.szmic
+    }
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, PartialOrd)]
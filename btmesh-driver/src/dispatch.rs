@@ -1,3 +1,4 @@
+use crate::stack::provisioned::network::replay_protection::MAX_REPLAY_PROTECTION_LIST_SIZE;
 use crate::storage::provisioned::subscriptions::Subscriptions;
 use crate::{DriverError, ProvisionedStack};
 use btmesh_common::address::UnicastAddress;
@@ -24,18 +25,22 @@ struct CacheEntry {
 pub struct Dispatcher {
     foundation_sender: InboundChannelSender,
     device_sender: InboundChannelSender,
-    lru: LRUCache<CacheEntry, 32>,
+    lru: LRUCache<CacheEntry, MAX_REPLAY_PROTECTION_LIST_SIZE>,
+    /// Number of sources tracked, at most [`MAX_REPLAY_PROTECTION_LIST_SIZE`].
+    capacity: usize,
 }
 
 impl Dispatcher {
     pub fn new(
         foundation_sender: InboundChannelSender,
         device_sender: InboundChannelSender,
+        capacity: usize,
     ) -> Self {
         Self {
             foundation_sender,
             device_sender,
             lru: Default::default(),
+            capacity,
         }
     }
 
@@ -60,6 +65,9 @@ impl Dispatcher {
                         false
                     }
                 }
+            } else if self.lru.len() >= self.capacity {
+                // unknown source, and no room to track it without evicting another.
+                true
             } else {
                 self.lru.insert(CacheEntry {
                     seq: replay_seq,
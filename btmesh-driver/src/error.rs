@@ -24,11 +24,16 @@ pub enum DriverError {
     FeatureNotSupported,
     NetKeyIndexAlreadyStored,
     AppKeyIndexAlreadyStored,
+    CannotRemove,
+    CannotUpdate,
+    InvalidBinding,
     InvalidElementAddress,
     InvalidPDU,
     IncompleteTransaction,
     Parse(ParseError),
     Network(NetworkError),
+    /// Sequence numbers are exhausted for the current IV Index; nothing
+    /// is transmitted until an IV Update completes.
     SeqRollover,
     Storage(StorageError),
 }
@@ -45,6 +50,9 @@ impl From<&DriverError> for (Status, Option<DriverError>) {
             DriverError::FeatureNotSupported => (Status::FeatureNotSupported, None),
             DriverError::AppKeyIndexAlreadyStored => (Status::KeyIndexAlreadyStored, None),
             DriverError::NetKeyIndexAlreadyStored => (Status::KeyIndexAlreadyStored, None),
+            DriverError::CannotRemove => (Status::CannotRemove, None),
+            DriverError::CannotUpdate => (Status::CannotUpdate, None),
+            DriverError::InvalidBinding => (Status::InvalidBinding, None),
             _ => (Status::UnspecifiedError, Some(*err)),
         }
     }
@@ -7,6 +7,9 @@ use btmesh_bearer::beacon::Beacon;
 use btmesh_bearer::PB_ADV_MTU;
 use btmesh_bearer::{AdvertisingBearer, BearerError};
 use btmesh_common::Uuid;
+use btmesh_pdu::provisioned::beacon::{
+    SecureNetworkBeacon, SECURE_NETWORK_BEACON, UNPROVISIONED_DEVICE_BEACON,
+};
 use btmesh_pdu::provisioned::network::NetworkPDU;
 use btmesh_pdu::provisioning::advertising::AdvertisingPDU;
 use btmesh_pdu::provisioning::generic::{
@@ -18,7 +21,7 @@ use core::cell::Cell;
 use core::cell::RefCell;
 use core::iter::Iterator;
 use embassy_time::{Duration, Instant};
-use heapless::Vec;
+use heapless::{Deque, Vec};
 
 mod segmentation;
 
@@ -28,8 +31,12 @@ pub struct AdvertisingBearerNetworkInterface<B: AdvertisingBearer> {
     link_id: Cell<Option<u32>>,
     inbound_transaction_number: Cell<Option<u8>>,
     acked_inbound_transaction_number: Cell<Option<u8>>,
-    outbound_pdu: RefCell<Option<OutboundPDU>>,
+    /// Outbound transactions, only the first of which is in flight.
+    outbound_pdus: RefCell<Deque<OutboundPDU, 3>>,
     outbound_transaction_number: Cell<u8>,
+    /// The device being provisioned, when this node opened the link as provisioner.
+    initiator: Cell<Option<Uuid>>,
+    link_acked: Cell<bool>,
 }
 
 impl<B: AdvertisingBearer> AdvertisingBearerNetworkInterface<B> {
@@ -40,8 +47,10 @@ impl<B: AdvertisingBearer> AdvertisingBearerNetworkInterface<B> {
             link_id: Cell::new(None),
             inbound_transaction_number: Cell::new(None),
             acked_inbound_transaction_number: Cell::new(None),
-            outbound_pdu: RefCell::new(None),
+            outbound_pdus: RefCell::new(Deque::new()),
             outbound_transaction_number: Cell::new(0x80),
+            initiator: Cell::new(None),
+            link_acked: Cell::new(false),
         }
     }
 
@@ -49,15 +58,48 @@ impl<B: AdvertisingBearer> AdvertisingBearerNetworkInterface<B> {
         self.link_id.take();
         self.inbound_transaction_number.take();
         self.acked_inbound_transaction_number.take();
-        self.outbound_pdu.borrow_mut().take();
+        self.outbound_pdus.borrow_mut().clear();
         self.outbound_transaction_number.replace(0x80);
+        self.initiator.take();
+        self.link_acked.replace(false);
+    }
+
+    /// Open a link to the unprovisioned device `uuid` as provisioner.
+    ///
+    /// Provisioning PDUs transmitted meanwhile are held back until the device
+    /// acknowledges the link.
+    pub async fn open_link(&self, uuid: Uuid, link_id: u32) -> Result<(), BearerError> {
+        self.reset();
+        self.link_id.replace(Some(link_id));
+        // transactions of the device start at 0x80, ours at 0x00.
+        self.inbound_transaction_number.replace(Some(0x80));
+        self.outbound_transaction_number.replace(0x00);
+        self.initiator.replace(Some(uuid));
+        self.transmit_link_open().await
+    }
+
+    /// Whether a link is open to a device this node provisions.
+    pub fn is_provisioner(&self) -> bool {
+        self.initiator.get().is_some()
+    }
+
+    async fn transmit_link_open(&self) -> Result<(), BearerError> {
+        if let (Some(link_id), Some(uuid)) = (self.link_id.get(), self.initiator.get()) {
+            self.transmit_advertising_pdu(&AdvertisingPDU {
+                link_id,
+                transaction_number: 0,
+                pdu: ProvisioningBearerControl::LinkOpen(uuid).into(),
+            })
+            .await?;
+        }
+        Ok(())
     }
 
     pub async fn beacon(&self, beacon: Beacon) -> Result<(), BearerError> {
         match beacon {
             Beacon::Unprovisioned(uuid) => {
                 let mut adv_data: Vec<u8, PB_ADV_MTU> = Vec::new();
-                adv_data.extend_from_slice(&[20, MESH_BEACON, 0x00])?;
+                adv_data.extend_from_slice(&[20, MESH_BEACON, UNPROVISIONED_DEVICE_BEACON])?;
                 adv_data.extend_from_slice(&uuid)?;
                 adv_data.extend_from_slice(&[0xa0, 0x40])?;
                 self.bearer.transmit(&adv_data).await?;
@@ -65,17 +107,31 @@ impl<B: AdvertisingBearer> AdvertisingBearerNetworkInterface<B> {
             Beacon::Provisioned(_network_id) => {
                 // not applicable to this role
             }
-            Beacon::Secure => {
-                // nothing yet.
+            Beacon::NodeIdentity { .. } => {
+                // not applicable to this bearer
+            }
+            Beacon::Secure(beacon) => {
+                self.transmit_secure_network_beacon(&beacon).await?;
             }
         }
         Ok(())
     }
 
-    pub async fn transmit(&self, pdu: &PDU) -> Result<(), BearerError> {
+    pub async fn transmit(&self, pdu: &PDU, is_retransmit: bool) -> Result<(), BearerError> {
         match pdu {
+            // retransmissions repeat the transaction in flight, until acknowledged.
+            PDU::Provisioning(_) if is_retransmit => self.retransmit().await,
             PDU::Provisioning(pdu) => self.transmit_provisioning_pdu(pdu).await,
             PDU::Network(pdu) => self.transmit_network_pdu(pdu).await,
+            PDU::ProxyConfiguration(_) => {
+                // not applicable to this bearer
+                Ok(())
+            }
+            PDU::SecureNetworkBeacon(beacon) => self.transmit_secure_network_beacon(beacon).await,
+            PDU::UnprovisionedBeacon(_) => {
+                // sent through beacon()
+                Ok(())
+            }
         }
     }
 
@@ -84,23 +140,33 @@ impl<B: AdvertisingBearer> AdvertisingBearerNetworkInterface<B> {
         let segments = self.segmentation.process_outbound(pdu)?;
 
         let transaction_number = self.outbound_transaction_number.get();
-        self.outbound_transaction_number
-            .replace(transaction_number + 1);
-
-        self.outbound_pdu.replace(Some(OutboundPDU {
+        let outbound = OutboundPDU {
             link_id: self.link_id.get().ok_or(BearerError::InvalidLink)?,
             transaction_number,
             segments,
-        }));
+        };
+        self.outbound_transaction_number
+            .replace(transaction_number + 1);
 
-        if let Some(pdu) = &*self.outbound_pdu.borrow() {
-            for pdu in pdu.iter() {
-                self.transmit_advertising_pdu(&pdu).await?;
+        let in_flight = {
+            let mut outbound_pdus = self.outbound_pdus.borrow_mut();
+            if outbound_pdus.is_full() {
+                outbound_pdus.pop_front();
             }
+            outbound_pdus.push_back(outbound).ok();
+            outbound_pdus.len() == 1
+        };
+
+        if in_flight && !self.awaiting_link_ack() {
+            self.retransmit().await?;
         }
         Ok(())
     }
 
+    fn awaiting_link_ack(&self) -> bool {
+        self.initiator.get().is_some() && !self.link_acked.get()
+    }
+
     async fn transmit_network_pdu(&self, pdu: &NetworkPDU) -> Result<(), BearerError> {
         let mut bytes = Vec::<u8, 64>::new();
         bytes.push(0x00)?;
@@ -111,6 +177,19 @@ impl<B: AdvertisingBearer> AdvertisingBearerNetworkInterface<B> {
         Ok(())
     }
 
+    async fn transmit_secure_network_beacon(
+        &self,
+        beacon: &SecureNetworkBeacon,
+    ) -> Result<(), BearerError> {
+        let mut bytes = Vec::<u8, 64>::new();
+        bytes.push(0x00)?;
+        bytes.push(MESH_BEACON)?;
+        beacon.emit(&mut bytes)?;
+        bytes[0] = bytes.len() as u8 - 1;
+        self.bearer.transmit(&bytes).await?;
+        Ok(())
+    }
+
     pub async fn receive(
         &self,
         state: &DeviceState,
@@ -131,13 +210,37 @@ impl<B: AdvertisingBearer> AdvertisingBearerNetworkInterface<B> {
                             return Ok(PDU::Provisioning(pdu));
                         }
                     }
+                    (DeviceState::Provisioned, PB_ADV) => {
+                        if let Some(uuid) = self.initiator.get() {
+                            if let Some(pdu) = self.receive_pb_adv(&data, &uuid, watchdog).await? {
+                                return Ok(PDU::Provisioning(pdu));
+                            }
+                        }
+                    }
                     (DeviceState::Provisioned, MESH_MESSAGE) => {
-                        self.link_id.take();
-                        self.inbound_transaction_number.take();
+                        if self.initiator.get().is_none() {
+                            self.link_id.take();
+                            self.inbound_transaction_number.take();
+                        }
                         if let Ok(pdu) = NetworkPDU::parse(&data[2..]) {
                             return Ok(PDU::Network(pdu));
                         }
                     }
+                    (DeviceState::Provisioned, MESH_BEACON)
+                        if data.get(2) == Some(&SECURE_NETWORK_BEACON) =>
+                    {
+                        if let Ok(beacon) = SecureNetworkBeacon::parse(&data[2..]) {
+                            return Ok(PDU::SecureNetworkBeacon(beacon));
+                        }
+                    }
+                    (DeviceState::Provisioned, MESH_BEACON)
+                        if data.get(2) == Some(&UNPROVISIONED_DEVICE_BEACON)
+                            && data.len() >= 19 =>
+                    {
+                        if let Ok(uuid) = data[3..19].try_into() {
+                            return Ok(PDU::UnprovisionedBeacon(Uuid::new(uuid)));
+                        }
+                    }
                     _ => {}
                 }
             }
@@ -154,6 +257,10 @@ impl<B: AdvertisingBearer> AdvertisingBearerNetworkInterface<B> {
             };
             self.transmit_advertising_pdu(&pdu).await?;
         }
+        if self.initiator.get().is_some() {
+            // as provisioner, the link is done with.
+            self.reset();
+        }
         Ok(())
     }
 
@@ -164,11 +271,15 @@ impl<B: AdvertisingBearer> AdvertisingBearerNetworkInterface<B> {
         watchdog: &Watchdog,
     ) -> Result<Option<ProvisioningPDU>, BearerError> {
         if let Ok(pdu) = AdvertisingPDU::parse(data) {
+            if self.initiator.get().is_some() && self.link_id.get() != Some(pdu.link_id) {
+                // another provisioner's link.
+                return Ok(None);
+            }
             match &pdu.pdu {
                 GenericProvisioningPDU::ProvisioningBearerControl(pbc) => {
                     match pbc {
                         ProvisioningBearerControl::LinkOpen(uuid) => {
-                            if *uuid == *device_uuid {
+                            if *uuid == *device_uuid && self.initiator.get().is_none() {
                                 if self.link_id.get().is_none() {
                                     watchdog.link_opening_timeout(
                                         Instant::now() + Duration::from_secs(60),
@@ -209,7 +320,10 @@ impl<B: AdvertisingBearer> AdvertisingBearerNetworkInterface<B> {
                             }
                         }
                         ProvisioningBearerControl::LinkAck => {
-                            /* not applicable for this role */
+                            if self.awaiting_link_ack() {
+                                self.link_acked.replace(true);
+                                self.retransmit().await?;
+                            }
                             Ok(None)
                         }
                         ProvisioningBearerControl::LinkClose(_reason) => {
@@ -227,6 +341,8 @@ impl<B: AdvertisingBearer> AdvertisingBearerNetworkInterface<B> {
                     if self.should_process_transaction(pdu.transaction_number) {
                         let result = self.segmentation.process_inbound(&pdu.pdu);
                         if let Ok(Some(result)) = result {
+                            // the peer only responds once it received all we sent.
+                            self.outbound_pdus.borrow_mut().clear();
                             self.ack_transaction().await?;
                             Ok(Some(result))
                         } else {
@@ -241,12 +357,22 @@ impl<B: AdvertisingBearer> AdvertisingBearerNetworkInterface<B> {
                 }
                 GenericProvisioningPDU::TransactionAck => {
                     watchdog.clear_link_open_timeout();
-                    let mut borrowed_pdu = self.outbound_pdu.borrow_mut();
-                    if let Some(outbound) = &*borrowed_pdu {
-                        if outbound.transaction_number == pdu.transaction_number {
-                            // They heard us, we can stop retransmitting.
-                            borrowed_pdu.take();
+                    let acked = {
+                        let mut outbound_pdus = self.outbound_pdus.borrow_mut();
+                        match outbound_pdus.front() {
+                            Some(outbound)
+                                if outbound.transaction_number == pdu.transaction_number =>
+                            {
+                                // They heard us, we can stop retransmitting.
+                                outbound_pdus.pop_front();
+                                true
+                            }
+                            _ => false,
                         }
+                    };
+                    if acked {
+                        // on to the next transaction, if any.
+                        self.retransmit().await?;
                     }
                     Ok(None)
                 }
@@ -327,7 +453,10 @@ impl<B: AdvertisingBearer> AdvertisingBearerNetworkInterface<B> {
 
     #[allow(clippy::await_holding_refcell_ref)]
     pub async fn retransmit(&self) -> Result<(), BearerError> {
-        if let Some(outbound) = &*self.outbound_pdu.borrow() {
+        if self.awaiting_link_ack() {
+            return self.transmit_link_open().await;
+        }
+        if let Some(outbound) = self.outbound_pdus.borrow().front() {
             for pdu in outbound.iter() {
                 self.transmit_advertising_pdu(&pdu).await?
             }
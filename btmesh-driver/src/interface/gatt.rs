@@ -1,19 +1,56 @@
+use crate::interface::proxy_filter::ProxyFilter;
+use crate::interface::proxy_sar::{segment, ProxyReassembly};
 use crate::interface::NetworkError;
 use btmesh_bearer::beacon::Beacon;
 use btmesh_bearer::{BearerError, GattBearer};
+use btmesh_common::address::Address;
+use btmesh_pdu::provisioned::beacon::SecureNetworkBeacon;
 use btmesh_pdu::provisioned::network::NetworkPDU;
-use btmesh_pdu::provisioned::proxy::{MessageType, ProxyPDU, SAR};
+use btmesh_pdu::provisioned::proxy::{MessageType, ProxyPDU};
 use btmesh_pdu::provisioning::ProvisioningPDU;
 use btmesh_pdu::PDU;
+use core::cell::RefCell;
+use embassy_futures::select::{select, Either};
+use embassy_time::Timer;
 use heapless::Vec;
 
 pub struct GattBearerNetworkInterface<B: GattBearer<MTU>, const MTU: usize> {
     bearer: B,
+    connection: RefCell<ConnectionState>,
+}
+
+/// State kept for the duration of a single connection.
+#[derive(Default)]
+struct ConnectionState {
+    id: Option<u32>,
+    filter: ProxyFilter,
+    reassembly: ProxyReassembly,
 }
 
 impl<B: GattBearer<MTU>, const MTU: usize> GattBearerNetworkInterface<B, MTU> {
     pub fn new(bearer: B) -> Self {
-        Self { bearer }
+        Self {
+            bearer,
+            connection: RefCell::new(ConnectionState::default()),
+        }
+    }
+
+    fn connection<R>(&self, f: impl FnOnce(&mut ConnectionState) -> R) -> R {
+        let mut connection = self.connection.borrow_mut();
+        let id = self.bearer.connection_id();
+        if connection.id != id {
+            // a new connection starts afresh.
+            *connection = ConnectionState {
+                id,
+                ..Default::default()
+            };
+        }
+        f(&mut connection)
+    }
+
+    /// Access the proxy filter of the current connection.
+    pub fn proxy_filter<R>(&self, f: impl FnOnce(&mut ProxyFilter) -> R) -> R {
+        self.connection(|connection| f(&mut connection.filter))
     }
 
     pub async fn run(&self) -> Result<(), NetworkError> {
@@ -27,53 +64,90 @@ impl<B: GattBearer<MTU>, const MTU: usize> GattBearerNetworkInterface<B, MTU> {
 
     pub async fn receive(&self) -> Result<PDU, BearerError> {
         loop {
-            let data = self.bearer.receive().await?;
+            let data = match self.connection(|connection| connection.reassembly.deadline()) {
+                Some(deadline) => match select(self.bearer.receive(), Timer::at(deadline)).await {
+                    Either::First(data) => data?,
+                    Either::Second(_) => {
+                        warn!("proxy PDU reassembly timed out, disconnecting");
+                        self.connection(|connection| connection.reassembly.discard());
+                        self.bearer.reset();
+                        continue;
+                    }
+                },
+                None => self.bearer.receive().await?,
+            };
+
             let proxy_pdu = ProxyPDU::parse(&data)?;
-            if let SAR::Complete = proxy_pdu.sar {
-                match proxy_pdu.message_type {
-                    MessageType::NetworkPDU => {
-                        let pdu = NetworkPDU::parse(&proxy_pdu.data)?;
-                        return Ok(PDU::Network(pdu));
+            let proxy_pdu =
+                match self.connection(|connection| connection.reassembly.receive(proxy_pdu)) {
+                    Ok(Some(proxy_pdu)) => proxy_pdu,
+                    Ok(None) => continue,
+                    Err(err) => {
+                        warn!("discarding segmented proxy PDU: {}", err);
+                        continue;
                     }
-                    MessageType::MeshBeacon => {}
-                    MessageType::ProxyConfiguration => {}
-                    MessageType::ProvisioningPDU => {
-                        let pdu = ProvisioningPDU::parse(&proxy_pdu.data)?;
-                        return Ok(PDU::Provisioning(pdu));
+                };
+
+            match proxy_pdu.message_type {
+                MessageType::NetworkPDU => {
+                    let pdu = NetworkPDU::parse(&proxy_pdu.data)?;
+                    return Ok(PDU::Network(pdu));
+                }
+                MessageType::MeshBeacon => {
+                    if let Ok(beacon) = SecureNetworkBeacon::parse(&proxy_pdu.data) {
+                        return Ok(PDU::SecureNetworkBeacon(beacon));
                     }
                 }
+                MessageType::ProxyConfiguration => {
+                    let pdu = NetworkPDU::parse(&proxy_pdu.data)?;
+                    return Ok(PDU::ProxyConfiguration(pdu));
+                }
+                MessageType::ProvisioningPDU => {
+                    let pdu = ProvisioningPDU::parse(&proxy_pdu.data)?;
+                    return Ok(PDU::Provisioning(pdu));
+                }
             }
         }
     }
 
     pub async fn transmit(&self, pdu: &PDU) -> Result<(), BearerError> {
-        match pdu {
+        let mut data = Vec::<u8, 66>::new();
+        let message_type = match pdu {
             PDU::Provisioning(pdu) => {
-                let mut all_proxy_data = Vec::<u8, 384>::new();
-                pdu.emit(&mut all_proxy_data)?;
-                let mut data = Vec::new();
-                data.extend_from_slice(&all_proxy_data)?;
-                let proxy_pdu = ProxyPDU {
-                    sar: SAR::Complete,
-                    message_type: MessageType::ProvisioningPDU,
-                    data,
-                };
-
-                self.transmit_proxy_pdu(&proxy_pdu).await
+                pdu.emit(&mut data)?;
+                MessageType::ProvisioningPDU
             }
             PDU::Network(pdu) => {
-                let mut all_proxy_data = Vec::<u8, 384>::new();
-                pdu.emit(&mut all_proxy_data)?;
-                let mut data = Vec::new();
-                data.extend_from_slice(&all_proxy_data)?;
-                let proxy_pdu = ProxyPDU {
-                    sar: SAR::Complete,
-                    message_type: MessageType::NetworkPDU,
-                    data,
-                };
-
-                self.transmit_proxy_pdu(&proxy_pdu).await
+                pdu.emit(&mut data)?;
+                MessageType::NetworkPDU
             }
+            PDU::ProxyConfiguration(pdu) => {
+                pdu.emit(&mut data)?;
+                MessageType::ProxyConfiguration
+            }
+            PDU::SecureNetworkBeacon(beacon) => {
+                beacon.emit(&mut data)?;
+                MessageType::MeshBeacon
+            }
+            PDU::UnprovisionedBeacon(_) => {
+                // not applicable to this bearer
+                return Ok(());
+            }
+        };
+
+        let mtu = self.bearer.mtu().min(MTU);
+        for proxy_pdu in segment(message_type, &data, mtu) {
+            self.transmit_proxy_pdu(&proxy_pdu).await?;
+        }
+        Ok(())
+    }
+
+    /// Forward a network PDU addressed to `dst`, if the proxy filter accepts it.
+    pub async fn proxy(&self, pdu: &PDU, dst: Address) -> Result<(), BearerError> {
+        if self.proxy_filter(|filter| filter.accepts(dst)) {
+            self.transmit(pdu).await
+        } else {
+            Ok(())
         }
     }
 
@@ -117,8 +191,24 @@ impl<B: GattBearer<MTU>, const MTU: usize> GattBearerNetworkInterface<B, MTU> {
                 adv_data.extend_from_slice(&network_id)?;
                 self.bearer.advertise(&adv_data).await?;
             }
-            Beacon::Secure => {
-                // nothing yet
+            Beacon::NodeIdentity { hash, random } => {
+                let mut adv_data = Vec::new();
+
+                #[rustfmt::skip]
+                adv_data.extend_from_slice(&[
+                    0x02, 0x01, 0x06,
+                    0x03, 0x03, 0x28, 0x18,
+                    0x14, 0x16, 0x28, 0x18
+                ]).unwrap();
+
+                adv_data.push(0x01)?; // node identity
+                adv_data.extend_from_slice(&hash)?;
+                adv_data.extend_from_slice(&random)?;
+                self.bearer.advertise(&adv_data).await?;
+            }
+            Beacon::Secure(beacon) => {
+                // beacons reach the proxy client over its connection.
+                self.transmit(&PDU::SecureNetworkBeacon(beacon)).await?;
             }
         }
 
@@ -1,8 +1,11 @@
 use crate::interface::advertising::AdvertisingBearerNetworkInterface;
 use crate::interface::gatt::GattBearerNetworkInterface;
+use crate::interface::proxy_filter::ProxyFilter;
 use crate::{DeviceState, Watchdog};
 use btmesh_bearer::beacon::Beacon;
 use btmesh_bearer::{AdvertisingBearer, BearerError, GattBearer};
+use btmesh_common::address::Address;
+use btmesh_common::Uuid;
 use btmesh_device::join;
 use btmesh_pdu::provisioning::generic::Reason;
 use btmesh_pdu::PDU;
@@ -13,6 +16,8 @@ use embassy_futures::select::{select, Either};
 
 pub mod advertising;
 pub mod gatt;
+pub mod proxy_filter;
+pub mod proxy_sar;
 
 /// A possibly plurality of network interfaces covering one or more bearers.
 ///
@@ -26,11 +31,11 @@ pub trait NetworkInterfaces {
     /// Run the network interfaces, stopping when the future is dropped.
     fn run(&self) -> Self::RunFuture<'_>;
 
-    type ReceiveFuture<'m>: Future<Output = Result<PDU, NetworkError>> + 'm
+    type ReceiveFuture<'m>: Future<Output = Result<(PDU, Bearer), NetworkError>> + 'm
     where
         Self: 'm;
 
-    /// Receive data from any of the network interfaces.
+    /// Receive data from any of the network interfaces, along with the bearer it arrived on.
     fn receive<'m>(
         &'m self,
         state: &'m DeviceState,
@@ -42,8 +47,22 @@ pub trait NetworkInterfaces {
         Self: 'm;
 
     /// Transmit data on all of the network interfaces.
+    ///
+    /// Network PDUs only reach proxy clients through [`NetworkInterfaces::proxy`],
+    /// which applies their proxy filters.
     fn transmit<'m>(&'m self, pdu: &'m PDU, is_retransmit: bool) -> Self::TransmitFuture<'m>;
 
+    type ProxyFuture<'m>: Future<Output = Result<(), NetworkError>> + 'm
+    where
+        Self: 'm;
+
+    /// Forward a network PDU addressed to `dst` to the proxy clients
+    /// connected over GATT whose proxy filter accepts it, if any.
+    fn proxy<'m>(&'m self, pdu: &'m PDU, dst: Address) -> Self::ProxyFuture<'m>;
+
+    /// Access the proxy filter of the proxy client connected over GATT, if any.
+    fn proxy_filter<R>(&self, f: impl FnOnce(&mut ProxyFilter) -> R) -> Option<R>;
+
     type BeaconFuture<'m>: Future<Output = Result<(), NetworkError>> + 'm
     where
         Self: 'm;
@@ -57,9 +76,24 @@ pub trait NetworkInterfaces {
 
     fn close_link(&self, reason: Reason) -> Self::CloseLinkFuture<'_>;
 
+    type OpenLinkFuture<'m>: Future<Output = Result<(), NetworkError>> + 'm
+    where
+        Self: 'm;
+
+    /// Open a provisioning link to the unprovisioned device `uuid`, as provisioner.
+    fn open_link(&self, uuid: Uuid, link_id: u32) -> Self::OpenLinkFuture<'_>;
+
     fn reset(&self);
 }
 
+/// The bearer a PDU was received on.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Bearer {
+    Advertising,
+    Gatt,
+}
+
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum NetworkError {
@@ -106,7 +140,7 @@ impl<AB: AdvertisingBearer, GB: GattBearer<MTU>, const MTU: usize> NetworkInterf
         self.gatt_interface.run()
     }
 
-    type ReceiveFuture<'m> = impl Future<Output=Result<PDU, NetworkError>> + 'm
+    type ReceiveFuture<'m> = impl Future<Output=Result<(PDU, Bearer), NetworkError>> + 'm
     where
     Self: 'm;
 
@@ -121,7 +155,8 @@ impl<AB: AdvertisingBearer, GB: GattBearer<MTU>, const MTU: usize> NetworkInterf
             let result = select(adv_fut, gatt_fut).await;
 
             match result {
-                Either::First(result) | Either::Second(result) => Ok(result?),
+                Either::First(result) => Ok((result?, Bearer::Advertising)),
+                Either::Second(result) => Ok((result?, Bearer::Gatt)),
             }
         }
     }
@@ -133,19 +168,37 @@ impl<AB: AdvertisingBearer, GB: GattBearer<MTU>, const MTU: usize> NetworkInterf
     fn transmit<'m>(&'m self, pdu: &'m PDU, is_retransmit: bool) -> Self::TransmitFuture<'m> {
         async move {
             let gatt_fut = async {
-                if !is_retransmit {
+                // provisioning PDUs of a device this node provisions only go over PB-ADV.
+                let is_provisioner = matches!(pdu, PDU::Provisioning(_))
+                    && self.advertising_interface.is_provisioner();
+                if !is_retransmit && !is_provisioner && !matches!(pdu, PDU::Network(_)) {
                     self.gatt_interface.transmit(pdu).await?;
                 }
 
                 Result::<(), NetworkError>::Ok(())
             };
-            let adv_fut = self.advertising_interface.transmit(pdu);
+            let adv_fut = self.advertising_interface.transmit(pdu, is_retransmit);
 
             let _result = join(gatt_fut, adv_fut).await;
             Ok(())
         }
     }
 
+    type ProxyFuture<'m> = impl Future<Output=Result<(), NetworkError>> + 'm
+    where
+    Self: 'm;
+
+    fn proxy<'m>(&'m self, pdu: &'m PDU, dst: Address) -> Self::ProxyFuture<'m> {
+        async move {
+            self.gatt_interface.proxy(pdu, dst).await?;
+            Ok(())
+        }
+    }
+
+    fn proxy_filter<R>(&self, f: impl FnOnce(&mut ProxyFilter) -> R) -> Option<R> {
+        Some(self.gatt_interface.proxy_filter(f))
+    }
+
     type BeaconFuture<'m> = impl Future<Output=Result<(), NetworkError>> + 'm
     where
     Self: 'm;
@@ -169,6 +222,17 @@ impl<AB: AdvertisingBearer, GB: GattBearer<MTU>, const MTU: usize> NetworkInterf
         }
     }
 
+    type OpenLinkFuture<'m> = impl Future<Output=Result<(), NetworkError>> + 'm
+    where
+    Self: 'm;
+
+    fn open_link(&self, uuid: Uuid, link_id: u32) -> Self::OpenLinkFuture<'_> {
+        async move {
+            self.advertising_interface.open_link(uuid, link_id).await?;
+            Ok(())
+        }
+    }
+
     fn reset(&self) {
         self.advertising_interface.reset();
         self.gatt_interface.reset();
@@ -204,7 +268,7 @@ impl<B: AdvertisingBearer> NetworkInterfaces for AdvertisingOnlyNetworkInterface
         NeverEnding
     }
 
-    type ReceiveFuture<'m> = impl Future<Output=Result<PDU, NetworkError>> + 'm
+    type ReceiveFuture<'m> = impl Future<Output=Result<(PDU, Bearer), NetworkError>> + 'm
     where
     Self: 'm;
 
@@ -213,15 +277,32 @@ impl<B: AdvertisingBearer> NetworkInterfaces for AdvertisingOnlyNetworkInterface
         state: &'m DeviceState,
         watchdog: &'m Watchdog,
     ) -> Self::ReceiveFuture<'m> {
-        async move { Ok(self.interface.receive(state, watchdog).await?) }
+        async move {
+            Ok((
+                self.interface.receive(state, watchdog).await?,
+                Bearer::Advertising,
+            ))
+        }
     }
 
     type TransmitFuture<'m> = impl Future<Output=Result<(), NetworkError>> + 'm
     where
     Self: 'm;
 
-    fn transmit<'m>(&'m self, pdu: &'m PDU, _is_retransmit: bool) -> Self::TransmitFuture<'m> {
-        async move { Ok(self.interface.transmit(pdu).await?) }
+    fn transmit<'m>(&'m self, pdu: &'m PDU, is_retransmit: bool) -> Self::TransmitFuture<'m> {
+        async move { Ok(self.interface.transmit(pdu, is_retransmit).await?) }
+    }
+
+    type ProxyFuture<'m> = impl Future<Output=Result<(), NetworkError>> + 'm
+    where
+    Self: 'm;
+
+    fn proxy<'m>(&'m self, _pdu: &'m PDU, _dst: Address) -> Self::ProxyFuture<'m> {
+        async move { Ok(()) }
+    }
+
+    fn proxy_filter<R>(&self, _f: impl FnOnce(&mut ProxyFilter) -> R) -> Option<R> {
+        None
     }
 
     type BeaconFuture<'m> = impl Future<Output=Result<(), NetworkError>> + 'm
@@ -243,6 +324,17 @@ impl<B: AdvertisingBearer> NetworkInterfaces for AdvertisingOnlyNetworkInterface
         }
     }
 
+    type OpenLinkFuture<'m> = impl Future<Output=Result<(), NetworkError>> + 'm
+    where
+    Self: 'm;
+
+    fn open_link(&self, uuid: Uuid, link_id: u32) -> Self::OpenLinkFuture<'_> {
+        async move {
+            self.interface.open_link(uuid, link_id).await?;
+            Ok(())
+        }
+    }
+
     fn reset(&self) {
         self.interface.reset();
     }
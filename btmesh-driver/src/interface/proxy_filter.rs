@@ -0,0 +1,121 @@
+use btmesh_common::address::{Address, UnicastAddress};
+use btmesh_pdu::provisioned::proxy::{FilterType, ProxyConfigurationMessage};
+use heapless::Vec;
+
+/// The proxy filter of a single proxy client connection, deciding which
+/// network PDUs are forwarded to the client.
+///
+/// A new connection starts with an empty accept list, so nothing is
+/// forwarded until the client asks for it.
+pub struct ProxyFilter<const N: usize = 16> {
+    filter_type: FilterType,
+    addresses: Vec<Address, N>,
+}
+
+impl<const N: usize> Default for ProxyFilter<N> {
+    fn default() -> Self {
+        Self {
+            filter_type: FilterType::AcceptList,
+            addresses: Vec::new(),
+        }
+    }
+}
+
+impl<const N: usize> ProxyFilter<N> {
+    /// Whether a network PDU addressed to `dst` is to be forwarded to the client.
+    pub fn accepts(&self, dst: Address) -> bool {
+        let listed = self.addresses.contains(&dst);
+        match self.filter_type {
+            FilterType::AcceptList => listed,
+            FilterType::RejectList => !listed,
+        }
+    }
+
+    /// Apply a proxy configuration message from the client, returning the status to reply with.
+    pub fn configure(
+        &mut self,
+        message: &ProxyConfigurationMessage,
+    ) -> Option<ProxyConfigurationMessage> {
+        match message {
+            ProxyConfigurationMessage::SetFilterType(filter_type) => {
+                self.filter_type = *filter_type;
+                self.addresses.clear();
+            }
+            ProxyConfigurationMessage::AddAddresses(addresses) => {
+                for address in addresses {
+                    self.add(*address);
+                }
+            }
+            ProxyConfigurationMessage::RemoveAddresses(addresses) => {
+                self.addresses
+                    .retain(|address| !addresses.contains(address));
+            }
+            ProxyConfigurationMessage::FilterStatus { .. } => {
+                // not applicable to a server
+                return None;
+            }
+        }
+
+        Some(ProxyConfigurationMessage::FilterStatus {
+            filter_type: self.filter_type,
+            list_size: self.addresses.len() as u16,
+        })
+    }
+
+    /// Account for a network PDU sent by the client, which implicitly
+    /// asks for the replies to its own address.
+    pub fn receive_from_client(&mut self, src: UnicastAddress) {
+        let src = Address::Unicast(src);
+        match self.filter_type {
+            FilterType::AcceptList => self.add(src),
+            FilterType::RejectList => self.addresses.retain(|address| *address != src),
+        }
+    }
+
+    fn add(&mut self, address: Address) {
+        if address != Address::Unassigned
+            && !self.addresses.contains(&address)
+            && self.addresses.push(address).is_err()
+        {
+            warn!("no space in proxy filter");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use btmesh_common::address::GroupAddress;
+
+    #[test]
+    fn accept_and_reject_lists() {
+        let client = UnicastAddress::new(0x0042).unwrap();
+        let all_nodes = Address::Group(GroupAddress::AllNodes);
+        let mut filter = ProxyFilter::<4>::default();
+        assert!(!filter.accepts(all_nodes));
+
+        filter.receive_from_client(client);
+        assert!(filter.accepts(Address::Unicast(client)));
+
+        let mut addresses = Vec::new();
+        addresses.push(all_nodes).unwrap();
+        addresses.push(all_nodes).unwrap();
+        assert!(matches!(
+            filter.configure(&ProxyConfigurationMessage::AddAddresses(addresses.clone())),
+            Some(ProxyConfigurationMessage::FilterStatus {
+                filter_type: FilterType::AcceptList,
+                list_size: 2,
+            })
+        ));
+        assert!(filter.accepts(all_nodes));
+
+        filter.configure(&ProxyConfigurationMessage::SetFilterType(
+            FilterType::RejectList,
+        ));
+        assert!(filter.accepts(all_nodes));
+        filter.configure(&ProxyConfigurationMessage::AddAddresses(addresses.clone()));
+        assert!(!filter.accepts(all_nodes));
+        filter.configure(&ProxyConfigurationMessage::RemoveAddresses(addresses));
+        assert!(filter.accepts(all_nodes));
+    }
+}
@@ -0,0 +1,154 @@
+use btmesh_common::ParseError;
+use btmesh_pdu::provisioned::proxy::{MessageType, ProxyPDU, SAR};
+use embassy_time::{Duration, Instant};
+use heapless::Vec;
+
+/// Time allowed between two segments of a proxy PDU.
+const SAR_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Reassembles proxy PDUs segmented by the proxy client.
+#[derive(Default)]
+pub struct ProxyReassembly {
+    in_flight: Option<InFlight>,
+}
+
+struct InFlight {
+    message_type: MessageType,
+    data: Vec<u8, 66>,
+    deadline: Instant,
+}
+
+impl ProxyReassembly {
+    /// Accept a proxy PDU, returning the complete message once all of its
+    /// segments have been received.
+    ///
+    /// Segments out of order discard the message being reassembled.
+    pub fn receive(&mut self, pdu: ProxyPDU) -> Result<Option<ProxyPDU>, ParseError> {
+        match pdu.sar {
+            SAR::Complete => {
+                self.discard();
+                Ok(Some(pdu))
+            }
+            SAR::First => {
+                self.discard();
+                self.in_flight.replace(InFlight {
+                    message_type: pdu.message_type,
+                    data: pdu.data,
+                    deadline: Instant::now() + SAR_TIMEOUT,
+                });
+                Ok(None)
+            }
+            SAR::Continuation | SAR::Last => {
+                let in_flight = match &mut self.in_flight {
+                    Some(in_flight) if in_flight.message_type == pdu.message_type => in_flight,
+                    _ => {
+                        self.discard();
+                        return Err(ParseError::InvalidPDUFormat);
+                    }
+                };
+
+                if in_flight.data.extend_from_slice(&pdu.data).is_err() {
+                    self.discard();
+                    return Err(ParseError::InsufficientBuffer);
+                }
+                in_flight.deadline = Instant::now() + SAR_TIMEOUT;
+
+                if let SAR::Last = pdu.sar {
+                    Ok(self.in_flight.take().map(|in_flight| ProxyPDU {
+                        sar: SAR::Complete,
+                        message_type: in_flight.message_type,
+                        data: in_flight.data,
+                    }))
+                } else {
+                    Ok(None)
+                }
+            }
+        }
+    }
+
+    /// The point in time by which the next segment must have been received, if any.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.in_flight.as_ref().map(|in_flight| in_flight.deadline)
+    }
+
+    pub fn discard(&mut self) {
+        self.in_flight.take();
+    }
+}
+
+/// Segment a message into proxy PDUs of at most `mtu` bytes each.
+pub fn segment(
+    message_type: MessageType,
+    data: &[u8],
+    mtu: usize,
+) -> impl Iterator<Item = ProxyPDU> + '_ {
+    // one byte goes to the SAR and message type header.
+    let chunk_size = mtu.saturating_sub(1).max(1);
+    let last = (data.len().max(1) - 1) / chunk_size;
+
+    (0..=last).map(move |index| {
+        let sar = match index {
+            _ if last == 0 => SAR::Complete,
+            0 => SAR::First,
+            _ if index == last => SAR::Last,
+            _ => SAR::Continuation,
+        };
+        let chunk = &data[index * chunk_size..data.len().min((index + 1) * chunk_size)];
+        ProxyPDU {
+            sar,
+            message_type,
+            data: Vec::from_slice(chunk).unwrap_or_default(),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn segment_and_reassemble() {
+        let data: Vec<u8, 66> = (0..65).collect();
+        let mut reassembly = ProxyReassembly::default();
+        let mut pdus = segment(MessageType::ProvisioningPDU, &data, 20);
+
+        for sar in [SAR::First, SAR::Continuation, SAR::Continuation] {
+            let pdu = pdus.next().unwrap();
+            assert_eq!(u8::from(sar), u8::from(pdu.sar));
+            assert_eq!(19, pdu.data.len());
+            assert!(reassembly.receive(pdu).unwrap().is_none());
+            assert!(reassembly.deadline().is_some());
+        }
+
+        let pdu = pdus.next().unwrap();
+        assert!(matches!(pdu.sar, SAR::Last));
+        assert!(pdus.next().is_none());
+
+        let message = reassembly.receive(pdu).unwrap().unwrap();
+        assert!(matches!(message.sar, SAR::Complete));
+        assert!(message.message_type == MessageType::ProvisioningPDU);
+        assert_eq!(data, message.data);
+        assert!(reassembly.deadline().is_none());
+
+        let mut pdus = segment(MessageType::NetworkPDU, &data[..19], 20);
+        assert!(matches!(pdus.next().unwrap().sar, SAR::Complete));
+        assert!(pdus.next().is_none());
+    }
+
+    #[test]
+    fn reject_segments_out_of_order() {
+        let data: Vec<u8, 66> = (0..40).collect();
+        let mut reassembly = ProxyReassembly::default();
+        let mut pdus = segment(MessageType::NetworkPDU, &data, 20);
+        let first = pdus.next().unwrap();
+        let continuation = pdus.next().unwrap();
+
+        assert!(reassembly.receive(continuation).is_err());
+
+        reassembly.receive(first).unwrap();
+        let mut other = pdus.next().unwrap();
+        other.message_type = MessageType::ProvisioningPDU;
+        assert!(reassembly.receive(other).is_err());
+        assert!(reassembly.deadline().is_none());
+    }
+}
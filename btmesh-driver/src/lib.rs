@@ -12,21 +12,30 @@ use btmesh_bearer::beacon::Beacon;
 use btmesh_common::address::{Address, UnicastAddress};
 use btmesh_common::{Composition, Seq, Ttl, Uuid};
 use btmesh_device::{
-    BluetoothMeshDevice, CompletionToken, CompositionExtra, InboundChannel, InboundChannelReceiver,
-    KeyHandle, OutboundChannel, OutboundExtra, OutboundPayload, PublicationCadence,
-    PublicationRetransmission, SendExtra,
+    BluetoothMeshDevice, Channel, CompletionToken, CompositionExtra, InboundChannel,
+    InboundChannelReceiver, KeyHandle, OutboundChannel, OutboundExtra, OutboundPayload,
+    PublicationCadence, PublicationRetransmission, Retransmission, SendExtra, Signal,
 };
+use btmesh_models::foundation::configuration::gatt_proxy::GattProxy;
 use btmesh_models::foundation::configuration::model_publication::PublishAddress;
+use btmesh_models::foundation::configuration::network_transmit::NetworkTransmitConfig;
+#[cfg(any(feature = "relay", feature = "proxy"))]
+use btmesh_models::foundation::configuration::relay::Relay;
 use btmesh_models::foundation::configuration::CONFIGURATION_SERVER;
 use btmesh_pdu::provisioned::access::AccessMessage;
 use btmesh_pdu::provisioned::network::NetworkPDU;
+use btmesh_pdu::provisioned::upper::control::ControlOpcode;
 use btmesh_pdu::provisioned::Message;
 use btmesh_pdu::provisioning::generic::Reason;
-use btmesh_pdu::provisioning::{Capabilities, ProvisioningPDU};
+use btmesh_pdu::provisioning::{
+    Algorithm, Algorithms, Capabilities, ErrorCode, ProvisioningPDU, PublicKey, PublicKeyType,
+    StaticOOBType,
+};
 use btmesh_pdu::PDU;
 use core::cell::RefCell;
 use core::future::{pending, Future};
-use embassy_futures::select::{select, select3, select4, Either, Either3, Either4};
+use embassy_futures::select::{select, select3, select4, Either3, Either4};
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
 use embassy_time::{Duration, Instant, Timer};
 use heapless::Vec;
 use rand_core::{CryptoRng, RngCore};
@@ -45,9 +54,14 @@ mod watchdog;
 
 use crate::device::DeviceContext;
 use crate::dispatch::Dispatcher;
-use crate::interface::{NetworkError, NetworkInterfaces};
+use crate::interface::{Bearer, NetworkError, NetworkInterfaces};
+use crate::models::configuration::low_power_node_poll_timeout;
 use crate::models::FoundationDevice;
+use crate::stack::provisioned::network::replay_protection::ReplayProtection;
 use crate::stack::provisioned::network::DeviceInfo;
+use crate::stack::provisioned::provisioning::{ProvisioningResult, ProvisioningSession};
+#[cfg(any(feature = "relay", feature = "proxy"))]
+use crate::stack::provisioned::relay_queue::random_relay_delay;
 use crate::stack::provisioned::secrets::Secrets;
 use crate::stack::provisioned::sequence::Sequence;
 use crate::stack::provisioned::system::{AccessMetadata, UpperMetadata};
@@ -60,11 +74,37 @@ use crate::storage::{BackingStore, Configuration, Storage};
 use crate::util::hash::hash_of;
 use crate::watchdog::{Watchdog, WatchdogEvent};
 pub use error::DriverError;
+#[cfg(feature = "low_power")]
+pub use stack::provisioned::low_power::LowPowerConfig;
+pub use stack::provisioned::network::replay_protection::{
+    MAX_REPLAY_PROTECTION_LIST_SIZE, REPLAY_PROTECTION_LIST_SIZE,
+};
+pub use stack::provisioned::provisioning::ProvisioningError;
+pub use stack::unprovisioned::oob::{OobAuthentication, OobConfig, OobValue, StaticOob};
+pub use stack::unprovisioned::provisioner::{ProvisionedNode, UnicastAddressAllocator};
+
+/// Transport retransmissions of unsegmented messages sent, rather than published, by models.
+const SEND_RETRANSMISSION: PublicationRetransmission =
+    PublicationRetransmission::RetransmitCountInterval(Retransmission {
+        count: 3,
+        interval: Duration::from_millis(200),
+    });
 
 #[derive(Default)]
 pub struct BluetoothMeshDriverConfig {
+    /// Interval at which the sequence number and the replay protection list
+    /// are written back to storage, never when `None`.
     pub persist_interval: Option<Duration>,
     pub uuid: Option<Uuid>,
+    /// Out-of-band authentication offered while being provisioned,
+    /// carried out through [`oob_authentication`] and [`input_oob`].
+    pub oob: OobConfig,
+    /// Number of sources tracked for replay protection, reported as the CRPL of
+    /// the node. Defaults to [`REPLAY_PROTECTION_LIST_SIZE`], and is capped at
+    /// [`MAX_REPLAY_PROTECTION_LIST_SIZE`].
+    pub replay_protection_list_size: Option<usize>,
+    #[cfg(feature = "low_power")]
+    pub low_power: LowPowerConfig,
 }
 
 pub trait BluetoothMeshDriver {
@@ -81,6 +121,10 @@ pub struct Driver<N: NetworkInterfaces, R: RngCore + CryptoRng, B: BackingStore>
     rng: Option<R>,
     storage: Storage<B>,
     persist_interval: Option<Duration>,
+    oob: OobConfig,
+    replay_protection_list_size: usize,
+    #[cfg(feature = "low_power")]
+    low_power: LowPowerConfig,
 }
 
 impl<N: NetworkInterfaces, R: RngCore + CryptoRng, B: BackingStore> Driver<N, R, B> {
@@ -98,6 +142,12 @@ impl<N: NetworkInterfaces, R: RngCore + CryptoRng, B: BackingStore> Driver<N, R,
             rng: Some(rng),
             storage: Storage::new(backing_store, upc),
             persist_interval: config.persist_interval,
+            oob: config.oob,
+            replay_protection_list_size: config
+                .replay_protection_list_size
+                .unwrap_or(REPLAY_PROTECTION_LIST_SIZE),
+            #[cfg(feature = "low_power")]
+            low_power: config.low_power,
         }
     }
 }
@@ -108,8 +158,13 @@ pub struct InnerDriver<'s, N: NetworkInterfaces, R: RngCore + CryptoRng, B: Back
     rng: RefCell<R>,
     storage: &'s Storage<B>,
     dispatcher: RefCell<Dispatcher>,
+    replay_protection: RefCell<ReplayProtection>,
     watchdog: Watchdog,
+    provisioning: RefCell<Option<ProvisioningSession>>,
     persist_interval: Option<Duration>,
+    oob: OobConfig,
+    #[cfg(feature = "low_power")]
+    low_power: LowPowerConfig,
 }
 
 impl<'s, N: NetworkInterfaces, R: RngCore + CryptoRng, B: BackingStore> InnerDriver<'s, N, R, B> {
@@ -118,7 +173,11 @@ impl<'s, N: NetworkInterfaces, R: RngCore + CryptoRng, B: BackingStore> InnerDri
         rng: R,
         storage: &'s Storage<B>,
         persist_interval: Option<Duration>,
+        oob: OobConfig,
+        replay_protection_list_size: usize,
+        #[cfg(feature = "low_power")] low_power: LowPowerConfig,
     ) -> Self {
+        let replay_protection = ReplayProtection::new(replay_protection_list_size);
         Self {
             stack: RefCell::new(Stack::None),
             network,
@@ -127,9 +186,15 @@ impl<'s, N: NetworkInterfaces, R: RngCore + CryptoRng, B: BackingStore> InnerDri
             dispatcher: RefCell::new(Dispatcher::new(
                 FOUNDATION_INBOUND.sender(),
                 DEVICE_INBOUND.sender(),
+                replay_protection.capacity(),
             )),
+            replay_protection: RefCell::new(replay_protection),
             watchdog: Default::default(),
+            provisioning: RefCell::new(None),
             persist_interval,
+            oob,
+            #[cfg(feature = "low_power")]
+            low_power,
         }
     }
 
@@ -142,12 +207,21 @@ impl<'s, N: NetworkInterfaces, R: RngCore + CryptoRng, B: BackingStore> InnerDri
             match provisioning_state {
                 ProvisioningState::Failed => {
                     warn!("provisioning failed");
-                    *stack = UnprovisionedStack::new(self.storage.capabilities());
+                    *stack = UnprovisionedStack::new(self.storage.capabilities(), &self.oob);
+                    OOB_AUTHENTICATION.signal(OobAuthentication::Complete);
                 }
                 ProvisioningState::Response(pdu) => {
                     debug!("outbound provisioning pdu: {}", pdu);
                     self.network.transmit(&(pdu.into()), false).await?;
                 }
+                ProvisioningState::Authentication(pdu, oob) => {
+                    if let Some(pdu) = pdu {
+                        debug!("outbound provisioning pdu: {}", pdu);
+                        self.network.transmit(&(pdu.into()), false).await?;
+                    }
+                    info!("out-of-band authentication: {}", oob);
+                    OOB_AUTHENTICATION.signal(oob);
+                }
                 ProvisioningState::Data(device_key, provisioning_data, pdu) => {
                     debug!("received provisioning data: {}", provisioning_data);
                     let primary_unicast_addr = provisioning_data.unicast_address;
@@ -165,6 +239,7 @@ impl<'s, N: NetworkInterfaces, R: RngCore + CryptoRng, B: BackingStore> InnerDri
                         Timer::after(Duration::from_millis(100)).await;
                     }
                     debug!("adjusting into fully provisioned state");
+                    OOB_AUTHENTICATION.signal(OobAuthentication::Complete);
 
                     let provisioned_config: ProvisionedConfiguration =
                         (device_info, secrets, network_state).into();
@@ -176,34 +251,39 @@ impl<'s, N: NetworkInterfaces, R: RngCore + CryptoRng, B: BackingStore> InnerDri
         Ok(())
     }
 
+    /// Process an inbound network PDU, received on `bearer` or looped back
+    /// from this node when `None`.
     async fn receive_network_pdu(
         &self,
         pdu: &NetworkPDU,
         stack: &mut ProvisionedStack,
         sequence: &Sequence,
-        is_loopback: bool,
+        bearer: Option<Bearer>,
     ) -> Result<(), DriverError> {
+        let is_loopback = bearer.is_none();
         let (relay_pdu, block_ack_pdu, result) = self
             .storage
             .read_provisioned(|config| {
                 let result = match stack.process_inbound_network_pdu(
-                    config.secrets(),
+                    config,
                     pdu,
                     &self.watchdog,
                     is_loopback,
-                    config.subscriptions(),
+                    &mut self.replay_protection.borrow_mut(),
                 ) {
                     Ok((relay_pdu, Some(result))) => {
                         if let Some((block_ack, meta)) = &result.block_ack {
                             // send outbound block-ack
                             if let Address::Unicast(addr) = result.dst {
-                                let block_ack_pdu = stack.process_outbound_block_ack(
-                                    config.secrets(),
-                                    sequence,
-                                    *block_ack,
-                                    meta,
-                                    &addr,
-                                )?;
+                                let block_ack_pdu = stack
+                                    .process_outbound_block_ack(
+                                        config.secrets(),
+                                        sequence,
+                                        *block_ack,
+                                        meta,
+                                        &addr,
+                                    )?
+                                    .map(|block_ack_pdu| (block_ack_pdu, meta.src().into()));
                                 (relay_pdu, block_ack_pdu, Some(result))
                             } else {
                                 (relay_pdu, None, Some(result))
@@ -226,24 +306,61 @@ impl<'s, N: NetworkInterfaces, R: RngCore + CryptoRng, B: BackingStore> InnerDri
             })
             .await?;
 
-        if let Some(network_pdu) = block_ack_pdu {
-            self.network
-                .transmit(&(network_pdu.into()), false)
-                .await
-                .ok();
+        if bearer == Some(Bearer::Gatt) {
+            let src = result
+                .as_ref()
+                .map(|result| result.src)
+                .or_else(|| relay_pdu.as_ref().map(|relay_pdu| relay_pdu.src()));
+            if let Some(src) = src {
+                self.network
+                    .proxy_filter(|filter| filter.receive_from_client(src));
+            }
         }
 
-        #[cfg(feature = "relay")]
+        if let Some((network_pdu, dst)) = block_ack_pdu {
+            let network_pdu = network_pdu.into();
+            self.network.transmit(&network_pdu, false).await.ok();
+            self.network.proxy(&network_pdu, dst).await.ok();
+        }
+
+        #[cfg(any(feature = "relay", feature = "proxy"))]
         if let Some(relay_pdu) = relay_pdu {
             let relay_pdu = self
                 .storage
                 .read_provisioned(|config| {
-                    stack.process_outbound_relay_network_pdu(config.secrets(), &relay_pdu)
+                    let relay = *config.foundation().configuration().relay();
+                    let proxy =
+                        config.foundation().configuration().gatt_proxy() == GattProxy::Enabled;
+
+                    // PDUs from proxy clients are relayed onto the advertising bearer by the proxy,
+                    // while PDUs from the advertising bearer are relayed by the relay and
+                    // forwarded to proxy clients by the proxy.
+                    let (should_relay, should_proxy) = match bearer {
+                        Some(Bearer::Gatt) => (proxy, false),
+                        _ => (matches!(relay.relay(), Relay::SupportedEnabled), proxy),
+                    };
+
+                    if should_relay || should_proxy {
+                        Ok(stack
+                            .process_outbound_relay_network_pdu(config.secrets(), &relay_pdu)?
+                            .map(|pdu| (pdu, relay_pdu.dst(), relay, should_relay, should_proxy)))
+                    } else {
+                        Ok(None)
+                    }
                 })
                 .await;
 
-            if let Ok(Some(relay_pdu)) = relay_pdu {
-                self.network.transmit(&(relay_pdu.into()), false).await.ok();
+            if let Ok(Some((relay_pdu, dst, relay, should_relay, should_proxy))) = relay_pdu {
+                if should_proxy {
+                    self.network
+                        .proxy(&(relay_pdu.clone().into()), dst)
+                        .await
+                        .ok();
+                }
+                if should_relay {
+                    let delay = random_relay_delay(&mut *self.rng.borrow_mut());
+                    stack.schedule_relay(relay_pdu, &relay, delay);
+                }
             }
         }
 
@@ -261,9 +378,40 @@ impl<'s, N: NetworkInterfaces, R: RngCore + CryptoRng, B: BackingStore> InnerDri
                             .dispatch(message, &subscriptions)
                             .await?;
                     }
-                    Message::Control(message) => {
-                        stack.process_inbound_control(message, &self.watchdog)?;
-                    }
+                    Message::Control(message) => match message.opcode() {
+                        ControlOpcode::Heartbeat => {
+                            let mut locked_config = self.storage.lock().await;
+                            if let Some(Configuration::Provisioned(config)) = &mut *locked_config {
+                                stack.process_inbound_heartbeat(config, message)?;
+                            }
+                        }
+                        #[cfg(feature = "friend")]
+                        ControlOpcode::FriendRequest
+                        | ControlOpcode::FriendPoll
+                        | ControlOpcode::FriendClear
+                        | ControlOpcode::FriendClearConfirm
+                        | ControlOpcode::FriendSubscriptionListAdd
+                        | ControlOpcode::FriendSubscriptionListRemove => {
+                            self.storage
+                                .read_provisioned(|config| {
+                                    stack.process_inbound_friend_control(config, message)
+                                })
+                                .await?;
+                        }
+                        #[cfg(feature = "low_power")]
+                        ControlOpcode::FriendOffer
+                        | ControlOpcode::FriendUpdate
+                        | ControlOpcode::FriendSubscriptionListConfirm => {
+                            let mut locked_config = self.storage.lock().await;
+                            if let Some(Configuration::Provisioned(config)) = &mut *locked_config {
+                                stack
+                                    .process_inbound_low_power_control(config, sequence, message)?;
+                            }
+                        }
+                        _ => {
+                            stack.process_inbound_control(message)?;
+                        }
+                    },
                 }
             }
         }
@@ -271,7 +419,52 @@ impl<'s, N: NetworkInterfaces, R: RngCore + CryptoRng, B: BackingStore> InnerDri
         Ok(())
     }
 
-    async fn receive_pdu(&self, pdu: &PDU) -> Result<(), DriverError> {
+    /// Process a proxy configuration message from the proxy client, replying with the
+    /// resulting filter status.
+    async fn receive_proxy_configuration_pdu(
+        &self,
+        pdu: &NetworkPDU,
+        stack: &ProvisionedStack,
+        sequence: &Sequence,
+    ) -> Result<(), DriverError> {
+        let status_pdu = self
+            .storage
+            .read_provisioned(|config| {
+                if let Some((message, network_key_handle)) =
+                    stack.process_inbound_proxy_configuration(config.secrets(), pdu)?
+                {
+                    debug!("inbound proxy configuration: {}", message);
+                    if let Some(Some(status)) = self
+                        .network
+                        .proxy_filter(|filter| filter.configure(&message))
+                    {
+                        let src = config
+                            .device_info()
+                            .local_element_address(0)
+                            .ok_or(DriverError::InvalidState)?;
+                        return Ok(Some(stack.process_outbound_proxy_configuration(
+                            config.secrets(),
+                            sequence,
+                            &status,
+                            src,
+                            network_key_handle,
+                            config.iv_index(),
+                        )?));
+                    }
+                }
+                Ok(None)
+            })
+            .await?;
+
+        if let Some(status_pdu) = status_pdu {
+            self.network
+                .transmit(&PDU::ProxyConfiguration(status_pdu), false)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn receive_pdu(&self, pdu: &PDU, bearer: Bearer) -> Result<(), DriverError> {
         let mut current_stack = &mut *self.stack.borrow_mut();
 
         match (&pdu, &mut current_stack) {
@@ -280,9 +473,27 @@ impl<'s, N: NetworkInterfaces, R: RngCore + CryptoRng, B: BackingStore> InnerDri
                 self.receive_provisioning_pdu(pdu, stack).await?;
             }
             (PDU::Network(pdu), Stack::Provisioned { stack, sequence }) => {
-                self.receive_network_pdu(pdu, stack, sequence, false)
+                self.receive_network_pdu(pdu, stack, sequence, Some(bearer))
+                    .await?;
+            }
+            (PDU::ProxyConfiguration(pdu), Stack::Provisioned { stack, sequence }) => {
+                self.receive_proxy_configuration_pdu(pdu, stack, sequence)
                     .await?;
             }
+            (PDU::SecureNetworkBeacon(beacon), Stack::Provisioned { stack, sequence }) => {
+                self.storage
+                    .modify_provisioned(|config| {
+                        stack.process_inbound_secure_network_beacon(config, sequence, beacon)
+                    })
+                    .await?;
+            }
+            (PDU::UnprovisionedBeacon(uuid), Stack::Provisioned { .. }) => {
+                self.receive_unprovisioned_beacon(*uuid).await?;
+            }
+            (PDU::Provisioning(pdu), Stack::Provisioned { .. }) => {
+                debug!("inbound provisioning pdu from provisionee: {}", pdu);
+                self.receive_provisionee_pdu(pdu).await?;
+            }
             _ => {
                 // PDU incompatible with stack state or stack not initialized; ignore.
             }
@@ -290,6 +501,133 @@ impl<'s, N: NetworkInterfaces, R: RngCore + CryptoRng, B: BackingStore> InnerDri
         Ok(())
     }
 
+    /// Complete input out-of-band authentication with the value input by the user.
+    async fn input_oob(&self, value: OobValue) -> Result<(), DriverError> {
+        let pdu = match &mut *self.stack.borrow_mut() {
+            Stack::Unprovisioned { stack, .. } => stack.input(value),
+            _ => None,
+        };
+        if let Some(pdu) = pdu {
+            debug!("outbound provisioning pdu: {}", pdu);
+            self.network.transmit(&(pdu.into()), false).await?;
+        }
+        Ok(())
+    }
+
+    fn start_provisioning(&self, request: ProvisioningRequest, device_state: &DeviceState) {
+        let mut provisioning = self.provisioning.borrow_mut();
+        if matches!(device_state, DeviceState::Provisioned) && provisioning.is_none() {
+            debug!("scanning for unprovisioned devices");
+            provisioning.replace(ProvisioningSession::new(
+                request.uuid,
+                request.static_oob,
+                request.public_key,
+                request.addresses,
+            ));
+        } else {
+            PROVISIONED.signal(Err(ProvisioningError::InvalidState));
+        }
+    }
+
+    /// Open a link to the device being scanned for, once it beacons, and invite it.
+    async fn receive_unprovisioned_beacon(&self, uuid: Uuid) -> Result<(), DriverError> {
+        let response = {
+            let mut provisioning = self.provisioning.borrow_mut();
+            match &mut *provisioning {
+                Some(session) if session.is_scanning_for(uuid) => {
+                    self.storage
+                        .read_provisioned(|config| session.invite(uuid, config))
+                        .await
+                }
+                _ => return Ok(()),
+            }
+        };
+
+        match response {
+            Ok(response) => {
+                let link_id = self.rng.borrow_mut().next_u32();
+                debug!("opening provisioning link to {}", uuid);
+                self.network.open_link(uuid, link_id).await?;
+                for pdu in &response {
+                    self.network.transmit(&(pdu.clone().into()), false).await?;
+                }
+            }
+            Err(_) => {
+                self.end_provisioning(Err(ProvisioningError::Failed(ErrorCode::UnexpectedError)))
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn receive_provisionee_pdu(&self, pdu: &ProvisioningPDU) -> Result<(), DriverError> {
+        let processed = match &mut *self.provisioning.borrow_mut() {
+            Some(session) if session.is_linked() => {
+                session.process(pdu, &mut *self.rng.borrow_mut())
+            }
+            _ => return Ok(()),
+        };
+
+        match processed {
+            Ok((response, None)) => {
+                for pdu in &response {
+                    debug!("outbound provisioning pdu: {}", pdu);
+                    self.network.transmit(&(pdu.clone().into()), false).await?;
+                }
+            }
+            Ok((_, Some(result))) => {
+                self.end_provisioning(result).await?;
+            }
+            Err(_) => {
+                self.end_provisioning(Err(ProvisioningError::Failed(ErrorCode::UnexpectedError)))
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Repeat unacknowledged provisioning PDUs, or give up once the session expires.
+    async fn retransmit_provisioning(&self) -> Result<(), DriverError> {
+        let response = match &mut *self.provisioning.borrow_mut() {
+            Some(session) if session.is_expired() => None,
+            Some(session) => Some(session.retransmit()),
+            None => return Ok(()),
+        };
+
+        match response {
+            None => {
+                warn!("provisioning timed out");
+                self.end_provisioning(Err(ProvisioningError::Timeout))
+                    .await?;
+            }
+            Some(response) => {
+                // the network interfaces repeat the transaction in flight.
+                if let Some(pdu) = (&response).into_iter().next() {
+                    self.network.transmit(&(pdu.clone().into()), true).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn end_provisioning(&self, result: ProvisioningResult) -> Result<(), DriverError> {
+        if let Some(session) = self.provisioning.borrow_mut().take() {
+            if session.is_linked() {
+                let reason = match result {
+                    Ok(_) => Reason::Success,
+                    Err(_) => Reason::Fail,
+                };
+                self.network.close_link(reason).await?;
+            }
+            match &result {
+                Ok((node, _)) => info!("provisioned {} at {}", node.uuid, node.unicast_address),
+                Err(err) => warn!("provisioning failed: {}", err),
+            }
+            PROVISIONED.signal(result);
+        }
+        Ok(())
+    }
+
     fn process_outbound_send(
         &self,
         element_address: UnicastAddress,
@@ -352,6 +690,7 @@ impl<'s, N: NetworkInterfaces, R: RngCore + CryptoRng, B: BackingStore> InnerDri
                     ttl: publication.details.publish_ttl.unwrap_or(default_ttl),
                     label_uuid,
                     replay_seq: None,
+                    szmic: None,
                 };
                 Ok((
                     Some(AccessMessage::<ProvisionedStack>::new(
@@ -381,7 +720,7 @@ impl<'s, N: NetworkInterfaces, R: RngCore + CryptoRng, B: BackingStore> InnerDri
                 .local_element_address(outbound_payload.element_index as u8)
                 .ok_or(DriverError::InvalidState)?;
             let default_ttl = config.foundation().configuration().default_ttl();
-            let (message, completion_token, retransmits) = match &outbound_payload.extra {
+            let (message, completion_token, retransmission) = match &outbound_payload.extra {
                 OutboundExtra::Send(extra) => {
                     let (message, completion_token) = self.process_outbound_send(
                         element_address,
@@ -389,47 +728,76 @@ impl<'s, N: NetworkInterfaces, R: RngCore + CryptoRng, B: BackingStore> InnerDri
                         outbound_payload,
                         extra,
                     )?;
-                    (message, completion_token, 3)
+                    (message, completion_token, SEND_RETRANSMISSION)
                 }
                 OutboundExtra::Publish => {
-                    let retransmits = config
+                    let retransmission = config
                         .publications()
                         .get(
                             outbound_payload.element_index as u8,
                             outbound_payload.model_identifer,
                         )
-                        .map(|p| p.details.publish_retransmit.count())
-                        .unwrap_or(3);
+                        .map(|p| p.details.publish_retransmit.into())
+                        .unwrap_or(PublicationRetransmission::None);
                     let (message, completion_token) = self.process_outbound_publish(
                         config,
                         element_address,
                         default_ttl,
                         outbound_payload,
                     )?;
-                    (message, completion_token, retransmits)
+                    (message, completion_token, retransmission)
                 }
             };
+            let network_transmit = *config.foundation().configuration().network_transmit();
 
             if let (Some(message), Stack::Provisioned { stack, sequence }) =
                 (message, &mut *self.stack.borrow_mut())
             {
+                let dst = message.meta().dst();
                 let message = message.into();
                 let pdus = stack.process_outbound(
                     config.secrets(),
                     sequence,
                     &message,
                     completion_token,
-                    &self.watchdog,
-                    retransmits,
+                    retransmission,
                 )?;
 
                 drop(locked_config);
-                for pdu in pdus {
-                    self.receive_network_pdu(&pdu, stack, sequence, true)
-                        .await?;
-                    let pdu = pdu.into();
-                    self.network.transmit(&pdu, false).await?;
+                for pdu in &pdus {
+                    self.receive_network_pdu(pdu, stack, sequence, None).await?;
                 }
+                self.transmit_network_pdus(stack, pdus, dst, &network_transmit)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Transmit network PDUs originating from this node, scheduling their
+    /// retransmissions on the advertising bearer according to the Network Transmit state.
+    ///
+    /// Proxy clients receive them once, if their proxy filter accepts `dst`.
+    async fn transmit_network_pdus<const S: usize>(
+        &self,
+        stack: &mut ProvisionedStack,
+        pdus: Vec<NetworkPDU, S>,
+        dst: Address,
+        network_transmit: &NetworkTransmitConfig,
+    ) -> Result<(), DriverError> {
+        for network_pdu in pdus {
+            let pdu = network_pdu.clone().into();
+            self.network.transmit(&pdu, false).await?;
+            self.network.proxy(&pdu, dst).await?;
+            stack.schedule_network_retransmissions(network_pdu, network_transmit);
+        }
+        Ok(())
+    }
+
+    async fn send_network_retransmissions(&self) -> Result<(), DriverError> {
+        if let Stack::Provisioned { stack, .. } = &mut *self.stack.borrow_mut() {
+            for pdu in stack.network_retransmit() {
+                self.network.transmit(&(pdu.into()), true).await?;
             }
         }
         Ok(())
@@ -457,8 +825,54 @@ impl<'s, N: NetworkInterfaces, R: RngCore + CryptoRng, B: BackingStore> InnerDri
         Ok(())
     }
 
+    #[cfg(any(feature = "relay", feature = "proxy"))]
+    async fn send_relay(&self) -> Result<(), DriverError> {
+        if let Stack::Provisioned { stack, .. } = &mut *self.stack.borrow_mut() {
+            // relayed PDUs reach proxy clients through the proxy,
+            // so they only go out on the advertising bearer.
+            for pdu in stack.relay() {
+                self.network.transmit(&(pdu.into()), true).await?;
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "friend")]
+    async fn send_friend(&self) -> Result<(), DriverError> {
+        if let Stack::Provisioned { stack, sequence } = &mut *self.stack.borrow_mut() {
+            let pdus = self
+                .storage
+                .read_provisioned(|config| stack.process_outbound_friend(config, sequence))
+                .await?;
+
+            // friendship messages are exchanged with Low Power nodes in range,
+            // and not repeated.
+            for pdu in pdus {
+                self.network.transmit(&(pdu.into()), false).await?;
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "low_power")]
+    async fn send_low_power(&self) -> Result<(), DriverError> {
+        if let Stack::Provisioned { stack, sequence } = &mut *self.stack.borrow_mut() {
+            let pdus = self
+                .storage
+                .read_provisioned(|config| {
+                    stack.process_outbound_low_power(config, sequence, &self.low_power)
+                })
+                .await?;
+
+            for pdu in pdus {
+                self.network.transmit(&(pdu.into()), false).await?;
+            }
+        }
+        Ok(())
+    }
+
     async fn send_beacon(&self) -> Result<(), DriverError> {
-        match &*self.stack.borrow() {
+        match &mut *self.stack.borrow_mut() {
             Stack::None => {
                 // nothing
             }
@@ -466,14 +880,66 @@ impl<'s, N: NetworkInterfaces, R: RngCore + CryptoRng, B: BackingStore> InnerDri
                 self.network.beacon(Beacon::Unprovisioned(*uuid)).await?;
             }
 
-            Stack::Provisioned { .. } => {
-                let network_id = self
+            Stack::Provisioned { stack, .. } => {
+                let mut random = [0; 8];
+                self.rng.borrow_mut().fill_bytes(&mut random);
+
+                let beacons: Vec<Beacon, 8> = self
                     .storage
                     .read_provisioned(|config| {
-                        Ok(config.secrets().network_key_by_index(0)?.network_id())
+                        let gatt_proxy = config.foundation().configuration().gatt_proxy();
+                        let mut beacons: Vec<_, 8> = stack
+                            .process_outbound_secure_network_beacons(config)?
+                            .into_iter()
+                            .map(Beacon::Secure)
+                            .collect();
+
+                        // advertising the network id or the node identity
+                        // invites proxy clients to connect. The network id is
+                        // advertised unless the proxy was explicitly disabled,
+                        // bearers without GATT ignore it.
+                        for (net_key_index, network_key) in config.secrets().network_keys_iter() {
+                            let beacon = if config.node_identities().is_running(net_key_index) {
+                                let address = config
+                                    .device_info()
+                                    .local_element_address(0)
+                                    .ok_or(DriverError::InvalidState)?;
+                                let hash = network_key
+                                    .identity_key()?
+                                    .node_identity_hash(&random, address)?;
+                                Beacon::NodeIdentity { hash, random }
+                            } else if gatt_proxy != GattProxy::Disabled {
+                                Beacon::Provisioned(network_key.network_id())
+                            } else {
+                                continue;
+                            };
+                            beacons.push(beacon).ok();
+                        }
+                        Ok(beacons)
                     })
                     .await?;
-                self.network.beacon(Beacon::Provisioned(network_id)).await?;
+                for beacon in beacons {
+                    self.network.beacon(beacon).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn send_heartbeat(&self) -> Result<(), DriverError> {
+        if let Stack::Provisioned { stack, sequence } = &mut *self.stack.borrow_mut() {
+            let mut locked_config = self.storage.lock().await;
+            if let Some(Configuration::Provisioned(config)) = &mut *locked_config {
+                let pdus = stack.process_outbound_heartbeat(config, sequence)?;
+                let dst = config
+                    .foundation()
+                    .configuration()
+                    .heartbeat_publication()
+                    .destination;
+                let network_transmit = *config.foundation().configuration().network_transmit();
+                drop(locked_config);
+                self.transmit_network_pdus(stack, pdus, dst, &network_transmit)
+                    .await?;
             }
         }
         Ok(())
@@ -489,6 +955,71 @@ impl<'s, N: NetworkInterfaces, R: RngCore + CryptoRng, B: BackingStore> InnerDri
         }
     }
 
+    fn next_heartbeat(&self) -> HeartbeatFuture<'_, N, R, B> {
+        async move {
+            if let Some(next_heartbeat) = self.stack.borrow().next_heartbeat() {
+                next_heartbeat.await
+            } else {
+                pending().await
+            }
+        }
+    }
+
+    fn next_network_retransmit(&self) -> NetworkRetransmitFuture<'_, N, R, B> {
+        async move {
+            if let Some(next_network_retransmit) = self.stack.borrow().next_network_retransmit() {
+                next_network_retransmit.await
+            } else {
+                pending().await
+            }
+        }
+    }
+
+    fn next_low_power(&self) -> LowPowerFuture<'_, N, R, B> {
+        async move {
+            #[cfg(feature = "low_power")]
+            if let Some(next_low_power) = self.stack.borrow().next_low_power() {
+                return next_low_power.await;
+            }
+            pending().await
+        }
+    }
+
+    fn next_relay(&self) -> RelayFuture<'_, N, R, B> {
+        async move {
+            #[cfg(any(feature = "relay", feature = "proxy"))]
+            if let Some(next_relay) = self.stack.borrow().next_relay() {
+                return next_relay.await;
+            }
+            pending().await
+        }
+    }
+
+    fn next_friend(&self) -> FriendFuture<'_, N, R, B> {
+        async move {
+            #[cfg(feature = "friend")]
+            if let Some(next_friend) = self.stack.borrow().next_friend() {
+                return next_friend.await;
+            }
+            pending().await
+        }
+    }
+
+    fn next_provisioning(&self) -> ProvisioningFuture<'_, N, R, B> {
+        async move {
+            let next_deadline = self
+                .provisioning
+                .borrow()
+                .as_ref()
+                .map(|session| session.next_deadline());
+            if let Some(next_deadline) = next_deadline {
+                next_deadline.await
+            } else {
+                pending().await
+            }
+        }
+    }
+
     fn next_retransmit(&self) -> RetransmitFuture<'_, N, R, B> {
         async move {
             if let Some(next_retransmit) = self.stack.borrow().next_retransmit() {
@@ -499,6 +1030,14 @@ impl<'s, N: NetworkInterfaces, R: RngCore + CryptoRng, B: BackingStore> InnerDri
         }
     }
 
+    /// Receive from the network interfaces, unless a Low Power node sleeps between polls.
+    async fn receive(&self, device_state: &DeviceState) -> Result<(PDU, Bearer), NetworkError> {
+        if !self.stack.borrow().is_scanning() {
+            return pending().await;
+        }
+        self.network.receive(device_state, &self.watchdog).await
+    }
+
     fn run_device<D: BluetoothMeshDevice>(
         device: &mut D,
         receiver: InboundChannelReceiver,
@@ -635,13 +1174,16 @@ impl<'s, N: NetworkInterfaces, R: RngCore + CryptoRng, B: BackingStore> InnerDri
             (Stack::None, Configuration::Unprovisioned(config))
             | (Stack::Provisioned { .. }, Configuration::Unprovisioned(config)) => {
                 *stack = Stack::Unprovisioned {
-                    stack: UnprovisionedStack::new(self.storage.capabilities()),
+                    stack: UnprovisionedStack::new(self.storage.capabilities(), &self.oob),
                     uuid: config.uuid,
                 };
                 self.network.reset();
             }
             (Stack::None, Configuration::Provisioned(config))
             | (Stack::Unprovisioned { .. }, Configuration::Provisioned(config)) => {
+                self.replay_protection
+                    .borrow_mut()
+                    .restore(config.replay_protection());
                 *stack = Stack::Provisioned {
                     sequence: Sequence::new(Seq::new(config.sequence())),
                     stack: config.into(),
@@ -653,6 +1195,14 @@ impl<'s, N: NetworkInterfaces, R: RngCore + CryptoRng, B: BackingStore> InnerDri
         }
     }
 
+    fn reconfigure_heartbeat(&self, config: &mut Configuration) {
+        if let (Stack::Provisioned { stack, .. }, Configuration::Provisioned(config)) =
+            (&mut *self.stack.borrow_mut(), config)
+        {
+            stack.reconfigure_heartbeat(config);
+        }
+    }
+
     async fn update_config(&self) -> Result<(), DriverError> {
         let stack = self.stack.borrow_mut();
         match &*stack {
@@ -661,6 +1211,7 @@ impl<'s, N: NetworkInterfaces, R: RngCore + CryptoRng, B: BackingStore> InnerDri
                     .modify_provisioned(|config| {
                         *config.sequence_mut() = sequence.current();
                         debug!("Updating config sequence counter to {}", sequence.current());
+                        *config.replay_protection_mut() = self.replay_protection.borrow().list();
                         Ok(())
                     })
                     .await?;
@@ -670,18 +1221,51 @@ impl<'s, N: NetworkInterfaces, R: RngCore + CryptoRng, B: BackingStore> InnerDri
         Ok(())
     }
 
+    async fn update_iv_index(&self) -> Result<(), DriverError> {
+        if let Stack::Provisioned { stack, sequence } = &mut *self.stack.borrow_mut() {
+            if stack.iv_update_hour_elapsed() || sequence.take_iv_update_request() {
+                let changed = match &mut *self.storage.lock().await {
+                    Some(Configuration::Provisioned(config)) => {
+                        stack.process_iv_update(config, sequence)
+                    }
+                    _ => false,
+                };
+                // only write back actual changes, not every hour.
+                if changed {
+                    self.storage.modify_provisioned(|_| Ok(())).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
     async fn run_driver(
         &self,
         composition: &mut Composition<CompositionExtra>,
     ) -> Result<(), DriverError> {
         info!("btmesh: starting up");
 
+        let mut algorithms = Algorithms::default();
+        // infallible, room for every algorithm.
+        algorithms.push(Algorithm::P256HmacSha256).ok();
+
         let capabilities = Capabilities {
             number_of_elements: composition.number_of_elements(),
-            ..Default::default()
+            algorithms,
+            output_oob_size: self.oob.output_size,
+            output_oob_action: self.oob.output_actions.clone(),
+            input_oob_size: self.oob.input_size,
+            input_oob_action: self.oob.input_actions.clone(),
+            public_key_type: PublicKeyType {
+                available: self.oob.secret_key().is_some(),
+            },
+            static_oob_type: StaticOOBType {
+                available: self.oob.static_oob.is_some(),
+            },
         };
 
-        enhance_composition(composition)?;
+        let crpl = self.replay_protection.borrow().capacity() as u16;
+        enhance_composition(composition, crpl)?;
 
         let simplified_composition = composition.simplify();
 
@@ -702,13 +1286,16 @@ impl<'s, N: NetworkInterfaces, R: RngCore + CryptoRng, B: BackingStore> InnerDri
                 }
             }
 
-            let config = self.storage.lock().await;
+            self.update_iv_index().await?;
+
+            let mut config = self.storage.lock().await;
             if config.is_none() {
                 return Err(DriverError::InvalidState);
             }
 
-            if let Some(config) = &*config {
+            if let Some(config) = &mut *config {
                 self.reconfigure_stack(config);
+                self.reconfigure_heartbeat(config);
                 last_displayed_hash.replace(Self::display_configuration(
                     &simplified_composition,
                     config,
@@ -717,46 +1304,102 @@ impl<'s, N: NetworkInterfaces, R: RngCore + CryptoRng, B: BackingStore> InnerDri
             }
 
             let device_state = self.stack.borrow().device_state();
+            low_power_node_poll_timeout::publish_poll_deadlines(
+                self.stack.borrow().lpn_poll_deadlines(),
+            );
             self.notify_publications(&config, composition).await;
             drop(config);
 
             if let Some(device_state) = device_state {
-                let receive_fut = self.network.receive(&device_state, &self.watchdog);
+                let receive_fut = self.receive(&device_state);
                 let transmit_fut = OUTBOUND.receive();
-                let io_fut = select(receive_fut, transmit_fut);
-
-                let beacon_fut = self.next_beacon();
-                let retransmit_fut = self.next_retransmit();
+                let provisioning_fut = PROVISIONING.receive();
+                let oob_fut = OOB_INPUT.receive();
+                let io_fut = select4(receive_fut, transmit_fut, provisioning_fut, oob_fut);
+
+                let beacon_fut = select4(
+                    self.next_beacon(),
+                    self.next_heartbeat(),
+                    self.next_low_power(),
+                    self.next_provisioning(),
+                );
+                let retransmit_fut = select4(
+                    self.next_retransmit(),
+                    self.next_network_retransmit(),
+                    self.next_relay(),
+                    self.next_friend(),
+                );
 
                 let watchdog_fut = self.watchdog.next();
 
                 match select4(io_fut, beacon_fut, retransmit_fut, watchdog_fut).await {
                     Either4::First(inner) => match inner {
-                        Either::First(Ok(pdu)) => {
+                        Either4::First(Ok((pdu, bearer))) => {
                             if !self.stack.borrow().has_ongoing_completion() {
-                                if let Err(result) = self.receive_pdu(&pdu).await {
+                                if let Err(result) = self.receive_pdu(&pdu, bearer).await {
                                     match result {
                                         DriverError::InvalidPDU | DriverError::Parse(_) => continue,
+                                        DriverError::SeqRollover => {
+                                            error!(
+                                                "sequence numbers exhausted, awaiting IV Update"
+                                            );
+                                            DRIVER_EVENT.signal(DriverEvent::SequenceExhausted);
+                                            continue;
+                                        }
                                         _ => return Err(result),
                                     }
                                 }
                             }
                         }
-                        Either::First(Err(err)) => {
+                        Either4::First(Err(err)) => {
                             return Err(err.into());
                         }
-                        Either::Second(outbound_payload) => {
+                        Either4::Second(outbound_payload) => {
                             if let DeviceState::Provisioned = device_state {
-                                self.process_outbound_payload(&outbound_payload).await?;
+                                match self.process_outbound_payload(&outbound_payload).await {
+                                    // the completion token, if any, is dropped as incomplete.
+                                    Err(DriverError::SeqRollover) => {
+                                        error!("sequence numbers exhausted, awaiting IV Update");
+                                        DRIVER_EVENT.signal(DriverEvent::SequenceExhausted);
+                                    }
+                                    result => result?,
+                                }
                             }
                         }
+                        Either4::Third(request) => {
+                            self.start_provisioning(request, &device_state);
+                        }
+                        Either4::Fourth(value) => {
+                            self.input_oob(value).await.ok();
+                        }
                     },
-                    Either4::Second(_) => {
+                    Either4::Second(Either4::First(_)) => {
                         self.send_beacon().await.ok();
                     }
-                    Either4::Third(_) => {
+                    Either4::Second(Either4::Second(_)) => {
+                        self.send_heartbeat().await.ok();
+                    }
+                    Either4::Second(Either4::Third(_)) => {
+                        #[cfg(feature = "low_power")]
+                        self.send_low_power().await.ok();
+                    }
+                    Either4::Second(Either4::Fourth(_)) => {
+                        self.retransmit_provisioning().await.ok();
+                    }
+                    Either4::Third(Either4::First(_)) => {
                         self.retransmit().await.ok();
                     }
+                    Either4::Third(Either4::Second(_)) => {
+                        self.send_network_retransmissions().await.ok();
+                    }
+                    Either4::Third(Either4::Third(_)) => {
+                        #[cfg(any(feature = "relay", feature = "proxy"))]
+                        self.send_relay().await.ok();
+                    }
+                    Either4::Third(Either4::Fourth(_)) => {
+                        #[cfg(feature = "friend")]
+                        self.send_friend().await.ok();
+                    }
                     Either4::Fourth(Some(expiration)) => {
                         self.handle_watchdog_event(&expiration.take()).await.ok();
                     }
@@ -774,12 +1417,7 @@ impl<'s, N: NetworkInterfaces, R: RngCore + CryptoRng, B: BackingStore> InnerDri
                 self.network.close_link(Reason::Timeout).await?;
                 *self.stack.borrow_mut() = Stack::None;
             }
-            WatchdogEvent::OutboundExpiration(seq_zero) => {
-                if let Stack::Provisioned { stack, .. } = &mut *self.stack.borrow_mut() {
-                    stack.outbound_expiration(seq_zero);
-                }
-            }
-            WatchdogEvent::InboundExpiration(seq_zero) => {
+            WatchdogEvent::InboundExpiration(src, seq_zero) => {
                 if let Stack::Provisioned {
                     stack, sequence, ..
                 } = &mut *self.stack.borrow_mut()
@@ -787,22 +1425,20 @@ impl<'s, N: NetworkInterfaces, R: RngCore + CryptoRng, B: BackingStore> InnerDri
                     let network_pdu = self
                         .storage
                         .read_provisioned(|config| {
-                            if let Some(src) = config.device_info().local_element_address(0) {
-                                Ok(stack.inbound_expiration(
-                                    config.secrets(),
-                                    sequence,
-                                    seq_zero,
-                                    &src,
-                                    &self.watchdog,
-                                )?)
-                            } else {
-                                Ok(None)
-                            }
+                            stack.inbound_expiration(
+                                config.secrets(),
+                                sequence,
+                                src,
+                                seq_zero,
+                                &self.watchdog,
+                            )
                         })
                         .await?;
 
-                    if let Some(network_pdu) = network_pdu {
-                        self.network.transmit(&network_pdu.into(), false).await.ok();
+                    if let Some((network_pdu, dst)) = network_pdu {
+                        let network_pdu = network_pdu.into();
+                        self.network.transmit(&network_pdu, false).await.ok();
+                        self.network.proxy(&network_pdu, dst).await.ok();
                     }
                 }
             }
@@ -860,6 +1496,10 @@ impl<N: NetworkInterfaces, R: RngCore + CryptoRng, B: BackingStore> BluetoothMes
                 unwrap!(self.rng.take()),
                 &self.storage,
                 self.persist_interval,
+                self.oob.clone(),
+                self.replay_protection_list_size,
+                #[cfg(feature = "low_power")]
+                self.low_power,
             )
             .run(device)
             .await
@@ -874,6 +1514,48 @@ where
     B: BackingStore + 'f,
 = impl Future<Output = ()> + 'f;
 
+type HeartbeatFuture<'f, N, R, B>
+where
+    N: NetworkInterfaces + 'f,
+    R: CryptoRng + RngCore + 'f,
+    B: BackingStore + 'f,
+= impl Future<Output = ()> + 'f;
+
+type NetworkRetransmitFuture<'f, N, R, B>
+where
+    N: NetworkInterfaces + 'f,
+    R: CryptoRng + RngCore + 'f,
+    B: BackingStore + 'f,
+= impl Future<Output = ()> + 'f;
+
+type LowPowerFuture<'f, N, R, B>
+where
+    N: NetworkInterfaces + 'f,
+    R: CryptoRng + RngCore + 'f,
+    B: BackingStore + 'f,
+= impl Future<Output = ()> + 'f;
+
+type ProvisioningFuture<'f, N, R, B>
+where
+    N: NetworkInterfaces + 'f,
+    R: CryptoRng + RngCore + 'f,
+    B: BackingStore + 'f,
+= impl Future<Output = ()> + 'f;
+
+type RelayFuture<'f, N, R, B>
+where
+    N: NetworkInterfaces + 'f,
+    R: CryptoRng + RngCore + 'f,
+    B: BackingStore + 'f,
+= impl Future<Output = ()> + 'f;
+
+type FriendFuture<'f, N, R, B>
+where
+    N: NetworkInterfaces + 'f,
+    R: CryptoRng + RngCore + 'f,
+    B: BackingStore + 'f,
+= impl Future<Output = ()> + 'f;
+
 type RetransmitFuture<'f, N, R, B>
 where
     N: NetworkInterfaces + 'f,
@@ -891,10 +1573,95 @@ static DEVICE_INBOUND: InboundChannel = InboundChannel::new();
 
 static OUTBOUND: OutboundChannel = OutboundChannel::new();
 
-fn enhance_composition<X: Default>(composition: &mut Composition<X>) -> Result<(), DriverError> {
+struct ProvisioningRequest {
+    uuid: Option<Uuid>,
+    static_oob: Option<StaticOob>,
+    public_key: Option<PublicKey>,
+    addresses: UnicastAddressAllocator,
+}
+
+static PROVISIONING: Channel<CriticalSectionRawMutex, ProvisioningRequest, 1> = Channel::new();
+static PROVISIONED: Signal<ProvisioningResult> = Signal::new();
+
+static OOB_AUTHENTICATION: Signal<OobAuthentication> = Signal::new();
+static OOB_INPUT: Channel<CriticalSectionRawMutex, OobValue, 1> = Channel::new();
+
+/// Conditions of the driver the application may need to act upon.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DriverEvent {
+    /// The sequence numbers of this node are exhausted: messages are dropped,
+    /// their completion tokens signalled incomplete, until the IV Update
+    /// procedure completes.
+    SequenceExhausted,
+}
+
+static DRIVER_EVENT: Signal<DriverEvent> = Signal::new();
+
+/// Wait for the next [`DriverEvent`].
+pub async fn driver_event() -> DriverEvent {
+    DRIVER_EVENT.wait().await
+}
+
+/// Wait for out-of-band authentication to carry out while this device is
+/// being provisioned, as selected by the provisioner among those offered in
+/// [`BluetoothMeshDriverConfig::oob`].
+///
+/// Output values are to be blinked, beeped, vibrated or displayed until
+/// [`OobAuthentication::Complete`], while input values are to be handed back
+/// through [`input_oob`].
+pub async fn oob_authentication() -> OobAuthentication {
+    OOB_AUTHENTICATION.wait().await
+}
+
+/// Hand the value input by the user to the driver, completing input out-of-band
+/// authentication: a count of pushes or twists, a number, or characters.
+pub async fn input_oob(value: OobValue) {
+    OOB_INPUT.send(value).await
+}
+
+/// Provision an unprovisioned device into the network of this node, acting as provisioner.
+///
+/// Scans for the unprovisioned beacon of `uuid`, or of any device when `None`, and
+/// provisions it over PB-ADV with the primary network key and the IV Index of this node.
+/// The unicast addresses of its elements are taken from `addresses`, which is only
+/// advanced on success, and should be persisted by the caller.
+///
+/// The device is authenticated with static OOB when its `static_oob` secret is
+/// known, typically scanned from its QR code, and no OOB authentication otherwise.
+/// Secrets of 32 octets are used in full with the HMAC-SHA256 algorithm.
+/// Likewise, its `public_key` is not exchanged in-band when known out of band,
+/// protecting the key exchange from man-in-the-middle attacks.
+///
+/// The driver must be running on a provisioned node, and provisions one device at a time.
+pub async fn provision(
+    uuid: Option<Uuid>,
+    static_oob: Option<StaticOob>,
+    public_key: Option<PublicKey>,
+    addresses: &mut UnicastAddressAllocator,
+) -> Result<ProvisionedNode, ProvisioningError> {
+    PROVISIONED.reset();
+    PROVISIONING
+        .send(ProvisioningRequest {
+            uuid,
+            static_oob,
+            public_key,
+            addresses: *addresses,
+        })
+        .await;
+    let (node, remaining) = PROVISIONED.wait().await?;
+    *addresses = remaining;
+    Ok(node)
+}
+
+fn enhance_composition<X: Default>(
+    composition: &mut Composition<X>,
+    crpl: u16,
+) -> Result<(), DriverError> {
     if composition.number_of_elements() > 0 {
         composition[0].add_model(CONFIGURATION_SERVER);
     }
+    composition.set_crpl(crpl);
 
     Ok(())
 }
@@ -69,7 +69,35 @@ pub async fn dispatch<C: BluetoothMeshModelContext<ConfigurationServer>, B: Back
             }
         }
         AppKeyMessage::List(_list) => {}
-        AppKeyMessage::Update(_update) => {}
+        AppKeyMessage::Update(update) => {
+            info!("app-key update");
+            let (status, err) = convert(
+                &storage
+                    .modify_provisioned(|config| {
+                        config.secrets_mut().update_application_key(
+                            update.net_key_index(),
+                            update.app_key_index(),
+                            update.app_key(),
+                        )?;
+                        Ok(())
+                    })
+                    .await,
+            );
+
+            ctx.send(
+                AppKeyMessage::Status(AppKeyStatusMessage {
+                    status,
+                    indexes: update.indexes,
+                })
+                .into(),
+                meta.reply(),
+            )
+            .await?;
+
+            if let Some(err) = err {
+                return Err(err);
+            }
+        }
         AppKeyMessage::Status(_) => {
             // not applicable
         }
@@ -0,0 +1,43 @@
+use crate::{BackingStore, DriverError, Storage};
+use btmesh_device::{BluetoothMeshModelContext, InboundMetadata};
+use btmesh_models::foundation::configuration::friend::{Friend, FriendMessage};
+use btmesh_models::foundation::configuration::ConfigurationServer;
+
+pub async fn dispatch<C: BluetoothMeshModelContext<ConfigurationServer>, B: BackingStore>(
+    ctx: &C,
+    storage: &Storage<B>,
+    message: &FriendMessage,
+    meta: &InboundMetadata,
+) -> Result<(), DriverError> {
+    match message {
+        FriendMessage::Get => {
+            let friend = storage
+                .read_provisioned(|config| Ok(config.foundation().configuration().friend()))
+                .await?;
+
+            ctx.send(FriendMessage::Status(friend).into(), meta.reply())
+                .await?;
+        }
+        FriendMessage::Set(friend) => {
+            storage
+                .modify_provisioned(|config| {
+                    let current = config.foundation_mut().configuration_mut().friend_mut();
+                    if *current != Friend::NotSupported {
+                        *current = *friend;
+                    }
+                    Ok(())
+                })
+                .await?;
+            let friend = storage
+                .read_provisioned(|config| Ok(config.foundation().configuration().friend()))
+                .await?;
+
+            ctx.send(FriendMessage::Status(friend).into(), meta.reply())
+                .await?;
+        }
+        FriendMessage::Status(_) => {
+            // not applicable
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use crate::{BackingStore, DriverError, Storage};
+use btmesh_device::{BluetoothMeshModelContext, InboundMetadata};
+use btmesh_models::foundation::configuration::gatt_proxy::{GattProxy, GattProxyMessage};
+use btmesh_models::foundation::configuration::ConfigurationServer;
+
+pub async fn dispatch<C: BluetoothMeshModelContext<ConfigurationServer>, B: BackingStore>(
+    ctx: &C,
+    storage: &Storage<B>,
+    message: &GattProxyMessage,
+    meta: &InboundMetadata,
+) -> Result<(), DriverError> {
+    match message {
+        GattProxyMessage::Get => {
+            let gatt_proxy = storage
+                .read_provisioned(|config| Ok(config.foundation().configuration().gatt_proxy()))
+                .await?;
+
+            ctx.send(GattProxyMessage::Status(gatt_proxy).into(), meta.reply())
+                .await?;
+        }
+        GattProxyMessage::Set(gatt_proxy) => {
+            storage
+                .modify_provisioned(|config| {
+                    let current = config.foundation_mut().configuration_mut().gatt_proxy_mut();
+                    if *current != GattProxy::NotSupported {
+                        *current = *gatt_proxy;
+                    }
+                    Ok(())
+                })
+                .await?;
+            let gatt_proxy = storage
+                .read_provisioned(|config| Ok(config.foundation().configuration().gatt_proxy()))
+                .await?;
+
+            ctx.send(GattProxyMessage::Status(gatt_proxy).into(), meta.reply())
+                .await?;
+        }
+        GattProxyMessage::Status(_) => {
+            // not applicable
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use crate::models::configuration::convert;
+use crate::storage::provisioned::ProvisionedConfiguration;
+use crate::{BackingStore, DriverError, Storage};
+use btmesh_device::{BluetoothMeshModelContext, InboundMetadata};
+use btmesh_models::foundation::configuration::heartbeat_publication::{
+    value_to_log, HeartbeatPublication, HeartbeatPublicationMessage,
+    HeartbeatPublicationStatusMessage,
+};
+use btmesh_models::foundation::configuration::ConfigurationServer;
+use btmesh_models::Status;
+
+pub async fn dispatch<C: BluetoothMeshModelContext<ConfigurationServer>, B: BackingStore>(
+    ctx: &C,
+    storage: &Storage<B>,
+    message: &HeartbeatPublicationMessage,
+    meta: &InboundMetadata,
+) -> Result<(), DriverError> {
+    match message {
+        HeartbeatPublicationMessage::Get => {
+            let publication = storage
+                .read_provisioned(|config| Ok(current_publication(config)))
+                .await?;
+
+            ctx.send(
+                HeartbeatPublicationMessage::Status(HeartbeatPublicationStatusMessage {
+                    status: Status::Success,
+                    publication,
+                })
+                .into(),
+                meta.reply(),
+            )
+            .await?;
+            Ok(())
+        }
+        HeartbeatPublicationMessage::Set(publication) => {
+            let (status, err) = convert(
+                &storage
+                    .modify_provisioned(|config| {
+                        config
+                            .secrets()
+                            .network_key_by_index(publication.net_key_index)?;
+                        *config
+                            .foundation_mut()
+                            .configuration_mut()
+                            .heartbeat_publication_mut() = *publication;
+                        config.heartbeat_mut().reset_publication(publication);
+                        Ok(())
+                    })
+                    .await,
+            );
+
+            ctx.send(
+                HeartbeatPublicationMessage::Status(HeartbeatPublicationStatusMessage {
+                    status,
+                    publication: *publication,
+                })
+                .into(),
+                meta.reply(),
+            )
+            .await?;
+
+            if let Some(err) = err {
+                Err(err)
+            } else {
+                Ok(())
+            }
+        }
+        HeartbeatPublicationMessage::Status(_) => {
+            // not applicable
+            Ok(())
+        }
+    }
+}
+
+/// The configured publication, with the count reflecting the heartbeats still to be sent.
+fn current_publication(config: &ProvisionedConfiguration) -> HeartbeatPublication {
+    let mut publication = *config.foundation().configuration().heartbeat_publication();
+    publication.count_log = value_to_log(config.heartbeat().publication_count());
+    publication
+}
@@ -0,0 +1,63 @@
+use crate::storage::provisioned::heartbeat::HeartbeatSubscription;
+use crate::{BackingStore, DriverError, Storage};
+use btmesh_device::{BluetoothMeshModelContext, InboundMetadata};
+use btmesh_models::foundation::configuration::heartbeat_subscription::{
+    HeartbeatSubscriptionMessage, HeartbeatSubscriptionStatusMessage,
+};
+use btmesh_models::foundation::configuration::ConfigurationServer;
+use btmesh_models::Status;
+
+pub async fn dispatch<C: BluetoothMeshModelContext<ConfigurationServer>, B: BackingStore>(
+    ctx: &C,
+    storage: &Storage<B>,
+    message: &HeartbeatSubscriptionMessage,
+    meta: &InboundMetadata,
+) -> Result<(), DriverError> {
+    let subscription = match message {
+        HeartbeatSubscriptionMessage::Get => {
+            storage
+                .read_provisioned(|config| Ok(config.heartbeat().subscription().clone()))
+                .await?
+        }
+        HeartbeatSubscriptionMessage::Set(set) => {
+            let mut subscription = Default::default();
+            storage
+                .modify_provisioned(|config| {
+                    let current = config.heartbeat_mut().subscription_mut();
+                    current.set(set.source, set.destination, set.period_log);
+                    subscription = current.clone();
+                    Ok(())
+                })
+                .await?;
+            subscription
+        }
+        HeartbeatSubscriptionMessage::Status(_) => {
+            // not applicable
+            return Ok(());
+        }
+    };
+
+    respond(ctx, &subscription, meta).await
+}
+
+async fn respond<C: BluetoothMeshModelContext<ConfigurationServer>>(
+    ctx: &C,
+    subscription: &HeartbeatSubscription,
+    meta: &InboundMetadata,
+) -> Result<(), DriverError> {
+    ctx.send(
+        HeartbeatSubscriptionMessage::Status(HeartbeatSubscriptionStatusMessage {
+            status: Status::Success,
+            source: subscription.source(),
+            destination: subscription.destination(),
+            period_log: subscription.period_log(),
+            count_log: subscription.count_log(),
+            min_hops: subscription.min_hops(),
+            max_hops: subscription.max_hops(),
+        })
+        .into(),
+        meta.reply(),
+    )
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,73 @@
+use crate::{BackingStore, DriverError, Storage};
+use btmesh_device::{BluetoothMeshModelContext, InboundMetadata};
+use btmesh_models::foundation::configuration::key_refresh_phase::{
+    KeyRefreshPhase, KeyRefreshPhaseMessage, KeyRefreshPhaseStatusMessage,
+};
+use btmesh_models::foundation::configuration::{ConfigurationServer, NetKeyIndex};
+use btmesh_models::Status;
+
+pub async fn dispatch<C: BluetoothMeshModelContext<ConfigurationServer>, B: BackingStore>(
+    ctx: &C,
+    storage: &Storage<B>,
+    message: &KeyRefreshPhaseMessage,
+    meta: &InboundMetadata,
+) -> Result<(), DriverError> {
+    let (net_key_index, result) = match message {
+        KeyRefreshPhaseMessage::Get(net_key_index) => {
+            let result = storage
+                .read_provisioned(|config| config.secrets().key_refresh_phase(*net_key_index))
+                .await;
+            (*net_key_index, result)
+        }
+        KeyRefreshPhaseMessage::Set(set) => {
+            let mut phase = KeyRefreshPhase::Normal;
+            let result = storage
+                .modify_provisioned(|config| {
+                    phase = config
+                        .secrets_mut()
+                        .key_refresh_transition(set.net_key_index, set.transition)?;
+                    Ok(())
+                })
+                .await
+                .map(|_| phase);
+            (set.net_key_index, result)
+        }
+        KeyRefreshPhaseMessage::Status(_) => {
+            // not applicable
+            return Ok(());
+        }
+    };
+
+    respond(ctx, net_key_index, result, meta).await
+}
+
+async fn respond<C: BluetoothMeshModelContext<ConfigurationServer>>(
+    ctx: &C,
+    net_key_index: NetKeyIndex,
+    result: Result<KeyRefreshPhase, DriverError>,
+    meta: &InboundMetadata,
+) -> Result<(), DriverError> {
+    let (status, phase, err) = match result {
+        Ok(phase) => (Status::Success, phase, None),
+        Err(err) => {
+            let (status, err) = (&err).into();
+            (status, KeyRefreshPhase::Normal, err)
+        }
+    };
+
+    ctx.send(
+        KeyRefreshPhaseMessage::Status(KeyRefreshPhaseStatusMessage {
+            status,
+            net_key_index,
+            phase,
+        })
+        .into(),
+        meta.reply(),
+    )
+    .await?;
+
+    if let Some(err) = err {
+        return Err(err);
+    }
+    Ok(())
+}
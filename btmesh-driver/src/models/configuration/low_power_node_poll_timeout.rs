@@ -0,0 +1,65 @@
+use crate::DriverError;
+use btmesh_common::address::UnicastAddress;
+use btmesh_device::{BluetoothMeshModelContext, InboundMetadata};
+use btmesh_models::foundation::configuration::low_power_node_poll_timeout::{
+    LowPowerNodePollTimeoutMessage, LowPowerNodePollTimeoutStatusMessage, POLL_TIMEOUT_MAX,
+};
+use btmesh_models::foundation::configuration::ConfigurationServer;
+use core::cell::RefCell;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_time::Instant;
+use heapless::Vec;
+
+type PollDeadlines = Vec<(UnicastAddress, Instant), 4>;
+
+/// Deadlines of the friendships established with Low Power nodes, as last
+/// published by the driver, which keeps the stack to itself.
+static POLL_DEADLINES: Mutex<CriticalSectionRawMutex, RefCell<PollDeadlines>> =
+    Mutex::new(RefCell::new(Vec::new()));
+
+pub(crate) fn publish_poll_deadlines(deadlines: PollDeadlines) {
+    POLL_DEADLINES.lock(|current| *current.borrow_mut() = deadlines);
+}
+
+/// Current PollTimeout timer of a befriended Low Power node, in units of 100 milliseconds,
+/// zero if not befriended.
+fn poll_timeout(lpn_address: UnicastAddress, now: Instant) -> u32 {
+    POLL_DEADLINES.lock(|deadlines| {
+        deadlines
+            .borrow()
+            .iter()
+            .find(|(address, _)| *address == lpn_address)
+            .map(|(_, deadline)| {
+                let remaining = deadline.saturating_duration_since(now);
+                (remaining.as_millis() / 100).min(POLL_TIMEOUT_MAX as u64) as u32
+            })
+            .unwrap_or(0)
+    })
+}
+
+pub async fn dispatch<C: BluetoothMeshModelContext<ConfigurationServer>>(
+    ctx: &C,
+    message: &LowPowerNodePollTimeoutMessage,
+    meta: &InboundMetadata,
+) -> Result<(), DriverError> {
+    match message {
+        LowPowerNodePollTimeoutMessage::Get(lpn_address) => {
+            let poll_timeout = poll_timeout(*lpn_address, Instant::now());
+
+            ctx.send(
+                LowPowerNodePollTimeoutMessage::Status(LowPowerNodePollTimeoutStatusMessage {
+                    lpn_address: *lpn_address,
+                    poll_timeout,
+                })
+                .into(),
+                meta.reply(),
+            )
+            .await?;
+        }
+        LowPowerNodePollTimeoutMessage::Status(_) => {
+            // not applicable
+        }
+    }
+    Ok(())
+}
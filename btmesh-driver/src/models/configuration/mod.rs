@@ -8,9 +8,18 @@ pub mod app_key;
 pub mod beacon;
 pub mod composition_data;
 pub mod default_ttl;
+pub mod friend;
+pub mod gatt_proxy;
+pub mod heartbeat_publication;
+pub mod heartbeat_subscription;
+pub mod key_refresh_phase;
+pub mod low_power_node_poll_timeout;
 pub mod model_app;
 pub mod model_publication;
 pub mod model_subscription;
+pub mod net_key;
+pub mod network_transmit;
+pub mod node_identity;
 pub mod node_reset;
 pub mod relay;
 
@@ -50,6 +59,31 @@ impl<'s, B: BackingStore + 's> BluetoothMeshModel<ConfigurationServer> for Confi
                             .await
                             .map_err(|_| ())?;
                     }
+                    ConfigurationMessage::NetworkTransmit(network_transmit) => {
+                        network_transmit::dispatch(&ctx, self.storage, network_transmit, &meta)
+                            .await
+                            .map_err(|_| ())?;
+                    }
+                    ConfigurationMessage::GattProxy(gatt_proxy) => {
+                        gatt_proxy::dispatch(&ctx, self.storage, gatt_proxy, &meta)
+                            .await
+                            .map_err(|_| ())?;
+                    }
+                    ConfigurationMessage::Friend(friend) => {
+                        friend::dispatch(&ctx, self.storage, friend, &meta)
+                            .await
+                            .map_err(|_| ())?;
+                    }
+                    ConfigurationMessage::LowPowerNodePollTimeout(poll_timeout) => {
+                        low_power_node_poll_timeout::dispatch(&ctx, poll_timeout, &meta)
+                            .await
+                            .map_err(|_| ())?;
+                    }
+                    ConfigurationMessage::NodeIdentity(node_identity) => {
+                        node_identity::dispatch(&ctx, self.storage, node_identity, &meta)
+                            .await
+                            .map_err(|_| ())?;
+                    }
                     ConfigurationMessage::CompositionData(composition_data) => {
                         composition_data::dispatch(&ctx, self.storage, composition_data, &meta)
                             .await
@@ -60,6 +94,16 @@ impl<'s, B: BackingStore + 's> BluetoothMeshModel<ConfigurationServer> for Confi
                             .await
                             .map_err(|_| ())?;
                     }
+                    ConfigurationMessage::NetKey(net_key) => {
+                        net_key::dispatch(&ctx, self.storage, net_key, &meta)
+                            .await
+                            .map_err(|_| ())?;
+                    }
+                    ConfigurationMessage::KeyRefreshPhase(key_refresh_phase) => {
+                        key_refresh_phase::dispatch(&ctx, self.storage, key_refresh_phase, &meta)
+                            .await
+                            .map_err(|_| ())?;
+                    }
                     ConfigurationMessage::ModelApp(model_app) => {
                         model_app::dispatch(&ctx, self.storage, model_app, &meta)
                             .await
@@ -80,6 +124,26 @@ impl<'s, B: BackingStore + 's> BluetoothMeshModel<ConfigurationServer> for Confi
                             .await
                             .map_err(|_| ())?;
                     }
+                    ConfigurationMessage::HeartbeatPublication(heartbeat_publication) => {
+                        heartbeat_publication::dispatch(
+                            &ctx,
+                            self.storage,
+                            heartbeat_publication,
+                            &meta,
+                        )
+                        .await
+                        .map_err(|_| ())?;
+                    }
+                    ConfigurationMessage::HeartbeatSubscription(heartbeat_subscription) => {
+                        heartbeat_subscription::dispatch(
+                            &ctx,
+                            self.storage,
+                            heartbeat_subscription,
+                            &meta,
+                        )
+                        .await
+                        .map_err(|_| ())?;
+                    }
                 }
             }
         }
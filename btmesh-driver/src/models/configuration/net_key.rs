@@ -0,0 +1,119 @@
+use crate::models::configuration::convert;
+use crate::{BackingStore, DriverError, Storage};
+use btmesh_device::{BluetoothMeshModelContext, InboundMetadata};
+use btmesh_models::foundation::configuration::net_key::{
+    NetKeyListMessage, NetKeyMessage, NetKeyStatusMessage,
+};
+use btmesh_models::foundation::configuration::ConfigurationServer;
+
+pub async fn dispatch<C: BluetoothMeshModelContext<ConfigurationServer>, B: BackingStore>(
+    ctx: &C,
+    storage: &Storage<B>,
+    message: &NetKeyMessage,
+    meta: &InboundMetadata,
+) -> Result<(), DriverError> {
+    match message {
+        NetKeyMessage::Add(add) => {
+            info!("net-key add");
+            let (status, err) = convert(
+                &storage
+                    .modify_provisioned(|config| {
+                        config
+                            .secrets_mut()
+                            .add_network_key(add.net_key_index(), add.net_key())?;
+                        Ok(())
+                    })
+                    .await,
+            );
+
+            ctx.send(
+                NetKeyMessage::Status(NetKeyStatusMessage {
+                    status,
+                    net_key_index: add.net_key_index(),
+                })
+                .into(),
+                meta.reply(),
+            )
+            .await?;
+
+            if let Some(err) = err {
+                return Err(err);
+            }
+        }
+        NetKeyMessage::Update(update) => {
+            info!("net-key update");
+            let (status, err) = convert(
+                &storage
+                    .modify_provisioned(|config| {
+                        config
+                            .secrets_mut()
+                            .update_network_key(update.net_key_index(), update.net_key())?;
+                        Ok(())
+                    })
+                    .await,
+            );
+
+            ctx.send(
+                NetKeyMessage::Status(NetKeyStatusMessage {
+                    status,
+                    net_key_index: update.net_key_index(),
+                })
+                .into(),
+                meta.reply(),
+            )
+            .await?;
+
+            if let Some(err) = err {
+                return Err(err);
+            }
+        }
+        NetKeyMessage::Delete(delete) => {
+            info!("net-key delete");
+            let (status, err) = convert(
+                &storage
+                    .modify_provisioned(|config| {
+                        // the key securing this very request may not be removed.
+                        if meta.network_key_handle().index() == delete.net_key_index() {
+                            return Err(DriverError::CannotRemove);
+                        }
+                        config
+                            .secrets_mut()
+                            .delete_network_key(delete.net_key_index())?;
+                        Ok(())
+                    })
+                    .await,
+            );
+
+            ctx.send(
+                NetKeyMessage::Status(NetKeyStatusMessage {
+                    status,
+                    net_key_index: delete.net_key_index(),
+                })
+                .into(),
+                meta.reply(),
+            )
+            .await?;
+
+            if let Some(err) = err {
+                return Err(err);
+            }
+        }
+        NetKeyMessage::Get => {
+            info!("net-key get");
+            let net_key_indexes = storage
+                .read_provisioned(|config| Ok(config.secrets().network_key_indexes().collect()))
+                .await?;
+
+            ctx.send(
+                NetKeyMessage::List(NetKeyListMessage { net_key_indexes }).into(),
+                meta.reply(),
+            )
+            .await?;
+        }
+        NetKeyMessage::List(_) | NetKeyMessage::Status(_) => {
+            // not applicable
+        }
+    }
+
+    Ok(())
+}
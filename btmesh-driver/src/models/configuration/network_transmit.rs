@@ -0,0 +1,47 @@
+use crate::{BackingStore, DriverError, Storage};
+use btmesh_device::{BluetoothMeshModelContext, InboundMetadata};
+use btmesh_models::foundation::configuration::network_transmit::NetworkTransmitMessage;
+use btmesh_models::foundation::configuration::ConfigurationServer;
+
+pub async fn dispatch<C: BluetoothMeshModelContext<ConfigurationServer>, B: BackingStore>(
+    ctx: &C,
+    storage: &Storage<B>,
+    message: &NetworkTransmitMessage,
+    meta: &InboundMetadata,
+) -> Result<(), DriverError> {
+    match message {
+        NetworkTransmitMessage::Get => {
+            let network_transmit = storage
+                .read_provisioned(|config| {
+                    Ok(*config.foundation().configuration().network_transmit())
+                })
+                .await?;
+
+            ctx.send(
+                NetworkTransmitMessage::Status(network_transmit).into(),
+                meta.reply(),
+            )
+            .await?;
+        }
+        NetworkTransmitMessage::Set(network_transmit) => {
+            storage
+                .modify_provisioned(|config| {
+                    *config
+                        .foundation_mut()
+                        .configuration_mut()
+                        .network_transmit_mut() = *network_transmit;
+                    Ok(())
+                })
+                .await?;
+            ctx.send(
+                NetworkTransmitMessage::Status(*network_transmit).into(),
+                meta.reply(),
+            )
+            .await?;
+        }
+        NetworkTransmitMessage::Status(_) => {
+            // not applicable
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use crate::storage::provisioned::ProvisionedConfiguration;
+use crate::{BackingStore, DriverError, Storage};
+use btmesh_device::{BluetoothMeshModelContext, InboundMetadata};
+use btmesh_models::foundation::configuration::gatt_proxy::GattProxy;
+use btmesh_models::foundation::configuration::node_identity::{
+    NodeIdentity, NodeIdentityMessage, NodeIdentityStatusMessage,
+};
+use btmesh_models::foundation::configuration::{ConfigurationServer, NetKeyIndex};
+use btmesh_models::Status;
+
+pub async fn dispatch<C: BluetoothMeshModelContext<ConfigurationServer>, B: BackingStore>(
+    ctx: &C,
+    storage: &Storage<B>,
+    message: &NodeIdentityMessage,
+    meta: &InboundMetadata,
+) -> Result<(), DriverError> {
+    let (net_key_index, result) = match message {
+        NodeIdentityMessage::Get(net_key_index) => {
+            let result = storage
+                .read_provisioned(|config| node_identity(config, *net_key_index))
+                .await;
+            (*net_key_index, result)
+        }
+        NodeIdentityMessage::Set(set) => {
+            let mut identity = NodeIdentity::NotSupported;
+            let result = storage
+                .modify_provisioned(|config| {
+                    if node_identity(config, set.net_key_index)? != NodeIdentity::NotSupported {
+                        let identities = config.node_identities_mut();
+                        match set.identity {
+                            NodeIdentity::Running => identities.start(set.net_key_index),
+                            _ => identities.stop(set.net_key_index),
+                        }
+                    }
+                    identity = node_identity(config, set.net_key_index)?;
+                    Ok(())
+                })
+                .await
+                .map(|_| identity);
+            (set.net_key_index, result)
+        }
+        NodeIdentityMessage::Status(_) => {
+            // not applicable
+            return Ok(());
+        }
+    };
+
+    respond(ctx, net_key_index, result, meta).await
+}
+
+fn node_identity(
+    config: &ProvisionedConfiguration,
+    net_key_index: NetKeyIndex,
+) -> Result<NodeIdentity, DriverError> {
+    config.secrets().network_key_by_index(net_key_index)?;
+
+    // advertising the node identity is part of the proxy feature.
+    if config.foundation().configuration().gatt_proxy() == GattProxy::NotSupported {
+        Ok(NodeIdentity::NotSupported)
+    } else if config.node_identities().is_running(net_key_index) {
+        Ok(NodeIdentity::Running)
+    } else {
+        Ok(NodeIdentity::Stopped)
+    }
+}
+
+async fn respond<C: BluetoothMeshModelContext<ConfigurationServer>>(
+    ctx: &C,
+    net_key_index: NetKeyIndex,
+    result: Result<NodeIdentity, DriverError>,
+    meta: &InboundMetadata,
+) -> Result<(), DriverError> {
+    let (status, identity, err) = match result {
+        Ok(identity) => (Status::Success, identity, None),
+        Err(err) => {
+            let (status, err) = (&err).into();
+            (status, NodeIdentity::Stopped, err)
+        }
+    };
+
+    ctx.send(
+        NodeIdentityMessage::Status(NodeIdentityStatusMessage {
+            status,
+            net_key_index,
+            identity,
+        })
+        .into(),
+        meta.reply(),
+    )
+    .await?;
+
+    if let Some(err) = err {
+        return Err(err);
+    }
+    Ok(())
+}
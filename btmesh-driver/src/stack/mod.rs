@@ -1,8 +1,11 @@
 use crate::stack::unprovisioned::UnprovisionedStack;
 use crate::util::deadline::DeadlineFuture;
 use crate::{DeviceState, ProvisionedStack, Sequence};
+use btmesh_common::address::UnicastAddress;
 use btmesh_common::Uuid;
 use core::future::{pending, Future};
+use embassy_time::{Instant, Timer};
+use heapless::Vec;
 
 pub mod provisioned;
 pub mod unprovisioned;
@@ -40,6 +43,62 @@ impl Stack {
         }
     }
 
+    pub fn next_heartbeat(&self) -> Option<Timer> {
+        match self {
+            Stack::Provisioned { stack, .. } => stack.next_heartbeat(),
+            _ => None,
+        }
+    }
+
+    pub fn next_network_retransmit(&self) -> Option<Timer> {
+        match self {
+            Stack::Provisioned { stack, .. } => stack.next_network_retransmit(),
+            _ => None,
+        }
+    }
+
+    #[cfg(any(feature = "relay", feature = "proxy"))]
+    pub fn next_relay(&self) -> Option<Timer> {
+        match self {
+            Stack::Provisioned { stack, .. } => stack.next_relay(),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "friend")]
+    pub fn next_friend(&self) -> Option<Timer> {
+        match self {
+            Stack::Provisioned { stack, .. } => stack.next_friend(),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "low_power")]
+    pub fn next_low_power(&self) -> Option<Timer> {
+        match self {
+            Stack::Provisioned { stack, .. } => stack.next_low_power(),
+            _ => None,
+        }
+    }
+
+    /// Whether to scan for PDUs, which a Low Power node only does while awaiting its friend.
+    pub fn is_scanning(&self) -> bool {
+        match self {
+            #[cfg(feature = "low_power")]
+            Stack::Provisioned { stack, .. } => stack.is_scanning(),
+            _ => true,
+        }
+    }
+
+    /// Deadlines of the friendships established with Low Power nodes.
+    pub fn lpn_poll_deadlines<const N: usize>(&self) -> Vec<(UnicastAddress, Instant), N> {
+        match self {
+            #[cfg(feature = "friend")]
+            Stack::Provisioned { stack, .. } => stack.lpn_poll_deadlines(),
+            _ => Vec::new(),
+        }
+    }
+
     pub fn next_retransmit(&self) -> Option<impl Future<Output = ()> + '_> {
         if let Stack::None = self {
             return None;
@@ -0,0 +1,173 @@
+use crate::stack::provisioned::sequence::Sequence;
+use crate::stack::provisioned::ProvisionedStack;
+use crate::storage::provisioned::ProvisionedConfiguration;
+use crate::DriverError;
+use btmesh_models::foundation::configuration::NetKeyIndex;
+use btmesh_pdu::provisioned::beacon::SecureNetworkBeacon;
+use embassy_time::{Duration, Instant};
+use heapless::Vec;
+
+/// Interval between two secure network beacons for a subnet.
+const SECURE_NETWORK_BEACON_INTERVAL: Duration = Duration::from_secs(10);
+
+impl ProvisionedStack {
+    /// Secure network beacons for every subnet, if due and enabled by the Beacon state.
+    pub fn process_outbound_secure_network_beacons(
+        &mut self,
+        config: &ProvisionedConfiguration,
+    ) -> Result<Vec<SecureNetworkBeacon, 4>, DriverError> {
+        let mut beacons = Vec::new();
+        if !config.foundation().configuration().beacon() {
+            return Ok(beacons);
+        }
+
+        let now = Instant::now();
+        if matches!(self.next_secure_network_beacon, Some(next) if next > now) {
+            return Ok(beacons);
+        }
+        self.next_secure_network_beacon
+            .replace(now + SECURE_NETWORK_BEACON_INTERVAL);
+
+        let iv_index_state = &self.network_state.iv_index_state;
+        for (net_key_index, network_key) in config.secrets().network_keys_iter() {
+            let beacon = SecureNetworkBeacon::new(
+                config.secrets().key_refresh_flag(net_key_index)?,
+                iv_index_state.iv_update_flag,
+                network_key.network_id(),
+                iv_index_state.iv_index,
+                &network_key.beacon_key()?,
+            )?;
+            beacons.push(beacon).ok();
+        }
+        Ok(beacons)
+    }
+
+    /// Authenticate a secure network beacon against the keys of the known subnets,
+    /// and act upon its flags.
+    ///
+    /// Beacons of unknown subnets, or failing authentication, are ignored. The IV Index
+    /// is taken from beacons of the primary subnet, or of any subnet if the node
+    /// is not part of the primary subnet.
+    pub fn process_inbound_secure_network_beacon(
+        &mut self,
+        config: &mut ProvisionedConfiguration,
+        sequence: &Sequence,
+        beacon: &SecureNetworkBeacon,
+    ) -> Result<(), DriverError> {
+        let authenticated = config.secrets().network_keys_receive_iter().find_map(
+            |(net_key_index, network_key, is_updated)| {
+                if network_key.network_id() != beacon.network_id {
+                    return None;
+                }
+                match network_key.beacon_key() {
+                    Ok(beacon_key) if beacon.authenticate(&beacon_key) => {
+                        Some((net_key_index, is_updated))
+                    }
+                    _ => None,
+                }
+            },
+        );
+
+        if let Some((net_key_index, is_updated)) = authenticated {
+            debug!(
+                "secure network beacon for net_key_index {}: iv_index {} {}",
+                net_key_index, beacon.iv_index, beacon.iv_update_flag
+            );
+            config.secrets_mut().receive_key_refresh_flag(
+                net_key_index,
+                beacon.key_refresh_flag,
+                is_updated,
+            )?;
+
+            let primary = NetKeyIndex::new(0);
+            if net_key_index == primary || config.secrets().network_key_by_index(primary).is_err() {
+                self.receive_iv_index(config, sequence, beacon.iv_index, beacon.iv_update_flag);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::provisioned::fixture::config;
+    use btmesh_common::crypto::network::NetworkKey;
+    use btmesh_common::{IvIndex, IvUpdateFlag, KeyRefreshFlag, Seq};
+    use btmesh_models::foundation::configuration::key_refresh_phase::KeyRefreshPhase;
+
+    fn beacon(flag: KeyRefreshFlag, network_key: &NetworkKey) -> SecureNetworkBeacon {
+        SecureNetworkBeacon::new(
+            flag,
+            IvUpdateFlag::Normal,
+            network_key.network_id(),
+            IvIndex::new(0),
+            &network_key.beacon_key().unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn key_refresh_driven_by_beacons() {
+        let old = NetworkKey::new([0x01; 16]).unwrap();
+        let new = NetworkKey::new([0x02; 16]).unwrap();
+        let index = NetKeyIndex::new(0);
+
+        let mut config = config(old);
+        config.secrets_mut().update_network_key(index, new).unwrap();
+        let mut stack = ProvisionedStack::from(&config);
+        let sequence = Sequence::new(Seq::new(0));
+
+        // only a beacon secured with the new key moves phase 1 to phase 2.
+        stack
+            .process_inbound_secure_network_beacon(
+                &mut config,
+                &sequence,
+                &beacon(KeyRefreshFlag(true), &old),
+            )
+            .unwrap();
+        assert_eq!(
+            KeyRefreshPhase::Phase1,
+            config.secrets().key_refresh_phase(index).unwrap()
+        );
+        stack
+            .process_inbound_secure_network_beacon(
+                &mut config,
+                &sequence,
+                &beacon(KeyRefreshFlag(true), &new),
+            )
+            .unwrap();
+        assert_eq!(
+            KeyRefreshPhase::Phase2,
+            config.secrets().key_refresh_phase(index).unwrap()
+        );
+
+        // a beacon secured with a key that is not known is ignored.
+        let other = NetworkKey::new([0x03; 16]).unwrap();
+        stack
+            .process_inbound_secure_network_beacon(
+                &mut config,
+                &sequence,
+                &beacon(KeyRefreshFlag(false), &other),
+            )
+            .unwrap();
+        assert_eq!(
+            KeyRefreshPhase::Phase2,
+            config.secrets().key_refresh_phase(index).unwrap()
+        );
+
+        // the flag cleared by the new key completes the procedure.
+        stack
+            .process_inbound_secure_network_beacon(
+                &mut config,
+                &sequence,
+                &beacon(KeyRefreshFlag(false), &new),
+            )
+            .unwrap();
+        assert_eq!(
+            KeyRefreshPhase::Normal,
+            config.secrets().key_refresh_phase(index).unwrap()
+        );
+        assert_eq!(0, config.secrets().network_keys_by_nid(old.nid()).count());
+    }
+}
@@ -0,0 +1,832 @@
+use crate::stack::provisioned::sequence::Sequence;
+use crate::stack::provisioned::system::ControlMetadata;
+use crate::stack::provisioned::ProvisionedStack;
+use crate::storage::provisioned::ProvisionedConfiguration;
+use crate::DriverError;
+use btmesh_common::address::{Address, GroupAddress, UnicastAddress};
+use btmesh_common::crypto::network::NetworkKey;
+use btmesh_common::{InsufficientBuffer, IvIndex, Ttl};
+use btmesh_device::NetworkKeyHandle;
+use btmesh_pdu::provisioned::control::ControlMessage;
+use btmesh_pdu::provisioned::friend::{
+    FriendClear, FriendOffer, FriendPoll, FriendRequest, FriendSubscriptionList,
+    FriendSubscriptionListConfirm, FriendUpdate,
+};
+use btmesh_pdu::provisioned::network::{CleartextNetworkPDU, NetworkPDU};
+use btmesh_pdu::provisioned::upper::control::ControlOpcode;
+use embassy_time::{Duration, Instant, Timer};
+use heapless::{Deque, Vec};
+
+/// Low Power nodes befriended at once.
+const MAX_FRIENDSHIPS: usize = 2;
+
+/// Network PDUs stored on behalf of each Low Power node.
+pub const FRIEND_QUEUE_SIZE: usize = 16;
+
+/// Addresses in the friend subscription list of each Low Power node.
+pub const FRIEND_SUBSCRIPTION_LIST_SIZE: usize = 8;
+
+/// Time the Low Power node is asked to listen for a response, in milliseconds.
+const RECEIVE_WINDOW: u8 = 255;
+
+/// Time within which a Low Power node has to poll once offered a friendship.
+const OFFER_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Shortest delay before answering a Friend Request.
+const MIN_OFFER_DELAY: Duration = Duration::from_millis(100);
+
+/// Initial interval between Friend Clear messages to the previous friend, doubled every time.
+const FRIEND_CLEAR_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Signal strength reported in offers, the bearer not exposing it.
+const RSSI_NOT_AVAILABLE: i8 = 0x7F;
+
+/// Friendships with Low Power nodes, offered or established.
+#[derive(Default)]
+pub struct FriendDriver {
+    friendships: Vec<Friendship, MAX_FRIENDSHIPS>,
+    friend_counter: u16,
+    clear_confirms: Vec<(UnicastAddress, NetworkKeyHandle, FriendClear), MAX_FRIENDSHIPS>,
+}
+
+struct Friendship {
+    lpn_address: UnicastAddress,
+    num_elements: u8,
+    lpn_counter: u16,
+    network_key_handle: NetworkKeyHandle,
+    credentials: NetworkKey,
+    receive_delay: Duration,
+    poll_timeout: Duration,
+    /// Offer timeout until the first poll, poll timeout afterwards.
+    deadline: Instant,
+    offer: Option<(Instant, FriendOffer)>,
+    established: bool,
+    fsn: Option<bool>,
+    response: Option<(Instant, Response)>,
+    last: Option<CleartextNetworkPDU<ProvisionedStack>>,
+    queue: Deque<CleartextNetworkPDU<ProvisionedStack>, FRIEND_QUEUE_SIZE>,
+    subscriptions: Vec<Address, FRIEND_SUBSCRIPTION_LIST_SIZE>,
+    transaction_number: Option<u8>,
+    clear: Option<FriendClearProcedure>,
+}
+
+/// What to send once the receive delay following a message of the Low Power node elapsed.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Response {
+    /// The previous response, unacknowledged by the Low Power node.
+    Repeat,
+    /// The next queued PDU, or a Friend Update if none.
+    Next,
+    Update,
+    SubscriptionListConfirm(u8),
+}
+
+/// Terminates the friendship between the Low Power node and its previous friend.
+struct FriendClearProcedure {
+    previous_address: UnicastAddress,
+    next: Instant,
+    interval: Duration,
+    until: Instant,
+}
+
+enum Outbound {
+    Offer(UnicastAddress, NetworkKeyHandle, FriendOffer),
+    Response(UnicastAddress, Response),
+    Clear(UnicastAddress, NetworkKeyHandle, FriendClear),
+    ClearConfirm(UnicastAddress, NetworkKeyHandle, FriendClear),
+}
+
+impl Friendship {
+    fn is_lpn_unicast(&self, address: Address) -> bool {
+        if let Address::Unicast(address) = address {
+            let lpn_address = u16::from(self.lpn_address);
+            let address = u16::from(address);
+            address >= lpn_address && address - lpn_address < self.num_elements as u16
+        } else {
+            false
+        }
+    }
+
+    /// Whether a network PDU is meant for the Low Power node, and not from it.
+    fn is_destined(&self, pdu: &CleartextNetworkPDU<ProvisionedStack>) -> bool {
+        if !self.established
+            || pdu.meta().network_key_handle().index() != self.network_key_handle.index()
+            || self.is_lpn_unicast(pdu.src().into())
+        {
+            return false;
+        }
+        let dst = pdu.dst();
+        self.is_lpn_unicast(dst)
+            || dst == Address::Group(GroupAddress::AllNodes)
+            || self.subscriptions.contains(&dst)
+    }
+
+    /// Queue a network PDU, dropping the oldest one when full.
+    fn enqueue(&mut self, pdu: CleartextNetworkPDU<ProvisionedStack>) {
+        if self
+            .queue
+            .iter()
+            .any(|queued| queued.src() == pdu.src() && queued.seq() == pdu.seq())
+        {
+            return;
+        }
+        if self.queue.is_full() {
+            warn!("friend queue of {} full, dropping oldest", self.lpn_address);
+            self.queue.pop_front();
+        }
+        self.queue.push_back(pdu).ok();
+    }
+
+    fn respond(&mut self, response: Response) {
+        let now = Instant::now();
+        self.deadline = now + self.poll_timeout;
+        self.response.replace((now + self.receive_delay, response));
+    }
+
+    fn next_deadline(&self) -> Instant {
+        let mut next = self.deadline;
+        for at in [
+            self.offer.map(|(at, _)| at),
+            self.response.map(|(at, _)| at),
+            self.clear.as_ref().map(|clear| clear.next),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            next = next.min(at);
+        }
+        next
+    }
+}
+
+impl FriendDriver {
+    fn friendship(&self, lpn_address: UnicastAddress) -> Option<&Friendship> {
+        self.friendships
+            .iter()
+            .find(|friendship| friendship.lpn_address == lpn_address)
+    }
+
+    fn friendship_mut(&mut self, lpn_address: UnicastAddress) -> Option<&mut Friendship> {
+        self.friendships
+            .iter_mut()
+            .find(|friendship| friendship.lpn_address == lpn_address)
+    }
+
+    fn terminate(&mut self, lpn_address: UnicastAddress) {
+        self.friendships.retain(|friendship| {
+            if friendship.lpn_address == lpn_address {
+                info!("friendship with {} terminated", lpn_address);
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    /// Terminate every friendship, the Friend feature having been disabled.
+    fn terminate_all(&mut self) {
+        for friendship in &self.friendships {
+            if friendship.established {
+                info!("friendship with {} terminated", friendship.lpn_address);
+            }
+        }
+        self.friendships.clear();
+    }
+
+    /// Terminate friendships whose Low Power node has gone silent.
+    fn expire(&mut self, now: Instant) {
+        self.friendships.retain(|friendship| {
+            if friendship.deadline <= now {
+                if friendship.established {
+                    info!("friendship with {} timed out", friendship.lpn_address);
+                }
+                false
+            } else {
+                true
+            }
+        });
+        for friendship in &mut self.friendships {
+            if matches!(&friendship.clear, Some(clear) if clear.until <= now) {
+                friendship.clear.take();
+            }
+        }
+    }
+
+    /// Store a network PDU in the friend queue of every Low Power node it is
+    /// meant for. PDUs received from the network are stored as relayed.
+    pub(crate) fn enqueue(
+        &mut self,
+        pdu: &CleartextNetworkPDU<ProvisionedStack>,
+        received: bool,
+    ) -> Result<(), InsufficientBuffer> {
+        for friendship in self.friendships.iter_mut() {
+            if !friendship.is_destined(pdu) {
+                continue;
+            }
+            if received {
+                if pdu.ttl().value() < 2 {
+                    continue;
+                }
+                if let Some(pdu) = pdu.relay()? {
+                    friendship.enqueue(pdu);
+                }
+            } else {
+                friendship.enqueue(pdu.clone());
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn next_deadline(&self) -> Option<Instant> {
+        let mut next = self.friendships.iter().map(Friendship::next_deadline).min();
+        if !self.clear_confirms.is_empty() {
+            next.replace(Instant::now());
+        }
+        next
+    }
+
+    /// Take everything due for transmission.
+    fn take_due(&mut self, now: Instant) -> Vec<Outbound, 8> {
+        let mut due = Vec::new();
+        for (dst, network_key_handle, clear) in self.clear_confirms.iter() {
+            due.push(Outbound::ClearConfirm(*dst, *network_key_handle, *clear))
+                .ok();
+        }
+        self.clear_confirms.clear();
+
+        for friendship in self.friendships.iter_mut() {
+            if let Some((at, offer)) = friendship.offer {
+                if at <= now {
+                    friendship.offer.take();
+                    due.push(Outbound::Offer(
+                        friendship.lpn_address,
+                        friendship.network_key_handle,
+                        offer,
+                    ))
+                    .ok();
+                }
+            }
+            if let Some((at, response)) = friendship.response {
+                if at <= now {
+                    friendship.response.take();
+                    due.push(Outbound::Response(friendship.lpn_address, response))
+                        .ok();
+                }
+            }
+            if let Some(clear) = &mut friendship.clear {
+                if clear.next <= now {
+                    clear.next = now + clear.interval;
+                    clear.interval *= 2;
+                    due.push(Outbound::Clear(
+                        clear.previous_address,
+                        friendship.network_key_handle,
+                        FriendClear {
+                            lpn_address: friendship.lpn_address,
+                            lpn_counter: friendship.lpn_counter,
+                        },
+                    ))
+                    .ok();
+                }
+            }
+        }
+        due
+    }
+}
+
+impl ProvisionedStack {
+    pub fn next_friend(&self) -> Option<Timer> {
+        self.friend.next_deadline().map(Timer::at)
+    }
+
+    /// Deadlines of the established friendships, past which they are terminated if not polled.
+    pub fn lpn_poll_deadlines<const N: usize>(&self) -> Vec<(UnicastAddress, Instant), N> {
+        let mut deadlines = Vec::new();
+        for friendship in self.friend.friendships.iter().filter(|f| f.established) {
+            deadlines
+                .push((friendship.lpn_address, friendship.deadline))
+                .ok();
+        }
+        deadlines
+    }
+
+    /// Decrypt a network PDU with the credentials of a friendship, these
+    /// securing the exchanges between the Low Power node and its friend.
+    pub(crate) fn try_decrypt_friend_network_pdu(
+        &self,
+        pdu: &NetworkPDU,
+        iv_index: IvIndex,
+    ) -> Option<CleartextNetworkPDU<ProvisionedStack>> {
+        self.friend
+            .friendships
+            .iter()
+            .filter(|friendship| friendship.credentials.nid() == pdu.nid())
+            .find_map(|friendship| {
+                self.try_decrypt_network_pdu_with_key(
+                    pdu,
+                    iv_index,
+                    friendship.network_key_handle,
+                    &friendship.credentials,
+                )
+                .ok()
+            })
+            .map(|mut pdu| {
+                pdu.meta_mut().friendship_credentials(true);
+                pdu
+            })
+    }
+
+    /// Act upon the friendship control messages of a Low Power node.
+    ///
+    /// Segments destined to a Low Power node are not acknowledged on its behalf.
+    pub fn process_inbound_friend_control(
+        &mut self,
+        config: &ProvisionedConfiguration,
+        message: &ControlMessage<ProvisionedStack>,
+    ) -> Result<(), DriverError> {
+        if !config.foundation().configuration().features().friend {
+            self.friend.terminate_all();
+            return Ok(());
+        }
+        let src = message.meta().src();
+        if message.opcode() != ControlOpcode::FriendRequest
+            && !config.device_info().is_local_unicast(message.meta().dst())
+        {
+            return Ok(());
+        }
+        // polls and subscription list updates are only accepted when secured
+        // with the credentials of the friendship they belong to.
+        if matches!(
+            message.opcode(),
+            ControlOpcode::FriendPoll
+                | ControlOpcode::FriendSubscriptionListAdd
+                | ControlOpcode::FriendSubscriptionListRemove
+        ) && !message.meta().is_friendship_credentials()
+        {
+            return Ok(());
+        }
+        match message.opcode() {
+            ControlOpcode::FriendRequest => {
+                if message.meta().dst() != Address::Group(GroupAddress::AllFriends) {
+                    return Ok(());
+                }
+                let request: FriendRequest = message.try_into()?;
+                self.receive_friend_request(config, message, &request)?;
+            }
+            ControlOpcode::FriendPoll => {
+                let poll: FriendPoll = message.try_into()?;
+                if let Some(friendship) = self.friend.friendship_mut(src) {
+                    let response = if !friendship.established {
+                        debug!("friendship with {} established", src);
+                        friendship.established = true;
+                        friendship.offer.take();
+                        Response::Update
+                    } else if friendship.fsn == Some(poll.fsn) {
+                        Response::Repeat
+                    } else {
+                        Response::Next
+                    };
+                    friendship.fsn.replace(poll.fsn);
+                    friendship.respond(response);
+                }
+            }
+            ControlOpcode::FriendClear => {
+                let clear: FriendClear = message.try_into()?;
+                if let Some(friendship) = self.friend.friendship(clear.lpn_address) {
+                    if clear.lpn_counter.wrapping_sub(friendship.lpn_counter) <= 255 {
+                        let network_key_handle = friendship.network_key_handle;
+                        self.friend.terminate(clear.lpn_address);
+                        self.friend
+                            .clear_confirms
+                            .push((src, network_key_handle, clear))
+                            .ok();
+                    }
+                }
+            }
+            ControlOpcode::FriendClearConfirm => {
+                let confirm: FriendClear = message.try_into()?;
+                if let Some(friendship) = self.friend.friendship_mut(confirm.lpn_address) {
+                    if matches!(&friendship.clear, Some(clear) if clear.previous_address == src) {
+                        friendship.clear.take();
+                    }
+                }
+            }
+            opcode @ (ControlOpcode::FriendSubscriptionListAdd
+            | ControlOpcode::FriendSubscriptionListRemove) => {
+                let list: FriendSubscriptionList = message.try_into()?;
+                if let Some(friendship) = self.friend.friendship_mut(src) {
+                    if !friendship.established {
+                        return Ok(());
+                    }
+                    if friendship.transaction_number != Some(list.transaction_number) {
+                        friendship
+                            .transaction_number
+                            .replace(list.transaction_number);
+                        for address in list.addresses {
+                            let subscriptions = &mut friendship.subscriptions;
+                            if opcode == ControlOpcode::FriendSubscriptionListAdd {
+                                if !subscriptions.contains(&address)
+                                    && subscriptions.push(address).is_err()
+                                {
+                                    warn!("friend subscription list of {} full", src);
+                                }
+                            } else {
+                                subscriptions.retain(|subscription| *subscription != address);
+                            }
+                        }
+                    }
+                    friendship.respond(Response::SubscriptionListConfirm(list.transaction_number));
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn receive_friend_request(
+        &mut self,
+        config: &ProvisionedConfiguration,
+        message: &ControlMessage<ProvisionedStack>,
+        request: &FriendRequest,
+    ) -> Result<(), DriverError> {
+        let lpn_address = message.meta().src();
+        // the Low Power node is starting over.
+        self.friend.terminate(lpn_address);
+
+        if (request.criteria.min_queue_size() as usize) > FRIEND_QUEUE_SIZE
+            || self.friend.friendships.is_full()
+        {
+            return Ok(());
+        }
+
+        let friend_address = config
+            .device_info()
+            .local_element_address(0)
+            .ok_or(DriverError::InvalidState)?;
+        let network_key_handle = message.meta().network_key_handle();
+        let friend_counter = self.friend.friend_counter;
+        self.friend.friend_counter = friend_counter.wrapping_add(1);
+
+        let credentials = config
+            .secrets()
+            .network_key(network_key_handle)?
+            .friendship_credentials(
+                lpn_address,
+                friend_address,
+                request.lpn_counter,
+                friend_counter,
+            )?;
+
+        // the signal strength of the request is unknown, so only the receive window counts.
+        let delay = Duration::from_millis(
+            (request.criteria.receive_window_factor() * RECEIVE_WINDOW as u32 / 10) as u64,
+        )
+        .max(MIN_OFFER_DELAY);
+        let offer_at = Instant::now() + delay;
+        let poll_timeout = Duration::from_millis(request.poll_timeout as u64 * 100);
+
+        let offer = FriendOffer {
+            receive_window: RECEIVE_WINDOW,
+            queue_size: FRIEND_QUEUE_SIZE as u8,
+            subscription_list_size: FRIEND_SUBSCRIPTION_LIST_SIZE as u8,
+            rssi: RSSI_NOT_AVAILABLE,
+            friend_counter,
+        };
+
+        let clear = match request.previous_address {
+            Some(previous_address) if previous_address != friend_address => {
+                Some(FriendClearProcedure {
+                    previous_address,
+                    next: offer_at + OFFER_TIMEOUT,
+                    interval: FRIEND_CLEAR_INTERVAL,
+                    until: offer_at + OFFER_TIMEOUT + poll_timeout,
+                })
+            }
+            _ => None,
+        };
+
+        debug!("offering friendship to {}", lpn_address);
+        self.friend
+            .friendships
+            .push(Friendship {
+                lpn_address,
+                num_elements: request.num_elements,
+                lpn_counter: request.lpn_counter,
+                network_key_handle,
+                credentials,
+                receive_delay: Duration::from_millis(request.receive_delay as u64),
+                poll_timeout,
+                deadline: offer_at + OFFER_TIMEOUT,
+                offer: Some((offer_at, offer)),
+                established: false,
+                fsn: None,
+                response: None,
+                last: None,
+                queue: Deque::new(),
+                subscriptions: Vec::new(),
+                transaction_number: None,
+                clear,
+            })
+            .ok();
+        Ok(())
+    }
+
+    /// Offers, poll responses and Friend Clear procedures which are due.
+    pub fn process_outbound_friend(
+        &mut self,
+        config: &ProvisionedConfiguration,
+        sequence: &Sequence,
+    ) -> Result<Vec<NetworkPDU, 8>, DriverError> {
+        let now = Instant::now();
+        self.friend.expire(now);
+        if !config.foundation().configuration().features().friend {
+            self.friend.terminate_all();
+        }
+
+        let src = config
+            .device_info()
+            .local_element_address(0)
+            .ok_or(DriverError::InvalidState)?;
+        let default_ttl = config.foundation().configuration().default_ttl();
+
+        let mut pdus = Vec::new();
+        for outbound in self.friend.take_due(now) {
+            let pdu = match outbound {
+                Outbound::Offer(lpn_address, network_key_handle, offer) => {
+                    let mut parameters = Vec::<u8, 6>::new();
+                    offer.emit(&mut parameters)?;
+                    let meta = ControlMetadata::new(
+                        network_key_handle,
+                        config.iv_index(),
+                        src,
+                        lpn_address.into(),
+                        Ttl::new(0),
+                    );
+                    let pdu = self.process_outbound_control_pdu(
+                        config.secrets(),
+                        sequence,
+                        ControlOpcode::FriendOffer,
+                        &parameters,
+                        meta,
+                    )?;
+                    self.encrypt_network_pdu(config.secrets(), &pdu)?
+                }
+                Outbound::Clear(dst, network_key_handle, clear)
+                | Outbound::ClearConfirm(dst, network_key_handle, clear) => {
+                    let opcode = if matches!(outbound, Outbound::Clear(..)) {
+                        ControlOpcode::FriendClear
+                    } else {
+                        ControlOpcode::FriendClearConfirm
+                    };
+                    let mut parameters = Vec::<u8, 4>::new();
+                    clear.emit(&mut parameters)?;
+                    let meta = ControlMetadata::new(
+                        network_key_handle,
+                        config.iv_index(),
+                        src,
+                        dst.into(),
+                        default_ttl,
+                    );
+                    let pdu = self.process_outbound_control_pdu(
+                        config.secrets(),
+                        sequence,
+                        opcode,
+                        &parameters,
+                        meta,
+                    )?;
+                    self.encrypt_network_pdu(config.secrets(), &pdu)?
+                }
+                Outbound::Response(lpn_address, response) => {
+                    match self.friend_response(config, sequence, src, lpn_address, response)? {
+                        Some(pdu) => pdu,
+                        None => continue,
+                    }
+                }
+            };
+            pdus.push(pdu).map_err(|_| DriverError::InsufficientSpace)?;
+        }
+        Ok(pdus)
+    }
+
+    /// Respond to the Low Power node, secured with the friendship credentials.
+    fn friend_response(
+        &mut self,
+        config: &ProvisionedConfiguration,
+        sequence: &Sequence,
+        src: UnicastAddress,
+        lpn_address: UnicastAddress,
+        response: Response,
+    ) -> Result<Option<NetworkPDU>, DriverError> {
+        let Some(friendship) = self.friend.friendship_mut(lpn_address) else {
+            return Ok(None);
+        };
+        let network_key_handle = friendship.network_key_handle;
+        let credentials = friendship.credentials;
+
+        let (pdu, is_poll_response) = match response {
+            Response::Repeat if friendship.last.is_some() => (friendship.last.clone(), true),
+            Response::Repeat | Response::Next => (friendship.queue.pop_front(), true),
+            _ => (
+                None,
+                !matches!(response, Response::SubscriptionListConfirm(_)),
+            ),
+        };
+        let md = !friendship.queue.is_empty();
+
+        let pdu = match (pdu, response) {
+            (Some(pdu), _) => pdu,
+            (None, Response::SubscriptionListConfirm(transaction_number)) => {
+                let mut parameters = Vec::<u8, 1>::new();
+                FriendSubscriptionListConfirm { transaction_number }.emit(&mut parameters)?;
+                let meta = ControlMetadata::new(
+                    network_key_handle,
+                    config.iv_index(),
+                    src,
+                    lpn_address.into(),
+                    Ttl::new(0),
+                );
+                self.process_outbound_control_pdu(
+                    config.secrets(),
+                    sequence,
+                    ControlOpcode::FriendSubscriptionListConfirm,
+                    &parameters,
+                    meta,
+                )?
+            }
+            (None, _) => {
+                let iv_index_state = self.network_state.iv_index_state;
+                let mut parameters = Vec::<u8, 6>::new();
+                FriendUpdate {
+                    key_refresh_flag: config
+                        .secrets()
+                        .key_refresh_flag(network_key_handle.index())?,
+                    iv_update_flag: iv_index_state.iv_update_flag,
+                    iv_index: iv_index_state.iv_index,
+                    md,
+                }
+                .emit(&mut parameters)?;
+                let meta = ControlMetadata::new(
+                    network_key_handle,
+                    config.iv_index(),
+                    src,
+                    lpn_address.into(),
+                    Ttl::new(0),
+                );
+                self.process_outbound_control_pdu(
+                    config.secrets(),
+                    sequence,
+                    ControlOpcode::FriendUpdate,
+                    &parameters,
+                    meta,
+                )?
+            }
+        };
+
+        let network_pdu = self.encrypt_network_pdu_with_key(&pdu, &credentials)?;
+        if is_poll_response {
+            if let Some(friendship) = self.friend.friendship_mut(lpn_address) {
+                friendship.last.replace(pdu);
+            }
+        }
+        Ok(Some(network_pdu))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stack::provisioned::system::{ControlMetadata, NetworkMetadata};
+    use crate::storage::provisioned::fixture::config;
+    use btmesh_common::crypto::network::Nid;
+    use btmesh_common::{Ctl, Ivi, Seq};
+    use btmesh_models::foundation::configuration::NetKeyIndex;
+
+    fn network_key_handle() -> NetworkKeyHandle {
+        NetworkKeyHandle::new(NetKeyIndex::new(0), Nid::new(0x68))
+    }
+
+    fn friendship() -> Friendship {
+        Friendship {
+            lpn_address: UnicastAddress::new(0x0100).unwrap(),
+            num_elements: 2,
+            lpn_counter: 0,
+            network_key_handle: network_key_handle(),
+            credentials: NetworkKey::new([0; 16]).unwrap(),
+            receive_delay: Duration::from_millis(100),
+            poll_timeout: Duration::from_secs(10),
+            deadline: Instant::now() + Duration::from_secs(10),
+            offer: None,
+            established: true,
+            fsn: None,
+            response: None,
+            last: None,
+            queue: Deque::new(),
+            subscriptions: Vec::from_slice(&[Address::Group(GroupAddress::AllRelays)]).unwrap(),
+            transaction_number: None,
+            clear: None,
+        }
+    }
+
+    fn pdu(src: u16, seq: u32, dst: Address, ttl: u8) -> CleartextNetworkPDU<ProvisionedStack> {
+        CleartextNetworkPDU::new(
+            Ivi::Zero,
+            Nid::new(0x68),
+            Ctl::Access,
+            Ttl::new(ttl),
+            Seq::new(seq),
+            UnicastAddress::new(src).unwrap(),
+            dst,
+            &[0; 8],
+            NetworkMetadata::new(IvIndex::new(0), None, network_key_handle()),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn friend_queue() {
+        let mut driver = FriendDriver::default();
+        driver.friendships.push(friendship()).ok().unwrap();
+
+        let lpn = UnicastAddress::new(0x0101).unwrap().into();
+        driver.enqueue(&pdu(0x0001, 1, lpn, 5), true).unwrap();
+        // duplicate.
+        driver.enqueue(&pdu(0x0001, 1, lpn, 5), true).unwrap();
+        // would not survive being relayed.
+        driver.enqueue(&pdu(0x0001, 2, lpn, 1), true).unwrap();
+        // from the Low Power node itself.
+        driver.enqueue(&pdu(0x0100, 3, lpn, 5), true).unwrap();
+        // not subscribed.
+        let all_proxies = Address::Group(GroupAddress::AllProxies);
+        driver
+            .enqueue(&pdu(0x0001, 4, all_proxies, 5), true)
+            .unwrap();
+        // subscribed, and originated locally.
+        let all_relays = Address::Group(GroupAddress::AllRelays);
+        driver
+            .enqueue(&pdu(0x0001, 5, all_relays, 5), false)
+            .unwrap();
+
+        let queue = &mut driver.friendships[0].queue;
+        assert_eq!(2, queue.len());
+        let first = queue.pop_front().unwrap();
+        assert_eq!(1, first.seq().value());
+        assert_eq!(4, first.ttl().value());
+        let second = queue.pop_front().unwrap();
+        assert_eq!(5, second.seq().value());
+        assert_eq!(5, second.ttl().value());
+
+        for seq in 0..FRIEND_QUEUE_SIZE as u32 + 2 {
+            driver
+                .enqueue(&pdu(0x0001, 10 + seq, lpn, 5), true)
+                .unwrap();
+        }
+        let queue = &driver.friendships[0].queue;
+        assert_eq!(FRIEND_QUEUE_SIZE, queue.len());
+        assert_eq!(12, queue.front().unwrap().seq().value());
+    }
+
+    #[test]
+    fn expire_friendships() {
+        let mut driver = FriendDriver::default();
+        driver.friendships.push(friendship()).ok().unwrap();
+        assert!(driver.next_deadline().is_some());
+
+        driver.friendships[0].respond(Response::Next);
+        assert!(driver.take_due(Instant::now()).is_empty());
+        let due = driver.take_due(Instant::now() + Duration::from_millis(100));
+        assert!(matches!(due[..], [Outbound::Response(_, Response::Next)]));
+
+        driver.expire(Instant::now() + Duration::from_secs(11));
+        assert!(driver.friendships.is_empty());
+        assert!(driver.next_deadline().is_none());
+    }
+
+    #[test]
+    fn friendship_credentials_required() {
+        let config = config(NetworkKey::new([0x01; 16]).unwrap());
+        let mut stack = ProvisionedStack::from(&config);
+        stack.friend.friendships.push(friendship()).ok().unwrap();
+
+        let mut meta = ControlMetadata::new(
+            network_key_handle(),
+            IvIndex::new(0),
+            UnicastAddress::new(0x0100).unwrap(),
+            UnicastAddress::new(0x00A1).unwrap().into(),
+            Ttl::new(0),
+        );
+        let poll = ControlMessage::new(ControlOpcode::FriendPoll, &[0x01], meta.clone()).unwrap();
+        stack
+            .process_inbound_friend_control(&config, &poll)
+            .unwrap();
+        assert!(stack.friend.friendships[0].response.is_none());
+
+        meta.friendship_credentials = true;
+        let poll = ControlMessage::new(ControlOpcode::FriendPoll, &[0x01], meta).unwrap();
+        stack
+            .process_inbound_friend_control(&config, &poll)
+            .unwrap();
+        assert!(matches!(
+            stack.friend.friendships[0].response,
+            Some((_, Response::Next))
+        ));
+    }
+}
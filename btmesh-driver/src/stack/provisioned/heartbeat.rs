@@ -0,0 +1,143 @@
+use crate::stack::provisioned::sequence::Sequence;
+use crate::stack::provisioned::system::ControlMetadata;
+use crate::stack::provisioned::ProvisionedStack;
+use crate::storage::provisioned::ProvisionedConfiguration;
+use crate::DriverError;
+use btmesh_common::address::Address;
+use btmesh_common::Features;
+use btmesh_device::{NetworkKeyHandle, PublicationRetransmission};
+use btmesh_models::foundation::configuration::heartbeat_publication::HeartbeatPublication;
+use btmesh_pdu::provisioned::control::{ControlMessage, Heartbeat};
+use btmesh_pdu::provisioned::network::NetworkPDU;
+use btmesh_pdu::provisioned::upper::control::ControlOpcode;
+use embassy_time::{Duration, Instant, Timer};
+use heapless::Vec;
+
+/// Schedules heartbeats, both periodic ones and those triggered
+/// by changes to the node's features.
+#[derive(Default)]
+pub struct HeartbeatDriver {
+    publication: Option<HeartbeatPublication>,
+    features: Option<Features>,
+    next_publication: Option<Instant>,
+    triggered: bool,
+}
+
+impl ProvisionedStack {
+    /// Pick up changes to the heartbeat publication and to the node's features.
+    pub fn reconfigure_heartbeat(&mut self, config: &mut ProvisionedConfiguration) {
+        let publication = *config.foundation().configuration().heartbeat_publication();
+        let features = config.foundation().configuration().features();
+        let heartbeat = &mut self.heartbeat;
+
+        if heartbeat.publication != Some(publication) {
+            if heartbeat.publication.is_none() {
+                // counters are not persisted, so start afresh after a reboot.
+                config.heartbeat_mut().reset_publication(&publication);
+            }
+            heartbeat.publication.replace(publication);
+            heartbeat.next_publication.take();
+        }
+
+        if heartbeat.next_publication.is_none()
+            && publication.period() != 0
+            && config.heartbeat().publication_count() != 0
+        {
+            heartbeat.next_publication.replace(Instant::now());
+        }
+
+        if let Some(previous) = heartbeat.features.replace(features) {
+            if (previous.bits() ^ features.bits()) & publication.features.bits() != 0 {
+                heartbeat.triggered = true;
+            }
+        }
+    }
+
+    pub fn next_heartbeat(&self) -> Option<Timer> {
+        if self.heartbeat.triggered {
+            Some(Timer::at(Instant::now()))
+        } else {
+            self.heartbeat.next_publication.map(Timer::at)
+        }
+    }
+
+    pub fn process_outbound_heartbeat(
+        &mut self,
+        config: &mut ProvisionedConfiguration,
+        sequence: &Sequence,
+    ) -> Result<Vec<NetworkPDU, 8>, DriverError> {
+        let publication = *config.foundation().configuration().heartbeat_publication();
+        let mut send = core::mem::take(&mut self.heartbeat.triggered);
+
+        if let Some(next) = self.heartbeat.next_publication {
+            let now = Instant::now();
+            if next <= now {
+                if config.heartbeat_mut().consume_publication() {
+                    self.heartbeat
+                        .next_publication
+                        .replace(now + Duration::from_secs(publication.period() as u64));
+                    send = true;
+                } else {
+                    self.heartbeat.next_publication.take();
+                }
+            }
+        }
+
+        if !send || publication.destination == Address::Unassigned {
+            return Ok(Vec::new());
+        }
+
+        let network_key = config
+            .secrets()
+            .network_key_by_index(publication.net_key_index)?;
+        let src = config
+            .device_info()
+            .local_element_address(0)
+            .ok_or(DriverError::InvalidState)?;
+
+        let mut parameters = Vec::<u8, 3>::new();
+        Heartbeat::new(
+            publication.ttl,
+            config.foundation().configuration().features(),
+        )
+        .emit(&mut parameters)?;
+
+        let message = ControlMessage::new(
+            ControlOpcode::Heartbeat,
+            &parameters,
+            ControlMetadata::new(
+                NetworkKeyHandle::new(publication.net_key_index, network_key.nid()),
+                config.iv_index(),
+                src,
+                publication.destination,
+                publication.ttl,
+            ),
+        )?;
+
+        self.process_outbound(
+            config.secrets(),
+            sequence,
+            &message.into(),
+            None,
+            PublicationRetransmission::None,
+        )
+    }
+
+    pub fn process_inbound_heartbeat(
+        &mut self,
+        config: &mut ProvisionedConfiguration,
+        message: &ControlMessage<ProvisionedStack>,
+    ) -> Result<(), DriverError> {
+        let heartbeat: Heartbeat = message.try_into()?;
+        let hops = heartbeat.hops(message.meta().ttl());
+
+        if config.heartbeat_mut().subscription_mut().receive(
+            message.meta().src(),
+            message.meta().dst(),
+            hops,
+        ) {
+            debug!("heartbeat from {} ({} hops)", message.meta().src(), hops);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,225 @@
+use crate::stack::provisioned::sequence::Sequence;
+use crate::stack::provisioned::{IvIndexState, ProvisionedStack};
+use crate::storage::provisioned::ProvisionedConfiguration;
+use btmesh_common::{IvIndex, IvUpdateFlag};
+use embassy_time::{Duration, Instant};
+
+/// Minimum time spent in either IV Update state before moving on, in hours.
+pub const IV_UPDATE_MIN_HOURS: u16 = 96;
+
+pub const IV_UPDATE_HOUR: Duration = Duration::from_secs(60 * 60);
+
+/// How far ahead of the current IV Index the network may have moved
+/// for the node to still recover.
+const IV_INDEX_RECOVERY_LIMIT: u32 = 42;
+
+/// Sequence number past which the node initiates an IV Update, leaving
+/// enough room to keep transmitting until the update completes.
+pub const IV_UPDATE_SEQ_THRESHOLD: u32 = 0x800000;
+
+/// A change to the IV Index state.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(::defmt::Format))]
+pub enum IvIndexUpdate {
+    /// The IV Index was incremented and an IV Update is in progress.
+    Started,
+    /// The IV Update completed, back to normal operation.
+    Completed,
+    /// The node caught up with a network which moved on without it.
+    Recovered,
+}
+
+impl IvIndexUpdate {
+    /// Whether sequence numbers start afresh with this change.
+    pub fn resets_sequence(&self) -> bool {
+        !matches!(self, Self::Started)
+    }
+}
+
+impl IvIndexState {
+    /// Account for another hour spent in the current state, returning whether
+    /// the count changed. Only reaching [`IV_UPDATE_MIN_HOURS`] matters, so the
+    /// count stops there rather than changing the persisted state every hour.
+    pub(crate) fn elapse_hour(&mut self) -> bool {
+        if self.hours_in_state < IV_UPDATE_MIN_HOURS {
+            self.hours_in_state += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Move to the given IV Index and IV Update flag, as far as the
+    /// IV Update procedure allows, returning the resulting change if any.
+    pub(crate) fn update(
+        &mut self,
+        iv_index: IvIndex,
+        iv_update_flag: IvUpdateFlag,
+    ) -> Option<IvIndexUpdate> {
+        match self.iv_update_flag {
+            IvUpdateFlag::InProgress => {
+                if iv_index != self.iv_index || iv_update_flag == IvUpdateFlag::InProgress {
+                    return None;
+                }
+            }
+            IvUpdateFlag::Normal => {
+                if iv_index.value() <= self.iv_index.value()
+                    || iv_index.value() > self.iv_index.value() + IV_INDEX_RECOVERY_LIMIT
+                {
+                    return None;
+                }
+                if iv_index.value() > self.iv_index.value() + 1
+                    || iv_update_flag == IvUpdateFlag::Normal
+                {
+                    if self.hours_in_state < IV_UPDATE_MIN_HOURS {
+                        return None;
+                    }
+                    self.enter(iv_index, iv_update_flag);
+                    return Some(IvIndexUpdate::Recovered);
+                }
+            }
+        }
+
+        if self.hours_in_state < IV_UPDATE_MIN_HOURS {
+            return None;
+        }
+        self.enter(iv_index, iv_update_flag);
+        Some(match iv_update_flag {
+            IvUpdateFlag::InProgress => IvIndexUpdate::Started,
+            IvUpdateFlag::Normal => IvIndexUpdate::Completed,
+        })
+    }
+
+    fn enter(&mut self, iv_index: IvIndex, iv_update_flag: IvUpdateFlag) {
+        self.iv_index = iv_index;
+        self.iv_update_flag = iv_update_flag;
+        self.hours_in_state = 0;
+    }
+}
+
+impl ProvisionedStack {
+    pub fn iv_update_hour_elapsed(&self) -> bool {
+        self.next_iv_update_hour <= Instant::now()
+    }
+
+    /// Account for another hour in the current IV Update state if elapsed,
+    /// initiating an IV Update when running out of sequence numbers, and
+    /// completing one in progress once allowed to.
+    ///
+    /// Returns whether the IV Index state changed, and is to be persisted.
+    pub fn process_iv_update(
+        &mut self,
+        config: &mut ProvisionedConfiguration,
+        sequence: &Sequence,
+    ) -> bool {
+        let hour_elapsed = self.iv_update_hour_elapsed();
+        if hour_elapsed {
+            self.next_iv_update_hour = Instant::now() + IV_UPDATE_HOUR;
+        }
+
+        let state = config.network_state_mut().iv_index_mut();
+        let hours_changed = hour_elapsed && state.elapse_hour();
+        let update = match state.iv_update_flag {
+            IvUpdateFlag::Normal if sequence.current() >= IV_UPDATE_SEQ_THRESHOLD => {
+                state.update(state.iv_index + 1, IvUpdateFlag::InProgress)
+            }
+            IvUpdateFlag::InProgress => state.update(state.iv_index, IvUpdateFlag::Normal),
+            _ => None,
+        };
+        let changed = hours_changed || update.is_some();
+        self.apply_iv_index_update(config, sequence, update);
+        changed
+    }
+
+    /// Act upon the IV Index and IV Update flag of an authenticated secure network beacon.
+    pub fn receive_iv_index(
+        &mut self,
+        config: &mut ProvisionedConfiguration,
+        sequence: &Sequence,
+        iv_index: IvIndex,
+        iv_update_flag: IvUpdateFlag,
+    ) {
+        let update = config
+            .network_state_mut()
+            .iv_index_mut()
+            .update(iv_index, iv_update_flag);
+        self.apply_iv_index_update(config, sequence, update);
+    }
+
+    fn apply_iv_index_update(
+        &mut self,
+        config: &mut ProvisionedConfiguration,
+        sequence: &Sequence,
+        update: Option<IvIndexUpdate>,
+    ) {
+        if let Some(update) = update {
+            info!(
+                "iv update {}: iv_index {}",
+                update,
+                config.network_state().iv_index().iv_index()
+            );
+            if update.resets_sequence() {
+                sequence.reset();
+                *config.sequence_mut() = 0;
+            }
+        }
+        self.network_state = *config.network_state();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(iv_index: u32, iv_update_flag: IvUpdateFlag) -> IvIndexState {
+        IvIndexState::new(IvIndex::new(iv_index), iv_update_flag)
+    }
+
+    #[test]
+    fn iv_update_procedure() {
+        let mut state = state(100, IvUpdateFlag::Normal);
+        assert_eq!(
+            Some(IvIndexUpdate::Started),
+            state.update(IvIndex::new(101), IvUpdateFlag::InProgress)
+        );
+        assert_eq!(IvIndex::new(100), state.transmission_iv_index());
+
+        // too early to complete.
+        assert_eq!(None, state.update(IvIndex::new(101), IvUpdateFlag::Normal));
+        for _ in 0..IV_UPDATE_MIN_HOURS {
+            assert!(state.elapse_hour());
+        }
+        // past the threshold, the state no longer changes by the hour.
+        assert!(!state.elapse_hour());
+        assert_eq!(IV_UPDATE_MIN_HOURS, state.hours_in_state);
+        assert_eq!(
+            None,
+            state.update(IvIndex::new(102), IvUpdateFlag::InProgress)
+        );
+        assert_eq!(
+            Some(IvIndexUpdate::Completed),
+            state.update(IvIndex::new(101), IvUpdateFlag::Normal)
+        );
+        assert_eq!(IvIndex::new(101), state.transmission_iv_index());
+
+        // too early to start another.
+        assert_eq!(
+            None,
+            state.update(IvIndex::new(102), IvUpdateFlag::InProgress)
+        );
+    }
+
+    #[test]
+    fn iv_index_recovery() {
+        let mut state = state(100, IvUpdateFlag::Normal);
+        assert_eq!(None, state.update(IvIndex::new(99), IvUpdateFlag::Normal));
+        assert_eq!(None, state.update(IvIndex::new(143), IvUpdateFlag::Normal));
+        assert_eq!(
+            Some(IvIndexUpdate::Recovered),
+            state.update(IvIndex::new(142), IvUpdateFlag::InProgress)
+        );
+        assert_eq!(IvUpdateFlag::InProgress, state.iv_update_flag());
+        assert!(IvIndexUpdate::Recovered.resets_sequence());
+        assert!(!IvIndexUpdate::Started.resets_sequence());
+    }
+}
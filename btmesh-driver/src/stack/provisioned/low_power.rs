@@ -0,0 +1,857 @@
+use crate::stack::provisioned::sequence::Sequence;
+use crate::stack::provisioned::system::ControlMetadata;
+use crate::stack::provisioned::ProvisionedStack;
+use crate::storage::provisioned::ProvisionedConfiguration;
+use crate::DriverError;
+use btmesh_common::address::{Address, GroupAddress, UnicastAddress};
+use btmesh_common::crypto::network::NetworkKey;
+use btmesh_common::{IvIndex, Ttl};
+use btmesh_device::NetworkKeyHandle;
+use btmesh_pdu::provisioned::control::ControlMessage;
+use btmesh_pdu::provisioned::friend::{
+    FriendCriteria, FriendOffer, FriendPoll, FriendRequest, FriendSubscriptionList,
+    FriendSubscriptionListConfirm, FriendUpdate,
+};
+use btmesh_pdu::provisioned::network::{CleartextNetworkPDU, NetworkPDU};
+use btmesh_pdu::provisioned::upper::control::ControlOpcode;
+use embassy_time::{Duration, Instant, Timer};
+use heapless::Vec;
+
+/// Addresses kept in the friend subscription list.
+const FRIEND_SUBSCRIPTION_LIST_SIZE: usize = 8;
+
+/// Delay between a Friend Request and the start of the offers.
+const OFFER_DELAY: Duration = Duration::from_millis(100);
+
+/// Time offers are listened for, following the offer delay.
+const OFFER_WINDOW: Duration = Duration::from_secs(1);
+
+/// Delay before looking for a friend again when none answered.
+const FRIEND_REQUEST_RETRY: Duration = Duration::from_secs(10);
+
+/// Unanswered repetitions of a request after which the friend is considered gone.
+const MAX_RETRIES: u8 = 3;
+
+/// Parameters of the Low Power feature.
+#[derive(Copy, Clone, Debug)]
+pub struct LowPowerConfig {
+    /// Time after which the friend terminates the friendship if not polled,
+    /// between 1 second and 96 hours.
+    pub poll_timeout: Duration,
+    /// Interval between two polls, shorter than the poll timeout.
+    pub poll_interval: Duration,
+    /// Delay between a request and the friend's response, between 10 and 255 milliseconds.
+    pub receive_delay: Duration,
+    /// Number of messages the friend is required to queue, as a power of two between 1 and 7.
+    pub min_queue_size_log: u8,
+}
+
+impl Default for LowPowerConfig {
+    fn default() -> Self {
+        Self {
+            poll_timeout: Duration::from_secs(30),
+            poll_interval: Duration::from_secs(10),
+            receive_delay: Duration::from_millis(100),
+            min_queue_size_log: 1,
+        }
+    }
+}
+
+impl LowPowerConfig {
+    fn friend_request(
+        &self,
+        previous_address: Option<UnicastAddress>,
+        num_elements: u8,
+        lpn_counter: u16,
+    ) -> Result<FriendRequest, DriverError> {
+        Ok(FriendRequest {
+            criteria: FriendCriteria::new(0, 0, self.min_queue_size_log)?,
+            receive_delay: self.receive_delay.as_millis().clamp(0x0A, 0xFF) as u8,
+            poll_timeout: (self.poll_timeout.as_millis() / 100).clamp(0x00000A, 0x34BBFF) as u32,
+            previous_address,
+            num_elements,
+            lpn_counter,
+        })
+    }
+}
+
+/// Friendship of the node, operating as a Low Power node.
+pub struct LowPowerDriver {
+    state: State,
+    lpn_counter: u16,
+    /// Friend of the last friendship, to be cleared by the next friend.
+    previous_address: Option<UnicastAddress>,
+}
+
+impl Default for LowPowerDriver {
+    fn default() -> Self {
+        Self {
+            state: State::Searching {
+                next_request: Instant::now(),
+            },
+            lpn_counter: 0,
+            previous_address: None,
+        }
+    }
+}
+
+enum State {
+    /// Without a friend, until the next Friend Request.
+    Searching {
+        next_request: Instant,
+    },
+    /// Listening for offers to a Friend Request.
+    Requested {
+        lpn_counter: u16,
+        network_key_handle: NetworkKeyHandle,
+        window: Window,
+    },
+    Befriended(Friend),
+}
+
+struct Friend {
+    address: UnicastAddress,
+    network_key_handle: NetworkKeyHandle,
+    credentials: NetworkKey,
+    receive_window: Duration,
+    established: bool,
+    fsn: bool,
+    /// Whether to poll right away, the friend having more to deliver.
+    poll_now: bool,
+    next_poll: Instant,
+    pending: Option<Pending>,
+    subscriptions: Vec<Address, FRIEND_SUBSCRIPTION_LIST_SIZE>,
+    transaction_number: u8,
+}
+
+/// A request awaiting the friend's response.
+struct Pending {
+    request: Request,
+    window: Window,
+    retries: u8,
+}
+
+#[derive(Clone)]
+enum Request {
+    Poll,
+    SubscriptionList(ControlOpcode, FriendSubscriptionList),
+}
+
+/// Time during which the node scans for a response.
+#[derive(Copy, Clone)]
+struct Window {
+    start: Instant,
+    end: Instant,
+}
+
+impl Window {
+    fn new(delay: Duration, duration: Duration) -> Self {
+        let start = Instant::now() + delay;
+        Self {
+            start,
+            end: start + duration,
+        }
+    }
+
+    fn contains(&self, now: Instant) -> bool {
+        self.start <= now && now < self.end
+    }
+
+    /// When scanning next starts or stops.
+    fn next_deadline(&self, now: Instant) -> Instant {
+        if now < self.start {
+            self.start
+        } else {
+            self.end
+        }
+    }
+}
+
+impl Friend {
+    /// The next change to the friend subscription list, if it differs from the subscriptions,
+    /// as many addresses as fit into a single message at a time.
+    fn subscription_list_update(
+        &self,
+        addresses: &[Address],
+    ) -> Option<(ControlOpcode, FriendSubscriptionList)> {
+        let mut list = FriendSubscriptionList {
+            transaction_number: self.transaction_number,
+            addresses: Vec::new(),
+        };
+        for address in addresses {
+            if !self.subscriptions.contains(address) {
+                list.addresses.push(*address).ok();
+            }
+        }
+        if !list.addresses.is_empty() {
+            return Some((ControlOpcode::FriendSubscriptionListAdd, list));
+        }
+        for address in &self.subscriptions {
+            if !addresses.contains(address) {
+                list.addresses.push(*address).ok();
+            }
+        }
+        if !list.addresses.is_empty() {
+            return Some((ControlOpcode::FriendSubscriptionListRemove, list));
+        }
+        None
+    }
+
+    fn confirm_subscription_list(&mut self, opcode: ControlOpcode, list: &FriendSubscriptionList) {
+        for address in &list.addresses {
+            if opcode == ControlOpcode::FriendSubscriptionListAdd {
+                self.subscriptions.push(*address).ok();
+            } else {
+                self.subscriptions
+                    .retain(|subscription| subscription != address);
+            }
+        }
+        self.transaction_number = self.transaction_number.wrapping_add(1);
+    }
+}
+
+impl LowPowerDriver {
+    /// Whether the node is to scan, rather than sleep until the next exchange with its friend.
+    ///
+    /// Until a friendship is established the node keeps scanning, so that it
+    /// remains reachable without a friend.
+    pub(crate) fn is_scanning(&self, now: Instant) -> bool {
+        match &self.state {
+            State::Searching { .. } | State::Requested { .. } => true,
+            State::Befriended(friend) if !friend.established => true,
+            State::Befriended(friend) => matches!(
+                &friend.pending,
+                Some(pending) if pending.window.contains(now)
+            ),
+        }
+    }
+
+    pub(crate) fn next_deadline(&self, now: Instant) -> Instant {
+        match &self.state {
+            State::Searching { next_request } => *next_request,
+            State::Requested { window, .. } => window.next_deadline(now),
+            State::Befriended(friend) => match &friend.pending {
+                Some(pending) => pending.window.next_deadline(now),
+                None if !friend.established || friend.poll_now => now,
+                None => friend.next_poll,
+            },
+        }
+    }
+
+    /// Start looking for a friend again, the friend being gone.
+    fn lose_friend(&mut self) {
+        if let State::Befriended(friend) = &self.state {
+            warn!("lost friend {}", friend.address);
+            if friend.established {
+                self.previous_address.replace(friend.address);
+            }
+        }
+        self.state = State::Searching {
+            next_request: Instant::now(),
+        };
+    }
+
+    /// A response to the pending request arrived, secured with the friendship credentials.
+    fn receive_from_friend(&mut self) {
+        if let State::Befriended(friend) = &mut self.state {
+            if let Some(Pending {
+                request: Request::Poll,
+                ..
+            }) = friend.pending
+            {
+                friend.pending.take();
+                friend.fsn = !friend.fsn;
+                // keep polling until the friend queue is drained.
+                friend.poll_now = true;
+            }
+        }
+    }
+}
+
+impl ProvisionedStack {
+    pub fn next_low_power(&self) -> Option<Timer> {
+        Some(Timer::at(self.low_power.next_deadline(Instant::now())))
+    }
+
+    pub fn is_scanning(&self) -> bool {
+        self.low_power.is_scanning(Instant::now())
+    }
+
+    /// Decrypt a network PDU with the credentials of the friendship,
+    /// these securing the responses of the friend.
+    pub(crate) fn try_decrypt_low_power_network_pdu(
+        &mut self,
+        pdu: &NetworkPDU,
+        iv_index: IvIndex,
+    ) -> Option<CleartextNetworkPDU<ProvisionedStack>> {
+        let State::Befriended(friend) = &self.low_power.state else {
+            return None;
+        };
+        if friend.credentials.nid() != pdu.nid() {
+            return None;
+        }
+        let mut result = self
+            .try_decrypt_network_pdu_with_key(
+                pdu,
+                iv_index,
+                friend.network_key_handle,
+                &friend.credentials,
+            )
+            .ok();
+        if let Some(pdu) = &mut result {
+            pdu.meta_mut().friendship_credentials(true);
+            self.low_power.receive_from_friend();
+        }
+        result
+    }
+
+    /// Act upon the offers of prospective friends and the responses of the friend.
+    pub fn process_inbound_low_power_control(
+        &mut self,
+        config: &mut ProvisionedConfiguration,
+        sequence: &Sequence,
+        message: &ControlMessage<ProvisionedStack>,
+    ) -> Result<(), DriverError> {
+        let src = message.meta().src();
+        if !config.device_info().is_local_unicast(message.meta().dst()) {
+            return Ok(());
+        }
+        // updates and subscription list confirmations are only accepted when
+        // secured with the credentials of the friendship, lest any node of the
+        // network impersonates the friend.
+        if matches!(
+            message.opcode(),
+            ControlOpcode::FriendUpdate | ControlOpcode::FriendSubscriptionListConfirm
+        ) && !message.meta().is_friendship_credentials()
+        {
+            return Ok(());
+        }
+        match message.opcode() {
+            ControlOpcode::FriendOffer => {
+                let offer: FriendOffer = message.try_into()?;
+                if let State::Requested {
+                    lpn_counter,
+                    network_key_handle,
+                    ..
+                } = self.low_power.state
+                {
+                    if message.meta().network_key_handle().index() != network_key_handle.index() {
+                        return Ok(());
+                    }
+                    let lpn_address = config
+                        .device_info()
+                        .local_element_address(0)
+                        .ok_or(DriverError::InvalidState)?;
+                    let credentials = config
+                        .secrets()
+                        .network_key(network_key_handle)?
+                        .friendship_credentials(
+                            lpn_address,
+                            src,
+                            lpn_counter,
+                            offer.friend_counter,
+                        )?;
+                    debug!("accepting friendship offered by {}", src);
+                    self.low_power.state = State::Befriended(Friend {
+                        address: src,
+                        network_key_handle,
+                        credentials,
+                        receive_window: Duration::from_millis(offer.receive_window as u64),
+                        established: false,
+                        fsn: false,
+                        poll_now: false,
+                        next_poll: Instant::now(),
+                        pending: None,
+                        subscriptions: Vec::new(),
+                        transaction_number: 0,
+                    });
+                }
+            }
+            ControlOpcode::FriendUpdate => {
+                let update: FriendUpdate = message.try_into()?;
+                if let State::Befriended(friend) = &mut self.low_power.state {
+                    if friend.address != src {
+                        return Ok(());
+                    }
+                    if !friend.established {
+                        info!("friendship with {} established", src);
+                        friend.established = true;
+                        self.low_power.previous_address.take();
+                    }
+                    friend.poll_now = update.md;
+                    self.receive_iv_index(config, sequence, update.iv_index, update.iv_update_flag);
+                }
+            }
+            ControlOpcode::FriendSubscriptionListConfirm => {
+                let confirm: FriendSubscriptionListConfirm = message.try_into()?;
+                if let State::Befriended(friend) = &mut self.low_power.state {
+                    if let Some(Pending {
+                        request: Request::SubscriptionList(opcode, list),
+                        ..
+                    }) = &friend.pending
+                    {
+                        if friend.address == src
+                            && list.transaction_number == confirm.transaction_number
+                        {
+                            let (opcode, list) = (*opcode, list.clone());
+                            friend.pending.take();
+                            friend.confirm_subscription_list(opcode, &list);
+                            // carry on with the rest of the list.
+                            friend.poll_now = true;
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Friend Requests, polls and friend subscription list updates which are due.
+    pub fn process_outbound_low_power(
+        &mut self,
+        config: &ProvisionedConfiguration,
+        sequence: &Sequence,
+        low_power: &LowPowerConfig,
+    ) -> Result<Vec<NetworkPDU, 1>, DriverError> {
+        let now = Instant::now();
+        let mut pdus = Vec::new();
+        let src = config
+            .device_info()
+            .local_element_address(0)
+            .ok_or(DriverError::InvalidState)?;
+
+        match &mut self.low_power.state {
+            State::Searching { next_request } => {
+                if *next_request > now {
+                    return Ok(pdus);
+                }
+                let Some((net_key_index, network_key)) =
+                    config.secrets().network_keys_iter().next()
+                else {
+                    return Ok(pdus);
+                };
+                let network_key_handle = NetworkKeyHandle::new(net_key_index, network_key.nid());
+                let lpn_counter = self.low_power.lpn_counter;
+                self.low_power.lpn_counter = lpn_counter.wrapping_add(1);
+
+                let mut parameters = Vec::<u8, 10>::new();
+                low_power
+                    .friend_request(
+                        self.low_power.previous_address,
+                        config.device_info().number_of_elements(),
+                        lpn_counter,
+                    )?
+                    .emit(&mut parameters)?;
+                let meta = ControlMetadata::new(
+                    network_key_handle,
+                    config.iv_index(),
+                    src,
+                    Address::Group(GroupAddress::AllFriends),
+                    Ttl::new(0),
+                );
+                let pdu = self.process_outbound_control_pdu(
+                    config.secrets(),
+                    sequence,
+                    ControlOpcode::FriendRequest,
+                    &parameters,
+                    meta,
+                )?;
+                pdus.push(self.encrypt_network_pdu(config.secrets(), &pdu)?)
+                    .map_err(|_| DriverError::InsufficientSpace)?;
+
+                debug!("looking for a friend");
+                self.low_power.state = State::Requested {
+                    lpn_counter,
+                    network_key_handle,
+                    window: Window::new(OFFER_DELAY, OFFER_WINDOW),
+                };
+            }
+            State::Requested { window, .. } => {
+                if window.end <= now {
+                    self.low_power.state = State::Searching {
+                        next_request: now + FRIEND_REQUEST_RETRY,
+                    };
+                }
+            }
+            State::Befriended(friend) => {
+                let receive_delay =
+                    Duration::from_millis(low_power.receive_delay.as_millis().clamp(0x0A, 0xFF));
+                let window = Window::new(receive_delay, friend.receive_window);
+                let request = match &mut friend.pending {
+                    Some(pending) if pending.window.end <= now => {
+                        if pending.retries == MAX_RETRIES {
+                            self.low_power.lose_friend();
+                            return Ok(pdus);
+                        }
+                        pending.retries += 1;
+                        pending.window = window;
+                        pending.request.clone()
+                    }
+                    Some(_) => return Ok(pdus),
+                    None if !friend.established || friend.poll_now || friend.next_poll <= now => {
+                        let addresses = config
+                            .subscriptions()
+                            .addresses::<FRIEND_SUBSCRIPTION_LIST_SIZE>();
+                        let request = match friend.subscription_list_update(&addresses) {
+                            Some((opcode, list)) if friend.established => {
+                                Request::SubscriptionList(opcode, list)
+                            }
+                            _ => Request::Poll,
+                        };
+                        friend.pending.replace(Pending {
+                            request: request.clone(),
+                            window,
+                            retries: 0,
+                        });
+                        friend.poll_now = false;
+                        friend.next_poll = now + low_power.poll_interval;
+                        request
+                    }
+                    None => return Ok(pdus),
+                };
+
+                let mut parameters = Vec::<u8, 11>::new();
+                let opcode = match &request {
+                    Request::Poll => {
+                        FriendPoll { fsn: friend.fsn }.emit(&mut parameters)?;
+                        ControlOpcode::FriendPoll
+                    }
+                    Request::SubscriptionList(opcode, list) => {
+                        list.emit(&mut parameters)?;
+                        *opcode
+                    }
+                };
+                let meta = ControlMetadata::new(
+                    friend.network_key_handle,
+                    config.iv_index(),
+                    src,
+                    friend.address.into(),
+                    Ttl::new(0),
+                );
+                let credentials = friend.credentials;
+                let pdu = self.process_outbound_control_pdu(
+                    config.secrets(),
+                    sequence,
+                    opcode,
+                    &parameters,
+                    meta,
+                )?;
+                pdus.push(self.encrypt_network_pdu_with_key(&pdu, &credentials)?)
+                    .map_err(|_| DriverError::InsufficientSpace)?;
+            }
+        }
+        Ok(pdus)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::provisioned::fixture::config;
+    use btmesh_common::address::LabelUuid;
+    use btmesh_common::{IvUpdateFlag, KeyRefreshFlag, Seq};
+
+    fn from_friend(
+        stack: &ProvisionedStack,
+        opcode: ControlOpcode,
+        parameters: &[u8],
+    ) -> ControlMessage<ProvisionedStack> {
+        let network_key_handle = match &stack.low_power.state {
+            State::Requested {
+                network_key_handle, ..
+            } => *network_key_handle,
+            State::Befriended(friend) => friend.network_key_handle,
+            State::Searching { .. } => panic!("no friend"),
+        };
+        let mut meta = ControlMetadata::new(
+            network_key_handle,
+            IvIndex::new(0),
+            UnicastAddress::new(0x0001).unwrap(),
+            UnicastAddress::new(0x00A1).unwrap().into(),
+            Ttl::new(0),
+        );
+        // only offers are secured with the master credentials.
+        meta.friendship_credentials = opcode != ControlOpcode::FriendOffer;
+        ControlMessage::new(opcode, parameters, meta).unwrap()
+    }
+
+    fn befriended(stack: &mut ProvisionedStack) -> &mut Friend {
+        match &mut stack.low_power.state {
+            State::Befriended(friend) => friend,
+            _ => panic!("not befriended"),
+        }
+    }
+
+    fn friend() -> Friend {
+        Friend {
+            address: UnicastAddress::new(0x0001).unwrap(),
+            network_key_handle: NetworkKeyHandle::new(
+                btmesh_models::foundation::configuration::NetKeyIndex::new(0),
+                btmesh_common::crypto::network::Nid::new(0x68),
+            ),
+            credentials: NetworkKey::new([0; 16]).unwrap(),
+            receive_window: Duration::from_millis(255),
+            established: true,
+            fsn: false,
+            poll_now: false,
+            next_poll: Instant::now(),
+            pending: None,
+            subscriptions: Vec::new(),
+            transaction_number: 0,
+        }
+    }
+
+    #[test]
+    fn friend_subscription_list() {
+        let mut friend = friend();
+        let group = Address::Group(GroupAddress::Normal(0xC000));
+        let label_uuid = LabelUuid::new([0x42; 16]).unwrap();
+        let virtual_address = Address::Virtual(label_uuid.virtual_address());
+
+        let (opcode, list) = friend
+            .subscription_list_update(&[group, virtual_address])
+            .unwrap();
+        assert_eq!(ControlOpcode::FriendSubscriptionListAdd, opcode);
+        assert_eq!(&[group, virtual_address], &*list.addresses);
+        assert_eq!(0, list.transaction_number);
+        friend.confirm_subscription_list(opcode, &list);
+        assert!(friend
+            .subscription_list_update(&[group, virtual_address])
+            .is_none());
+
+        let (opcode, list) = friend.subscription_list_update(&[virtual_address]).unwrap();
+        assert_eq!(ControlOpcode::FriendSubscriptionListRemove, opcode);
+        assert_eq!(&[group], &*list.addresses);
+        assert_eq!(1, list.transaction_number);
+        friend.confirm_subscription_list(opcode, &list);
+        assert_eq!(&[virtual_address], &*friend.subscriptions);
+    }
+
+    #[test]
+    fn friend_request() {
+        let config = LowPowerConfig {
+            poll_timeout: Duration::from_secs(60 * 60 * 100),
+            receive_delay: Duration::from_millis(5),
+            ..Default::default()
+        };
+        let request = config.friend_request(None, 2, 7).unwrap();
+        assert_eq!(0x34BBFF, request.poll_timeout);
+        assert_eq!(0x0A, request.receive_delay);
+        assert_eq!(2, request.criteria.min_queue_size());
+    }
+
+    #[test]
+    fn friendship_establishment() {
+        let mut config = config(NetworkKey::new([0x01; 16]).unwrap());
+        let mut stack = ProvisionedStack::from(&config);
+        let sequence = Sequence::new(Seq::new(0));
+        let low_power = LowPowerConfig::default();
+
+        // friendless, the node keeps scanning.
+        assert!(stack.low_power.is_scanning(Instant::now()));
+
+        let pdus = stack
+            .process_outbound_low_power(&config, &sequence, &low_power)
+            .unwrap();
+        assert_eq!(1, pdus.len());
+        assert!(matches!(stack.low_power.state, State::Requested { .. }));
+        assert!(stack.low_power.is_scanning(Instant::now()));
+
+        let mut parameters = Vec::<u8, 6>::new();
+        FriendOffer {
+            receive_window: 100,
+            queue_size: 4,
+            subscription_list_size: 8,
+            rssi: 0x7F,
+            friend_counter: 1,
+        }
+        .emit(&mut parameters)
+        .unwrap();
+        let offer = from_friend(&stack, ControlOpcode::FriendOffer, &parameters);
+        stack
+            .process_inbound_low_power_control(&mut config, &sequence, &offer)
+            .unwrap();
+        assert!(!befriended(&mut stack).established);
+        assert!(stack.low_power.is_scanning(Instant::now()));
+
+        // the first poll establishes the friendship.
+        let pdus = stack
+            .process_outbound_low_power(&config, &sequence, &low_power)
+            .unwrap();
+        assert_eq!(1, pdus.len());
+        assert!(matches!(
+            befriended(&mut stack).pending,
+            Some(Pending {
+                request: Request::Poll,
+                ..
+            })
+        ));
+
+        let mut parameters = Vec::<u8, 6>::new();
+        FriendUpdate {
+            key_refresh_flag: KeyRefreshFlag(false),
+            iv_update_flag: IvUpdateFlag::Normal,
+            iv_index: IvIndex::new(0),
+            md: false,
+        }
+        .emit(&mut parameters)
+        .unwrap();
+        let update = from_friend(&stack, ControlOpcode::FriendUpdate, &parameters);
+        stack.low_power.receive_from_friend();
+        stack
+            .process_inbound_low_power_control(&mut config, &sequence, &update)
+            .unwrap();
+        let established = befriended(&mut stack);
+        assert!(established.established);
+        assert!(established.pending.is_none());
+
+        // established, the node only scans during receive windows.
+        let now = Instant::now();
+        stack.low_power.state = State::Befriended(Friend {
+            poll_now: false,
+            next_poll: now + low_power.poll_interval,
+            ..friend()
+        });
+        assert!(!stack.low_power.is_scanning(now));
+    }
+
+    #[test]
+    fn friendship_credentials_required() {
+        let mut config = config(NetworkKey::new([0x01; 16]).unwrap());
+        let mut stack = ProvisionedStack::from(&config);
+        let sequence = Sequence::new(Seq::new(0));
+        stack.low_power.state = State::Befriended(Friend {
+            established: false,
+            ..friend()
+        });
+
+        let mut parameters = Vec::<u8, 6>::new();
+        FriendUpdate {
+            key_refresh_flag: KeyRefreshFlag(false),
+            iv_update_flag: IvUpdateFlag::InProgress,
+            iv_index: IvIndex::new(1),
+            md: true,
+        }
+        .emit(&mut parameters)
+        .unwrap();
+
+        // spoofing the address of the friend with the network credentials.
+        let update = from_friend(&stack, ControlOpcode::FriendUpdate, &parameters);
+        let mut meta = update.meta().clone();
+        meta.friendship_credentials = false;
+        let update = ControlMessage::new(ControlOpcode::FriendUpdate, &parameters, meta).unwrap();
+        stack
+            .process_inbound_low_power_control(&mut config, &sequence, &update)
+            .unwrap();
+        let friend = befriended(&mut stack);
+        assert!(!friend.established);
+        assert!(!friend.poll_now);
+        assert_eq!(
+            IvIndex::new(0),
+            config.network_state().iv_index().iv_index()
+        );
+
+        let update = from_friend(&stack, ControlOpcode::FriendUpdate, &parameters);
+        stack
+            .process_inbound_low_power_control(&mut config, &sequence, &update)
+            .unwrap();
+        let friend = befriended(&mut stack);
+        assert!(friend.established);
+        assert!(friend.poll_now);
+    }
+
+    #[test]
+    fn poll_toggles_fsn() {
+        let config = config(NetworkKey::new([0x01; 16]).unwrap());
+        let mut stack = ProvisionedStack::from(&config);
+        let sequence = Sequence::new(Seq::new(0));
+        let low_power = LowPowerConfig::default();
+        stack.low_power.state = State::Befriended(friend());
+
+        let pdus = stack
+            .process_outbound_low_power(&config, &sequence, &low_power)
+            .unwrap();
+        assert_eq!(1, pdus.len());
+        let now = Instant::now();
+        assert!(!stack.low_power.is_scanning(now));
+        assert!(stack
+            .low_power
+            .is_scanning(now + low_power.receive_delay + Duration::from_millis(1)));
+        assert!(stack
+            .process_outbound_low_power(&config, &sequence, &low_power)
+            .unwrap()
+            .is_empty());
+
+        stack.low_power.receive_from_friend();
+        let friend = befriended(&mut stack);
+        assert!(friend.fsn);
+        assert!(friend.pending.is_none());
+        // the friend may have more to deliver.
+        let now = Instant::now();
+        assert_eq!(now, stack.low_power.next_deadline(now));
+
+        stack
+            .process_outbound_low_power(&config, &sequence, &low_power)
+            .unwrap();
+        stack.low_power.receive_from_friend();
+        assert!(!befriended(&mut stack).fsn);
+    }
+
+    #[test]
+    fn unanswered_polls_lose_friend() {
+        let config = config(NetworkKey::new([0x01; 16]).unwrap());
+        let mut stack = ProvisionedStack::from(&config);
+        let sequence = Sequence::new(Seq::new(0));
+        let low_power = LowPowerConfig::default();
+        stack.low_power.state = State::Befriended(friend());
+
+        let expire = |stack: &mut ProvisionedStack| {
+            befriended(stack).pending.as_mut().unwrap().window = Window {
+                start: Instant::from_ticks(0),
+                end: Instant::from_ticks(0),
+            };
+        };
+
+        stack
+            .process_outbound_low_power(&config, &sequence, &low_power)
+            .unwrap();
+        for retries in 1..=MAX_RETRIES {
+            expire(&mut stack);
+            let pdus = stack
+                .process_outbound_low_power(&config, &sequence, &low_power)
+                .unwrap();
+            assert_eq!(1, pdus.len());
+            assert_eq!(
+                retries,
+                befriended(&mut stack).pending.as_ref().unwrap().retries
+            );
+        }
+
+        expire(&mut stack);
+        assert!(stack
+            .process_outbound_low_power(&config, &sequence, &low_power)
+            .unwrap()
+            .is_empty());
+        assert!(matches!(stack.low_power.state, State::Searching { .. }));
+        assert_eq!(
+            Some(UnicastAddress::new(0x0001).unwrap()),
+            stack.low_power.previous_address
+        );
+        assert!(stack.low_power.is_scanning(Instant::now()));
+    }
+
+    #[test]
+    fn lose_friend_before_establishment() {
+        let mut driver = LowPowerDriver {
+            state: State::Befriended(Friend {
+                established: false,
+                ..friend()
+            }),
+            ..Default::default()
+        };
+        driver.lose_friend();
+        assert!(matches!(driver.state, State::Searching { .. }));
+        assert!(driver.previous_address.is_none());
+    }
+}
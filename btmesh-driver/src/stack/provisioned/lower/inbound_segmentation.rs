@@ -1,12 +1,13 @@
 use embassy_time::{Duration, Instant};
-use heapless::FnvIndexMap;
+use heapless::{FnvIndexMap, Vec};
 
+use crate::stack::provisioned::lower::CONCURRENT_REASSEMBLIES;
 use crate::stack::provisioned::system::UpperMetadata;
 use crate::stack::provisioned::{DriverError, ProvisionedStack};
 use crate::Watchdog;
 use btmesh_common::address::UnicastAddress;
 use btmesh_common::mic::SzMic;
-use btmesh_common::SeqZero;
+use btmesh_common::{SeqZero, Ttl};
 use btmesh_pdu::provisioned::lower::access::SegmentedLowerAccessPDU;
 use btmesh_pdu::provisioned::lower::control::SegmentedLowerControlPDU;
 use btmesh_pdu::provisioned::lower::{BlockAck, SegmentedLowerPDU};
@@ -14,14 +15,27 @@ use btmesh_pdu::provisioned::upper::access::UpperAccessPDU;
 use btmesh_pdu::provisioned::upper::control::{ControlOpcode, UpperControlPDU};
 use btmesh_pdu::provisioned::upper::UpperPDU;
 
-pub struct InboundSegmentation<const N: usize = 4> {
-    current: FnvIndexMap<UnicastAddress, InFlight, N>,
+/// Time without receiving any new segment after which a reassembly is abandoned.
+const INCOMPLETE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The acknowledgment timer, 150 + 50 × TTL milliseconds.
+fn acknowledgment_interval(ttl: Ttl) -> Duration {
+    Duration::from_millis(150 + 50 * ttl.value() as u64)
+}
+
+/// Reassemblies in progress, keyed by source and `SeqZero`, each with timers of its own.
+pub struct InboundSegmentation<const N: usize = CONCURRENT_REASSEMBLIES> {
+    current: FnvIndexMap<(UnicastAddress, SeqZero), InFlight, N>,
+    /// The most recently completed reassemblies, oldest first, so that segments
+    /// retransmitted before our acknowledgment arrived are acknowledged again.
+    completed: Vec<((UnicastAddress, SeqZero), BlockAck), N>,
 }
 
 impl<const N: usize> Default for InboundSegmentation<N> {
     fn default() -> Self {
         Self {
             current: Default::default(),
+            completed: Vec::new(),
         }
     }
 }
@@ -33,32 +47,26 @@ pub struct SegmentationResult {
 }
 
 impl<const N: usize> InboundSegmentation<N> {
+    /// Handle the acknowledgment or incomplete timer of a reassembly expiring,
+    /// returning the `BlockAck` to send if it is still in progress.
     pub fn expire_inbound(
         &mut self,
+        src: &UnicastAddress,
         seq_zero: &SeqZero,
         watchdog: &Watchdog,
     ) -> Option<(BlockAck, UpperMetadata)> {
-        let result = self
-            .current
-            .values_mut()
-            .find(|e| e.seq_zero == *seq_zero)
-            .map(|in_flight| in_flight.expire());
+        let key = (*src, *seq_zero);
+        let in_flight = self.current.get_mut(&key)?;
 
-        for e in self.current.values() {
-            e.set_watchdog_expiration(watchdog);
-        }
-
-        if let Some(result) = result {
-            match result {
-                Err(src) => {
-                    self.current.remove(&src);
-                    None
-                }
-                Ok(inner @ Some(_)) => inner,
-                _ => None,
+        match in_flight.expire(Instant::now()) {
+            Err(_) => {
+                self.current.remove(&key);
+                None
+            }
+            Ok(result) => {
+                in_flight.set_watchdog_expiration(watchdog);
+                result
             }
-        } else {
-            None
         }
     }
 
@@ -66,21 +74,32 @@ impl<const N: usize> InboundSegmentation<N> {
     /// into an `UpperPDU`. If processed without error, will return a tuple
     /// containing the current `BlockAck` set and optionally the completely
     /// reassembled `UpperPDU`, if all segments have been processed.
+    ///
+    /// Incomplete reassemblies are acknowledged once their acknowledgment timer expires.
     pub fn process(
         &mut self,
         pdu: &SegmentedLowerPDU<ProvisionedStack>,
         watchdog: &Watchdog,
     ) -> Result<SegmentationResult, DriverError> {
-        let src = pdu.meta().src();
-        let in_flight = if let Some(current) = self.current.get_mut(&src) {
+        let key = (pdu.meta().src(), pdu.seq_zero());
+        if let Some((_, block_ack)) = self.completed.iter().find(|(k, _)| *k == key) {
+            return Ok(SegmentationResult {
+                block_ack: *block_ack,
+                meta: UpperMetadata::from_segmented_lower_pdu(pdu),
+                upper_pdu: None,
+            });
+        }
+
+        let in_flight = if let Some(current) = self.current.get_mut(&key) {
             current
         } else {
             let in_flight = InFlight::new(pdu);
-            in_flight.set_watchdog_expiration(watchdog);
             self.current
-                .insert(src, in_flight)
+                .insert(key, in_flight)
                 .map_err(|_| DriverError::InsufficientSpace)?;
-            self.current.get_mut(&src).unwrap()
+            // a new message from the source, its earlier ones are no longer retransmitted.
+            self.completed.retain(|(k, _)| k.0 != key.0);
+            self.current.get_mut(&key).unwrap()
         };
 
         if !in_flight.is_valid(pdu) {
@@ -94,18 +113,26 @@ impl<const N: usize> InboundSegmentation<N> {
                 upper_pdu: None,
             })
         } else {
-            in_flight.ingest(pdu)?;
+            in_flight.ingest(pdu, Instant::now())?;
+            let block_ack = in_flight.block_ack();
+            let upper_pdu = if in_flight.is_complete()? {
+                let reassembled =
+                    Some(in_flight.reassemble(UpperMetadata::from_segmented_lower_pdu(pdu))?);
+                watchdog.clear_inbound_expiration(key.0, key.1);
+                self.current.remove(&key);
+                if self.completed.is_full() {
+                    self.completed.remove(0);
+                }
+                self.completed.push((key, block_ack)).ok();
+                reassembled
+            } else {
+                in_flight.set_watchdog_expiration(watchdog);
+                None
+            };
             Ok(SegmentationResult {
-                block_ack: in_flight.block_ack(),
+                block_ack,
                 meta: UpperMetadata::from_segmented_lower_pdu(pdu),
-                upper_pdu: if in_flight.is_complete()? {
-                    let reassembled =
-                        Some(in_flight.reassemble(UpperMetadata::from_segmented_lower_pdu(pdu))?);
-                    self.current.remove(&src);
-                    reassembled
-                } else {
-                    None
-                },
+                upper_pdu,
             })
         }
     }
@@ -168,8 +195,8 @@ struct InFlight {
     blocks: Blocks,
     reassembly: Reassembly,
     meta: UpperMetadata,
-    last_ack: Instant,
-    watchdogs: u8,
+    ack_deadline: Option<Instant>,
+    incomplete_deadline: Instant,
 }
 
 impl InFlight {
@@ -193,8 +220,8 @@ impl InFlight {
             blocks: Blocks::new(seq_zero, seg_n),
             reassembly: Reassembly::new_access(szmic),
             meta,
-            last_ack: Instant::now(),
-            watchdogs: 0,
+            ack_deadline: None,
+            incomplete_deadline: Instant::now() + INCOMPLETE_TIMEOUT,
         }
     }
 
@@ -209,18 +236,25 @@ impl InFlight {
             blocks: Blocks::new(seq_zero, seg_n),
             reassembly: Reassembly::new_control(opcode),
             meta,
-            last_ack: Instant::now(),
-            watchdogs: 0,
+            ack_deadline: None,
+            incomplete_deadline: Instant::now() + INCOMPLETE_TIMEOUT,
         }
     }
 
-    fn expire(&mut self) -> Result<Option<(BlockAck, UpperMetadata)>, UnicastAddress> {
-        self.watchdogs += 1;
-        if self.watchdogs > 2 {
+    /// Check the timers against `now`, returning the `BlockAck` to send if the
+    /// acknowledgment timer expired, or the source if the incomplete timer expired
+    /// and the reassembly is to be abandoned.
+    fn expire(
+        &mut self,
+        now: Instant,
+    ) -> Result<Option<(BlockAck, UpperMetadata)>, UnicastAddress> {
+        if self.incomplete_deadline <= now {
             Err(self.meta.src())
-        } else {
-            self.last_ack = Instant::now();
+        } else if matches!(self.ack_deadline, Some(ack_deadline) if ack_deadline <= now) {
+            self.ack_deadline.take();
             Ok(Some((self.blocks.block_ack, self.meta.clone())))
+        } else {
+            Ok(None)
         }
     }
 
@@ -258,24 +292,38 @@ impl InFlight {
         self.blocks.already_seen(pdu.seg_o())
     }
 
-    /// Ingest a segment.
+    /// Ingest a segment, restarting the incomplete timer and starting the
+    /// acknowledgment timer if not already running.
     ///
     /// Returns a result of `()` or a `DriverError`.
-    fn ingest(&mut self, pdu: &SegmentedLowerPDU<ProvisionedStack>) -> Result<(), DriverError> {
+    fn ingest(
+        &mut self,
+        pdu: &SegmentedLowerPDU<ProvisionedStack>,
+        now: Instant,
+    ) -> Result<(), DriverError> {
         if !self.is_valid(pdu) {
             return Err(DriverError::InvalidPDU);
         }
-        self.last_ack = Instant::now();
+        self.incomplete_deadline = now + INCOMPLETE_TIMEOUT;
+        if self.ack_deadline.is_none() {
+            self.ack_deadline
+                .replace(now + acknowledgment_interval(self.meta.ttl()));
+        }
         self.reassembly.ingest(pdu)?;
         self.blocks.ack(pdu.seg_o())?;
         Ok(())
     }
 
+    /// The earliest of the acknowledgment and incomplete timers.
+    fn next_deadline(&self) -> Instant {
+        match self.ack_deadline {
+            Some(ack_deadline) => ack_deadline.min(self.incomplete_deadline),
+            None => self.incomplete_deadline,
+        }
+    }
+
     fn set_watchdog_expiration(&self, watchdog: &Watchdog) {
-        watchdog.inbound_expiration((
-            self.last_ack + Duration::from_millis(150 + (50 * self.meta.ttl().value() as u64)),
-            self.seq_zero,
-        ))
+        watchdog.inbound_expiration((self.next_deadline(), self.meta.src(), self.seq_zero))
     }
 
     /// Determine if all expected blocks have been processed.
@@ -300,7 +348,7 @@ impl InFlight {
 enum Reassembly {
     Access {
         szmic: SzMic,
-        data: [u8; 380],
+        data: [u8; 384],
         len: usize,
     },
     Control {
@@ -314,7 +362,7 @@ impl Reassembly {
     fn new_access(szmic: SzMic) -> Self {
         Self::Access {
             szmic,
-            data: [0; 380],
+            data: [0; 384],
             len: 0,
         }
     }
@@ -378,9 +426,14 @@ impl Reassembly {
 
 #[cfg(test)]
 mod tests {
-    use crate::stack::provisioned::lower::inbound_segmentation::{Blocks, InFlight, Reassembly};
+    use crate::stack::provisioned::lower::inbound_segmentation::{
+        acknowledgment_interval, Blocks, InFlight, InboundSegmentation, Reassembly,
+        INCOMPLETE_TIMEOUT,
+    };
+    use crate::stack::provisioned::lower::CONCURRENT_REASSEMBLIES;
     use crate::stack::provisioned::system::{LowerMetadata, UpperMetadata};
     use crate::stack::provisioned::{DriverError, ProvisionedStack};
+    use crate::Watchdog;
     use btmesh_common::address::UnicastAddress;
     use btmesh_common::crypto::network::Nid;
     use btmesh_common::mic::SzMic;
@@ -392,6 +445,7 @@ mod tests {
     use btmesh_pdu::provisioned::lower::SegmentedLowerPDU;
     use btmesh_pdu::provisioned::upper::control::ControlOpcode;
     use btmesh_pdu::provisioned::upper::UpperPDU;
+    use embassy_time::{Duration, Instant};
 
     #[test]
     fn in_flight_is_valid_seq_zero() {
@@ -591,4 +645,283 @@ mod tests {
             panic!("shouldn't happen")
         }
     }
+
+    #[test]
+    fn in_flight_timers() {
+        let network_key_handle = NetworkKeyHandle::new(NetKeyIndex::new(0), Nid::new(42));
+        let segment = |seg_o| {
+            SegmentedLowerPDU::Access(
+                SegmentedLowerAccessPDU::<ProvisionedStack>::new(
+                    None,
+                    SzMic::Bit32,
+                    SeqZero::new(42),
+                    seg_o,
+                    2,
+                    b"ABCDEFGHIJKL",
+                    LowerMetadata::new(
+                        network_key_handle,
+                        IvIndex::parse(&[1, 2, 3, 4]).unwrap(),
+                        UnicastAddress::parse([0x00, 0x0A]).unwrap(),
+                        UnicastAddress::parse([0x00, 0x0B]).unwrap().into(),
+                        Seq::parse(1001).unwrap(),
+                        Ttl::new(4),
+                    ),
+                )
+                .unwrap(),
+            )
+        };
+
+        let pdu = segment(0);
+        let mut in_flight = InFlight::new(&pdu);
+        let now = Instant::now();
+        in_flight.ingest(&pdu, now).unwrap();
+
+        // acknowledgment timer of 150 + 50 * 4 milliseconds.
+        let ack_deadline = now + Duration::from_millis(350);
+        assert_eq!(ack_deadline, in_flight.next_deadline());
+        assert!(matches!(in_flight.expire(now), Ok(None)));
+        match in_flight.expire(ack_deadline) {
+            Ok(Some((block_ack, _))) => assert_eq!(0b1, block_ack.value()),
+            _ => panic!("expected block ack"),
+        }
+
+        // not restarted until another segment arrives.
+        assert!(matches!(in_flight.expire(ack_deadline), Ok(None)));
+        let later = now + Duration::from_secs(1);
+        in_flight.ingest(&segment(1), later).unwrap();
+        assert_eq!(
+            later + Duration::from_millis(350),
+            in_flight.next_deadline()
+        );
+
+        // abandoned once no segment arrives within the incomplete timer.
+        in_flight
+            .expire(later + Duration::from_millis(350))
+            .unwrap();
+        assert_eq!(later + INCOMPLETE_TIMEOUT, in_flight.next_deadline());
+        assert!(in_flight.expire(later + INCOMPLETE_TIMEOUT).is_err());
+    }
+
+    #[test]
+    fn concurrent_reassembly() {
+        let network_key_handle = NetworkKeyHandle::new(NetKeyIndex::new(0), Nid::new(42));
+        let segment = |src, seq_zero, seg_o| {
+            SegmentedLowerPDU::Access(
+                SegmentedLowerAccessPDU::<ProvisionedStack>::new(
+                    None,
+                    SzMic::Bit32,
+                    SeqZero::new(seq_zero),
+                    seg_o,
+                    1,
+                    b"ABCDEFGHIJKL",
+                    LowerMetadata::new(
+                        network_key_handle,
+                        IvIndex::parse(&[1, 2, 3, 4]).unwrap(),
+                        UnicastAddress::new(src).unwrap(),
+                        UnicastAddress::parse([0x00, 0x0B]).unwrap().into(),
+                        Seq::parse(1001).unwrap(),
+                        Ttl::new(4),
+                    ),
+                )
+                .unwrap(),
+            )
+        };
+
+        let watchdog = Watchdog::default();
+        let mut segmentation = InboundSegmentation::<4>::default();
+
+        // two sources, one of them with two messages in flight.
+        for (src, seq_zero) in [(0x000A, 42), (0x000C, 42), (0x000A, 43)] {
+            let result = segmentation
+                .process(&segment(src, seq_zero, 0), &watchdog)
+                .unwrap();
+            assert!(result.upper_pdu.is_none());
+        }
+        assert_eq!(3, segmentation.current.len());
+
+        for (src, seq_zero) in [(0x000C, 42), (0x000A, 43), (0x000A, 42)] {
+            let result = segmentation
+                .process(&segment(src, seq_zero, 1), &watchdog)
+                .unwrap();
+            assert!(result.upper_pdu.is_some());
+            assert_eq!(0b11, result.block_ack.value());
+            assert!(SeqZero::new(seq_zero) == result.block_ack.seq_zero());
+        }
+        assert!(segmentation.current.is_empty());
+    }
+
+    #[test]
+    fn acknowledgment_interval_per_ttl() {
+        assert_eq!(
+            Duration::from_millis(150),
+            acknowledgment_interval(Ttl::new(0))
+        );
+        assert_eq!(
+            Duration::from_millis(6500),
+            acknowledgment_interval(Ttl::new(127))
+        );
+    }
+
+    #[test]
+    fn completed_reassembly_acknowledged_at_once() {
+        let network_key_handle = NetworkKeyHandle::new(NetKeyIndex::new(0), Nid::new(42));
+        let segment = |seg_o| {
+            SegmentedLowerPDU::Access(
+                SegmentedLowerAccessPDU::<ProvisionedStack>::new(
+                    None,
+                    SzMic::Bit32,
+                    SeqZero::new(42),
+                    seg_o,
+                    1,
+                    b"ABCDEFGHIJKL",
+                    LowerMetadata::new(
+                        network_key_handle,
+                        IvIndex::parse(&[1, 2, 3, 4]).unwrap(),
+                        UnicastAddress::parse([0x00, 0x0A]).unwrap(),
+                        UnicastAddress::parse([0x00, 0x0B]).unwrap().into(),
+                        Seq::parse(1001).unwrap(),
+                        Ttl::new(4),
+                    ),
+                )
+                .unwrap(),
+            )
+        };
+
+        let watchdog = Watchdog::default();
+        let mut segmentation = InboundSegmentation::<4>::default();
+
+        let result = segmentation.process(&segment(0), &watchdog).unwrap();
+        assert!(result.upper_pdu.is_none());
+        let (_, in_flight) = segmentation.current.first().unwrap();
+        assert!(in_flight.ack_deadline.is_some());
+
+        // the last segment completes the message, without waiting for the acknowledgment timer.
+        let result = segmentation.process(&segment(1), &watchdog).unwrap();
+        assert!(result.upper_pdu.is_some());
+        assert_eq!(0b11, result.block_ack.value());
+        assert!(segmentation.current.is_empty());
+    }
+
+    fn segment(src: u16, seq_zero: u16, seg_o: u8) -> SegmentedLowerPDU<ProvisionedStack> {
+        SegmentedLowerPDU::Access(
+            SegmentedLowerAccessPDU::<ProvisionedStack>::new(
+                None,
+                SzMic::Bit32,
+                SeqZero::new(seq_zero),
+                seg_o,
+                1,
+                b"ABCDEFGHIJKL",
+                LowerMetadata::new(
+                    NetworkKeyHandle::new(NetKeyIndex::new(0), Nid::new(42)),
+                    IvIndex::parse(&[1, 2, 3, 4]).unwrap(),
+                    UnicastAddress::new(src).unwrap(),
+                    UnicastAddress::parse([0x00, 0x0B]).unwrap().into(),
+                    Seq::parse(1001).unwrap(),
+                    Ttl::new(4),
+                ),
+            )
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn interleaved_reassemblies_expire_independently() {
+        let watchdog = Watchdog::default();
+        let mut segmentation = InboundSegmentation::<4>::default();
+        let first = (UnicastAddress::new(0x000A).unwrap(), SeqZero::new(42));
+        let second = (UnicastAddress::new(0x000C).unwrap(), SeqZero::new(42));
+
+        segmentation
+            .process(&segment(0x000A, 42, 0), &watchdog)
+            .unwrap();
+        segmentation
+            .process(&segment(0x000C, 42, 1), &watchdog)
+            .unwrap();
+
+        // the first one is due for acknowledgment, the second one is not yet.
+        segmentation.current.get_mut(&first).unwrap().ack_deadline = Some(Instant::from_ticks(0));
+        assert!(segmentation
+            .expire_inbound(&second.0, &second.1, &watchdog)
+            .is_none());
+        let (block_ack, meta) = segmentation
+            .expire_inbound(&first.0, &first.1, &watchdog)
+            .unwrap();
+        assert_eq!(0b01, block_ack.value());
+        assert_eq!(first.0, meta.src());
+
+        // the second one is abandoned, leaving the first one in progress.
+        segmentation
+            .current
+            .get_mut(&second)
+            .unwrap()
+            .incomplete_deadline = Instant::from_ticks(0);
+        assert!(segmentation
+            .expire_inbound(&second.0, &second.1, &watchdog)
+            .is_none());
+        assert!(!segmentation.current.contains_key(&second));
+
+        let result = segmentation
+            .process(&segment(0x000A, 42, 1), &watchdog)
+            .unwrap();
+        assert!(result.upper_pdu.is_some());
+        assert_eq!(0b11, result.block_ack.value());
+    }
+
+    #[test]
+    fn late_segments_of_completed_reassembly() {
+        let watchdog = Watchdog::default();
+        let mut segmentation = InboundSegmentation::<4>::default();
+
+        segmentation
+            .process(&segment(0x000A, 42, 0), &watchdog)
+            .unwrap();
+        let result = segmentation
+            .process(&segment(0x000A, 42, 1), &watchdog)
+            .unwrap();
+        assert!(result.upper_pdu.is_some());
+
+        // retransmitted before our acknowledgment arrived, acknowledged again without a reassembly.
+        for seg_o in [0, 1] {
+            let result = segmentation
+                .process(&segment(0x000A, 42, seg_o), &watchdog)
+                .unwrap();
+            assert!(result.upper_pdu.is_none());
+            assert_eq!(0b11, result.block_ack.value());
+            assert!(SeqZero::new(42) == result.block_ack.seq_zero());
+            assert!(segmentation.current.is_empty());
+        }
+
+        // the next message from the source is reassembled as usual.
+        let result = segmentation
+            .process(&segment(0x000A, 43, 0), &watchdog)
+            .unwrap();
+        assert_eq!(0b01, result.block_ack.value());
+        assert_eq!(1, segmentation.current.len());
+        assert!(segmentation.completed.is_empty());
+    }
+
+    #[test]
+    fn too_many_concurrent_reassemblies() {
+        let watchdog = Watchdog::default();
+        let mut segmentation = InboundSegmentation::<CONCURRENT_REASSEMBLIES>::default();
+
+        for src in 0..CONCURRENT_REASSEMBLIES as u16 {
+            segmentation
+                .process(&segment(0x0010 + src, 42, 0), &watchdog)
+                .unwrap();
+        }
+        assert!(matches!(
+            segmentation.process(&segment(0x0020, 42, 0), &watchdog),
+            Err(DriverError::InsufficientSpace)
+        ));
+
+        // segments of the reassemblies in progress are still accepted.
+        let result = segmentation
+            .process(&segment(0x0010, 42, 1), &watchdog)
+            .unwrap();
+        assert!(result.upper_pdu.is_some());
+        assert!(segmentation
+            .process(&segment(0x0020, 42, 0), &watchdog)
+            .is_ok());
+    }
 }
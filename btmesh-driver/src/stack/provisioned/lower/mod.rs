@@ -1,6 +1,8 @@
 mod inbound_segmentation;
 mod outbound_segmentation;
 
+pub(crate) use outbound_segmentation::select_szmic;
+
 use crate::stack::provisioned::lower::inbound_segmentation::InboundSegmentation;
 use crate::stack::provisioned::lower::outbound_segmentation::OutboundSegmentation;
 use crate::stack::provisioned::sequence::Sequence;
@@ -10,7 +12,7 @@ use crate::storage::provisioned::subscriptions::Subscriptions;
 use crate::{DriverError, Secrets, Watchdog};
 use btmesh_common::address::UnicastAddress;
 use btmesh_common::mic::SzMic;
-use btmesh_common::{InsufficientBuffer, SeqZero};
+use btmesh_common::SeqZero;
 use btmesh_pdu::provisioned::lower::{BlockAck, LowerPDU, UnsegmentedLowerPDU};
 use btmesh_pdu::provisioned::network::{CleartextNetworkPDU, NetworkPDU};
 use btmesh_pdu::provisioned::upper::access::UpperAccessPDU;
@@ -18,6 +20,9 @@ use btmesh_pdu::provisioned::upper::control::{ControlOpcode, UpperControlPDU};
 use btmesh_pdu::provisioned::upper::UpperPDU;
 use heapless::Vec;
 
+/// Inbound segmented messages which may be reassembled at once.
+pub(crate) const CONCURRENT_REASSEMBLIES: usize = 4;
+
 #[derive(Default)]
 pub struct LowerDriver {
     inbound_segmentation: InboundSegmentation,
@@ -27,10 +32,12 @@ pub struct LowerDriver {
 impl LowerDriver {
     pub fn expire_inbound(
         &mut self,
+        src: &UnicastAddress,
         seq_zero: &SeqZero,
         watchdog: &Watchdog,
     ) -> Option<(BlockAck, UpperMetadata)> {
-        self.inbound_segmentation.expire_inbound(seq_zero, watchdog)
+        self.inbound_segmentation
+            .expire_inbound(src, seq_zero, watchdog)
     }
 }
 
@@ -121,10 +128,11 @@ impl ProvisionedStack {
         sequence: &Sequence,
         upper_pdu: &UpperPDU<ProvisionedStack>,
         is_retransmit: bool,
+        acked: Option<&BlockAck>,
     ) -> Result<Vec<CleartextNetworkPDU<ProvisionedStack>, N>, DriverError> {
         self.lower
             .outbound_segmentation
-            .process(sequence, upper_pdu, is_retransmit)
+            .process(sequence, upper_pdu, is_retransmit, acked)
     }
 }
 
@@ -133,7 +141,7 @@ fn block_ack_to_upper_pdu(
     block_ack: BlockAck,
     meta: &UpperMetadata,
     src: &UnicastAddress,
-) -> Result<UpperControlPDU<ProvisionedStack>, InsufficientBuffer> {
+) -> Result<UpperControlPDU<ProvisionedStack>, DriverError> {
     let mut parameters = [0; 6];
 
     let seq_zero = ((block_ack.seq_zero().value() & 0b0111111111111111) << 2).to_be_bytes();
@@ -151,14 +159,19 @@ fn block_ack_to_upper_pdu(
         iv_index: meta.iv_index(),
         local_element_index: None,
         akf_aid: meta.aid(),
-        seq: sequence.next(),
+        seq: sequence.next()?,
         src: *src,
         dst: meta.src().into(),
         ttl: meta.ttl(),
         label_uuids: Vec::from_slice(meta.label_uuids())?,
         seq_auth: None,
         replay_seq: None,
+        friendship_credentials: false,
     };
 
-    UpperControlPDU::new(ControlOpcode::SegmentAcknowledgement, &parameters, meta)
+    Ok(UpperControlPDU::new(
+        ControlOpcode::SegmentAcknowledgement,
+        &parameters,
+        meta,
+    )?)
 }
@@ -6,6 +6,7 @@ use btmesh_common::mic::SzMic;
 use btmesh_common::{Ctl, InsufficientBuffer};
 use btmesh_pdu::provisioned::lower::access::{SegmentedLowerAccessPDU, UnsegmentedLowerAccessPDU};
 use btmesh_pdu::provisioned::lower::control::UnsegmentedLowerControlPDU;
+use btmesh_pdu::provisioned::lower::BlockAck;
 use btmesh_pdu::provisioned::network::CleartextNetworkPDU;
 use btmesh_pdu::provisioned::upper::control::UpperControlPDU;
 use btmesh_pdu::provisioned::upper::UpperPDU;
@@ -13,28 +14,52 @@ use heapless::Vec;
 
 const SEGMENTED_ACCESS_MTU: usize = 12;
 const NONSEGMENTED_ACCESS_MUT: usize = 15;
+/// Largest upper transport access PDU, 32 segments.
+const MAX_UPPER_ACCESS_PDU: usize = 32 * SEGMENTED_ACCESS_MTU;
 
 const SEGMENT_LOWER_PDU_SIZE: usize = SEGMENTED_ACCESS_MTU + 4;
 
+/// Choose the size of TransMIC for an access payload of `len` octets: the 64-bit TransMIC
+/// when the payload is segmented regardless and it takes no additional segment,
+/// otherwise the 32-bit TransMIC.
+pub(crate) fn select_szmic(len: usize) -> SzMic {
+    let segments =
+        |szmic: SzMic| (len + szmic.size() + SEGMENTED_ACCESS_MTU - 1) / SEGMENTED_ACCESS_MTU;
+
+    if len + SzMic::Bit32.size() > NONSEGMENTED_ACCESS_MUT
+        && len + SzMic::Bit64.size() <= MAX_UPPER_ACCESS_PDU
+        && segments(SzMic::Bit64) == segments(SzMic::Bit32)
+    {
+        SzMic::Bit64
+    } else {
+        SzMic::Bit32
+    }
+}
+
 #[derive(Default)]
 pub struct OutboundSegmentation {}
 
 impl OutboundSegmentation {
+    /// Segment an `UpperPDU` if required, omitting any segments already
+    /// acknowledged when retransmitting.
     pub fn process<const N: usize>(
         &mut self,
         sequence: &Sequence,
         pdu: &UpperPDU<ProvisionedStack>,
         is_retransmit: bool,
+        acked: Option<&BlockAck>,
     ) -> Result<Vec<CleartextNetworkPDU<ProvisionedStack>, N>, DriverError> {
         let meta = NetworkMetadata::from_upper_pdu(pdu);
         let mut result = Vec::new();
 
         match pdu {
             UpperPDU::Access(inner) => {
-                let mut payload = Vec::<_, 380>::new();
+                let mut payload = Vec::<_, MAX_UPPER_ACCESS_PDU>::new();
                 inner.emit(&mut payload)?;
+                let szmic = inner.transmic().szmic();
 
-                if payload.len() <= NONSEGMENTED_ACCESS_MUT {
+                // the 64-bit TransMIC is only signalled by segmented PDUs.
+                if payload.len() <= NONSEGMENTED_ACCESS_MUT && szmic == SzMic::Bit32 {
                     let lower_pdu =
                         UnsegmentedLowerAccessPDU::<()>::new(inner.meta().aid(), &payload, ())?;
 
@@ -62,16 +87,22 @@ impl OutboundSegmentation {
                     let seg_n = payload.len() - 1;
 
                     for (seg_o, segment_m) in payload.enumerate() {
+                        if let Some(acked) = acked {
+                            if acked.is_acked(seg_o as u8)? {
+                                continue;
+                            }
+                        }
+
                         let seq = if !is_retransmit && seg_o == 0 {
                             pdu.meta().seq()
                         } else {
-                            sequence.next()
+                            sequence.next()?
                         };
 
                         // it's just a pass-through, so the `()`-centric System is perfectly good.
                         let lower_pdu = SegmentedLowerAccessPDU::<()>::new(
                             pdu.meta().aid(),
-                            SzMic::Bit32,
+                            szmic,
                             seq_zero,
                             seg_o as u8,
                             seg_n as u8,
@@ -125,7 +156,7 @@ impl OutboundSegmentation {
             pdu.meta().network_key_handle().nid(),
             Ctl::Control,
             pdu.meta().ttl(),
-            sequence.next(),
+            sequence.next()?,
             pdu.meta().src(),
             pdu.meta().dst(),
             &transport_pdu,
@@ -133,3 +164,24 @@ impl OutboundSegmentation {
         )?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::select_szmic;
+    use btmesh_common::mic::SzMic;
+
+    #[test]
+    fn szmic_selection() {
+        // unsegmented
+        assert_eq!(SzMic::Bit32, select_szmic(11));
+        // segmented, with room for the larger TransMIC
+        assert_eq!(SzMic::Bit64, select_szmic(12));
+        assert_eq!(SzMic::Bit64, select_szmic(16));
+        // segmented, but an additional segment would be required
+        assert_eq!(SzMic::Bit32, select_szmic(17));
+        assert_eq!(SzMic::Bit32, select_szmic(20));
+        assert_eq!(SzMic::Bit64, select_szmic(25));
+        // the largest payloads only fit with the smaller TransMIC
+        assert_eq!(SzMic::Bit32, select_szmic(380));
+    }
+}
@@ -1,32 +1,60 @@
+#[cfg(feature = "friend")]
+use crate::stack::provisioned::friend::FriendDriver;
+use crate::stack::provisioned::heartbeat::HeartbeatDriver;
+use crate::stack::provisioned::iv_update::{IV_UPDATE_HOUR, IV_UPDATE_MIN_HOURS};
+#[cfg(feature = "low_power")]
+use crate::stack::provisioned::low_power::LowPowerDriver;
 use crate::stack::provisioned::lower::LowerDriver;
+use crate::stack::provisioned::network::replay_protection::ReplayProtection;
 use crate::stack::provisioned::network::{DeviceInfo, NetworkDriver};
+use crate::stack::provisioned::network_transmit_queue::NetworkTransmitQueue;
+#[cfg(any(feature = "relay", feature = "proxy"))]
+use crate::stack::provisioned::relay_queue::RelayQueue;
 use crate::stack::provisioned::sequence::Sequence;
+#[cfg(any(feature = "friend", feature = "low_power"))]
+use crate::stack::provisioned::system::ControlMetadata;
 use crate::stack::provisioned::transmit_queue::TransmitQueue;
 use crate::stack::provisioned::upper::UpperDriver;
-use crate::storage::provisioned::subscriptions::Subscriptions;
 use crate::storage::provisioned::ProvisionedConfiguration;
 use crate::{DriverError, UpperMetadata, Watchdog};
+use btmesh_common::mic::SzMic;
 use btmesh_common::{IvIndex, IvUpdateFlag, Ivi, SeqZero};
+use btmesh_models::foundation::configuration::network_transmit::NetworkTransmitConfig;
+#[cfg(any(feature = "relay", feature = "proxy"))]
+use btmesh_models::foundation::configuration::relay::RelayConfig;
 use btmesh_pdu::provisioned::lower::BlockAck;
 use btmesh_pdu::provisioned::network::{CleartextNetworkPDU, NetworkPDU};
 use btmesh_pdu::provisioned::Message;
 use btmesh_pdu::provisioning::ProvisioningData;
 use core::cmp::Ordering;
 use core::future::Future;
-use embassy_time::{Duration, Timer};
+use embassy_time::{Duration, Instant, Timer};
 use heapless::Vec;
 use secrets::Secrets;
 
 use crate::util::deadline::{Deadline, DeadlineFuture};
-use btmesh_common::address::{Address, UnicastAddress};
-use btmesh_device::CompletionToken;
+use btmesh_common::address::{Address, GroupAddress, UnicastAddress};
+use btmesh_device::{CompletionToken, PublicationRetransmission};
 use btmesh_pdu::provisioned::control::ControlMessage;
 use btmesh_pdu::provisioned::upper::control::ControlOpcode;
+use btmesh_pdu::provisioned::upper::UpperPDU;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+pub mod beacon;
+#[cfg(feature = "friend")]
+pub mod friend;
+pub mod heartbeat;
+pub mod iv_update;
+#[cfg(feature = "low_power")]
+pub mod low_power;
 pub mod lower;
 pub mod network;
+pub mod network_transmit_queue;
+pub mod provisioning;
+pub mod proxy;
+#[cfg(any(feature = "relay", feature = "proxy"))]
+pub mod relay_queue;
 pub mod secrets;
 pub mod sequence;
 pub mod system;
@@ -39,6 +67,8 @@ pub mod upper;
 pub struct IvIndexState {
     iv_index: IvIndex,
     iv_update_flag: IvUpdateFlag,
+    /// Hours spent in the current IV Update state.
+    hours_in_state: u16,
 }
 
 impl IvIndexState {
@@ -46,9 +76,20 @@ impl IvIndexState {
         Self {
             iv_index,
             iv_update_flag,
+            // how long the network has been in this state is unknown,
+            // so do not hold back the first transition.
+            hours_in_state: IV_UPDATE_MIN_HOURS,
         }
     }
 
+    pub fn iv_index(&self) -> IvIndex {
+        self.iv_index
+    }
+
+    pub fn iv_update_flag(&self) -> IvUpdateFlag {
+        self.iv_update_flag
+    }
+
     pub fn accepted_iv_index(&self, ivi: Ivi) -> IvIndex {
         self.iv_index.accepted_iv_index(ivi)
     }
@@ -69,6 +110,7 @@ impl NetworkState {
     pub fn display(&self) {
         info!("iv_index: {}", self.iv_index_state.iv_index);
         info!("iv_update_flag: {}", self.iv_index_state.iv_update_flag);
+        info!("hours_in_state: {}", self.iv_index_state.hours_in_state);
     }
 
     pub fn new(iv_index: IvIndex, iv_update_flag: IvUpdateFlag) -> Self {
@@ -80,6 +122,10 @@ impl NetworkState {
     pub fn iv_index(&self) -> &IvIndexState {
         &self.iv_index_state
     }
+
+    pub fn iv_index_mut(&mut self) -> &mut IvIndexState {
+        &mut self.iv_index_state
+    }
 }
 
 impl From<ProvisioningData> for NetworkState {
@@ -92,10 +138,7 @@ impl From<ProvisioningData> for NetworkState {
 
 impl From<ProvisioningData> for IvIndexState {
     fn from(data: ProvisioningData) -> Self {
-        Self {
-            iv_index: IvIndex::new(data.iv_index),
-            iv_update_flag: data.iv_update_flag,
-        }
+        Self::new(IvIndex::new(data.iv_index), data.iv_update_flag)
     }
 }
 
@@ -106,7 +149,17 @@ pub struct ProvisionedStack {
     network: NetworkDriver,
     //
     transmit_queue: TransmitQueue,
+    network_transmit_queue: NetworkTransmitQueue,
+    #[cfg(any(feature = "relay", feature = "proxy"))]
+    relay_queue: RelayQueue,
     beacon: Deadline,
+    next_secure_network_beacon: Option<Instant>,
+    next_iv_update_hour: Instant,
+    heartbeat: HeartbeatDriver,
+    #[cfg(feature = "friend")]
+    friend: FriendDriver,
+    #[cfg(feature = "low_power")]
+    low_power: LowPowerDriver,
 }
 
 impl From<&ProvisionedConfiguration> for ProvisionedStack {
@@ -117,7 +170,17 @@ impl From<&ProvisionedConfiguration> for ProvisionedStack {
             lower: Default::default(),
             network: NetworkDriver::new(*content.device_info()),
             transmit_queue: Default::default(),
+            network_transmit_queue: Default::default(),
+            #[cfg(any(feature = "relay", feature = "proxy"))]
+            relay_queue: Default::default(),
             beacon: Deadline::new(Duration::from_secs(3), true),
+            next_secure_network_beacon: None,
+            next_iv_update_hour: Instant::now() + IV_UPDATE_HOUR,
+            heartbeat: Default::default(),
+            #[cfg(feature = "friend")]
+            friend: Default::default(),
+            #[cfg(feature = "low_power")]
+            low_power: Default::default(),
         }
     }
 }
@@ -125,6 +188,7 @@ impl From<&ProvisionedConfiguration> for ProvisionedStack {
 pub struct ReceiveResult {
     pub block_ack: Option<(BlockAck, UpperMetadata)>,
     pub message: Option<Message<ProvisionedStack>>,
+    pub src: UnicastAddress,
     pub dst: Address,
 }
 
@@ -132,6 +196,7 @@ impl
     TryFrom<(
         Option<(BlockAck, UpperMetadata)>,
         Option<Message<ProvisionedStack>>,
+        UnicastAddress,
         Address,
     )> for ReceiveResult
 {
@@ -141,15 +206,17 @@ impl
         value: (
             Option<(BlockAck, UpperMetadata)>,
             Option<Message<ProvisionedStack>>,
+            UnicastAddress,
             Address,
         ),
     ) -> Result<Self, Self::Error> {
         match value {
-            (None, None, _) => Err(()),
+            (None, None, _, _) => Err(()),
             _ => Ok(ReceiveResult {
                 block_ack: value.0,
                 message: value.1,
-                dst: value.2,
+                src: value.2,
+                dst: value.3,
             }),
         }
     }
@@ -163,7 +230,17 @@ impl ProvisionedStack {
             lower: Default::default(),
             network: NetworkDriver::new(device_info),
             transmit_queue: Default::default(),
+            network_transmit_queue: Default::default(),
+            #[cfg(any(feature = "relay", feature = "proxy"))]
+            relay_queue: Default::default(),
             beacon: Deadline::new(Duration::from_secs(3), true),
+            next_secure_network_beacon: None,
+            next_iv_update_hour: Instant::now() + IV_UPDATE_HOUR,
+            heartbeat: Default::default(),
+            #[cfg(feature = "friend")]
+            friend: Default::default(),
+            #[cfg(feature = "low_power")]
+            low_power: Default::default(),
         }
     }
 
@@ -184,7 +261,7 @@ impl ProvisionedStack {
     }
 
     pub fn next_retransmit(&self) -> Option<impl Future<Output = ()>> {
-        Some(Timer::after(Duration::from_millis(200)))
+        self.transmit_queue.next_deadline().map(Timer::at)
     }
 
     pub fn retransmit(
@@ -196,9 +273,9 @@ impl ProvisionedStack {
 
         let upper_pdus: Vec<_, 8> = self.transmit_queue.iter().collect();
 
-        for upper_pdu in upper_pdus {
+        for (upper_pdu, acked) in upper_pdus {
             for network_pdu in self
-                .process_outbound_upper_pdu::<8>(sequence, &upper_pdu, true)?
+                .process_outbound_upper_pdu::<8>(sequence, &upper_pdu, true, acked.as_ref())?
                 .iter()
                 .map_while(|pdu| self.encrypt_network_pdu(secrets, pdu).ok())
             {
@@ -209,13 +286,29 @@ impl ProvisionedStack {
         Ok(pdus)
     }
 
+    /// Whether pdus sent to `dst` are delivered to this node: those sent to its
+    /// elements, to the fixed group addresses, or to group and virtual addresses
+    /// subscribed to by its models or its heartbeat subscription. Tracking the
+    /// sources of any other traffic would only fill the replay protection list.
+    fn is_delivered(&self, config: &ProvisionedConfiguration, dst: Address) -> bool {
+        match dst {
+            Address::Unicast(_) => self.device_info().is_local_unicast(dst),
+            Address::Group(GroupAddress::Normal(_)) | Address::Virtual(_) => {
+                config.subscriptions().matches(dst)
+                    || config.heartbeat().subscription().destination() == dst
+            }
+            Address::Group(GroupAddress::RFU(_)) | Address::Unassigned => false,
+            Address::Group(_) => true,
+        }
+    }
+
     pub fn process_inbound_network_pdu(
         &mut self,
-        secrets: &Secrets,
+        config: &ProvisionedConfiguration,
         network_pdu: &NetworkPDU,
         watchdog: &Watchdog,
         is_loopback: bool,
-        subscriptions: &Subscriptions,
+        replay_protection: &mut ReplayProtection,
     ) -> Result<
         (
             Option<CleartextNetworkPDU<ProvisionedStack>>,
@@ -223,6 +316,8 @@ impl ProvisionedStack {
         ),
         DriverError,
     > {
+        let secrets = config.secrets();
+        let subscriptions = config.subscriptions();
         let iv_index = self
             .network_state
             .iv_index_state
@@ -238,11 +333,13 @@ impl ProvisionedStack {
             {
                 return Ok((None, None));
             }
-            if cleartext_network_pdu.meta().is_replay_protected() {
-                return Ok((None, None));
+
+            #[cfg(feature = "friend")]
+            if !is_loopback {
+                self.friend.enqueue(&cleartext_network_pdu, true)?;
             }
 
-            #[cfg(feature = "relay")]
+            #[cfg(any(feature = "relay", feature = "proxy"))]
             if !is_loopback {
                 // do not relay loopback'd pdus.
                 if self
@@ -259,6 +356,21 @@ impl ProvisionedStack {
                 }
             }
 
+            // only pdus delivered to this node are subject to replay protection,
+            // relaying relies on the network message cache.
+            let is_delivered = self.is_delivered(config, cleartext_network_pdu.dst());
+            if is_delivered {
+                replay_protection.check_network_pdu(&mut cleartext_network_pdu);
+            }
+            if !is_delivered || cleartext_network_pdu.meta().is_replay_protected() {
+                let relay_pdu = if cleartext_network_pdu.meta().is_relay() {
+                    Some(cleartext_network_pdu)
+                } else {
+                    None
+                };
+                return Ok((relay_pdu, None));
+            }
+
             let (block_ack_meta, mut upper_pdu) = self.process_inbound_cleartext_network_pdu(
                 &cleartext_network_pdu,
                 watchdog,
@@ -266,17 +378,16 @@ impl ProvisionedStack {
             )?;
 
             if let Some((block_ack, meta)) = &block_ack_meta {
-                if let Some(replacement_block_ack) = self.network.replay_protection.check_upper_pdu(
-                    meta,
-                    block_ack,
-                    upper_pdu.is_some(),
-                ) {
+                if let Some(replacement_block_ack) =
+                    replay_protection.check_upper_pdu(meta, block_ack, upper_pdu.is_some())
+                {
                     // we have already seen it and fully ack'd it, so just keep ack'ing for now.
                     return Ok((
                         None,
                         (
                             Some((replacement_block_ack, meta.clone())),
                             None,
+                            cleartext_network_pdu.src(),
                             cleartext_network_pdu.dst(),
                         )
                             .try_into()
@@ -285,12 +396,17 @@ impl ProvisionedStack {
                 }
             }
 
+            // acknowledge right away once all segments are received, otherwise
+            // leave it to the acknowledgment timer.
+            let block_ack_meta = block_ack_meta.filter(|_| upper_pdu.is_some());
+
             let message = if let Some(upper_pdu) = &mut upper_pdu {
                 self.process_inbound_upper_pdu(secrets, upper_pdu).ok()
             } else {
                 None
             };
 
+            let src = cleartext_network_pdu.src();
             let dst = cleartext_network_pdu.dst();
 
             let relay_pdu = if cleartext_network_pdu.meta().is_relay() {
@@ -299,7 +415,10 @@ impl ProvisionedStack {
                 None
             };
 
-            Ok((relay_pdu, (block_ack_meta, message, dst).try_into().ok()))
+            Ok((
+                relay_pdu,
+                (block_ack_meta, message, src, dst).try_into().ok(),
+            ))
         } else {
             // nothing doing, bad result, nothing parsed, keep on truckin'
             Ok((None, None))
@@ -311,12 +430,12 @@ impl ProvisionedStack {
     pub fn process_inbound_control(
         &mut self,
         message: &ControlMessage<ProvisionedStack>,
-        watchdog: &Watchdog,
     ) -> Result<(), DriverError> {
         match message.opcode() {
             ControlOpcode::SegmentAcknowledgement => {
                 if let Ok(block_ack) = message.try_into() {
-                    self.transmit_queue.receive_ack(block_ack, watchdog)?;
+                    self.transmit_queue
+                        .receive_ack(message.meta().src(), block_ack)?;
                 }
             }
             _ => {}
@@ -331,29 +450,39 @@ impl ProvisionedStack {
         sequence: &Sequence,
         message: &Message<ProvisionedStack>,
         completion_token: Option<CompletionToken>,
-        watchdog: &Watchdog,
-        retransmits: u8,
+        retransmission: PublicationRetransmission,
     ) -> Result<Vec<NetworkPDU, 8>, DriverError> {
         let upper_pdu = self.process_outbound_message(secrets, sequence, message)?;
-        let network_pdus = self.process_outbound_upper_pdu::<8>(sequence, &upper_pdu, false)?;
+        let network_pdus =
+            self.process_outbound_upper_pdu::<8>(sequence, &upper_pdu, false, None)?;
+
+        // a message with the 64-bit TransMIC is segmented, even as a single segment.
+        let is_bit64 = matches!(&upper_pdu, UpperPDU::Access(access) if access.transmic().szmic() == SzMic::Bit64);
 
         match network_pdus.len().cmp(&1) {
             Ordering::Less => { /* nothing */ }
-            Ordering::Equal => {
-                self.transmit_queue
-                    .add_nonsegmented(upper_pdu, retransmits, completion_token)?;
+            Ordering::Equal if !is_bit64 => {
+                self.transmit_queue.add_nonsegmented(
+                    upper_pdu,
+                    retransmission,
+                    completion_token,
+                )?;
             }
-            Ordering::Greater => {
+            _ => {
                 self.transmit_queue.add_segmented(
                     upper_pdu,
                     network_pdus.len() as u8,
                     completion_token,
-                    watchdog,
-                    retransmits,
+                    retransmission,
                 )?;
             }
         }
 
+        #[cfg(feature = "friend")]
+        for pdu in &network_pdus {
+            self.friend.enqueue(pdu, false)?;
+        }
+
         let network_pdus = network_pdus
             .iter()
             .map_while(|pdu| self.encrypt_network_pdu(secrets, pdu).ok())
@@ -362,6 +491,24 @@ impl ProvisionedStack {
         Ok(network_pdus)
     }
 
+    /// A single unsegmented control PDU, kept out of the transmit queue
+    /// as friendship messages have retransmissions of their own.
+    #[cfg(any(feature = "friend", feature = "low_power"))]
+    pub(crate) fn process_outbound_control_pdu(
+        &mut self,
+        secrets: &Secrets,
+        sequence: &Sequence,
+        opcode: ControlOpcode,
+        parameters: &[u8],
+        meta: ControlMetadata,
+    ) -> Result<CleartextNetworkPDU<ProvisionedStack>, DriverError> {
+        let message = ControlMessage::new(opcode, parameters, meta)?;
+        let upper_pdu = self.process_outbound_message(secrets, sequence, &message.into())?;
+        self.process_outbound_upper_pdu::<1>(sequence, &upper_pdu, false, None)?
+            .pop()
+            .ok_or(DriverError::InvalidState)
+    }
+
     pub fn process_outbound_relay_network_pdu(
         &mut self,
         secrets: &Secrets,
@@ -376,22 +523,63 @@ impl ProvisionedStack {
         }
     }
 
-    pub fn outbound_expiration(&mut self, seq_zero: &SeqZero) {
-        self.transmit_queue.expire_outbound(seq_zero);
+    /// Schedule the retransmissions of a network PDU originating from this node,
+    /// once transmitted.
+    pub fn schedule_network_retransmissions(
+        &mut self,
+        pdu: NetworkPDU,
+        network_transmit: &NetworkTransmitConfig,
+    ) {
+        self.network_transmit_queue
+            .add(pdu, network_transmit, Instant::now());
+    }
+
+    pub fn next_network_retransmit(&self) -> Option<Timer> {
+        self.network_transmit_queue.next_deadline().map(Timer::at)
+    }
+
+    pub fn network_retransmit(&mut self) -> Vec<NetworkPDU, 16> {
+        self.network_transmit_queue.take_due(Instant::now())
+    }
+
+    /// Schedule a relayed network PDU, along with its retransmissions.
+    #[cfg(any(feature = "relay", feature = "proxy"))]
+    pub fn schedule_relay(&mut self, pdu: NetworkPDU, relay: &RelayConfig, delay: Duration) {
+        self.relay_queue.add(pdu, relay, delay, Instant::now());
     }
 
+    #[cfg(any(feature = "relay", feature = "proxy"))]
+    pub fn next_relay(&self) -> Option<Timer> {
+        self.relay_queue.next_deadline().map(Timer::at)
+    }
+
+    #[cfg(any(feature = "relay", feature = "proxy"))]
+    pub fn relay(&mut self) -> Vec<NetworkPDU, 8> {
+        self.relay_queue.take_due(Instant::now())
+    }
+
+    /// Block-ack an expired inbound segmented message from `src`, returning the block-ack
+    /// along with its destination.
     pub fn inbound_expiration(
         &mut self,
         secrets: &Secrets,
         sequence: &Sequence,
-        seq_zero: &SeqZero,
         src: &UnicastAddress,
+        seq_zero: &SeqZero,
         watchdog: &Watchdog,
-    ) -> Result<Option<NetworkPDU>, DriverError> {
-        if let Some((block_ack, meta)) = self.lower.expire_inbound(seq_zero, watchdog) {
-            // We only send acks for unicast addresses
-            if meta.dst().is_unicast() {
-                self.process_outbound_block_ack(secrets, sequence, block_ack, &meta, src)
+    ) -> Result<Option<(NetworkPDU, Address)>, DriverError> {
+        if let Some((block_ack, meta)) = self.lower.expire_inbound(src, seq_zero, watchdog) {
+            // We only send acks for unicast addresses, from the element addressed.
+            if let Address::Unicast(element_address) = meta.dst() {
+                Ok(self
+                    .process_outbound_block_ack(
+                        secrets,
+                        sequence,
+                        block_ack,
+                        &meta,
+                        &element_address,
+                    )?
+                    .map(|pdu| (pdu, meta.src().into())))
             } else {
                 Ok(None)
             }
@@ -400,3 +588,148 @@ impl ProvisionedStack {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stack::provisioned::system::{LowerMetadata, NetworkMetadata};
+    use crate::storage::provisioned::fixture::config;
+    use btmesh_common::crypto::network::{NetworkKey, Nid};
+    use btmesh_common::{Ctl, Seq, Ttl};
+    use btmesh_device::NetworkKeyHandle;
+    use btmesh_models::foundation::configuration::NetKeyIndex;
+    use btmesh_pdu::provisioned::lower::access::SegmentedLowerAccessPDU;
+
+    fn nid() -> Nid {
+        NetworkKey::new([0x42; 16]).unwrap().nid()
+    }
+
+    fn pdu(
+        src: u16,
+        dst: Address,
+        seq: u32,
+        transport_pdu: &[u8],
+    ) -> CleartextNetworkPDU<ProvisionedStack> {
+        CleartextNetworkPDU::new(
+            Ivi::Zero,
+            nid(),
+            Ctl::Access,
+            Ttl::new(5),
+            Seq::new(seq),
+            UnicastAddress::new(src).unwrap(),
+            dst,
+            transport_pdu,
+            NetworkMetadata::new(
+                IvIndex::new(0),
+                None,
+                NetworkKeyHandle::new(NetKeyIndex::new(0), nid()),
+            ),
+        )
+        .unwrap()
+    }
+
+    fn receive(
+        stack: &mut ProvisionedStack,
+        config: &ProvisionedConfiguration,
+        replay_protection: &mut ReplayProtection,
+        pdu: CleartextNetworkPDU<ProvisionedStack>,
+    ) -> Option<ReceiveResult> {
+        let pdu = stack.encrypt_network_pdu(config.secrets(), &pdu).unwrap();
+        stack
+            .process_inbound_network_pdu(
+                config,
+                &pdu,
+                &Watchdog::default(),
+                false,
+                replay_protection,
+            )
+            .unwrap()
+            .1
+    }
+
+    #[test]
+    fn replay_protection_of_delivered_pdus_only() {
+        let config = config(NetworkKey::new([0x42; 16]).unwrap());
+        let mut stack = ProvisionedStack::from(&config);
+        let mut replay_protection = ReplayProtection::new(2);
+
+        let elsewhere = Address::Unicast(UnicastAddress::new(0x00B0).unwrap());
+        let pdu_elsewhere = pdu(0x0001, elsewhere, 1, &[0; 8]);
+        receive(&mut stack, &config, &mut replay_protection, pdu_elsewhere);
+        assert_eq!(0, replay_protection.list().iter().count());
+
+        let local = Address::Unicast(UnicastAddress::new(0x00A1).unwrap());
+        let pdu_local = pdu(0x0002, local, 1, &[0; 8]);
+        receive(&mut stack, &config, &mut replay_protection, pdu_local);
+        let group = Address::Group(GroupAddress::AllNodes);
+        let pdu_group = pdu(0x0003, group, 1, &[0; 8]);
+        receive(&mut stack, &config, &mut replay_protection, pdu_group);
+        assert_eq!(
+            [0x0002, 0x0003],
+            replay_protection
+                .list()
+                .iter()
+                .map(|entry| entry.src.into())
+                .collect::<Vec<u16, 2>>()
+                .as_slice()
+        );
+    }
+
+    #[test]
+    fn unsubscribed_group_traffic_not_tracked() {
+        let config = config(NetworkKey::new([0x42; 16]).unwrap());
+        let mut stack = ProvisionedStack::from(&config);
+        let mut replay_protection = ReplayProtection::new(2);
+
+        let group = Address::Group(GroupAddress::Normal(0xC000));
+        for src in 0x0010..0x0020 {
+            let pdu_group = pdu(src, group, 1, &[0; 8]);
+            receive(&mut stack, &config, &mut replay_protection, pdu_group);
+        }
+        assert_eq!(0, replay_protection.list().iter().count());
+
+        // sources of messages actually delivered are still tracked.
+        let local = Address::Unicast(UnicastAddress::new(0x00A1).unwrap());
+        let pdu_local = pdu(0x0002, local, 1, &[0; 8]);
+        receive(&mut stack, &config, &mut replay_protection, pdu_local);
+        assert_eq!(1, replay_protection.list().iter().count());
+    }
+
+    #[test]
+    fn block_ack_once_all_segments_received() {
+        let config = config(NetworkKey::new([0x42; 16]).unwrap());
+        let mut stack = ProvisionedStack::from(&config);
+        let mut replay_protection = ReplayProtection::new(2);
+        let local = Address::Unicast(UnicastAddress::new(0x00A1).unwrap());
+
+        let mut results = (0..2).map(|seg_o| {
+            let seq = 10 + seg_o as u32;
+            let segment = SegmentedLowerAccessPDU::<ProvisionedStack>::new(
+                None,
+                SzMic::Bit32,
+                SeqZero::new(10),
+                seg_o,
+                1,
+                b"ABCDEFGHIJKL",
+                LowerMetadata::new(
+                    NetworkKeyHandle::new(NetKeyIndex::new(0), nid()),
+                    IvIndex::new(0),
+                    UnicastAddress::new(0x0002).unwrap(),
+                    local,
+                    Seq::new(seq),
+                    Ttl::new(5),
+                ),
+            )
+            .unwrap();
+            let mut transport_pdu = Vec::<_, 16>::new();
+            segment.emit(&mut transport_pdu).unwrap();
+            let pdu = pdu(0x0002, local, seq, &transport_pdu);
+            receive(&mut stack, &config, &mut replay_protection, pdu)
+        });
+
+        // left to the acknowledgment timer while segments are missing.
+        assert!(results.next().unwrap().is_none());
+        let (block_ack, _) = results.next().unwrap().unwrap().block_ack.unwrap();
+        assert_eq!(0b11, block_ack.value());
+    }
+}
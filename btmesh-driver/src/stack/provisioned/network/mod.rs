@@ -1,22 +1,21 @@
 use crate::stack::provisioned::system::NetworkMetadata;
 use crate::stack::provisioned::{DriverError, ProvisionedStack};
 use btmesh_common::address::{Address, UnicastAddress};
-use btmesh_common::crypto::network::NetMic;
-use btmesh_common::crypto::nonce::NetworkNonce;
+use btmesh_common::crypto::network::{NetMic, NetworkKey};
+use btmesh_common::crypto::nonce::{NetworkNonce, ProxyNonce};
 use btmesh_common::{crypto, Ctl, IvIndex, Seq, Ttl};
 use btmesh_pdu::provisioned::network::{CleartextNetworkPDU, NetworkPDU};
 use heapless::Vec;
 
-use crate::stack::provisioned::network::replay_protection::ReplayProtection;
 use crate::Secrets;
 use btmesh_device::NetworkKeyHandle;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-#[cfg(feature = "relay")]
+#[cfg(any(feature = "relay", feature = "proxy"))]
 use crate::stack::provisioned::network::network_message_cache::NetworkMessageCache;
 
-#[cfg(feature = "relay")]
+#[cfg(any(feature = "relay", feature = "proxy"))]
 pub mod network_message_cache;
 pub mod replay_protection;
 
@@ -41,6 +40,10 @@ impl DeviceInfo {
         }
     }
 
+    pub fn number_of_elements(&self) -> u8 {
+        self.number_of_elements
+    }
+
     pub fn local_element_index(&self, dst: Address) -> Option<u8> {
         if let Address::Unicast(dst) = dst {
             if dst >= self.primary_unicast_address {
@@ -75,8 +78,7 @@ impl DeviceInfo {
 
 pub struct NetworkDriver {
     device_info: DeviceInfo,
-    pub(crate) replay_protection: ReplayProtection,
-    #[cfg(feature = "relay")]
+    #[cfg(any(feature = "relay", feature = "proxy"))]
     pub(crate) network_message_cache: NetworkMessageCache,
 }
 
@@ -84,8 +86,7 @@ impl NetworkDriver {
     pub(crate) fn new(device_info: DeviceInfo) -> Self {
         Self {
             device_info,
-            replay_protection: Default::default(),
-            #[cfg(feature = "relay")]
+            #[cfg(any(feature = "relay", feature = "proxy"))]
             network_message_cache: Default::default(),
         }
     }
@@ -100,15 +101,64 @@ impl NetworkDriver {
     }
 }
 
-impl ProvisionedStack {
-    pub fn validate_cleartext_network_pdu(&mut self, pdu: &mut CleartextNetworkPDU<Self>) {
-        self.network.replay_protection.check_network_pdu(pdu);
+/// Network PDUs carrying proxy configuration messages are secured
+/// with a nonce of their own.
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum NonceType {
+    Network,
+    Proxy,
+}
+
+impl NonceType {
+    fn nonce(&self, ctl_ttl: u8, seq: Seq, src: UnicastAddress, iv_index: IvIndex) -> [u8; 13] {
+        match self {
+            NonceType::Network => NetworkNonce::new(ctl_ttl, seq, src, iv_index).into_bytes(),
+            NonceType::Proxy => ProxyNonce::new(seq, src, iv_index).into_bytes(),
+        }
     }
+}
 
+impl ProvisionedStack {
     pub fn encrypt_network_pdu(
         &mut self,
         secrets: &Secrets,
         cleartext_pdu: &CleartextNetworkPDU<ProvisionedStack>,
+    ) -> Result<NetworkPDU, DriverError> {
+        self.encrypt(secrets, cleartext_pdu, NonceType::Network)
+    }
+
+    pub fn encrypt_proxy_configuration_pdu(
+        &self,
+        secrets: &Secrets,
+        cleartext_pdu: &CleartextNetworkPDU<ProvisionedStack>,
+    ) -> Result<NetworkPDU, DriverError> {
+        self.encrypt(secrets, cleartext_pdu, NonceType::Proxy)
+    }
+
+    /// Encrypt a network PDU with the given key rather than the one of its network key handle.
+    pub fn encrypt_network_pdu_with_key(
+        &self,
+        cleartext_pdu: &CleartextNetworkPDU<ProvisionedStack>,
+        network_key: &NetworkKey,
+    ) -> Result<NetworkPDU, DriverError> {
+        self.encrypt_with_key(cleartext_pdu, network_key, NonceType::Network)
+    }
+
+    fn encrypt(
+        &self,
+        secrets: &Secrets,
+        cleartext_pdu: &CleartextNetworkPDU<ProvisionedStack>,
+        nonce_type: NonceType,
+    ) -> Result<NetworkPDU, DriverError> {
+        let network_key = secrets.network_key(cleartext_pdu.meta().network_key_handle())?;
+        self.encrypt_with_key(cleartext_pdu, &network_key, nonce_type)
+    }
+
+    fn encrypt_with_key(
+        &self,
+        cleartext_pdu: &CleartextNetworkPDU<ProvisionedStack>,
+        network_key: &NetworkKey,
+        nonce_type: NonceType,
     ) -> Result<NetworkPDU, DriverError> {
         let ctl_ttl = match cleartext_pdu.ctl() {
             Ctl::Access => 0,
@@ -125,9 +175,7 @@ impl ProvisionedStack {
             .extend_from_slice(cleartext_pdu.transport_pdu())
             .map_err(|_| DriverError::InsufficientSpace)?;
 
-        let network_key = secrets.network_key(cleartext_pdu.meta().network_key_handle())?;
-
-        let nonce = NetworkNonce::new(
+        let nonce = nonce_type.nonce(
             ctl_ttl,
             cleartext_pdu.seq(),
             cleartext_pdu.src(),
@@ -139,7 +187,7 @@ impl ProvisionedStack {
                 let mut mic = NetMic::new_access();
 
                 crypto::network::encrypt_network(
-                    &network_key,
+                    network_key,
                     &nonce,
                     &mut encrypted_and_mic,
                     &mut mic,
@@ -154,7 +202,7 @@ impl ProvisionedStack {
                 let mut mic = NetMic::new_control();
 
                 crypto::network::encrypt_network(
-                    &network_key,
+                    network_key,
                     &nonce,
                     &mut encrypted_and_mic,
                     &mut mic,
@@ -186,9 +234,11 @@ impl ProvisionedStack {
         unobfuscated[5] = src_bytes[1];
         let obfuscated = crypto::pecb_xor(pecb, unobfuscated);
 
+        // the NID follows the key actually used, which may differ from the
+        // handle's during a key refresh.
         let network_pdu = NetworkPDU::new(
             cleartext_pdu.ivi(),
-            cleartext_pdu.nid(),
+            network_key.nid(),
             obfuscated,
             &encrypted_and_mic,
         )?;
@@ -203,30 +253,79 @@ impl ProvisionedStack {
         iv_index: IvIndex,
     ) -> Result<Option<CleartextNetworkPDU<ProvisionedStack>>, DriverError> {
         let mut result = None;
-        for network_key in secrets.network_keys_by_nid(pdu.nid()) {
-            if let Ok(pdu) =
-                self.try_decrypt_network_pdu_with_key(secrets, pdu, iv_index, network_key)
-            {
+        for (network_key_handle, network_key) in secrets.network_keys_by_nid(pdu.nid()) {
+            if let Ok(pdu) = self.try_decrypt_network_pdu_with_key(
+                pdu,
+                iv_index,
+                network_key_handle,
+                &network_key,
+            ) {
                 result.replace(pdu);
                 break;
             }
         }
 
-        if let Some(result) = &mut result {
-            self.validate_cleartext_network_pdu(result);
+        #[cfg(feature = "friend")]
+        if result.is_none() {
+            result = self.try_decrypt_friend_network_pdu(pdu, iv_index);
+        }
+
+        #[cfg(feature = "low_power")]
+        if result.is_none() {
+            result = self.try_decrypt_low_power_network_pdu(pdu, iv_index);
         }
 
         Ok(result)
     }
 
-    pub fn try_decrypt_network_pdu_with_key(
+    /// Decrypt a network PDU carrying a proxy configuration message.
+    ///
+    /// These are exchanged with a proxy client only, so they bypass
+    /// the replay protection of regular network PDUs.
+    pub fn try_decrypt_proxy_configuration_pdu(
         &self,
         secrets: &Secrets,
         pdu: &NetworkPDU,
         iv_index: IvIndex,
+    ) -> Option<CleartextNetworkPDU<ProvisionedStack>> {
+        secrets
+            .network_keys_by_nid(pdu.nid())
+            .find_map(|(network_key_handle, network_key)| {
+                self.try_decrypt(
+                    pdu,
+                    iv_index,
+                    network_key_handle,
+                    &network_key,
+                    NonceType::Proxy,
+                )
+                .ok()
+            })
+    }
+
+    pub fn try_decrypt_network_pdu_with_key(
+        &self,
+        pdu: &NetworkPDU,
+        iv_index: IvIndex,
+        network_key_handle: NetworkKeyHandle,
+        network_key: &NetworkKey,
+    ) -> Result<CleartextNetworkPDU<ProvisionedStack>, DriverError> {
+        self.try_decrypt(
+            pdu,
+            iv_index,
+            network_key_handle,
+            network_key,
+            NonceType::Network,
+        )
+    }
+
+    fn try_decrypt(
+        &self,
+        pdu: &NetworkPDU,
+        iv_index: IvIndex,
         network_key_handle: NetworkKeyHandle,
+        network_key: &NetworkKey,
+        nonce_type: NonceType,
     ) -> Result<CleartextNetworkPDU<ProvisionedStack>, DriverError> {
-        let network_key = secrets.network_key(network_key_handle)?;
         let mut encrypted_and_mic = Vec::<_, 28>::from_slice(pdu.encrypted_and_mic())
             .map_err(|_| DriverError::InsufficientSpace)?;
         let privacy_plaintext = crypto::privacy_plaintext(iv_index, &encrypted_and_mic);
@@ -244,7 +343,7 @@ impl ProvisionedStack {
             unobfuscated[3],
         ]))?;
 
-        let nonce = NetworkNonce::new(
+        let nonce = nonce_type.nonce(
             unobfuscated[0],
             seq,
             UnicastAddress::parse([unobfuscated[4], unobfuscated[5]])?,
@@ -257,7 +356,7 @@ impl ProvisionedStack {
 
         let mic = NetMic::parse(mic)?;
 
-        if crypto::network::try_decrypt_network(&network_key, &nonce, payload, &mic).is_ok() {
+        if crypto::network::try_decrypt_network(network_key, &nonce, payload, &mic).is_ok() {
             let ttl = Ttl::parse(unobfuscated[0] & 0b01111111)?;
 
             let src = UnicastAddress::parse([unobfuscated[4], unobfuscated[5]])?;
@@ -1,38 +1,102 @@
 use crate::stack::provisioned::ProvisionedStack;
+use crate::storage::provisioned::replay_protection::{ReplayProtectionEntry, ReplayProtectionList};
 use crate::UpperMetadata;
 use btmesh_common::address::UnicastAddress;
-use btmesh_common::Seq;
+use btmesh_common::{IvIndex, Seq};
 use btmesh_pdu::provisioned::lower::BlockAck;
 use btmesh_pdu::provisioned::network::CleartextNetworkPDU;
 use core::cmp::Ordering;
-use uluru::LRUCache;
+use heapless::Vec;
 
-#[derive(PartialEq)]
-struct NetworkCacheEntry {
+/// Default number of sources tracked by the replay protection list.
+pub const REPLAY_PROTECTION_LIST_SIZE: usize = 32;
+
+/// Largest number of sources the replay protection list, and its persisted
+/// copy, have room for.
+pub const MAX_REPLAY_PROTECTION_LIST_SIZE: usize = 64;
+
+struct Entry {
     seq: Seq,
     src: UnicastAddress,
-    iv_index: u16,
+    iv_index: IvIndex,
+    /// Last segmented message completely received from the source.
+    upper: Option<UpperEntry>,
 }
 
-struct UpperCacheEntry {
+struct UpperEntry {
     seq: Seq,
-    src: UnicastAddress,
     iv_index: u16,
     block_ack: BlockAck,
 }
 
-#[derive(Default)]
-pub struct ReplayProtection<const N: usize = 32> {
-    network: LRUCache<NetworkCacheEntry, N>,
-    upper: LRUCache<UpperCacheEntry, N>,
+/// Replay protection list, tracking up to `capacity` sources of messages
+/// delivered to this node. Sources are never evicted, messages from new ones
+/// being dropped once the list is full.
+pub struct ReplayProtection {
+    entries: Vec<Entry, MAX_REPLAY_PROTECTION_LIST_SIZE>,
+    capacity: usize,
+}
+
+impl Default for ReplayProtection {
+    fn default() -> Self {
+        Self::new(REPLAY_PROTECTION_LIST_SIZE)
+    }
 }
 
-impl<const N: usize> ReplayProtection<N> {
+impl ReplayProtection {
+    /// A list tracking up to `capacity` sources, capped at [`MAX_REPLAY_PROTECTION_LIST_SIZE`].
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Vec::new(),
+            capacity: capacity.min(MAX_REPLAY_PROTECTION_LIST_SIZE),
+        }
+    }
+
+    /// Number of sources tracked, reported as the CRPL of the node.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn is_full(&self) -> bool {
+        self.entries.len() >= self.capacity
+    }
+
+    /// Resume from the persisted replay protection list.
+    pub(crate) fn restore(&mut self, list: &ReplayProtectionList) {
+        self.entries.clear();
+        for entry in list.iter() {
+            if self.is_full() {
+                warn!("persisted replay protection list exceeds its capacity");
+                break;
+            }
+            // infallible, the capacity being capped at the size of the list.
+            self.entries
+                .push(Entry {
+                    seq: Seq::new(entry.seq),
+                    src: entry.src,
+                    iv_index: entry.iv_index,
+                    upper: None,
+                })
+                .ok();
+        }
+    }
+
+    /// The list to persist.
+    pub(crate) fn list(&self) -> ReplayProtectionList {
+        let mut list = ReplayProtectionList::default();
+        list.replace(self.entries.iter().map(|entry| ReplayProtectionEntry {
+            src: entry.src,
+            seq: entry.seq.value(),
+            iv_index: entry.iv_index,
+        }));
+        list
+    }
+
     pub fn check_network_pdu(&mut self, pdu: &mut CleartextNetworkPDU<ProvisionedStack>) {
-        let iv_index = (pdu.meta().iv_index().value() & 0xFFFF) as u16;
+        let iv_index = pdu.meta().iv_index();
 
-        if let Some(entry) = self.network.find(|e| e.src == pdu.src()) {
-            match iv_index.cmp(&entry.iv_index) {
+        if let Some(entry) = self.entries.iter_mut().find(|e| e.src == pdu.src()) {
+            match iv_index.value().cmp(&entry.iv_index.value()) {
                 Ordering::Less => {
                     pdu.meta_mut().replay_protected(true);
                 }
@@ -50,13 +114,24 @@ impl<const N: usize> ReplayProtection<N> {
                     pdu.meta_mut().replay_protected(false);
                 }
             }
-        } else {
-            self.network.insert(NetworkCacheEntry {
-                seq: pdu.seq(),
-                src: pdu.src(),
-                iv_index,
-            });
+        } else if !self.is_full() {
+            // infallible, the capacity being capped at the size of the list.
+            self.entries
+                .push(Entry {
+                    seq: pdu.seq(),
+                    src: pdu.src(),
+                    iv_index,
+                    upper: None,
+                })
+                .ok();
             pdu.meta_mut().replay_protected(false);
+        } else {
+            // evicting a source would allow its messages to be replayed.
+            warn!(
+                "replay protection list full, dropping pdu from {}",
+                pdu.src()
+            );
+            pdu.meta_mut().replay_protected(true);
         }
     }
 
@@ -68,13 +143,16 @@ impl<const N: usize> ReplayProtection<N> {
     ) -> Option<BlockAck> {
         let iv_index = (meta.iv_index().value() & 0xFFFF) as u16;
 
-        if let Some(entry) = self.upper.find(|e| e.src == meta.src()) {
-            match iv_index.cmp(&entry.iv_index) {
+        // the source was tracked when its network pdus were accepted.
+        let entry = self.entries.iter_mut().find(|e| e.src == meta.src())?;
+
+        if let Some(upper) = &mut entry.upper {
+            match iv_index.cmp(&upper.iv_index) {
                 Ordering::Less => None,
                 Ordering::Equal => {
                     if let Some(replay_seq) = meta.replay_seq() {
-                        if replay_seq == entry.seq {
-                            Some(entry.block_ack)
+                        if replay_seq == upper.seq {
+                            Some(upper.block_ack)
                         } else {
                             None
                         }
@@ -85,9 +163,9 @@ impl<const N: usize> ReplayProtection<N> {
                 Ordering::Greater => {
                     if is_complete {
                         if let Some(replay_seq) = meta.replay_seq() {
-                            entry.iv_index = iv_index;
-                            entry.seq = replay_seq;
-                            entry.block_ack = *block_ack;
+                            upper.iv_index = iv_index;
+                            upper.seq = replay_seq;
+                            upper.block_ack = *block_ack;
                         }
                     }
                     None
@@ -96,9 +174,8 @@ impl<const N: usize> ReplayProtection<N> {
         } else {
             if is_complete {
                 if let Some(replay_seq) = meta.replay_seq() {
-                    self.upper.insert(UpperCacheEntry {
+                    entry.upper.replace(UpperEntry {
                         seq: replay_seq,
-                        src: meta.src(),
                         iv_index,
                         block_ack: *block_ack,
                     });
@@ -108,3 +185,129 @@ impl<const N: usize> ReplayProtection<N> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stack::provisioned::system::NetworkMetadata;
+    use btmesh_common::address::Address;
+    use btmesh_common::crypto::network::Nid;
+    use btmesh_common::{Ctl, Ivi, SeqZero, Ttl};
+    use btmesh_device::NetworkKeyHandle;
+    use btmesh_models::foundation::configuration::NetKeyIndex;
+    use heapless::Vec;
+
+    fn entry(src: u16, seq: u32) -> ReplayProtectionEntry {
+        ReplayProtectionEntry {
+            src: UnicastAddress::new(src).unwrap(),
+            seq,
+            iv_index: IvIndex::new(42),
+        }
+    }
+
+    #[test]
+    fn persisted_list_round_trip() {
+        let mut list = ReplayProtectionList::default();
+        list.replace([entry(0x0003, 30), entry(0x0001, 10), entry(0x0002, 20)].into_iter());
+        assert_eq!(
+            [entry(0x0001, 10), entry(0x0002, 20), entry(0x0003, 30)],
+            list.iter().copied().collect::<Vec<_, 3>>()
+        );
+
+        let mut replay_protection = ReplayProtection::default();
+        replay_protection.restore(&list);
+
+        let restored = replay_protection.list();
+        assert_eq!(
+            list.iter().collect::<Vec<_, 3>>(),
+            restored.iter().collect::<Vec<_, 3>>()
+        );
+    }
+
+    fn pdu(src: u16, seq: u32) -> CleartextNetworkPDU<ProvisionedStack> {
+        CleartextNetworkPDU::new(
+            Ivi::Zero,
+            Nid::new(0x68),
+            Ctl::Access,
+            Ttl::new(5),
+            Seq::new(seq),
+            UnicastAddress::new(src).unwrap(),
+            Address::Unicast(UnicastAddress::new(0x00A1).unwrap()),
+            &[0; 8],
+            NetworkMetadata::new(
+                IvIndex::new(42),
+                None,
+                NetworkKeyHandle::new(NetKeyIndex::new(0), Nid::new(0x68)),
+            ),
+        )
+        .unwrap()
+    }
+
+    fn is_replay(replay_protection: &mut ReplayProtection, src: u16, seq: u32) -> bool {
+        let mut pdu = pdu(src, seq);
+        replay_protection.check_network_pdu(&mut pdu);
+        pdu.meta().is_replay_protected()
+    }
+
+    #[test]
+    fn full_list_drops_unknown_sources() {
+        let mut list = ReplayProtectionList::default();
+        list.replace([entry(0x0001, 10), entry(0x0002, 20)].into_iter());
+
+        let mut replay_protection = ReplayProtection::new(2);
+        replay_protection.restore(&list);
+
+        assert!(is_replay(&mut replay_protection, 0x0001, 10));
+        assert!(!is_replay(&mut replay_protection, 0x0001, 11));
+        assert!(is_replay(&mut replay_protection, 0x0003, 1));
+        assert!(!is_replay(&mut replay_protection, 0x0002, 21));
+        assert_eq!(2, replay_protection.list().iter().count());
+    }
+
+    fn upper_meta(src: u16, seq: u32) -> UpperMetadata {
+        UpperMetadata {
+            network_key_handle: NetworkKeyHandle::new(NetKeyIndex::new(0), Nid::new(0x68)),
+            iv_index: IvIndex::new(42),
+            local_element_index: Some(0),
+            akf_aid: None,
+            seq: Seq::new(seq),
+            src: UnicastAddress::new(src).unwrap(),
+            dst: Address::Unicast(UnicastAddress::new(0x00A1).unwrap()),
+            ttl: Ttl::new(5),
+            label_uuids: Default::default(),
+            seq_auth: None,
+            replay_seq: Some(Seq::new(seq)),
+            friendship_credentials: false,
+        }
+    }
+
+    #[test]
+    fn completed_segmented_messages_kept_per_source() {
+        let mut replay_protection = ReplayProtection::new(2);
+        assert!(!is_replay(&mut replay_protection, 0x0001, 10));
+        assert!(!is_replay(&mut replay_protection, 0x0002, 20));
+
+        let block_ack = BlockAck::new(SeqZero::new(10));
+        assert!(replay_protection
+            .check_upper_pdu(&upper_meta(0x0001, 10), &block_ack, true)
+            .is_none());
+        // untracked sources are never remembered.
+        assert!(replay_protection
+            .check_upper_pdu(&upper_meta(0x0003, 30), &block_ack, true)
+            .is_none());
+        assert!(replay_protection
+            .check_upper_pdu(&upper_meta(0x0003, 30), &block_ack, true)
+            .is_none());
+        assert!(replay_protection
+            .check_upper_pdu(&upper_meta(0x0002, 20), &block_ack, true)
+            .is_none());
+
+        // segments of completed messages keep being acknowledged.
+        assert!(replay_protection
+            .check_upper_pdu(&upper_meta(0x0001, 10), &block_ack, false)
+            .is_some());
+        assert!(replay_protection
+            .check_upper_pdu(&upper_meta(0x0002, 20), &block_ack, false)
+            .is_some());
+    }
+}
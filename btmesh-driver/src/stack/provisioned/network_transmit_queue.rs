@@ -0,0 +1,107 @@
+use btmesh_models::foundation::configuration::network_transmit::NetworkTransmitConfig;
+use btmesh_pdu::provisioned::network::NetworkPDU;
+use embassy_time::{Duration, Instant};
+use heapless::Vec;
+
+/// Schedules the retransmissions of network PDUs originating from this node
+/// according to the Network Transmit state, their first transmission having
+/// already taken place.
+pub struct NetworkTransmitQueue<const N: usize = 16> {
+    queue: Vec<NetworkTransmitQueueEntry, N>,
+}
+
+struct NetworkTransmitQueueEntry {
+    pdu: NetworkPDU,
+    num_retransmit: u8,
+    interval: Duration,
+    next: Instant,
+}
+
+impl<const N: usize> Default for NetworkTransmitQueue<N> {
+    fn default() -> Self {
+        Self { queue: Vec::new() }
+    }
+}
+
+impl<const N: usize> NetworkTransmitQueue<N> {
+    /// Schedule the retransmissions of a PDU first transmitted at `now`.
+    pub fn add(&mut self, pdu: NetworkPDU, network_transmit: &NetworkTransmitConfig, now: Instant) {
+        if network_transmit.network_retransmit_count == 0 {
+            return;
+        }
+        let interval = Duration::from_millis(network_transmit.interval_millis() as u64);
+        let entry = NetworkTransmitQueueEntry {
+            pdu,
+            num_retransmit: network_transmit.network_retransmit_count,
+            interval,
+            next: now + interval,
+        };
+
+        if self.queue.push(entry).is_err() {
+            warn!("no space in network transmit queue");
+        }
+    }
+
+    /// The earliest point in time at which a PDU is due for retransmission.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.queue.iter().map(|entry| entry.next).min()
+    }
+
+    /// Take the PDUs which are due for retransmission at `now`.
+    pub fn take_due(&mut self, now: Instant) -> Vec<NetworkPDU, N> {
+        let mut due = Vec::new();
+
+        for entry in self.queue.iter_mut().filter(|entry| entry.next <= now) {
+            due.push(entry.pdu.clone()).ok();
+            entry.num_retransmit -= 1;
+            entry.next = now + entry.interval;
+        }
+
+        self.queue.retain(|entry| entry.num_retransmit > 0);
+
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use btmesh_common::crypto::network::Nid;
+    use btmesh_common::Ivi;
+
+    fn pdu() -> NetworkPDU {
+        NetworkPDU::new(Ivi::Zero, Nid::new(0x42), [0; 6], &[0; 12]).unwrap()
+    }
+
+    #[test]
+    fn retransmit_per_network_transmit() {
+        let mut queue = NetworkTransmitQueue::<4>::default();
+        // 2 retransmissions, 30ms apart.
+        let network_transmit = NetworkTransmitConfig::parse(&[0b01000010]).unwrap();
+        let start = Instant::from_millis(1_000);
+
+        queue.add(pdu(), &network_transmit, start);
+        assert_eq!(
+            Some(start + Duration::from_millis(30)),
+            queue.next_deadline()
+        );
+        assert!(queue.take_due(start).is_empty());
+
+        let next = start + Duration::from_millis(30);
+        assert_eq!(1, queue.take_due(next).len());
+        assert!(queue.take_due(next).is_empty());
+
+        let next = next + Duration::from_millis(30);
+        assert_eq!(1, queue.take_due(next).len());
+        assert!(queue.next_deadline().is_none());
+    }
+
+    #[test]
+    fn no_retransmissions() {
+        let mut queue = NetworkTransmitQueue::<4>::default();
+        let network_transmit = NetworkTransmitConfig::parse(&[0b00011111]).unwrap();
+
+        queue.add(pdu(), &network_transmit, Instant::from_millis(0));
+        assert!(queue.next_deadline().is_none());
+    }
+}
@@ -0,0 +1,176 @@
+use crate::stack::unprovisioned::oob::StaticOob;
+use crate::stack::unprovisioned::provisioner::{
+    ProvisionedNode, Provisioner, ResponsePDU, UnicastAddressAllocator,
+};
+use crate::storage::provisioned::ProvisionedConfiguration;
+use crate::DriverError;
+use btmesh_common::Uuid;
+use btmesh_pdu::provisioning::{ErrorCode, ProvisioningData, ProvisioningPDU, PublicKey};
+use embassy_time::{Duration, Instant, Timer};
+use rand_core::{CryptoRng, RngCore};
+
+/// How long to scan for the device, and then to provision it.
+const PROVISIONING_TIMEOUT: Duration = Duration::from_secs(60);
+
+const RETRANSMIT_INTERVAL: Duration = Duration::from_millis(1000);
+
+const ATTENTION_DURATION: u8 = 5;
+
+/// Why provisioning another device did not succeed.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Debug)]
+pub enum ProvisioningError {
+    /// This node is not provisioned itself, or is already provisioning a device.
+    InvalidState,
+    /// The device was not heard from, or did not complete provisioning in time.
+    Timeout,
+    /// Provisioning failed, as reported by either side.
+    Failed(ErrorCode),
+}
+
+pub(crate) type ProvisioningResult =
+    Result<(ProvisionedNode, UnicastAddressAllocator), ProvisioningError>;
+
+/// Provisioning of another device by this node: scanning for its unprovisioned
+/// beacon, then running the provisioner over a PB-ADV link opened to it.
+pub struct ProvisioningSession {
+    uuid: Option<Uuid>,
+    static_oob: Option<StaticOob>,
+    public_key: Option<PublicKey>,
+    addresses: UnicastAddressAllocator,
+    provisioner: Option<Provisioner>,
+    deadline: Instant,
+    next_retransmit: Instant,
+}
+
+impl ProvisioningSession {
+    /// Provision the device beaconing `uuid`, or the first one heard if `None`,
+    /// authenticating it with its `static_oob` secret and `public_key` if known.
+    pub fn new(
+        uuid: Option<Uuid>,
+        static_oob: Option<StaticOob>,
+        public_key: Option<PublicKey>,
+        addresses: UnicastAddressAllocator,
+    ) -> Self {
+        let deadline = Instant::now() + PROVISIONING_TIMEOUT;
+        Self {
+            uuid,
+            static_oob,
+            public_key,
+            addresses,
+            provisioner: None,
+            deadline,
+            next_retransmit: deadline,
+        }
+    }
+
+    /// Whether the unprovisioned beacon of `uuid` is the one being scanned for.
+    pub fn is_scanning_for(&self, uuid: Uuid) -> bool {
+        self.provisioner.is_none() && self.uuid.map_or(true, |expected| expected == uuid)
+    }
+
+    /// Invite the device once a link to it is open, distributing the network
+    /// described by `config` to it.
+    pub fn invite(
+        &mut self,
+        uuid: Uuid,
+        config: &ProvisionedConfiguration,
+    ) -> Result<ResponsePDU, DriverError> {
+        let provisioner = Provisioner::new(
+            uuid,
+            provisioning_data(config)?,
+            ATTENTION_DURATION,
+            self.static_oob,
+            self.public_key,
+        )?;
+        let response = provisioner.response();
+        self.uuid.replace(uuid);
+        self.provisioner.replace(provisioner);
+        self.deadline = Instant::now() + PROVISIONING_TIMEOUT;
+        self.next_retransmit = Instant::now() + RETRANSMIT_INTERVAL;
+        Ok(response)
+    }
+
+    /// Advance on an inbound provisioning PDU, returning the PDUs to transmit,
+    /// along with the result once provisioning is over.
+    pub fn process<RNG: RngCore + CryptoRng>(
+        &mut self,
+        pdu: &ProvisioningPDU,
+        rng: &mut RNG,
+    ) -> Result<(ResponsePDU, Option<ProvisioningResult>), DriverError> {
+        let provisioner = self
+            .provisioner
+            .take()
+            .ok_or(DriverError::InvalidState)?
+            .next(pdu, rng, &mut self.addresses)?;
+
+        let result = match &provisioner {
+            Provisioner::Success(node) => Some(Ok((*node, self.addresses))),
+            Provisioner::Failure(ResponsePDU::One(ProvisioningPDU::Failed(failed))) => {
+                Some(Err(ProvisioningError::Failed(failed.error_code.clone())))
+            }
+            Provisioner::Failure(_) => {
+                Some(Err(ProvisioningError::Failed(ErrorCode::UnexpectedError)))
+            }
+            _ => None,
+        };
+
+        // as provisioner, failures are not reported to the device but end the link.
+        let response = if result.is_none() {
+            self.next_retransmit = Instant::now() + RETRANSMIT_INTERVAL;
+            provisioner.response()
+        } else {
+            ResponsePDU::None
+        };
+        self.provisioner.replace(provisioner);
+        Ok((response, result))
+    }
+
+    /// PDUs to repeat until acknowledged by the device, if due.
+    pub fn retransmit(&mut self) -> ResponsePDU {
+        match &self.provisioner {
+            Some(provisioner) if Instant::now() >= self.next_retransmit => {
+                self.next_retransmit = Instant::now() + RETRANSMIT_INTERVAL;
+                provisioner.response()
+            }
+            _ => ResponsePDU::None,
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        Instant::now() >= self.deadline
+    }
+
+    /// Whether a link to the device is open.
+    pub fn is_linked(&self) -> bool {
+        self.provisioner.is_some()
+    }
+
+    pub fn next_deadline(&self) -> Timer {
+        Timer::at(self.deadline.min(self.next_retransmit))
+    }
+}
+
+/// The network of this node, as distributed to the devices it provisions,
+/// on the primary subnet when available.
+fn provisioning_data(config: &ProvisionedConfiguration) -> Result<ProvisioningData, DriverError> {
+    let secrets = config.secrets();
+    let (key_index, network_key) = secrets
+        .network_keys_iter()
+        .min_by_key(|(index, _)| usize::from(*index))
+        .ok_or(DriverError::InvalidState)?;
+    let iv_index = config.network_state().iv_index();
+
+    Ok(ProvisioningData {
+        network_key: network_key.to_bytes(),
+        key_index: usize::from(key_index) as u16,
+        key_refresh_flag: secrets.key_refresh_flag(key_index)?,
+        iv_update_flag: iv_index.iv_update_flag(),
+        iv_index: iv_index.iv_index().value(),
+        // allocated once the device reports its number of elements.
+        unicast_address: config
+            .device_info()
+            .local_element_address(0)
+            .ok_or(DriverError::InvalidState)?,
+    })
+}
@@ -0,0 +1,68 @@
+use crate::stack::provisioned::sequence::Sequence;
+use crate::stack::provisioned::system::NetworkMetadata;
+use crate::stack::provisioned::ProvisionedStack;
+use crate::{DriverError, Secrets};
+use btmesh_common::address::{Address, UnicastAddress};
+use btmesh_common::{Ctl, IvIndex, Ttl};
+use btmesh_device::NetworkKeyHandle;
+use btmesh_pdu::provisioned::network::{CleartextNetworkPDU, NetworkPDU};
+use btmesh_pdu::provisioned::proxy::ProxyConfigurationMessage;
+use heapless::Vec;
+
+impl ProvisionedStack {
+    /// Decrypt and parse a proxy configuration message received from a proxy client,
+    /// along with the network key it was secured with.
+    pub fn process_inbound_proxy_configuration(
+        &self,
+        secrets: &Secrets,
+        pdu: &NetworkPDU,
+    ) -> Result<Option<(ProxyConfigurationMessage, NetworkKeyHandle)>, DriverError> {
+        let iv_index = self
+            .network_state
+            .iv_index_state
+            .accepted_iv_index(pdu.ivi());
+
+        if let Some(cleartext_pdu) =
+            self.try_decrypt_proxy_configuration_pdu(secrets, pdu, iv_index)
+        {
+            if !matches!(cleartext_pdu.ctl(), Ctl::Control)
+                || cleartext_pdu.dst() != Address::Unassigned
+            {
+                return Err(DriverError::InvalidPDU);
+            }
+            let message = ProxyConfigurationMessage::parse(cleartext_pdu.transport_pdu())?;
+            Ok(Some((message, cleartext_pdu.meta().network_key_handle())))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Secure a proxy configuration message for the proxy client, using
+    /// the network key the client used.
+    pub fn process_outbound_proxy_configuration(
+        &self,
+        secrets: &Secrets,
+        sequence: &Sequence,
+        message: &ProxyConfigurationMessage,
+        src: UnicastAddress,
+        network_key_handle: NetworkKeyHandle,
+        iv_index: IvIndex,
+    ) -> Result<NetworkPDU, DriverError> {
+        let mut transport_pdu = Vec::<u8, 16>::new();
+        message.emit(&mut transport_pdu)?;
+
+        let cleartext_pdu = CleartextNetworkPDU::new(
+            iv_index.ivi(),
+            network_key_handle.nid(),
+            Ctl::Control,
+            Ttl::new(0),
+            sequence.next()?,
+            src,
+            Address::Unassigned,
+            &transport_pdu,
+            NetworkMetadata::new(iv_index, None, network_key_handle),
+        )?;
+
+        self.encrypt_proxy_configuration_pdu(secrets, &cleartext_pdu)
+    }
+}
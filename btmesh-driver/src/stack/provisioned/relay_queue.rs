@@ -0,0 +1,137 @@
+use btmesh_models::foundation::configuration::relay::RelayConfig;
+use btmesh_pdu::provisioned::network::NetworkPDU;
+use embassy_time::{Duration, Instant};
+use heapless::Vec;
+use rand_core::RngCore;
+
+const RELAY_DELAY_MIN_MS: u32 = 20;
+const RELAY_DELAY_MAX_MS: u32 = 50;
+
+/// Random delay before a PDU is relayed for the first time, so that
+/// neighbouring relays hearing the same PDU do not all transmit at once.
+pub fn random_relay_delay<R: RngCore>(rng: &mut R) -> Duration {
+    let delay = RELAY_DELAY_MIN_MS + rng.next_u32() % (RELAY_DELAY_MAX_MS - RELAY_DELAY_MIN_MS + 1);
+    Duration::from_millis(delay as u64)
+}
+
+/// Schedules the transmissions of relayed network PDUs according to
+/// the Relay Retransmit state.
+pub struct RelayQueue<const N: usize = 8> {
+    queue: Vec<RelayQueueEntry, N>,
+}
+
+struct RelayQueueEntry {
+    pdu: NetworkPDU,
+    num_transmissions: u8,
+    num_retransmit: u8,
+    interval: Duration,
+    next: Instant,
+}
+
+impl<const N: usize> Default for RelayQueue<N> {
+    fn default() -> Self {
+        Self { queue: Vec::new() }
+    }
+}
+
+impl<const N: usize> RelayQueue<N> {
+    /// Schedule a PDU heard at `now`, to be relayed after `delay`.
+    pub fn add(&mut self, pdu: NetworkPDU, relay: &RelayConfig, delay: Duration, now: Instant) {
+        let entry = RelayQueueEntry {
+            pdu,
+            num_transmissions: 0,
+            num_retransmit: relay.retransmit_count(),
+            interval: Duration::from_millis(relay.retransmit_interval_millis() as u64),
+            next: now + delay,
+        };
+
+        if self.queue.push(entry).is_err() {
+            warn!("no space in relay queue");
+        }
+    }
+
+    /// The earliest point in time at which a PDU is due for transmission.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.queue.iter().map(|entry| entry.next).min()
+    }
+
+    /// Take the PDUs which are due for transmission at `now`.
+    pub fn take_due(&mut self, now: Instant) -> Vec<NetworkPDU, N> {
+        let mut due = Vec::new();
+
+        for entry in self.queue.iter_mut().filter(|entry| entry.next <= now) {
+            due.push(entry.pdu.clone()).ok();
+            entry.num_transmissions += 1;
+            entry.next = now + entry.interval;
+        }
+
+        self.queue
+            .retain(|entry| entry.num_transmissions <= entry.num_retransmit);
+
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use btmesh_common::crypto::network::Nid;
+    use btmesh_common::Ivi;
+
+    fn pdu() -> NetworkPDU {
+        NetworkPDU::new(Ivi::Zero, Nid::new(0x42), [0; 6], &[0; 12]).unwrap()
+    }
+
+    #[test]
+    fn relay_with_retransmissions() {
+        let mut queue = RelayQueue::<4>::default();
+        let relay = RelayConfig::parse(&[0x01, 0b01000000]).unwrap();
+
+        let now = Instant::from_millis(1_000);
+        let delay = Duration::from_millis(20);
+
+        queue.add(pdu(), &relay, delay, now);
+        assert_eq!(Some(now + delay), queue.next_deadline());
+        assert!(queue.take_due(now).is_empty());
+
+        let mut now = now + delay;
+        assert_eq!(1, queue.take_due(now).len());
+        assert!(queue.take_due(now).is_empty());
+
+        // retransmitted twice, 10ms apart.
+        for _ in 0..2 {
+            assert!(queue.take_due(now + Duration::from_millis(9)).is_empty());
+            now += Duration::from_millis(10);
+            assert_eq!(1, queue.take_due(now).len());
+        }
+
+        assert!(queue.next_deadline().is_none());
+    }
+
+    #[test]
+    fn random_delay_is_bounded() {
+        struct Counter(u32);
+        impl RngCore for Counter {
+            fn next_u32(&mut self) -> u32 {
+                self.0 = self.0.wrapping_add(7);
+                self.0
+            }
+            fn next_u64(&mut self) -> u64 {
+                self.next_u32() as u64
+            }
+            fn fill_bytes(&mut self, dest: &mut [u8]) {
+                dest.fill(self.next_u32() as u8)
+            }
+            fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+                self.fill_bytes(dest);
+                Ok(())
+            }
+        }
+
+        let mut rng = Counter(0);
+        for _ in 0..100 {
+            let delay = random_relay_delay(&mut rng).as_millis() as u32;
+            assert!((RELAY_DELAY_MIN_MS..=RELAY_DELAY_MAX_MS).contains(&delay));
+        }
+    }
+}
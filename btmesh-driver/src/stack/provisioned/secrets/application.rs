@@ -3,15 +3,35 @@ use btmesh_common::crypto::application::{Aid, ApplicationKey};
 use heapless::Vec;
 
 use btmesh_device::ApplicationKeyHandle;
+use btmesh_models::foundation::configuration::key_refresh_phase::KeyRefreshPhase;
 use btmesh_models::foundation::configuration::{AppKeyIndex, NetKeyIndex};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+/// An application key bound to a network key.
+///
+/// During a Key Refresh Procedure of the bound network key, `updated`
+/// holds the new key material until phase 3 revokes the old key.
+#[derive(Copy, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(::defmt::Format))]
+struct ApplicationKeyEntry {
+    net_key_index: NetKeyIndex,
+    key: ApplicationKey,
+    updated: Option<ApplicationKey>,
+}
+
+impl ApplicationKeyEntry {
+    fn receive_keys(&self) -> impl Iterator<Item = &ApplicationKey> + '_ {
+        Some(&self.key).into_iter().chain(self.updated.iter())
+    }
+}
+
 #[derive(Clone, Debug, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "defmt", derive(::defmt::Format))]
 pub struct ApplicationKeys<const N: usize = 4> {
-    keys: Vec<Option<(NetKeyIndex, ApplicationKey)>, N>,
+    keys: Vec<Option<ApplicationKeyEntry>, N>,
 }
 
 impl<const N: usize> Default for ApplicationKeys<N> {
@@ -25,11 +45,14 @@ impl<const N: usize> Default for ApplicationKeys<N> {
 impl<const N: usize> ApplicationKeys<N> {
     pub fn display(&self) {
         for (index, entry) in self.keys.iter().enumerate() {
-            if let Some((net_key_index, app_key)) = entry {
+            if let Some(entry) = entry {
                 info!(
                     "application_key[{}]: {} (net_key_index: {})",
-                    index, app_key, net_key_index
+                    index, entry.key, entry.net_key_index
                 );
+                if let Some(updated) = &entry.updated {
+                    info!("  updated: {}", updated);
+                }
             }
         }
     }
@@ -41,48 +64,68 @@ impl<const N: usize> ApplicationKeys<N> {
         self.keys[usize::from(app_key_index)].is_some()
     }
 
-    pub(crate) fn by_aid_iter(&self, aid: Aid) -> impl Iterator<Item = ApplicationKeyHandle> + '_ {
+    pub(crate) fn by_aid_iter(
+        &self,
+        aid: Aid,
+    ) -> impl Iterator<Item = (ApplicationKeyHandle, ApplicationKey)> + '_ {
         self.keys
             .iter()
             .enumerate()
-            .filter(move |e| {
-                if let (_, Some((_, application_key))) = e {
-                    application_key.aid() == aid
-                } else {
-                    false
-                }
+            .filter_map(|(index, entry)| entry.as_ref().map(|entry| (index, entry)))
+            .flat_map(move |(index, entry)| {
+                entry
+                    .receive_keys()
+                    .filter(move |application_key| application_key.aid() == aid)
+                    .map(move |application_key| {
+                        (
+                            ApplicationKeyHandle::new(AppKeyIndex::new(index as u16), aid),
+                            *application_key,
+                        )
+                    })
             })
-            .map(move |(index, _)| ApplicationKeyHandle::new(AppKeyIndex::new(index as u16), aid))
     }
 
     pub(crate) fn get_key_details(
         &self,
         index: AppKeyIndex,
     ) -> Option<(NetKeyIndex, ApplicationKeyHandle)> {
-        if let Some((net_key_index, app_key)) = self.keys[usize::from(index)] {
-            Some((
-                net_key_index,
-                ApplicationKeyHandle::new(index, app_key.aid()),
-            ))
-        } else {
-            None
+        if usize::from(index) >= N {
+            return None;
         }
+        self.keys[usize::from(index)].as_ref().map(|entry| {
+            (
+                entry.net_key_index,
+                ApplicationKeyHandle::new(index, entry.key.aid()),
+            )
+        })
     }
 
+    /// Retrieve the key to use for transmission, given the phase of the bound network key.
     pub(crate) fn get<I: Into<AppKeyIndex>>(
         &self,
         index: I,
+        phase: KeyRefreshPhase,
     ) -> Result<ApplicationKey, DriverError> {
         let index = index.into();
         debug!("get app-key {}", index);
-        debug!(" --> {}", self.keys);
-        if let Some(entry) = self.keys[usize::from(index)] {
-            Ok(entry.1)
+        if usize::from(index) >= N {
+            return Err(DriverError::InvalidAppKeyIndex);
+        }
+        if let Some(entry) = &self.keys[usize::from(index)] {
+            match (phase, entry.updated) {
+                (KeyRefreshPhase::Phase2, Some(updated)) => Ok(updated),
+                _ => Ok(entry.key),
+            }
         } else {
             Err(DriverError::InvalidAppKeyIndex)
         }
     }
 
+    pub(crate) fn bound_net_key_index(&self, index: AppKeyIndex) -> Option<NetKeyIndex> {
+        self.get_key_details(index)
+            .map(|(net_key_index, _)| net_key_index)
+    }
+
     pub fn add(
         &mut self,
         index: AppKeyIndex,
@@ -93,14 +136,49 @@ impl<const N: usize> ApplicationKeys<N> {
             return Err(DriverError::InvalidAppKeyIndex);
         }
 
-        if let Some(..) = self.keys[usize::from(index)] {
+        if self.keys[usize::from(index)].is_some() {
             Err(DriverError::AppKeyIndexAlreadyStored)
         } else {
-            self.keys[usize::from(index)].replace((net_key_index, application_key));
+            self.keys[usize::from(index)].replace(ApplicationKeyEntry {
+                net_key_index,
+                key: application_key,
+                updated: None,
+            });
             Ok(())
         }
     }
 
+    /// Distribute a new key during phase 1 of the bound network key's Key Refresh Procedure.
+    pub(crate) fn update(
+        &mut self,
+        index: AppKeyIndex,
+        net_key_index: NetKeyIndex,
+        application_key: ApplicationKey,
+        phase: KeyRefreshPhase,
+    ) -> Result<(), DriverError> {
+        if usize::from(index) >= N {
+            return Err(DriverError::InvalidAppKeyIndex);
+        }
+
+        if let Some(entry) = &mut self.keys[usize::from(index)] {
+            if entry.net_key_index != net_key_index {
+                return Err(DriverError::InvalidBinding);
+            }
+            match (phase, &entry.updated) {
+                (KeyRefreshPhase::Phase1, None) => {
+                    entry.updated.replace(application_key);
+                    Ok(())
+                }
+                (KeyRefreshPhase::Phase1, Some(updated)) if **updated == *application_key => {
+                    Ok(())
+                }
+                _ => Err(DriverError::CannotUpdate),
+            }
+        } else {
+            Err(DriverError::InvalidAppKeyIndex)
+        }
+    }
+
     pub(crate) fn delete(
         &mut self,
         index: AppKeyIndex,
@@ -110,8 +188,8 @@ impl<const N: usize> ApplicationKeys<N> {
             return Err(DriverError::InvalidAppKeyIndex);
         }
 
-        if let Some((current_net_key_index, ..)) = self.keys[usize::from(index)] {
-            if net_key_index == current_net_key_index {
+        if let Some(entry) = &self.keys[usize::from(index)] {
+            if net_key_index == entry.net_key_index {
                 self.keys[usize::from(index)].take();
                 Ok(())
             } else {
@@ -121,4 +199,23 @@ impl<const N: usize> ApplicationKeys<N> {
             Err(DriverError::InvalidAppKeyIndex)
         }
     }
+
+    pub(crate) fn delete_bound_to(&mut self, net_key_index: NetKeyIndex) {
+        for entry in self.keys.iter_mut() {
+            if matches!(entry, Some(current) if current.net_key_index == net_key_index) {
+                entry.take();
+            }
+        }
+    }
+
+    /// Complete the Key Refresh Procedure for all keys bound to the network key.
+    pub(crate) fn revoke_bound_to(&mut self, net_key_index: NetKeyIndex) {
+        for entry in self.keys.iter_mut().flatten() {
+            if entry.net_key_index == net_key_index {
+                if let Some(updated) = entry.updated.take() {
+                    entry.key = updated;
+                }
+            }
+        }
+    }
 }
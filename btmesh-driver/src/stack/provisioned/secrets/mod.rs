@@ -6,7 +6,11 @@ use btmesh_common::crypto::device::DeviceKey;
 use btmesh_common::crypto::network::{NetworkKey, Nid};
 use btmesh_pdu::provisioning::ProvisioningData;
 
+use btmesh_common::KeyRefreshFlag;
 use btmesh_device::{ApplicationKeyHandle, NetworkKeyHandle};
+use btmesh_models::foundation::configuration::key_refresh_phase::{
+    KeyRefreshPhase, KeyRefreshTransition,
+};
 use btmesh_models::foundation::configuration::{AppKeyIndex, NetKeyIndex};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
@@ -59,7 +63,7 @@ impl Secrets {
     pub(crate) fn network_keys_by_nid(
         &self,
         nid: Nid,
-    ) -> impl Iterator<Item = NetworkKeyHandle> + '_ {
+    ) -> impl Iterator<Item = (NetworkKeyHandle, NetworkKey)> + '_ {
         self.network_keys.by_nid_iter(nid)
     }
 
@@ -67,17 +71,109 @@ impl Secrets {
         &self,
         network_key: NetworkKeyHandle,
     ) -> Result<NetworkKey, DriverError> {
-        self.network_keys.keys[usize::from(network_key.index()) as usize]
-            .as_ref()
-            .ok_or(DriverError::InvalidKeyHandle)
-            .cloned()
+        self.network_keys
+            .get(network_key.index())
+            .map_err(|_| DriverError::InvalidKeyHandle)
     }
 
-    pub(crate) fn network_key_by_index(&self, index: u8) -> Result<NetworkKey, DriverError> {
-        if let Some(network_key) = self.network_keys.keys[index as usize] {
-            Ok(network_key)
-        } else {
-            Err(DriverError::InvalidKeyHandle)
+    pub(crate) fn network_key_by_index(
+        &self,
+        net_key_index: NetKeyIndex,
+    ) -> Result<NetworkKey, DriverError> {
+        self.network_keys.get(net_key_index)
+    }
+
+    pub(crate) fn network_keys_iter(
+        &self,
+    ) -> impl Iterator<Item = (NetKeyIndex, &NetworkKey)> + '_ {
+        self.network_keys.iter()
+    }
+
+    pub(crate) fn network_keys_receive_iter(
+        &self,
+    ) -> impl Iterator<Item = (NetKeyIndex, &NetworkKey, bool)> + '_ {
+        self.network_keys.receive_iter()
+    }
+
+    pub(crate) fn network_key_indexes(&self) -> impl Iterator<Item = NetKeyIndex> + '_ {
+        self.network_keys.indexes()
+    }
+
+    pub(crate) fn add_network_key(
+        &mut self,
+        net_key_index: NetKeyIndex,
+        network_key: NetworkKey,
+    ) -> Result<(), DriverError> {
+        self.network_keys.add(net_key_index, network_key)
+    }
+
+    pub(crate) fn delete_network_key(
+        &mut self,
+        net_key_index: NetKeyIndex,
+    ) -> Result<(), DriverError> {
+        self.network_keys.delete(net_key_index)?;
+        self.application_keys.delete_bound_to(net_key_index);
+        Ok(())
+    }
+
+    pub(crate) fn update_network_key(
+        &mut self,
+        net_key_index: NetKeyIndex,
+        network_key: NetworkKey,
+    ) -> Result<(), DriverError> {
+        self.network_keys.update(net_key_index, network_key)
+    }
+
+    pub(crate) fn key_refresh_phase(
+        &self,
+        net_key_index: NetKeyIndex,
+    ) -> Result<KeyRefreshPhase, DriverError> {
+        self.network_keys.phase(net_key_index)
+    }
+
+    pub(crate) fn key_refresh_flag(
+        &self,
+        net_key_index: NetKeyIndex,
+    ) -> Result<KeyRefreshFlag, DriverError> {
+        self.network_keys.key_refresh_flag(net_key_index)
+    }
+
+    pub(crate) fn key_refresh_transition(
+        &mut self,
+        net_key_index: NetKeyIndex,
+        transition: KeyRefreshTransition,
+    ) -> Result<KeyRefreshPhase, DriverError> {
+        let previous = self.network_keys.phase(net_key_index)?;
+        let phase = self.network_keys.transition(net_key_index, transition)?;
+        self.complete_key_refresh(net_key_index, previous, phase);
+        Ok(phase)
+    }
+
+    /// Act upon the Key Refresh flag of an authenticated secure network beacon.
+    pub(crate) fn receive_key_refresh_flag(
+        &mut self,
+        net_key_index: NetKeyIndex,
+        flag: KeyRefreshFlag,
+        authenticated_with_updated: bool,
+    ) -> Result<KeyRefreshPhase, DriverError> {
+        let previous = self.network_keys.phase(net_key_index)?;
+        let phase = self.network_keys.receive_key_refresh_flag(
+            net_key_index,
+            flag,
+            authenticated_with_updated,
+        )?;
+        self.complete_key_refresh(net_key_index, previous, phase);
+        Ok(phase)
+    }
+
+    fn complete_key_refresh(
+        &mut self,
+        net_key_index: NetKeyIndex,
+        previous: KeyRefreshPhase,
+        phase: KeyRefreshPhase,
+    ) {
+        if previous != KeyRefreshPhase::Normal && phase == KeyRefreshPhase::Normal {
+            self.application_keys.revoke_bound_to(net_key_index);
         }
     }
 
@@ -88,7 +184,7 @@ impl Secrets {
         if let Some((net_key_index, app_key_handle)) =
             self.application_keys.get_key_details(app_key_index)
         {
-            self.network_keys.keys[usize::from(net_key_index)].map(|net_key| {
+            self.network_keys.get(net_key_index).ok().map(|net_key| {
                 (
                     NetworkKeyHandle::new(net_key_index, net_key.nid()),
                     app_key_handle,
@@ -105,7 +201,7 @@ impl Secrets {
         app_key_index: AppKeyIndex,
         app_key: ApplicationKey,
     ) -> Result<(), DriverError> {
-        if self.network_keys.keys[usize::from(net_key_index)].is_some() {
+        if self.network_keys.has_key(net_key_index) {
             self.application_keys
                 .add(app_key_index, net_key_index, app_key)?;
             Ok(())
@@ -119,7 +215,7 @@ impl Secrets {
         net_key_index: NetKeyIndex,
         app_key_index: AppKeyIndex,
     ) -> Result<(), DriverError> {
-        if self.network_keys.keys[usize::from(net_key_index)].is_some() {
+        if self.network_keys.has_key(net_key_index) {
             self.application_keys.delete(app_key_index, net_key_index)?;
             Ok(())
         } else {
@@ -130,7 +226,7 @@ impl Secrets {
     pub(crate) fn application_keys_by_aid(
         &self,
         aid: Aid,
-    ) -> impl Iterator<Item = ApplicationKeyHandle> + '_ {
+    ) -> impl Iterator<Item = (ApplicationKeyHandle, ApplicationKey)> + '_ {
         self.application_keys.by_aid_iter(aid)
     }
 
@@ -138,10 +234,204 @@ impl Secrets {
         &self,
         application_key: ApplicationKeyHandle,
     ) -> Result<ApplicationKey, DriverError> {
-        self.application_keys.get(application_key)
+        let phase = self
+            .application_keys
+            .bound_net_key_index(application_key.into())
+            .map(|net_key_index| self.network_keys.phase(net_key_index))
+            .transpose()?
+            .unwrap_or_default();
+        self.application_keys.get(application_key, phase)
+    }
+
+    pub(crate) fn update_application_key(
+        &mut self,
+        net_key_index: NetKeyIndex,
+        app_key_index: AppKeyIndex,
+        app_key: ApplicationKey,
+    ) -> Result<(), DriverError> {
+        let phase = self.network_keys.phase(net_key_index)?;
+        self.application_keys
+            .update(app_key_index, net_key_index, app_key, phase)
     }
 
     pub(crate) fn has_application_key(&self, app_key_index: AppKeyIndex) -> bool {
         self.application_keys.has_key(app_key_index)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::provisioned::fixture::config;
+
+    struct Fixture {
+        secrets: Secrets,
+        net_key_index: NetKeyIndex,
+        app_key_index: AppKeyIndex,
+        old_net_key: NetworkKey,
+        new_net_key: NetworkKey,
+        old_app_key: ApplicationKey,
+        new_app_key: ApplicationKey,
+    }
+
+    /// Secrets in phase 1, with both the network and application keys updated.
+    fn phase1() -> Fixture {
+        let net_key_index = NetKeyIndex::new(0);
+        let app_key_index = AppKeyIndex::new(0);
+        let old_net_key = NetworkKey::new([0x01; 16]).unwrap();
+        let new_net_key = NetworkKey::new([0x02; 16]).unwrap();
+        let old_app_key = ApplicationKey::new([0x0A; 16]).unwrap();
+        let new_app_key = ApplicationKey::new([0x0B; 16]).unwrap();
+        assert_ne!(old_app_key.aid(), new_app_key.aid());
+
+        let mut secrets = config(old_net_key).secrets().clone();
+        secrets
+            .add_application_key(net_key_index, app_key_index, old_app_key)
+            .unwrap();
+
+        // application keys can only be updated once the network key is.
+        assert_eq!(
+            Err(DriverError::CannotUpdate),
+            secrets.update_application_key(net_key_index, app_key_index, new_app_key)
+        );
+        secrets
+            .update_network_key(net_key_index, new_net_key)
+            .unwrap();
+        secrets
+            .update_application_key(net_key_index, app_key_index, new_app_key)
+            .unwrap();
+
+        Fixture {
+            secrets,
+            net_key_index,
+            app_key_index,
+            old_net_key,
+            new_net_key,
+            old_app_key,
+            new_app_key,
+        }
+    }
+
+    impl Fixture {
+        fn transmit_keys(&self) -> (NetworkKey, ApplicationKey) {
+            let (_, app_key_handle) = self.secrets.get_key_pair(self.app_key_index).unwrap();
+            (
+                self.secrets
+                    .network_key_by_index(self.net_key_index)
+                    .unwrap(),
+                self.secrets.application_key(app_key_handle).unwrap(),
+            )
+        }
+    }
+
+    #[test]
+    fn transmit_keys_per_phase() {
+        let mut fixture = phase1();
+
+        // phase 1: transmit with the old keys, receive with both.
+        let (net_key, app_key) = fixture.transmit_keys();
+        assert_eq!(fixture.old_net_key, net_key);
+        assert_eq!(fixture.old_app_key.aid(), app_key.aid());
+        assert!(
+            !fixture
+                .secrets
+                .key_refresh_flag(fixture.net_key_index)
+                .unwrap()
+                .0
+        );
+        assert_eq!(
+            1,
+            fixture
+                .secrets
+                .application_keys_by_aid(fixture.new_app_key.aid())
+                .count()
+        );
+
+        // phase 2: transmit with the new keys, still receive with both.
+        assert_eq!(
+            KeyRefreshPhase::Phase2,
+            fixture
+                .secrets
+                .key_refresh_transition(fixture.net_key_index, KeyRefreshTransition::Phase2)
+                .unwrap()
+        );
+        let (net_key, app_key) = fixture.transmit_keys();
+        assert_eq!(fixture.new_net_key, net_key);
+        assert_eq!(fixture.new_app_key.aid(), app_key.aid());
+        assert!(
+            fixture
+                .secrets
+                .key_refresh_flag(fixture.net_key_index)
+                .unwrap()
+                .0
+        );
+        assert_eq!(
+            1,
+            fixture
+                .secrets
+                .network_keys_by_nid(fixture.old_net_key.nid())
+                .count()
+        );
+        assert_eq!(
+            1,
+            fixture
+                .secrets
+                .application_keys_by_aid(fixture.old_app_key.aid())
+                .count()
+        );
+
+        // going back to phase 1 is prohibited.
+        assert_eq!(
+            KeyRefreshPhase::Phase2,
+            fixture
+                .secrets
+                .key_refresh_transition(fixture.net_key_index, KeyRefreshTransition::Phase2)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn phase3_revokes_old_keys() {
+        let mut fixture = phase1();
+        assert_eq!(
+            KeyRefreshPhase::Normal,
+            fixture
+                .secrets
+                .key_refresh_transition(fixture.net_key_index, KeyRefreshTransition::Phase3)
+                .unwrap()
+        );
+
+        // the old keys are gone, both for reception and transmission.
+        assert_eq!(
+            0,
+            fixture
+                .secrets
+                .network_keys_by_nid(fixture.old_net_key.nid())
+                .count()
+        );
+        assert_eq!(
+            0,
+            fixture
+                .secrets
+                .application_keys_by_aid(fixture.old_app_key.aid())
+                .count()
+        );
+        let (net_key, app_key) = fixture.transmit_keys();
+        assert_eq!(fixture.new_net_key, net_key);
+        assert_eq!(fixture.new_app_key.aid(), app_key.aid());
+
+        // and a new procedure can be started.
+        let newer = NetworkKey::new([0x03; 16]).unwrap();
+        fixture
+            .secrets
+            .update_network_key(fixture.net_key_index, newer)
+            .unwrap();
+        assert_eq!(
+            KeyRefreshPhase::Phase1,
+            fixture
+                .secrets
+                .key_refresh_phase(fixture.net_key_index)
+                .unwrap()
+        );
+    }
+}
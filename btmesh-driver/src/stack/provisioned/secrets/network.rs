@@ -1,65 +1,258 @@
 use crate::stack::provisioned::DriverError;
 use btmesh_common::crypto::network::{NetworkKey, Nid};
+use btmesh_common::KeyRefreshFlag;
 use btmesh_device::NetworkKeyHandle;
+use btmesh_models::foundation::configuration::key_refresh_phase::{
+    KeyRefreshPhase, KeyRefreshTransition,
+};
 use btmesh_models::foundation::configuration::NetKeyIndex;
 use btmesh_pdu::provisioning::ProvisioningData;
 use heapless::Vec;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+/// A network key along with its Key Refresh Procedure state.
+///
+/// While a refresh is in progress, `updated` holds the new key material
+/// and `key` the old one, which is kept around until phase 3 revokes it.
+#[derive(Copy, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(::defmt::Format))]
+struct NetworkKeyEntry {
+    index: NetKeyIndex,
+    key: NetworkKey,
+    updated: Option<NetworkKey>,
+    phase: KeyRefreshPhase,
+}
+
+impl NetworkKeyEntry {
+    fn new(index: NetKeyIndex, key: NetworkKey) -> Self {
+        Self {
+            index,
+            key,
+            updated: None,
+            phase: KeyRefreshPhase::Normal,
+        }
+    }
+
+    /// The key to be used for transmission given the current phase.
+    fn transmit_key(&self) -> &NetworkKey {
+        match (self.phase, &self.updated) {
+            (KeyRefreshPhase::Phase2, Some(updated)) => updated,
+            _ => &self.key,
+        }
+    }
+
+    /// All keys acceptable for reception given the current phase.
+    fn receive_keys(&self) -> impl Iterator<Item = &NetworkKey> + '_ {
+        Some(&self.key).into_iter().chain(self.updated.iter())
+    }
+
+    fn revoke(&mut self) {
+        if let Some(updated) = self.updated.take() {
+            self.key = updated;
+        }
+        self.phase = KeyRefreshPhase::Normal;
+    }
+}
+
+/// Network keys of the node, as entries keyed by NetKey index.
+///
+/// Configurations persisted with the earlier slot-per-index layout are not
+/// migrated: they fail to load, and the node has to be provisioned again.
 #[derive(Clone, Debug, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "defmt", derive(::defmt::Format))]
 pub struct NetworkKeys<const N: usize = 4> {
-    pub(crate) keys: Vec<Option<NetworkKey>, N>,
+    keys: Vec<NetworkKeyEntry, N>,
 }
 
 impl<const N: usize> Default for NetworkKeys<N> {
     fn default() -> Self {
-        let mut keys = Vec::new();
-        keys.resize(N, None).ok();
-        Self { keys }
+        Self {
+            keys: Default::default(),
+        }
     }
 }
 
 impl<const N: usize> From<ProvisioningData> for NetworkKeys<N> {
     fn from(data: ProvisioningData) -> Self {
         let mut keys = Self::default();
-        keys.keys[0].replace(NetworkKey::new(data.network_key).unwrap());
+        let key = NetworkKey::new(data.network_key).unwrap();
+        let mut entry = NetworkKeyEntry::new(NetKeyIndex::new(data.key_index), key);
+        if data.key_refresh_flag.0 {
+            // provisioned mid-refresh, the provisioned key is the new key.
+            entry.updated.replace(key);
+            entry.phase = KeyRefreshPhase::Phase2;
+        }
+        keys.keys.push(entry).ok();
         keys
     }
 }
 
 impl<const N: usize> NetworkKeys<N> {
     pub fn display(&self) {
-        for (index, key) in self.keys.iter().enumerate() {
-            if let Some(key) = key {
-                info!("network_key[{}]: {}", index, key);
+        for entry in self.keys.iter() {
+            info!(
+                "network_key[{}]: {} ({})",
+                entry.index, entry.key, entry.phase
+            );
+            if let Some(updated) = &entry.updated {
+                info!("  updated: {}", updated);
             }
         }
     }
 
-    pub(crate) fn by_nid_iter(&self, nid: Nid) -> impl Iterator<Item = NetworkKeyHandle> + '_ {
+    pub(crate) fn by_nid_iter(
+        &self,
+        nid: Nid,
+    ) -> impl Iterator<Item = (NetworkKeyHandle, NetworkKey)> + '_ {
+        self.keys.iter().flat_map(move |entry| {
+            entry
+                .receive_keys()
+                .filter(move |network_key| network_key.nid() == nid)
+                .map(move |network_key| (NetworkKeyHandle::new(entry.index, nid), *network_key))
+        })
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (NetKeyIndex, &NetworkKey)> + '_ {
         self.keys
             .iter()
-            .enumerate()
-            .filter(move |e| {
-                if let (_, Some(network_key)) = e {
-                    network_key.nid() == nid
-                } else {
-                    false
-                }
-            })
-            .map(move |(index, _)| NetworkKeyHandle::new(NetKeyIndex::new(index as u16), nid))
-    }
-
-    pub fn set(&mut self, index: u8, network_key: NetworkKey) -> Result<(), DriverError> {
-        if index as usize >= N {
-            return Err(DriverError::InsufficientSpace);
+            .map(|entry| (entry.index, entry.transmit_key()))
+    }
+
+    /// All keys acceptable for reception, each flagged if it is
+    /// the new key of a refresh in progress.
+    pub(crate) fn receive_iter(
+        &self,
+    ) -> impl Iterator<Item = (NetKeyIndex, &NetworkKey, bool)> + '_ {
+        self.keys.iter().flat_map(|entry| {
+            Some((entry.index, &entry.key, false))
+                .into_iter()
+                .chain(entry.updated.iter().map(|updated| (entry.index, updated, true)))
+        })
+    }
+
+    pub(crate) fn indexes(&self) -> impl Iterator<Item = NetKeyIndex> + '_ {
+        self.keys.iter().map(|entry| entry.index)
+    }
+
+    pub(crate) fn has_key(&self, index: NetKeyIndex) -> bool {
+        self.keys.iter().any(|entry| entry.index == index)
+    }
+
+    fn entry(&self, index: NetKeyIndex) -> Result<&NetworkKeyEntry, DriverError> {
+        self.keys
+            .iter()
+            .find(|entry| entry.index == index)
+            .ok_or(DriverError::InvalidNetKeyIndex)
+    }
+
+    fn entry_mut(&mut self, index: NetKeyIndex) -> Result<&mut NetworkKeyEntry, DriverError> {
+        self.keys
+            .iter_mut()
+            .find(|entry| entry.index == index)
+            .ok_or(DriverError::InvalidNetKeyIndex)
+    }
+
+    /// Retrieve the key to use for transmission on the subnet.
+    pub(crate) fn get(&self, index: NetKeyIndex) -> Result<NetworkKey, DriverError> {
+        Ok(*self.entry(index)?.transmit_key())
+    }
+
+    pub(crate) fn phase(&self, index: NetKeyIndex) -> Result<KeyRefreshPhase, DriverError> {
+        Ok(self.entry(index)?.phase)
+    }
+
+    pub(crate) fn key_refresh_flag(&self, index: NetKeyIndex) -> Result<KeyRefreshFlag, DriverError> {
+        Ok(KeyRefreshFlag(
+            self.entry(index)?.phase == KeyRefreshPhase::Phase2,
+        ))
+    }
+
+    pub fn add(&mut self, index: NetKeyIndex, network_key: NetworkKey) -> Result<(), DriverError> {
+        if let Ok(current) = self.entry(index) {
+            if current.key == network_key {
+                // re-adding the same key is idempotent.
+                return Ok(());
+            }
+            return Err(DriverError::NetKeyIndexAlreadyStored);
         }
 
-        self.keys[index as usize].replace(network_key);
+        self.keys
+            .push(NetworkKeyEntry::new(index, network_key))
+            .map_err(|_| DriverError::InsufficientSpace)
+    }
+
+    /// Distribute a new key for the subnet, starting phase 1 of the Key Refresh Procedure.
+    pub(crate) fn update(
+        &mut self,
+        index: NetKeyIndex,
+        network_key: NetworkKey,
+    ) -> Result<(), DriverError> {
+        let entry = self.entry_mut(index)?;
+        match (entry.phase, &entry.updated) {
+            (KeyRefreshPhase::Normal, _) => {
+                entry.updated.replace(network_key);
+                entry.phase = KeyRefreshPhase::Phase1;
+                Ok(())
+            }
+            (KeyRefreshPhase::Phase1, Some(updated)) if *updated == network_key => Ok(()),
+            _ => Err(DriverError::CannotUpdate),
+        }
+    }
 
+    /// Apply an explicit phase transition, returning the resulting phase.
+    ///
+    /// A transition to phase 3 revokes the old key and immediately
+    /// returns the subnet to normal operation.
+    pub(crate) fn transition(
+        &mut self,
+        index: NetKeyIndex,
+        transition: KeyRefreshTransition,
+    ) -> Result<KeyRefreshPhase, DriverError> {
+        let entry = self.entry_mut(index)?;
+        match (entry.phase, transition) {
+            (KeyRefreshPhase::Phase1, KeyRefreshTransition::Phase2) => {
+                entry.phase = KeyRefreshPhase::Phase2;
+            }
+            (KeyRefreshPhase::Phase1 | KeyRefreshPhase::Phase2, KeyRefreshTransition::Phase3) => {
+                entry.revoke();
+            }
+            _ => {
+                // prohibited or no-op transitions leave the phase untouched.
+            }
+        }
+        Ok(entry.phase)
+    }
+
+    /// Apply the Key Refresh flag of an authenticated secure network beacon.
+    ///
+    /// Only beacons secured with the new key may drive the procedure forward.
+    pub(crate) fn receive_key_refresh_flag(
+        &mut self,
+        index: NetKeyIndex,
+        flag: KeyRefreshFlag,
+        authenticated_with_updated: bool,
+    ) -> Result<KeyRefreshPhase, DriverError> {
+        if !authenticated_with_updated {
+            return self.phase(index);
+        }
+        let transition = if flag.0 {
+            KeyRefreshTransition::Phase2
+        } else {
+            KeyRefreshTransition::Phase3
+        };
+        self.transition(index, transition)
+    }
+
+    pub(crate) fn delete(&mut self, index: NetKeyIndex) -> Result<(), DriverError> {
+        if let Some(position) = self.keys.iter().position(|entry| entry.index == index) {
+            if self.keys.len() == 1 {
+                return Err(DriverError::CannotRemove);
+            }
+            self.keys.swap_remove(position);
+        }
         Ok(())
     }
 }
@@ -67,7 +260,13 @@ impl<const N: usize> NetworkKeys<N> {
 #[cfg(test)]
 mod tests {
     use crate::stack::provisioned::secrets::network::NetworkKeys;
+    use crate::DriverError;
     use btmesh_common::crypto::network::{EncryptionKey, NetworkKey, Nid, PrivacyKey};
+    use btmesh_common::KeyRefreshFlag;
+    use btmesh_models::foundation::configuration::key_refresh_phase::{
+        KeyRefreshPhase, KeyRefreshTransition,
+    };
+    use btmesh_models::foundation::configuration::NetKeyIndex;
 
     #[test]
     fn network_key_iteration_empty() {
@@ -82,6 +281,97 @@ mod tests {
         assert_eq!(0, found)
     }
 
+    #[test]
+    fn network_key_add_delete() {
+        let mut keys = NetworkKeys::<2>::default();
+        let primary = NetworkKey::new([0x01; 16]).unwrap();
+        let secondary = NetworkKey::new([0x02; 16]).unwrap();
+
+        keys.add(NetKeyIndex::new(0), primary).unwrap();
+        keys.add(NetKeyIndex::new(0x123), secondary).unwrap();
+
+        // same key at the same index is fine, a different one is not.
+        assert!(keys.add(NetKeyIndex::new(0x123), secondary).is_ok());
+        assert_eq!(
+            Err(DriverError::NetKeyIndexAlreadyStored),
+            keys.add(NetKeyIndex::new(0x123), primary)
+        );
+        assert_eq!(
+            Err(DriverError::InsufficientSpace),
+            keys.add(NetKeyIndex::new(7), primary)
+        );
+
+        assert_eq!(1, keys.by_nid_iter(secondary.nid()).count());
+        assert_eq!(
+            secondary.network_id(),
+            keys.get(NetKeyIndex::new(0x123)).unwrap().network_id()
+        );
+
+        keys.delete(NetKeyIndex::new(0x123)).unwrap();
+        assert!(!keys.has_key(NetKeyIndex::new(0x123)));
+
+        // the last key cannot be removed.
+        assert_eq!(
+            Err(DriverError::CannotRemove),
+            keys.delete(NetKeyIndex::new(0))
+        );
+    }
+
+    #[test]
+    fn network_key_refresh() {
+        let mut keys = NetworkKeys::<2>::default();
+        let old = NetworkKey::new([0x01; 16]).unwrap();
+        let new = NetworkKey::new([0x02; 16]).unwrap();
+        let index = NetKeyIndex::new(0);
+
+        keys.add(index, old).unwrap();
+
+        // beacons alone cannot start the procedure.
+        assert_eq!(
+            KeyRefreshPhase::Normal,
+            keys.receive_key_refresh_flag(index, KeyRefreshFlag(true), true)
+                .unwrap()
+        );
+
+        keys.update(index, new).unwrap();
+        assert!(keys.update(index, new).is_ok());
+        assert_eq!(Err(DriverError::CannotUpdate), keys.update(index, old));
+
+        // phase 1: receive with either, transmit with old.
+        assert_eq!(KeyRefreshPhase::Phase1, keys.phase(index).unwrap());
+        assert_eq!(1, keys.by_nid_iter(old.nid()).count());
+        assert_eq!(1, keys.by_nid_iter(new.nid()).count());
+        assert_eq!(old.network_id(), keys.get(index).unwrap().network_id());
+        assert!(keys
+            .receive_iter()
+            .any(|(_, key, is_updated)| is_updated && key.network_id() == new.network_id()));
+
+        // beacons secured with the old key do not advance the procedure.
+        assert_eq!(
+            KeyRefreshPhase::Phase1,
+            keys.receive_key_refresh_flag(index, KeyRefreshFlag(true), false)
+                .unwrap()
+        );
+
+        // phase 2: transmit with new.
+        assert_eq!(
+            KeyRefreshPhase::Phase2,
+            keys.receive_key_refresh_flag(index, KeyRefreshFlag(true), true)
+                .unwrap()
+        );
+        assert_eq!(new.network_id(), keys.get(index).unwrap().network_id());
+        assert!(keys.key_refresh_flag(index).unwrap().0);
+
+        // phase 3: old key revoked.
+        assert_eq!(
+            KeyRefreshPhase::Normal,
+            keys.transition(index, KeyRefreshTransition::Phase3)
+                .unwrap()
+        );
+        assert_eq!(0, keys.by_nid_iter(old.nid()).count());
+        assert_eq!(new.network_id(), keys.get(index).unwrap().network_id());
+    }
+
     #[test]
     fn network_key_derivation() {
         // 8.2.2 Encryption and privacy keys (Master)
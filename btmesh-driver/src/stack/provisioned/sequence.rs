@@ -1,22 +1,82 @@
-use btmesh_common::Seq;
-use core::sync::atomic::{AtomicU32, Ordering};
+use crate::stack::provisioned::iv_update::IV_UPDATE_SEQ_THRESHOLD;
+use btmesh_common::{Seq, SeqRolloverError};
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+/// Largest sequence number representable in the 24-bit SEQ field.
+pub const SEQ_MAX: u32 = 0xFFFFFF;
 
 pub struct Sequence {
     seq: AtomicU32,
+    iv_update_requested: AtomicBool,
 }
 
 impl Sequence {
     pub fn new(initial_seq: Seq) -> Self {
         Self {
             seq: AtomicU32::new(initial_seq.value()),
+            iv_update_requested: AtomicBool::new(initial_seq.value() >= IV_UPDATE_SEQ_THRESHOLD),
         }
     }
 
-    pub fn next(&self) -> Seq {
-        Seq::new(self.seq.fetch_add(1, Ordering::Relaxed))
+    /// Allocate the next sequence number.
+    ///
+    /// Crossing the IV Update threshold requests an IV Update, and once the
+    /// 24-bit space is exhausted no further sequence numbers are handed out
+    /// until a new IV Index is in use.
+    pub fn next(&self) -> Result<Seq, SeqRolloverError> {
+        let seq = self
+            .seq
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |seq| {
+                if seq <= SEQ_MAX {
+                    Some(seq + 1)
+                } else {
+                    None
+                }
+            })
+            .map_err(|_| SeqRolloverError)?;
+
+        if seq == IV_UPDATE_SEQ_THRESHOLD {
+            self.iv_update_requested.store(true, Ordering::Relaxed);
+        }
+        Ok(Seq::new(seq))
     }
 
     pub fn current(&self) -> u32 {
         self.seq.load(Ordering::Relaxed)
     }
+
+    /// Whether sequence numbers are running out and an IV Update should be
+    /// initiated, clearing the request.
+    pub fn take_iv_update_request(&self) -> bool {
+        self.iv_update_requested.swap(false, Ordering::Relaxed)
+    }
+
+    /// Start afresh, once a new IV Index is in use.
+    pub fn reset(&self) {
+        self.seq.store(0, Ordering::Relaxed);
+        self.iv_update_requested.store(false, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refuse_once_exhausted() {
+        let sequence = Sequence::new(Seq::new(IV_UPDATE_SEQ_THRESHOLD - 1));
+        assert!(!sequence.take_iv_update_request());
+        sequence.next().unwrap();
+        sequence.next().unwrap();
+        assert!(sequence.take_iv_update_request());
+        assert!(!sequence.take_iv_update_request());
+
+        let sequence = Sequence::new(Seq::new(SEQ_MAX));
+        assert_eq!(SEQ_MAX, sequence.next().unwrap().value());
+        assert!(sequence.next().is_err());
+        assert!(sequence.next().is_err());
+
+        sequence.reset();
+        assert_eq!(0, sequence.next().unwrap().value());
+    }
 }
@@ -2,11 +2,13 @@ use crate::stack::provisioned::ProvisionedStack;
 use crate::DriverError;
 use btmesh_common::address::{Address, LabelUuid, UnicastAddress};
 use btmesh_common::crypto::application::Aid;
+use btmesh_common::mic::SzMic;
 use btmesh_common::{IvIndex, Seq, SeqAuth, SeqZero, Ttl};
 use btmesh_device::{
     ApplicationKeyHandle, InboundMetadata, KeyHandle, NetworkKeyHandle, OutboundMetadata,
 };
 use btmesh_pdu::provisioned::access::AccessMessage;
+use btmesh_pdu::provisioned::control::ControlMessage;
 use btmesh_pdu::provisioned::lower::{LowerPDU, SegmentedLowerPDU, UnsegmentedLowerPDU};
 use btmesh_pdu::provisioned::network::CleartextNetworkPDU;
 use btmesh_pdu::provisioned::upper::access::UpperAccessPDU;
@@ -23,6 +25,7 @@ pub struct NetworkMetadata {
     should_relay: bool,
     local_element_index: Option<u8>,
     network_key_handle: NetworkKeyHandle,
+    friendship_credentials: bool,
 }
 
 impl NetworkMetadata {
@@ -37,6 +40,7 @@ impl NetworkMetadata {
             should_relay: false,
             local_element_index,
             network_key_handle: network_key,
+            friendship_credentials: false,
         }
     }
 
@@ -68,6 +72,16 @@ impl NetworkMetadata {
         self.local_element_index
     }
 
+    /// Mark the PDU as secured with the credentials of a friendship
+    /// rather than the master credentials of its subnet.
+    pub fn friendship_credentials(&mut self, friendship: bool) {
+        self.friendship_credentials = friendship;
+    }
+
+    pub fn is_friendship_credentials(&self) -> bool {
+        self.friendship_credentials
+    }
+
     pub fn from_upper_pdu(pdu: &UpperPDU<ProvisionedStack>) -> Self {
         Self {
             iv_index: pdu.meta().iv_index(),
@@ -75,6 +89,7 @@ impl NetworkMetadata {
             should_relay: false,
             local_element_index: pdu.meta().local_element_index(),
             network_key_handle: pdu.meta().network_key_handle(),
+            friendship_credentials: false,
         }
     }
 
@@ -85,6 +100,7 @@ impl NetworkMetadata {
             should_relay: false,
             local_element_index: pdu.meta().local_element_index(),
             network_key_handle: pdu.meta().network_key_handle(),
+            friendship_credentials: false,
         }
     }
 }
@@ -99,6 +115,7 @@ pub struct LowerMetadata {
     dst: Address,
     ttl: Ttl,
     seq: Seq,
+    friendship_credentials: bool,
 }
 
 impl LowerMetadata {
@@ -118,6 +135,7 @@ impl LowerMetadata {
             dst,
             seq,
             ttl,
+            friendship_credentials: false,
         }
     }
 
@@ -130,6 +148,7 @@ impl LowerMetadata {
             dst: pdu.dst(),
             seq: pdu.seq(),
             ttl: pdu.ttl(),
+            friendship_credentials: pdu.meta().is_friendship_credentials(),
         }
     }
 
@@ -173,6 +192,10 @@ impl LowerMetadata {
     pub fn local_element_index(&self) -> Option<u8> {
         self.local_element_index
     }
+
+    pub fn is_friendship_credentials(&self) -> bool {
+        self.friendship_credentials
+    }
 }
 
 #[derive(Clone)]
@@ -189,6 +212,7 @@ pub struct UpperMetadata {
     pub(crate) label_uuids: Vec<LabelUuid, 3>,
     pub(crate) seq_auth: Option<SeqAuth>,
     pub(crate) replay_seq: Option<Seq>,
+    pub(crate) friendship_credentials: bool,
 }
 
 impl UpperMetadata {
@@ -226,6 +250,7 @@ impl UpperMetadata {
             label_uuids: Default::default(),
             seq_auth: Some(seq_auth),
             replay_seq: Some(Self::first_seq_number(seq, seq_zero)),
+            friendship_credentials: pdu.meta().is_friendship_credentials(),
         }
     }
 
@@ -246,6 +271,7 @@ impl UpperMetadata {
             label_uuids: Default::default(),
             seq_auth: None,
             replay_seq: Some(pdu.meta().seq()),
+            friendship_credentials: pdu.meta().is_friendship_credentials(),
         }
     }
 
@@ -272,6 +298,24 @@ impl UpperMetadata {
             label_uuids: Default::default(),
             seq_auth: None,
             replay_seq: Some(seq),
+            friendship_credentials: false,
+        }
+    }
+
+    pub fn from_control_message(message: &ControlMessage<ProvisionedStack>, seq: Seq) -> Self {
+        Self {
+            network_key_handle: message.meta().network_key_handle(),
+            iv_index: message.meta().iv_index(),
+            local_element_index: None,
+            akf_aid: None,
+            seq,
+            src: message.meta().src(),
+            dst: message.meta().dst(),
+            ttl: message.meta().ttl(),
+            label_uuids: Default::default(),
+            seq_auth: None,
+            replay_seq: Some(seq),
+            friendship_credentials: false,
         }
     }
 
@@ -303,6 +347,10 @@ impl UpperMetadata {
         self.replay_seq
     }
 
+    pub fn is_friendship_credentials(&self) -> bool {
+        self.friendship_credentials
+    }
+
     pub fn src(&self) -> UnicastAddress {
         self.src
     }
@@ -339,6 +387,8 @@ pub struct AccessMetadata {
     pub(crate) ttl: Ttl,
     pub(crate) label_uuid: Option<LabelUuid>,
     pub(crate) replay_seq: Option<Seq>,
+    /// Size of the TransMIC, chosen automatically when sending if `None`.
+    pub(crate) szmic: Option<SzMic>,
 }
 
 impl From<(UnicastAddress, OutboundMetadata, Ttl)> for AccessMetadata {
@@ -357,6 +407,7 @@ impl From<(UnicastAddress, OutboundMetadata, Ttl)> for AccessMetadata {
             ttl: meta.ttl().unwrap_or(default_ttl),
             label_uuid: None,
             replay_seq: None,
+            szmic: meta.szmic(),
         }
     }
 }
@@ -381,6 +432,7 @@ impl AccessMetadata {
             } else {
                 pdu.meta().seq()
             }),
+            szmic: Some(pdu.transmic().szmic()),
         }
     }
 
@@ -388,6 +440,10 @@ impl AccessMetadata {
         self.replay_seq
     }
 
+    pub fn szmic(&self) -> Option<SzMic> {
+        self.szmic
+    }
+
     pub fn network_key_handle(&self) -> NetworkKeyHandle {
         self.network_key_handle
     }
@@ -437,11 +493,67 @@ impl From<&AccessMetadata> for InboundMetadata {
 
 #[derive(Clone)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
-pub struct ControlMetadata {}
+pub struct ControlMetadata {
+    pub(crate) network_key_handle: NetworkKeyHandle,
+    pub(crate) iv_index: IvIndex,
+    pub(crate) src: UnicastAddress,
+    pub(crate) dst: Address,
+    pub(crate) ttl: Ttl,
+    pub(crate) friendship_credentials: bool,
+}
 
 impl ControlMetadata {
-    pub fn from_upper_control_pdu(_pdu: &UpperControlPDU<ProvisionedStack>) -> Self {
-        Self {}
+    pub fn new(
+        network_key_handle: NetworkKeyHandle,
+        iv_index: IvIndex,
+        src: UnicastAddress,
+        dst: Address,
+        ttl: Ttl,
+    ) -> Self {
+        Self {
+            network_key_handle,
+            iv_index,
+            src,
+            dst,
+            ttl,
+            friendship_credentials: false,
+        }
+    }
+
+    pub fn from_upper_control_pdu(pdu: &UpperControlPDU<ProvisionedStack>) -> Self {
+        Self {
+            network_key_handle: pdu.meta().network_key_handle(),
+            iv_index: pdu.meta().iv_index(),
+            src: pdu.meta().src(),
+            dst: pdu.meta().dst(),
+            ttl: pdu.meta().ttl(),
+            friendship_credentials: pdu.meta().is_friendship_credentials(),
+        }
+    }
+
+    pub fn network_key_handle(&self) -> NetworkKeyHandle {
+        self.network_key_handle
+    }
+
+    pub fn iv_index(&self) -> IvIndex {
+        self.iv_index
+    }
+
+    pub fn src(&self) -> UnicastAddress {
+        self.src
+    }
+
+    pub fn dst(&self) -> Address {
+        self.dst
+    }
+
+    pub fn ttl(&self) -> Ttl {
+        self.ttl
+    }
+
+    /// Whether the message was secured with the credentials of a friendship.
+    pub fn is_friendship_credentials(&self) -> bool {
+        self.friendship_credentials
     }
 }
 
@@ -1,12 +1,26 @@
 use crate::stack::provisioned::ProvisionedStack;
-use crate::{DriverError, Watchdog};
-use btmesh_common::{address::Address, InsufficientBuffer, SeqZero};
-use btmesh_device::CompletionToken;
+use crate::DriverError;
+use btmesh_common::address::{Address, UnicastAddress};
+use btmesh_common::{InsufficientBuffer, SeqZero, Ttl};
+use btmesh_device::{CompletionToken, PublicationRetransmission};
 use btmesh_pdu::provisioned::lower::{BlockAck, InvalidBlock};
 use btmesh_pdu::provisioned::upper::UpperPDU;
 use embassy_time::{Duration, Instant};
 use heapless::Vec;
 
+/// Retransmissions of the unacknowledged segments of a message to a unicast
+/// destination, without any of them being acknowledged, before giving up.
+const UNICAST_RETRANSMISSIONS: u8 = 2;
+
+/// Retransmissions of the segments of a message to a group or virtual destination,
+/// which cannot acknowledge them.
+const MULTICAST_RETRANSMISSIONS: u8 = 2;
+
+/// The segment transmission timer, 200 + 50 × TTL milliseconds.
+fn segment_transmission_interval(ttl: Ttl) -> Duration {
+    Duration::from_millis(200 + 50 * ttl.value() as u64)
+}
+
 pub struct TransmitQueue<const N: usize = 8> {
     queue: Vec<Option<QueueEntry>, N>,
 }
@@ -16,15 +30,28 @@ enum QueueEntry {
     Segmented(SegmentedQueueEntry),
 }
 
+impl QueueEntry {
+    fn next(&self) -> Instant {
+        match self {
+            QueueEntry::Nonsegmented(entry) => entry.next,
+            QueueEntry::Segmented(entry) => entry.next,
+        }
+    }
+}
+
 struct NonsegmentedQueueEntry {
     upper_pdu: UpperPDU<ProvisionedStack>,
     num_retransmit: u8,
+    interval: Duration,
+    next: Instant,
     completion_token: Option<CompletionToken>,
 }
 
 struct SegmentedQueueEntry {
     upper_pdu: UpperPDU<ProvisionedStack>,
     acked: Acked,
+    num_retransmit: u8,
+    next: Instant,
     completion_token: Option<CompletionToken>,
 }
 
@@ -39,12 +66,11 @@ impl<const N: usize> Default for TransmitQueue<N> {
 }
 
 impl<const N: usize> TransmitQueue<N> {
+    /// Whether an unacknowledged message awaits completion. Segmented messages are
+    /// completed by acknowledgments, so must not hold up receiving them.
     pub fn has_ongoing_completion(&self) -> bool {
         self.queue.iter().any(|e| match e {
-            Some(entry) => match entry {
-                QueueEntry::Nonsegmented(entry) => entry.completion_token.is_some(),
-                QueueEntry::Segmented(entry) => entry.completion_token.is_some(),
-            },
+            Some(QueueEntry::Nonsegmented(entry)) => entry.completion_token.is_some(),
             _ => false,
         })
     }
@@ -54,14 +80,14 @@ impl<const N: usize> TransmitQueue<N> {
         upper_pdu: UpperPDU<ProvisionedStack>,
         num_segments: u8,
         completion_token: Option<CompletionToken>,
-        watchdog: &Watchdog,
-        num_retransmits: u8,
+        retransmission: PublicationRetransmission,
     ) -> Result<(), InsufficientBuffer> {
         // Only add as segmented message in the queue if destination is an unicast address that can ack.
         // If not, add it as a non-segmented message.
         //
         // TODO The name 'segmented' really means 'ackable' in this context, so
         // consider doing a proper renaming in this code.
+        let interval = segment_transmission_interval(upper_pdu.meta().ttl());
         if let Address::Unicast(_) = upper_pdu.meta().dst() {
             let slot = self.queue.iter_mut().find(|e| e.is_none());
             let seq_zero = upper_pdu.meta().seq().into();
@@ -69,24 +95,22 @@ impl<const N: usize> TransmitQueue<N> {
                 slot.replace(QueueEntry::Segmented(SegmentedQueueEntry {
                     upper_pdu,
                     acked: Acked::new(seq_zero, num_segments),
+                    num_retransmit: UNICAST_RETRANSMISSIONS,
+                    next: Instant::now() + interval,
                     completion_token,
                 }));
             } else {
                 warn!("no space in retransmit queue");
             }
-
-            for slot in self.queue.iter().flatten() {
-                if let QueueEntry::Segmented(slot) = slot {
-                    let now = Instant::now();
-                    let timeout = Duration::from_millis(
-                        200 + (50 * slot.upper_pdu.meta().ttl().value() as u64),
-                    );
-                    let sz: SeqZero = slot.upper_pdu.meta().seq().into();
-                    watchdog.outbound_expiration((now + timeout, sz));
-                }
-            }
+        } else if let PublicationRetransmission::None = retransmission {
+            self.add(
+                upper_pdu,
+                MULTICAST_RETRANSMISSIONS,
+                interval,
+                completion_token,
+            );
         } else {
-            self.add_nonsegmented(upper_pdu, num_retransmits, completion_token)?;
+            self.add_nonsegmented(upper_pdu, retransmission, completion_token)?;
         }
 
         Ok(())
@@ -95,116 +119,153 @@ impl<const N: usize> TransmitQueue<N> {
     pub fn add_nonsegmented(
         &mut self,
         upper_pdu: UpperPDU<ProvisionedStack>,
-        num_retransmit: u8,
+        retransmission: PublicationRetransmission,
         completion_token: Option<CompletionToken>,
     ) -> Result<(), InsufficientBuffer> {
+        let (num_retransmit, interval) = match retransmission {
+            PublicationRetransmission::None => (0, Duration::from_ticks(0)),
+            PublicationRetransmission::RetransmitCountInterval(retransmission) => {
+                (retransmission.count, retransmission.interval)
+            }
+        };
+
+        self.add(upper_pdu, num_retransmit, interval, completion_token);
+
+        Ok(())
+    }
+
+    fn add(
+        &mut self,
+        upper_pdu: UpperPDU<ProvisionedStack>,
+        num_retransmit: u8,
+        interval: Duration,
+        completion_token: Option<CompletionToken>,
+    ) {
         let slot = self.queue.iter_mut().find(|e| e.is_none());
 
         if let Some(slot) = slot {
             slot.replace(QueueEntry::Nonsegmented(NonsegmentedQueueEntry {
                 upper_pdu,
                 num_retransmit,
+                interval,
+                next: Instant::now() + interval,
                 completion_token,
             }));
         } else {
             warn!("no space in retransmit queue");
         }
+    }
 
-        Ok(())
+    /// The earliest point in time at which an entry is due for retransmission.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.queue.iter().flatten().map(QueueEntry::next).min()
     }
 
-    pub fn iter(&mut self) -> impl Iterator<Item = UpperPDU<ProvisionedStack>> + '_ {
+    /// Iterate the PDUs which are due for retransmission, along with the
+    /// segments already acknowledged, which need not be sent again.
+    pub fn iter(
+        &mut self,
+    ) -> impl Iterator<Item = (UpperPDU<ProvisionedStack>, Option<BlockAck>)> + '_ {
         QueueIter {
             inner: self.queue.iter_mut(),
+            now: Instant::now(),
         }
     }
 
-    pub fn expire_outbound(&mut self, seq_zero: &SeqZero) {
-        for slot in self.queue.iter_mut() {
-            if let Some(QueueEntry::Segmented(entry)) = slot {
-                let sz = SeqZero::from(entry.upper_pdu.meta().seq());
-                if sz == *seq_zero {
-                    slot.take();
-                }
-            }
-        }
-    }
-
+    /// Record a `BlockAck` received from `src`, matching the message sent to it,
+    /// so that messages to several destinations may be in flight at once.
     pub fn receive_ack(
         &mut self,
+        src: UnicastAddress,
         block_ack: BlockAck,
-        watchdog: &Watchdog,
     ) -> Result<(), DriverError> {
         if let Some(slot) = self.queue.iter_mut().find(|e| {
             if let Some(QueueEntry::Segmented(entry)) = e {
                 let seq_zero: SeqZero = entry.upper_pdu.meta().seq().into();
-                seq_zero == block_ack.seq_zero()
+                seq_zero == block_ack.seq_zero() && entry.upper_pdu.meta().dst() == src.into()
             } else {
                 false
             }
         }) {
             if let Some(QueueEntry::Segmented(entry)) = slot {
-                let fully_acked = entry.acked.ack(block_ack, watchdog)?;
+                if block_ack.value() == 0 {
+                    // the receiver is busy, and the message is cancelled.
+                    slot.take();
+                    return Ok(());
+                }
+                let (fully_acked, progressed) = entry.acked.ack(block_ack)?;
                 if fully_acked {
-                    watchdog.clear_outbound_expiration(entry.upper_pdu.meta().seq().into());
                     if let Some(token) = entry.completion_token.as_ref() {
                         token.complete();
                     };
                     slot.take();
+                } else if progressed {
+                    // retransmit the remaining segments right away.
+                    entry.num_retransmit = UNICAST_RETRANSMISSIONS;
+                    entry.next = Instant::now();
                 }
             }
         }
-
-        for slot in self.queue.iter().flatten() {
-            if let QueueEntry::Segmented(slot) = slot {
-                let now = Instant::now();
-                let timeout =
-                    Duration::from_millis(200 + (50 * slot.upper_pdu.meta().ttl().value() as u64));
-                watchdog.outbound_expiration((now + timeout, slot.upper_pdu.meta().seq().into()));
-            }
-        }
         Ok(())
     }
 }
 
 struct QueueIter<'i, I: Iterator<Item = &'i mut Option<QueueEntry>>> {
     inner: I,
+    now: Instant,
 }
 
 impl<'i, I: Iterator<Item = &'i mut Option<QueueEntry>>> Iterator for QueueIter<'i, I> {
-    type Item = UpperPDU<ProvisionedStack>;
+    type Item = (UpperPDU<ProvisionedStack>, Option<BlockAck>);
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(outer) = self.inner.next() {
+        for outer in self.inner.by_ref() {
             let mut should_take = false;
 
-            let result = if let Some(next) = outer {
-                match next {
+            let result = match outer {
+                Some(next) if next.next() <= self.now => match next {
                     QueueEntry::Nonsegmented(inner) => {
+                        let result = if inner.num_retransmit == 0 {
+                            None
+                        } else {
+                            inner.num_retransmit -= 1;
+                            inner.next = self.now + inner.interval;
+                            Some((inner.upper_pdu.clone(), None))
+                        };
                         if inner.num_retransmit == 0 {
                             should_take = true;
                             if let Some(token) = inner.completion_token.as_ref() {
                                 token.complete();
                             }
+                        }
+                        result
+                    }
+                    QueueEntry::Segmented(inner) => {
+                        if inner.num_retransmit == 0 {
+                            // dropping the completion token signals it as incomplete.
+                            warn!("segmented message not acknowledged, giving up");
+                            should_take = true;
+                            None
                         } else {
                             inner.num_retransmit -= 1;
+                            inner.next = self.now
+                                + segment_transmission_interval(inner.upper_pdu.meta().ttl());
+                            Some((inner.upper_pdu.clone(), Some(inner.acked.block_ack)))
                         }
-                        Some(inner.upper_pdu.clone())
                     }
-                    QueueEntry::Segmented(inner) => Some(inner.upper_pdu.clone()),
-                }
-            } else {
-                None
+                },
+                _ => None,
             };
 
             if should_take {
                 outer.take();
             }
 
-            result
-        } else {
-            None
+            if result.is_some() {
+                return result;
+            }
         }
+        None
     }
 }
 
@@ -222,11 +283,156 @@ impl Acked {
         }
     }
 
-    fn ack(&mut self, block_ack: BlockAck, _watchdog: &Watchdog) -> Result<bool, InvalidBlock> {
+    /// Record the acknowledged segments, returning whether all of them have now been
+    /// acknowledged, and whether any of them had not been before.
+    fn ack(&mut self, block_ack: BlockAck) -> Result<(bool, bool), InvalidBlock> {
+        let before = self.block_ack.value();
         for ack in block_ack.acked_iter() {
             self.block_ack.ack(ack)?;
         }
 
-        Ok(self.block_ack.is_fully_acked(self.num_segments))
+        Ok((
+            self.block_ack.is_fully_acked(self.num_segments),
+            self.block_ack.value() != before,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stack::provisioned::system::UpperMetadata;
+    use btmesh_common::crypto::network::Nid;
+    use btmesh_common::mic::SzMic;
+    use btmesh_common::{IvIndex, Seq};
+    use btmesh_device::{CompletionStatus, NetworkKeyHandle, Signal};
+    use btmesh_models::foundation::configuration::NetKeyIndex;
+    use btmesh_pdu::provisioned::upper::access::UpperAccessPDU;
+    use embassy_futures::block_on;
+
+    fn upper_pdu(ttl: u8) -> UpperPDU<ProvisionedStack> {
+        let meta = UpperMetadata {
+            network_key_handle: NetworkKeyHandle::new(NetKeyIndex::new(0), Nid::new(0x68)),
+            iv_index: IvIndex::new(0),
+            local_element_index: Some(0),
+            akf_aid: None,
+            seq: Seq::new(42),
+            src: UnicastAddress::new(0x00A1).unwrap(),
+            dst: UnicastAddress::new(0x00B0).unwrap().into(),
+            ttl: Ttl::new(ttl),
+            label_uuids: Default::default(),
+            seq_auth: None,
+            replay_seq: None,
+            friendship_credentials: false,
+        };
+        UpperAccessPDU::parse(&[0; 20], &SzMic::Bit32, meta)
+            .unwrap()
+            .into()
+    }
+
+    /// Make every entry due for retransmission.
+    fn elapse<const N: usize>(queue: &mut TransmitQueue<N>) {
+        for entry in queue.queue.iter_mut().flatten() {
+            match entry {
+                QueueEntry::Nonsegmented(entry) => entry.next = Instant::from_ticks(0),
+                QueueEntry::Segmented(entry) => entry.next = Instant::from_ticks(0),
+            }
+        }
+    }
+
+    fn block_ack(segments: &[u8]) -> BlockAck {
+        let mut block_ack = BlockAck::new(SeqZero::new(42));
+        for seg_o in segments {
+            block_ack.ack(*seg_o).unwrap();
+        }
+        block_ack
+    }
+
+    #[test]
+    fn segment_transmission_interval_per_ttl() {
+        assert_eq!(
+            Duration::from_millis(200),
+            segment_transmission_interval(Ttl::new(0))
+        );
+        assert_eq!(
+            Duration::from_millis(700),
+            segment_transmission_interval(Ttl::new(10))
+        );
+
+        let mut queue = TransmitQueue::<4>::default();
+        let before = Instant::now();
+        queue
+            .add_segmented(upper_pdu(10), 2, None, PublicationRetransmission::None)
+            .unwrap();
+        let after = Instant::now();
+
+        let deadline = queue.next_deadline().unwrap();
+        assert!(deadline >= before + Duration::from_millis(700));
+        assert!(deadline <= after + Duration::from_millis(700));
+    }
+
+    #[test]
+    fn unacknowledged_segments_given_up() {
+        static COMPLETION: Signal<CompletionStatus> = Signal::new();
+        let mut queue = TransmitQueue::<4>::default();
+        queue
+            .add_segmented(
+                upper_pdu(4),
+                2,
+                Some(CompletionToken::new(&COMPLETION)),
+                PublicationRetransmission::None,
+            )
+            .unwrap();
+
+        elapse(&mut queue);
+        assert_eq!(1, queue.iter().count());
+
+        // acknowledging some of the segments restarts the retransmissions.
+        queue
+            .receive_ack(UnicastAddress::new(0x00B0).unwrap(), block_ack(&[0]))
+            .unwrap();
+        for _ in 0..UNICAST_RETRANSMISSIONS {
+            elapse(&mut queue);
+            let (_, acked) = queue.iter().next().unwrap();
+            assert_eq!(0b1, acked.unwrap().value());
+        }
+        assert!(!COMPLETION.signaled());
+
+        elapse(&mut queue);
+        assert_eq!(0, queue.iter().count());
+        assert!(queue.next_deadline().is_none());
+        assert!(matches!(
+            block_on(COMPLETION.wait()),
+            CompletionStatus::Incomplete
+        ));
+    }
+
+    #[test]
+    fn acknowledged_segments_complete() {
+        static COMPLETION: Signal<CompletionStatus> = Signal::new();
+        let mut queue = TransmitQueue::<4>::default();
+        queue
+            .add_segmented(
+                upper_pdu(4),
+                2,
+                Some(CompletionToken::new(&COMPLETION)),
+                PublicationRetransmission::None,
+            )
+            .unwrap();
+
+        // acknowledgments from another source are not for this message.
+        queue
+            .receive_ack(UnicastAddress::new(0x00B1).unwrap(), block_ack(&[0, 1]))
+            .unwrap();
+        assert!(queue.next_deadline().is_some());
+
+        queue
+            .receive_ack(UnicastAddress::new(0x00B0).unwrap(), block_ack(&[0, 1]))
+            .unwrap();
+        assert!(queue.next_deadline().is_none());
+        assert!(matches!(
+            block_on(COMPLETION.wait()),
+            CompletionStatus::Complete
+        ));
     }
 }
@@ -1,3 +1,4 @@
+use crate::stack::provisioned::lower::select_szmic;
 use crate::stack::provisioned::sequence::Sequence;
 use crate::stack::provisioned::system::{AccessMetadata, ControlMetadata, UpperMetadata};
 use crate::stack::provisioned::{DriverError, ProvisionedStack};
@@ -10,6 +11,7 @@ use btmesh_device::KeyHandle;
 use btmesh_pdu::provisioned::access::AccessMessage;
 use btmesh_pdu::provisioned::control::ControlMessage;
 use btmesh_pdu::provisioned::upper::access::UpperAccessPDU;
+use btmesh_pdu::provisioned::upper::control::UpperControlPDU;
 use btmesh_pdu::provisioned::upper::UpperPDU;
 use btmesh_pdu::provisioned::Message;
 use core::ops::ControlFlow;
@@ -78,9 +80,12 @@ impl ProvisionedStack {
     ) -> Result<UpperPDU<ProvisionedStack>, DriverError> {
         match message {
             Message::Access(access) => Ok(self.encrypt_access(secrets, sequence, access)?.into()),
-            Message::Control(_control) => {
-                todo!()
-            }
+            Message::Control(control) => Ok(UpperControlPDU::new(
+                control.opcode(),
+                control.parameters(),
+                UpperMetadata::from_control_message(control, sequence.next()?),
+            )?
+            .into()),
         }
     }
 
@@ -115,15 +120,24 @@ impl ProvisionedStack {
         sequence: &Sequence,
         message: &AccessMessage<ProvisionedStack>,
     ) -> Result<UpperAccessPDU<ProvisionedStack>, DriverError> {
-        let seq_zero = sequence.next();
+        let seq_zero = sequence.next()?;
 
         let mut payload = Vec::<u8, 379>::new();
         message.emit(&mut payload)?;
 
+        let szmic = message
+            .meta()
+            .szmic()
+            .unwrap_or_else(|| select_szmic(payload.len()));
+        let mut transmic = match szmic {
+            SzMic::Bit32 => TransMic::new32(),
+            SzMic::Bit64 => TransMic::new64(),
+        };
+
         match message.meta().key_handle() {
             KeyHandle::Device => {
                 let nonce = DeviceNonce::new(
-                    SzMic::Bit32,
+                    szmic,
                     seq_zero,
                     message.meta().src(),
                     message.meta().dst(),
@@ -132,8 +146,6 @@ impl ProvisionedStack {
 
                 let device_key = secrets.device_key();
 
-                let mut transmic = TransMic::new32();
-
                 crypto::device::encrypt_device_key(
                     &device_key,
                     &nonce,
@@ -153,7 +165,7 @@ impl ProvisionedStack {
             }
             KeyHandle::Application(key_handle) => {
                 let nonce = ApplicationNonce::new(
-                    SzMic::Bit32,
+                    szmic,
                     seq_zero,
                     message.meta().src(),
                     message.meta().dst(),
@@ -162,8 +174,6 @@ impl ProvisionedStack {
 
                 let application_key = secrets.application_key(key_handle)?;
 
-                let mut transmic = TransMic::new32();
-
                 crypto::application::encrypt_application_key(
                     &application_key,
                     nonce,
@@ -173,11 +183,12 @@ impl ProvisionedStack {
                 )
                 .map_err(|_| DriverError::CryptoError)?;
 
-                Ok(UpperAccessPDU::new(
-                    &payload,
-                    transmic,
-                    UpperMetadata::from_access_message(message, seq_zero),
-                )?)
+                let mut meta = UpperMetadata::from_access_message(message, seq_zero);
+                // the AID follows the key actually used, which may differ from the
+                // handle's during a key refresh.
+                meta.akf_aid.replace(application_key.aid());
+
+                Ok(UpperAccessPDU::new(&payload, transmic, meta)?)
             }
         }
     }
@@ -187,11 +198,18 @@ impl ProvisionedStack {
         secrets: &Secrets,
         pdu: &UpperAccessPDU<ProvisionedStack>,
     ) -> Result<AccessMessage<ProvisionedStack>, DriverError> {
+        // segmented messages are encrypted with the sequence number of their first segment.
+        let seq = if let Some(seq_auth) = pdu.meta().seq_auth() {
+            seq_auth.into()
+        } else {
+            pdu.meta().seq()
+        };
+
         if let Some(aid) = pdu.meta().aid() {
             // akf=true and an AID was provided.
             let nonce = ApplicationNonce::new(
                 pdu.transmic().szmic(),
-                pdu.meta().seq(),
+                seq,
                 pdu.meta().src(),
                 pdu.meta().dst(),
                 pdu.meta().iv_index(),
@@ -200,8 +218,9 @@ impl ProvisionedStack {
             let mut bytes: Vec<_, 380> = Vec::new();
             let mut decrypt_result = None;
 
-            'outer: for application_key_handle in secrets.application_keys_by_aid(aid) {
-                let application_key = secrets.application_key(application_key_handle)?;
+            'outer: for (application_key_handle, application_key) in
+                secrets.application_keys_by_aid(aid)
+            {
                 if pdu.meta().label_uuids().is_empty() {
                     bytes.clear();
                     bytes
@@ -260,12 +279,6 @@ impl ProvisionedStack {
                 )?);
             }
         } else {
-            let seq = if let Some(seq_auth) = pdu.meta().seq_auth() {
-                seq_auth.into()
-            } else {
-                pdu.meta().seq()
-            };
-
             let nonce = DeviceNonce::new(
                 pdu.transmic().szmic(),
                 seq,
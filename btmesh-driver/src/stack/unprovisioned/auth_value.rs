@@ -1,3 +1,4 @@
+use super::oob::{OobValue, StaticOob};
 use btmesh_common::ParseError;
 use btmesh_pdu::provisioning::{InputOOBAction, OOBAction, OOBSize, OutputOOBAction, Start};
 use heapless::Vec;
@@ -13,6 +14,7 @@ pub enum AuthValue {
     OutputNumeric(u32),
     InputAlphanumeric(Vec<u8, 8>),
     OutputAlphanumeric(Vec<u8, 8>),
+    Static(StaticOob),
 }
 
 impl AuthValue {
@@ -37,12 +39,53 @@ impl AuthValue {
                     bytes[i] = *byte
                 }
             }
+            AuthValue::Static(StaticOob::Octets16(secret)) => bytes = *secret,
+            // the most significant octets of a longer secret.
+            AuthValue::Static(StaticOob::Octets32(secret)) => bytes.copy_from_slice(&secret[0..16]),
         }
 
         bytes
     }
+
+    /// The 32-octet AuthValue of the HMAC-SHA256 algorithm: numbers padded
+    /// on the left like above, characters and shorter static OOB on the right.
+    pub fn get_bytes_256(&self) -> [u8; 32] {
+        let mut bytes = [0; 32];
+        match self {
+            AuthValue::Static(StaticOob::Octets16(secret)) => bytes[0..16].copy_from_slice(secret),
+            AuthValue::Static(StaticOob::Octets32(secret)) => bytes = *secret,
+            AuthValue::InputAlphanumeric(chars) | AuthValue::OutputAlphanumeric(chars) => {
+                bytes[0..chars.len()].copy_from_slice(chars)
+            }
+            _ => bytes[16..32].copy_from_slice(&self.get_bytes()),
+        }
+        bytes
+    }
+
+    /// The value input by the user for `action`.
+    pub fn input(action: &InputOOBAction, value: OobValue) -> Self {
+        match (action, value) {
+            (InputOOBAction::Push | InputOOBAction::Twist, OobValue::Numeric(num)) => {
+                AuthValue::InputEvents(num)
+            }
+            (_, OobValue::Numeric(num)) => AuthValue::InputNumeric(num),
+            (_, OobValue::Alphanumeric(chars)) => AuthValue::InputAlphanumeric(chars),
+        }
+    }
+
+    /// The value to be output by the device, if any.
+    pub fn output(&self) -> Option<OobValue> {
+        match self {
+            AuthValue::OutputEvents(num) | AuthValue::OutputNumeric(num) => {
+                Some(OobValue::Numeric(*num))
+            }
+            AuthValue::OutputAlphanumeric(chars) => Some(OobValue::Alphanumeric(chars.clone())),
+            _ => None,
+        }
+    }
 }
 
+/// A random value to be output for the authentication selected by `start`.
 pub fn determine_auth_value<RNG: RngCore>(
     rng: &mut RNG,
     start: &Start,
@@ -69,7 +112,8 @@ pub fn determine_auth_value<RNG: RngCore>(
                 let auth_raw = random_numeric(rng, *size);
                 AuthValue::OutputNumeric(auth_raw)
             }
-            // TODO actually dispatch to device/app/thing's UI for inputs instead of just making up shit.
+            // inputs on the device are output by the provisioner, which makes them up here,
+            // while the device awaits them from the user instead.
             (OOBAction::Input(InputOOBAction::InputNumeric), OOBSize::MaximumSize(size)) => {
                 let auth_raw = random_numeric(rng, *size);
                 AuthValue::InputNumeric(auth_raw)
@@ -97,89 +141,49 @@ fn random_physical_oob<RNG: RngCore>(rng: &mut RNG, size: u8) -> u32 {
     // "select a random integer between 0 and 10 to the power of the Authentication Size exclusive"
     //
     // ... which could be an absolute metric tonne of beeps/twists/pushes if AuthSize is large-ish.
-    let mut max = 1;
-    for _ in 0..size {
-        max *= 10;
-    }
-
-    loop {
-        let candidate = rng.next_u32();
-        if candidate > 0 && candidate < max {
-            return candidate;
-        }
-    }
+    // Zero events could not be told apart from no output at all, so start from one.
+    1 + rng.next_u32() % (max_numeric(size) - 1)
 }
 
 fn random_numeric<RNG: RngCore>(rng: &mut RNG, size: u8) -> u32 {
-    loop {
-        let candidate = rng.next_u32();
+    rng.next_u32() % max_numeric(size)
+}
 
-        match size {
-            1 => {
-                if candidate < 10 {
-                    return candidate;
-                }
-            }
-            2 => {
-                if candidate < 100 {
-                    return candidate;
-                }
-            }
-            3 => {
-                if candidate < 1_000 {
-                    return candidate;
-                }
-            }
-            4 => {
-                if candidate < 10_000 {
-                    return candidate;
-                }
-            }
-            5 => {
-                if candidate < 100_000 {
-                    return candidate;
-                }
-            }
-            6 => {
-                if candidate < 1_000_000 {
-                    return candidate;
-                }
-            }
-            7 => {
-                if candidate < 10_000_000 {
-                    return candidate;
-                }
-            }
-            8 => {
-                if candidate < 100_000_000 {
-                    return candidate;
-                }
-            }
-            _ => {
-                // should never get here, but...
-                return 0;
-            }
-        }
-    }
+/// 10 to the power of `size`, exclusive upper bound of a value of `size` digits.
+fn max_numeric(size: u8) -> u32 {
+    10u32.pow(size.clamp(1, 8) as u32)
 }
 
 fn random_alphanumeric<RNG: RngCore>(rng: &mut RNG, size: u8) -> Result<Vec<u8, 8>, ParseError> {
+    // Capital ASCII letters A-Z and numbers 0-9
+    const CHARACTERS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
     let mut random = Vec::new();
     for _ in 0..size {
-        loop {
-            let candidate = (rng.next_u32() & 0xFF) as u8;
-            if (64..=90).contains(&candidate) {
-                // Capital ASCII letters A-Z
-                random
-                    .push(candidate)
-                    .map_err(|_| ParseError::InsufficientBuffer)?;
-            } else if (48..=57).contains(&candidate) {
-                // ASCII numbers 0-9
-                random
-                    .push(candidate)
-                    .map_err(|_| ParseError::InsufficientBuffer)?;
-            }
-        }
+        let candidate = CHARACTERS[rng.next_u32() as usize % CHARACTERS.len()];
+        random
+            .push(candidate)
+            .map_err(|_| ParseError::InsufficientBuffer)?;
     }
     Ok(random)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_core::OsRng;
+
+    #[test]
+    fn random_values_fit_size() {
+        for size in 1..=8 {
+            let events = random_physical_oob(&mut OsRng, size);
+            assert!(events > 0 && events < max_numeric(size));
+            assert!(random_numeric(&mut OsRng, size) < max_numeric(size));
+
+            let chars = random_alphanumeric(&mut OsRng, size).unwrap();
+            assert_eq!(chars.len(), size as usize);
+            assert!(chars
+                .iter()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit()));
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::stack::unprovisioned::oob::{OobAuthentication, OobConfig, OobValue};
 use crate::stack::unprovisioned::provisionee::Provisionee;
 use crate::util::deadline::{Deadline, DeadlineFuture};
 use crate::util::hash::FnvHasher;
@@ -10,12 +11,15 @@ use embassy_time::{Duration, Timer};
 use rand_core::{CryptoRng, RngCore};
 
 mod auth_value;
+pub mod oob;
 mod provisionee;
-mod provisioner;
+pub mod provisioner;
 mod transcript;
 
 pub enum ProvisioningState {
     Response(ProvisioningPDU),
+    /// Public keys are exchanged, and the application is to carry out out-of-band authentication.
+    Authentication(Option<ProvisioningPDU>, OobAuthentication),
     Data(DeviceKey, ProvisioningData, ProvisioningPDU),
     Failed,
 }
@@ -27,9 +31,13 @@ pub struct UnprovisionedStack {
 }
 
 impl UnprovisionedStack {
-    pub fn new(capabilities: Capabilities) -> Self {
+    pub fn new(capabilities: Capabilities, oob: &OobConfig) -> Self {
         Self {
-            provisionee: Some(Provisionee::new(capabilities)),
+            provisionee: Some(Provisionee::new(
+                capabilities,
+                oob.static_oob,
+                oob.secret_key(),
+            )),
             last_transmit_hash: None,
             beacon: Deadline::new(Duration::from_secs(3), true),
         }
@@ -85,6 +93,7 @@ impl UnprovisionedStack {
         }
 
         if let Some(current_state) = self.provisionee.take() {
+            let exchanging_keys = matches!(current_state, Provisionee::KeyExchange(..));
             let next_state = current_state.next(pdu, rng)?;
 
             self.provisionee.replace(next_state);
@@ -98,22 +107,39 @@ impl UnprovisionedStack {
                     )))
                 }
                 Some(Provisionee::Failure(..)) => Ok(Some(ProvisioningState::Failed)),
-                Some(p) => match p.response() {
-                    Some(response) => {
+                Some(p) => {
+                    let response = p.response();
+                    if response.is_some() {
                         self.last_transmit_hash.replace(hash);
-                        Ok(Some(ProvisioningState::Response(response)))
-                    }
-                    None => {
+                    } else {
                         self.last_transmit_hash.take();
-                        Ok(None)
                     }
-                },
+                    match (response, p.oob()) {
+                        (response, Some(oob)) if exchanging_keys => Ok(Some(
+                            ProvisioningState::Authentication(response, oob.clone()),
+                        )),
+                        (Some(response), _) => Ok(Some(ProvisioningState::Response(response))),
+                        (None, _) => Ok(None),
+                    }
+                }
                 None => unreachable!(),
             }
         } else {
             Err(DriverError::InvalidState)
         }
     }
+
+    /// Complete input out-of-band authentication with the value input by the
+    /// user, returning the Input Complete PDU to transmit if it was awaited.
+    pub fn input(&mut self, value: OobValue) -> Option<ProvisioningPDU> {
+        let provisionee = self.provisionee.take()?.input(value);
+        let response = match provisionee.response() {
+            Some(pdu @ ProvisioningPDU::InputComplete) => Some(pdu),
+            _ => None,
+        };
+        self.provisionee.replace(provisionee);
+        response
+    }
 }
 
 #[cfg(test)]
@@ -122,7 +148,7 @@ mod test {
 
     #[test]
     pub fn in_progress() {
-        let unprov = UnprovisionedStack::new(Default::default());
+        let unprov = UnprovisionedStack::new(Default::default(), &Default::default());
         assert_eq!(unprov.in_progress(), false);
     }
 }
@@ -0,0 +1,78 @@
+use btmesh_pdu::provisioning::{
+    InputOOBAction, InputOOBActions, OOBSize, OutputOOBAction, OutputOOBActions, PublicKey,
+};
+use heapless::Vec;
+use p256::SecretKey;
+
+/// Out-of-band authentication methods this device offers to a provisioner,
+/// as reported in its provisioning capabilities.
+#[derive(Clone, Default)]
+pub struct OobConfig {
+    /// Largest number of digits, characters or events this device can output.
+    pub output_size: OOBSize,
+    pub output_actions: OutputOOBActions,
+    /// Largest number of digits, characters or events the user can input.
+    pub input_size: OOBSize,
+    pub input_actions: InputOOBActions,
+    /// Secret of this device known to the provisioner by other means,
+    /// such as a QR code, for static OOB authentication.
+    pub static_oob: Option<StaticOob>,
+    /// Fixed P-256 private key of this device, whose public key is distributed
+    /// out of band, such as in a QR code, instead of being exchanged in-band.
+    pub private_key: Option<[u8; 32]>,
+}
+
+impl OobConfig {
+    /// The public key to distribute out of band, if a valid private key is configured.
+    pub fn public_key(&self) -> Option<PublicKey> {
+        self.secret_key()?.public_key().try_into().ok()
+    }
+
+    pub(crate) fn secret_key(&self) -> Option<SecretKey> {
+        SecretKey::from_be_bytes(self.private_key.as_ref()?).ok()
+    }
+}
+
+/// Static OOB information of a device: 16 octets, or 32 octets, which the
+/// HMAC-SHA256 algorithm authenticates with in full.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StaticOob {
+    Octets16([u8; 16]),
+    Octets32([u8; 32]),
+}
+
+impl From<[u8; 16]> for StaticOob {
+    fn from(secret: [u8; 16]) -> Self {
+        Self::Octets16(secret)
+    }
+}
+
+impl From<[u8; 32]> for StaticOob {
+    fn from(secret: [u8; 32]) -> Self {
+        Self::Octets32(secret)
+    }
+}
+
+/// A value authenticated out of band, either a number (or count of events),
+/// or a string of characters.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Debug, PartialEq)]
+pub enum OobValue {
+    Numeric(u32),
+    Alphanumeric(Vec<u8, 8>),
+}
+
+/// Out-of-band authentication selected by the provisioner, to be carried out
+/// by the application.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Debug)]
+pub enum OobAuthentication {
+    /// Output the value: blink, beep or vibrate as many times, or display it.
+    Output(OutputOOBAction, OobValue),
+    /// Have the user input the value output by the provisioner, of at most as
+    /// many digits, characters or events, and hand it to [`crate::input_oob`].
+    Input(InputOOBAction, u8),
+    /// Provisioning is over, whether successful or not.
+    Complete,
+}
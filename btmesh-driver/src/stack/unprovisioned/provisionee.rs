@@ -1,15 +1,12 @@
 use super::auth_value::{determine_auth_value, AuthValue};
+use super::oob::{OobAuthentication, OobValue, StaticOob};
 use super::transcript::Transcript;
 use crate::DriverError;
 use btmesh_common::crypto::device::DeviceKey;
-use btmesh_common::crypto::{
-    aes_cmac,
-    provisioning::{prck, prdk, prsk, prsn, try_decrypt_data},
-    s1,
-};
+use btmesh_common::crypto::provisioning::{prdk, prsk, prsn, try_decrypt_data};
 use btmesh_pdu::provisioning::{
-    Capabilities, Confirmation, Data, ErrorCode, Failed, ProvisioningData, ProvisioningPDU,
-    PublicKey, Random,
+    AuthenticationMethod, Capabilities, Confirmation, Data, ErrorCode, Failed, OOBAction, OOBSize,
+    ProvisioningData, ProvisioningPDU, PublicKey, PublicKeySelected, Random,
 };
 use heapless::Vec;
 use p256::elliptic_curve::ecdh::diffie_hellman;
@@ -28,8 +25,19 @@ pub enum Provisionee {
 }
 
 impl Provisionee {
-    pub fn new(capabilities: Capabilities) -> Self {
-        Self::Beaconing(Phase::<Beaconing>::new(capabilities))
+    /// Await provisioning with `capabilities`, authenticating with the
+    /// `static_oob` secret, and exchanging keys with the `private_key` whose
+    /// public key is known out of band, when selected by the provisioner.
+    pub fn new(
+        capabilities: Capabilities,
+        static_oob: Option<StaticOob>,
+        private_key: Option<SecretKey>,
+    ) -> Self {
+        Self::Beaconing(Phase::<Beaconing>::new(
+            capabilities,
+            static_oob,
+            private_key,
+        ))
     }
 
     pub fn in_progress(&self) -> bool {
@@ -46,12 +54,15 @@ impl Provisionee {
                 Some(ProvisioningPDU::Capabilities(phase.capabilities.clone()))
             }
             Self::KeyExchange(_) => None,
-            Self::Authentication(phase) => Some(ProvisioningPDU::PublicKey(phase.state.public_key)),
+            Self::Authentication(phase) if phase.state.input_complete => {
+                Some(ProvisioningPDU::InputComplete)
+            }
+            Self::Authentication(phase) => phase.state.public_key.map(ProvisioningPDU::PublicKey),
             Self::Confirming(phase) => Some(ProvisioningPDU::Confirmation(Confirmation {
-                confirmation: phase.state.confirmation_device,
+                confirmation: phase.state.confirmation_device.clone(),
             })),
             Self::DataDistribution(phase) => Some(ProvisioningPDU::Random(Random {
-                random: phase.random_device,
+                random: phase.random_device.clone(),
             })),
             Self::Failure(ec) => Some(ProvisioningPDU::Failed(Failed {
                 error_code: ec.clone(),
@@ -75,18 +86,63 @@ impl Provisionee {
             // START
             (Provisionee::Invitation(mut phase), ProvisioningPDU::Start(start)) => {
                 // TODO: spec says to set the "Attention Timer" to 0x00
+                if !phase.capabilities.algorithms.contains(start.algorithm) {
+                    return Provisionee::fail(ErrorCode::InvalidFormat);
+                }
                 phase.transcript.add_start(start)?;
-                phase.auth_value = determine_auth_value(rng, start)?;
-                // TODO: actually let the device/app/thingy know what
-                // it is so that it can blink/flash/accept input
+                match (&start.public_key, &phase.private_key) {
+                    (PublicKeySelected::OOBPublicKey, None) => {
+                        return Provisionee::fail(ErrorCode::InvalidFormat)
+                    }
+                    (PublicKeySelected::NoPublicKey, _) => phase.private_key = None,
+                    _ => {}
+                }
+                if let AuthenticationMethod::Static = start.authentication_method {
+                    match phase.static_oob {
+                        Some(secret) => phase.auth_value = AuthValue::Static(secret),
+                        None => return Provisionee::fail(ErrorCode::InvalidFormat),
+                    }
+                }
+                phase.oob = match (&start.authentication_action, &start.authentication_size) {
+                    (OOBAction::Input(action), OOBSize::MaximumSize(size)) => {
+                        if !phase.capabilities.input_oob_action.contains(*action)
+                            || !is_within(
+                                &phase.capabilities.input_oob_size,
+                                &start.authentication_size,
+                            )
+                        {
+                            return Provisionee::fail(ErrorCode::InvalidFormat);
+                        }
+                        // awaited from the user once public keys are exchanged.
+                        Some(OobAuthentication::Input(*action, *size))
+                    }
+                    (OOBAction::Output(action), _) => {
+                        if !phase.capabilities.output_oob_action.contains(*action)
+                            || !is_within(
+                                &phase.capabilities.output_oob_size,
+                                &start.authentication_size,
+                            )
+                        {
+                            return Provisionee::fail(ErrorCode::InvalidFormat);
+                        }
+                        phase.auth_value = determine_auth_value(rng, start)?;
+                        phase
+                            .auth_value
+                            .output()
+                            .map(|value| OobAuthentication::Output(*action, value))
+                    }
+                    _ => None,
+                };
                 Ok(Provisionee::KeyExchange(phase.into()))
             }
             // PUBLIC KEY
             (Provisionee::KeyExchange(mut phase), ProvisioningPDU::PublicKey(peer_key)) => {
+                // a public key known out of band is not sent back.
+                let oob_public_key = phase.private_key.is_some();
                 match phase.calculate_ecdh(peer_key, rng) {
                     Ok(pk) => {
                         let mut next: Phase<Authentication> = phase.into();
-                        next.state.public_key = pk;
+                        next.state.public_key = (!oob_public_key).then_some(pk);
                         Ok(Provisionee::Authentication(next))
                     }
                     Err(DriverError::InvalidFormat) => Provisionee::fail(ErrorCode::InvalidFormat),
@@ -94,7 +150,12 @@ impl Provisionee {
                 }
             }
             // CONFIRMATION
-            (Provisionee::Authentication(phase), ProvisioningPDU::Confirmation(value)) => {
+            (Provisionee::Authentication(phase), ProvisioningPDU::Confirmation(value))
+                if !phase.is_awaiting_input() =>
+            {
+                if value.confirmation.len() != phase.transcript.algorithm().value_size() {
+                    return Provisionee::fail(ErrorCode::InvalidFormat);
+                }
                 let mut next: Phase<Confirming> = phase.into();
                 next.confirm(value, rng)?;
                 Ok(Provisionee::Confirming(next))
@@ -119,6 +180,29 @@ impl Provisionee {
         }
     }
 
+    /// Out-of-band authentication to be carried out by the application, once
+    /// public keys are exchanged.
+    pub fn oob(&self) -> Option<&OobAuthentication> {
+        match self {
+            Self::Authentication(phase) => phase.oob.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Complete input out-of-band authentication with the value input by the user.
+    pub fn input(self, value: OobValue) -> Self {
+        match self {
+            Self::Authentication(mut phase) if phase.is_awaiting_input() => {
+                if let Some(OobAuthentication::Input(action, _)) = &phase.oob {
+                    phase.auth_value = AuthValue::input(action, value);
+                    phase.state.input_complete = true;
+                }
+                Self::Authentication(phase)
+            }
+            current => current,
+        }
+    }
+
     fn fail(error_code: ErrorCode) -> Result<Provisionee, DriverError> {
         Ok(Provisionee::Failure(error_code))
     }
@@ -128,21 +212,20 @@ impl Provisionee {
 pub struct Phase<S> {
     transcript: Transcript,
     capabilities: Capabilities,
+    static_oob: Option<StaticOob>,
+    private_key: Option<SecretKey>,
     auth_value: AuthValue,
+    oob: Option<OobAuthentication>,
     shared_secret: [u8; 32],
-    random_provisioner: [u8; 16],
-    random_device: [u8; 16],
+    random_provisioner: Vec<u8, 32>,
+    random_device: Vec<u8, 32>,
     state: S,
 }
 
 impl<S> Phase<S> {
-    fn confirmation(&self, random: &[u8]) -> Result<[u8; 16], DriverError> {
-        let salt = self.transcript.confirmation_salt()?;
-        let key = prck(&self.shared_secret, &salt.into_bytes())?;
-        let mut bytes: Vec<u8, 32> = Vec::new();
-        bytes.extend_from_slice(random)?;
-        bytes.extend_from_slice(&self.auth_value.get_bytes())?;
-        Ok(aes_cmac(&key.into_bytes(), &bytes)?.into_bytes().into())
+    fn confirmation(&self, random: &[u8]) -> Result<Vec<u8, 32>, DriverError> {
+        self.transcript
+            .confirmation(&self.shared_secret, random, &self.auth_value)
     }
 }
 
@@ -154,20 +237,27 @@ pub struct Invitation {}
 pub struct KeyExchange {}
 #[derive(Default)]
 pub struct Authentication {
-    public_key: PublicKey,
+    public_key: Option<PublicKey>,
+    input_complete: bool,
 }
 #[derive(Default)]
 pub struct Confirming {
-    confirmation_provider: [u8; 16],
-    confirmation_device: [u8; 16],
+    confirmation_provider: Vec<u8, 32>,
+    confirmation_device: Vec<u8, 32>,
 }
 #[derive(Default)]
 pub struct DataDistribution {}
 
 impl Phase<Beaconing> {
-    pub fn new(capabilities: Capabilities) -> Self {
+    pub fn new(
+        capabilities: Capabilities,
+        static_oob: Option<StaticOob>,
+        private_key: Option<SecretKey>,
+    ) -> Self {
         Phase {
             capabilities,
+            static_oob,
+            private_key,
             state: Beaconing {},
             ..Default::default()
         }
@@ -178,6 +268,8 @@ impl From<Phase<Beaconing>> for Phase<Invitation> {
         Phase {
             transcript: p.transcript,
             capabilities: p.capabilities,
+            static_oob: p.static_oob,
+            private_key: p.private_key,
             ..Default::default()
         }
     }
@@ -188,6 +280,8 @@ impl From<Phase<Invitation>> for Phase<KeyExchange> {
         Phase {
             transcript: p.transcript,
             auth_value: p.auth_value,
+            oob: p.oob,
+            private_key: p.private_key,
             state: KeyExchange {},
             ..Default::default()
         }
@@ -204,7 +298,10 @@ impl Phase<KeyExchange> {
             Ok(v) => Ok(v),
             Err(_) => Err(DriverError::InvalidFormat),
         }?;
-        let private = SecretKey::random(rng);
+        let private = self
+            .private_key
+            .take()
+            .unwrap_or_else(|| SecretKey::random(rng));
         let secret = &diffie_hellman(private.to_nonzero_scalar(), public.as_affine());
         self.shared_secret = secret.as_bytes()[0..].try_into()?;
         let pk = private.public_key().try_into()?;
@@ -218,6 +315,7 @@ impl From<Phase<KeyExchange>> for Phase<Authentication> {
         Phase {
             transcript: p.transcript,
             auth_value: p.auth_value,
+            oob: p.oob,
             shared_secret: p.shared_secret,
             random_provisioner: p.random_provisioner,
             state: Authentication::default(),
@@ -226,6 +324,12 @@ impl From<Phase<KeyExchange>> for Phase<Authentication> {
     }
 }
 
+impl Phase<Authentication> {
+    fn is_awaiting_input(&self) -> bool {
+        matches!(self.oob, Some(OobAuthentication::Input(..))) && !self.state.input_complete
+    }
+}
+
 impl From<Phase<Authentication>> for Phase<Confirming> {
     fn from(p: Phase<Authentication>) -> Self {
         Phase {
@@ -245,8 +349,8 @@ impl Phase<Confirming> {
         value: &Confirmation,
         rng: &mut RNG,
     ) -> Result<(), DriverError> {
-        self.state.confirmation_provider = value.confirmation;
-        rng.fill_bytes(&mut self.random_device);
+        self.state.confirmation_provider = value.confirmation.clone();
+        self.random_device = self.transcript.random(rng);
         self.state.confirmation_device = self.confirmation(&self.random_device)?;
         Ok(())
     }
@@ -255,7 +359,7 @@ impl Phase<Confirming> {
         if self.state.confirmation_provider != confirmation {
             return Err(DriverError::CryptoError);
         }
-        self.random_provisioner = value.random;
+        self.random_provisioner = value.random.clone();
         Ok(())
     }
 }
@@ -274,11 +378,9 @@ impl From<Phase<Confirming>> for Phase<DataDistribution> {
 
 impl Phase<DataDistribution> {
     pub fn decrypt(&self, data: &Data) -> Result<(DeviceKey, [u8; 25]), DriverError> {
-        let mut salt = [0; 48];
-        salt[0..16].copy_from_slice(&self.transcript.confirmation_salt()?.into_bytes());
-        salt[16..32].copy_from_slice(&self.random_provisioner);
-        salt[32..48].copy_from_slice(&self.random_device);
-        let salt = &s1(&salt)?.into_bytes()[0..];
+        let salt = &self
+            .transcript
+            .provisioning_salt(&self.random_provisioner, &self.random_device)?;
         let session_key = &prsk(&self.shared_secret, salt)?.into_bytes()[0..];
         let nonce = &prsn(&self.shared_secret, salt)?.into_bytes()[3..];
 
@@ -295,10 +397,20 @@ impl Phase<DataDistribution> {
     }
 }
 
+/// Whether the `selected` size does not exceed the `advertised` maximum.
+fn is_within(advertised: &OOBSize, selected: &OOBSize) -> bool {
+    match (advertised, selected) {
+        (OOBSize::MaximumSize(max), OOBSize::MaximumSize(size)) => size <= max,
+        _ => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use btmesh_pdu::provisioning::{Confirmation, Invite, PublicKey, Random, Start};
+    use btmesh_pdu::provisioning::{
+        Algorithm, Confirmation, InputOOBAction, Invite, OutputOOBAction, PublicKey, Random, Start,
+    };
     use p256::SecretKey;
     use rand_core::OsRng;
 
@@ -309,7 +421,7 @@ mod tests {
             number_of_elements: size,
             ..Default::default()
         };
-        let mut fsm = Provisionee::new(caps);
+        let mut fsm = Provisionee::new(caps, None, None);
         assert!(matches!(fsm, Provisionee::Beaconing(_)));
         let pdu = ProvisioningPDU::Invite(Invite {
             attention_duration: 30,
@@ -352,6 +464,7 @@ mod tests {
         let mut random = [0; 16];
         OsRng.fill_bytes(&mut random);
         let mut fsm = confirmation(&random);
+        let random = Vec::from_slice(&random).unwrap();
         let pdu = ProvisioningPDU::Random(Random { random });
         fsm = fsm.next(&pdu, &mut OsRng).unwrap();
         assert!(matches!(fsm, Provisionee::DataDistribution(_)));
@@ -363,17 +476,248 @@ mod tests {
         let mut fsm = confirmation(&random);
         // Use a different random to break confirmation...
         OsRng.fill_bytes(&mut random);
+        let random = Vec::from_slice(&random).unwrap();
         let pdu = ProvisioningPDU::Random(Random { random });
         fsm = fsm.next(&pdu, &mut OsRng).unwrap();
         assert!(matches!(fsm, Provisionee::Failure(..)));
     }
 
+    #[test]
+    fn output_oob() {
+        let mut fsm = keyexchange_with(
+            oob_capabilities(),
+            Start {
+                authentication_method: AuthenticationMethod::Output,
+                authentication_action: OOBAction::Output(OutputOOBAction::OutputNumeric),
+                authentication_size: OOBSize::MaximumSize(4),
+                ..Default::default()
+            },
+        );
+        assert!(fsm.oob().is_none());
+        let private = SecretKey::random(OsRng);
+        let pdu = ProvisioningPDU::PublicKey(PublicKey::try_from(private.public_key()).unwrap());
+        fsm = fsm.next(&pdu, &mut OsRng).unwrap();
+        assert!(matches!(
+            fsm.oob(),
+            Some(OobAuthentication::Output(
+                OutputOOBAction::OutputNumeric,
+                OobValue::Numeric(value)
+            )) if *value < 10_000
+        ));
+        assert!(matches!(
+            fsm.response(),
+            Some(ProvisioningPDU::PublicKey(_))
+        ));
+    }
+
+    #[test]
+    fn input_oob() {
+        let mut fsm = keyexchange_with(
+            oob_capabilities(),
+            Start {
+                authentication_method: AuthenticationMethod::Input,
+                authentication_action: OOBAction::Input(InputOOBAction::InputNumeric),
+                authentication_size: OOBSize::MaximumSize(4),
+                ..Default::default()
+            },
+        );
+        let private = SecretKey::random(OsRng);
+        let pdu = ProvisioningPDU::PublicKey(PublicKey::try_from(private.public_key()).unwrap());
+        fsm = fsm.next(&pdu, &mut OsRng).unwrap();
+        assert!(matches!(
+            fsm.oob(),
+            Some(OobAuthentication::Input(InputOOBAction::InputNumeric, 4))
+        ));
+        assert!(matches!(
+            fsm.response(),
+            Some(ProvisioningPDU::PublicKey(_))
+        ));
+
+        // no confirmation until the user has input the value.
+        let random = [0; 16];
+        let confirmation = match &fsm {
+            Provisionee::Authentication(auth) => auth.confirmation(&random).unwrap(),
+            _ => panic!("wrong state returned"),
+        };
+        let pdu = ProvisioningPDU::Confirmation(Confirmation { confirmation });
+        fsm = fsm.next(&pdu, &mut OsRng).unwrap();
+        assert!(matches!(fsm, Provisionee::Authentication(_)));
+
+        fsm = fsm.input(OobValue::Numeric(1234));
+        assert!(matches!(
+            fsm.response(),
+            Some(ProvisioningPDU::InputComplete)
+        ));
+        let confirmation = match &fsm {
+            Provisionee::Authentication(auth) => {
+                assert!(matches!(auth.auth_value, AuthValue::InputNumeric(1234)));
+                auth.confirmation(&random).unwrap()
+            }
+            _ => panic!("wrong state returned"),
+        };
+        let pdu = ProvisioningPDU::Confirmation(Confirmation { confirmation });
+        fsm = fsm.next(&pdu, &mut OsRng).unwrap();
+        assert!(matches!(fsm, Provisionee::Confirming(_)));
+    }
+
+    #[test]
+    fn output_oob_not_advertised() {
+        let start = Start {
+            authentication_method: AuthenticationMethod::Output,
+            authentication_action: OOBAction::Output(OutputOOBAction::Blink),
+            authentication_size: OOBSize::MaximumSize(4),
+            ..Default::default()
+        };
+        let fsm = start_with(oob_capabilities(), start.clone());
+        assert!(matches!(
+            fsm,
+            Provisionee::Failure(ErrorCode::InvalidFormat)
+        ));
+
+        let fsm = start_with(
+            oob_capabilities(),
+            Start {
+                authentication_action: OOBAction::Output(OutputOOBAction::OutputNumeric),
+                authentication_size: OOBSize::MaximumSize(5),
+                ..start
+            },
+        );
+        assert!(matches!(
+            fsm,
+            Provisionee::Failure(ErrorCode::InvalidFormat)
+        ));
+    }
+
+    #[test]
+    fn input_oob_not_advertised() {
+        let start = Start {
+            authentication_method: AuthenticationMethod::Input,
+            authentication_action: OOBAction::Input(InputOOBAction::Push),
+            authentication_size: OOBSize::MaximumSize(4),
+            ..Default::default()
+        };
+        let fsm = start_with(oob_capabilities(), start.clone());
+        assert!(matches!(
+            fsm,
+            Provisionee::Failure(ErrorCode::InvalidFormat)
+        ));
+
+        let fsm = start_with(
+            oob_capabilities(),
+            Start {
+                authentication_action: OOBAction::Input(InputOOBAction::InputNumeric),
+                authentication_size: OOBSize::MaximumSize(5),
+                ..start
+            },
+        );
+        assert!(matches!(
+            fsm,
+            Provisionee::Failure(ErrorCode::InvalidFormat)
+        ));
+    }
+
+    #[test]
+    fn static_oob_unavailable() {
+        let mut fsm = Provisionee::new(Capabilities::default(), None, None);
+        let invite = ProvisioningPDU::Invite(Invite::default());
+        fsm = fsm.next(&invite, &mut OsRng).unwrap();
+        let start = ProvisioningPDU::Start(Start {
+            authentication_method: AuthenticationMethod::Static,
+            ..Default::default()
+        });
+        fsm = fsm.next(&start, &mut OsRng).unwrap();
+        assert!(matches!(
+            fsm,
+            Provisionee::Failure(ErrorCode::InvalidFormat)
+        ));
+    }
+
+    #[test]
+    fn oob_public_key() {
+        let start = Start {
+            public_key: PublicKeySelected::OOBPublicKey,
+            ..Default::default()
+        };
+        let invite = ProvisioningPDU::Invite(Invite::default());
+
+        // not available without a fixed key pair.
+        let mut fsm = Provisionee::new(Capabilities::default(), None, None);
+        fsm = fsm.next(&invite, &mut OsRng).unwrap();
+        fsm = fsm
+            .next(&ProvisioningPDU::Start(start.clone()), &mut OsRng)
+            .unwrap();
+        assert!(matches!(
+            fsm,
+            Provisionee::Failure(ErrorCode::InvalidFormat)
+        ));
+
+        let mut fsm = Provisionee::new(
+            Capabilities::default(),
+            None,
+            Some(SecretKey::random(OsRng)),
+        );
+        fsm = fsm.next(&invite, &mut OsRng).unwrap();
+        fsm = fsm
+            .next(&ProvisioningPDU::Start(start), &mut OsRng)
+            .unwrap();
+        let private = SecretKey::random(OsRng);
+        let pdu = ProvisioningPDU::PublicKey(PublicKey::try_from(private.public_key()).unwrap());
+        fsm = fsm.next(&pdu, &mut OsRng).unwrap();
+        assert!(matches!(fsm, Provisionee::Authentication(_)));
+        assert!(fsm.response().is_none());
+    }
+
+    #[test]
+    fn unsupported_algorithm() {
+        let mut fsm = Provisionee::new(Capabilities::default(), None, None);
+        let invite = ProvisioningPDU::Invite(Invite::default());
+        fsm = fsm.next(&invite, &mut OsRng).unwrap();
+        let start = ProvisioningPDU::Start(Start {
+            algorithm: Algorithm::P256HmacSha256,
+            ..Default::default()
+        });
+        fsm = fsm.next(&start, &mut OsRng).unwrap();
+        assert!(matches!(
+            fsm,
+            Provisionee::Failure(ErrorCode::InvalidFormat)
+        ));
+    }
+
     fn keyexchange() -> Provisionee {
-        let mut fsm = Provisionee::new(Capabilities::default());
+        keyexchange_with(Capabilities::default(), Start::default())
+    }
+
+    fn oob_capabilities() -> Capabilities {
+        let mut capabilities = Capabilities {
+            output_oob_size: OOBSize::MaximumSize(4),
+            input_oob_size: OOBSize::MaximumSize(4),
+            ..Default::default()
+        };
+        capabilities
+            .output_oob_action
+            .push(OutputOOBAction::OutputNumeric)
+            .unwrap();
+        capabilities
+            .input_oob_action
+            .push(InputOOBAction::InputNumeric)
+            .unwrap();
+        capabilities
+    }
+
+    fn start_with(capabilities: Capabilities, start: Start) -> Provisionee {
+        let mut fsm = Provisionee::new(capabilities, None, None);
+        let invite = ProvisioningPDU::Invite(Invite::default());
+        fsm = fsm.next(&invite, &mut OsRng).unwrap();
+        fsm.next(&ProvisioningPDU::Start(start), &mut OsRng)
+            .unwrap()
+    }
+
+    fn keyexchange_with(capabilities: Capabilities, start: Start) -> Provisionee {
+        let mut fsm = Provisionee::new(capabilities, None, None);
         let invite = ProvisioningPDU::Invite(Invite::default());
         fsm = fsm.next(&invite, &mut OsRng).unwrap();
         assert!(matches!(fsm, Provisionee::Invitation(_)));
-        let start = ProvisioningPDU::Start(Start::default());
+        let start = ProvisioningPDU::Start(start);
         fsm = fsm.next(&start, &mut OsRng).unwrap();
         assert!(matches!(fsm, Provisionee::KeyExchange(_)));
         assert!(matches!(fsm.response(), None));
@@ -389,7 +733,9 @@ mod tests {
             Provisionee::Authentication(auth) => auth.confirmation(random).unwrap(),
             _ => panic!("wrong state returned"),
         };
-        let pdu = ProvisioningPDU::Confirmation(Confirmation { confirmation });
+        let pdu = ProvisioningPDU::Confirmation(Confirmation {
+            confirmation: confirmation.clone(),
+        });
         fsm = fsm.next(&pdu, &mut OsRng).unwrap();
         match fsm.response() {
             Some(ProvisioningPDU::Confirmation(c)) => assert_ne!(c.confirmation, confirmation),
@@ -1,12 +1,13 @@
 use crate::DriverError;
+use btmesh_common::address::UnicastAddress;
 use btmesh_common::crypto::{
-    aes_cmac,
-    provisioning::{encrypt_data, prck, prsk, prsn},
-    s1,
+    device::DeviceKey,
+    provisioning::{encrypt_data, prdk, prsk, prsn},
 };
+use btmesh_common::Uuid;
 use btmesh_pdu::provisioning::{
-    Capabilities, Confirmation, Data, ErrorCode, Failed, Invite, ProvisioningData, ProvisioningPDU,
-    PublicKey, Random, Start,
+    Algorithm, AuthenticationMethod, Capabilities, Confirmation, Data, ErrorCode, Failed, Invite,
+    ProvisioningData, ProvisioningPDU, PublicKey, PublicKeySelected, Random, Start,
 };
 use heapless::Vec;
 use p256::{elliptic_curve::ecdh::diffie_hellman, SecretKey};
@@ -14,26 +15,101 @@ use rand_core::{CryptoRng, RngCore};
 
 use super::{
     auth_value::{determine_auth_value, AuthValue},
+    oob::StaticOob,
     transcript::Transcript,
 };
 
+/// A node provisioned into the network by this node.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Copy, Clone, Debug)]
+pub struct ProvisionedNode {
+    pub uuid: Uuid,
+    pub device_key: DeviceKey,
+    /// Address of the primary element, followed by those of the other elements.
+    pub unicast_address: UnicastAddress,
+    pub number_of_elements: u8,
+}
+
+/// Hands out consecutive unicast addresses to newly provisioned nodes,
+/// one per element.
+///
+/// The range must not overlap the elements of this node, nor those
+/// of nodes provisioned by other means.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct UnicastAddressAllocator {
+    next: u16,
+    last: u16,
+}
+
+impl UnicastAddressAllocator {
+    /// Allocate from `first` up to and including `last`.
+    pub fn new(first: UnicastAddress, last: UnicastAddress) -> Self {
+        Self {
+            next: first.into(),
+            last: last.into(),
+        }
+    }
+
+    /// The next address to be handed out, if any remain.
+    pub fn next(&self) -> Option<UnicastAddress> {
+        if self.next <= self.last {
+            UnicastAddress::new(self.next).ok()
+        } else {
+            None
+        }
+    }
+
+    /// Reserve the addresses of a node with `number_of_elements` elements,
+    /// returning the address of its primary element.
+    pub fn allocate(&mut self, number_of_elements: u8) -> Option<UnicastAddress> {
+        let primary = self.next()?;
+        let last = self
+            .next
+            .checked_add(number_of_elements.checked_sub(1)? as u16)?;
+        if last > self.last {
+            return None;
+        }
+        self.next = last + 1;
+        Some(primary)
+    }
+}
+
 pub enum Provisioner {
     Invitation(Phase<Invitation>),
     KeyExchange(Phase<KeyExchange>),
     Authentication(Phase<Authentication>),
     DataDistribution(Phase<DataDistribution>),
-    Success,
+    Success(ProvisionedNode),
     Failure(ResponsePDU),
 }
 
 impl Provisioner {
-    pub fn new(data: ProvisioningData, attention_duration: u8) -> Result<Self, DriverError> {
+    /// Provision the device identified by `uuid` with `data`, whose unicast
+    /// address is allocated once the device reports its number of elements.
+    ///
+    /// The device is authenticated with its `static_oob` secret, and its
+    /// `public_key` is not exchanged in-band, if known and supported by the device.
+    pub fn new(
+        uuid: Uuid,
+        data: ProvisioningData,
+        attention_duration: u8,
+        static_oob: Option<StaticOob>,
+        public_key: Option<PublicKey>,
+    ) -> Result<Self, DriverError> {
         Ok(Self::Invitation(Phase::<Invitation>::new(
+            uuid,
             data,
             attention_duration,
+            static_oob,
+            public_key,
         )?))
     }
 
+    pub fn in_progress(&self) -> bool {
+        !matches!(self, Self::Success(..) | Self::Failure(..))
+    }
+
     pub fn response(&self) -> ResponsePDU {
         match self {
             Self::Invitation(phase) => phase.response.clone(),
@@ -41,40 +117,55 @@ impl Provisioner {
             Self::Authentication(phase) => phase.response.clone(),
             Self::DataDistribution(phase) => phase.response.clone(),
             Self::Failure(response) => response.clone(),
-            Self::Success => ResponsePDU::None,
+            Self::Success(..) => ResponsePDU::None,
         }
     }
 
+    /// Advance on an inbound PDU, allocating the unicast addresses of the
+    /// device from `addresses` when it reports its capabilities.
     pub fn next<RNG: RngCore + CryptoRng>(
         self,
         pdu: &ProvisioningPDU,
         rng: &mut RNG,
+        addresses: &mut UnicastAddressAllocator,
     ) -> Result<Self, DriverError> {
         match (self, pdu) {
             // CAPABILITIES
             (Provisioner::Invitation(mut phase), ProvisioningPDU::Capabilities(caps)) => {
-                // TODO: This is when we know how many elements the
-                // device has. How do we let the caller of this state
-                // machine know that we need to reserve the
-                // data.unicast_address passed to Provisioner::new
-                // plus caps.number_of_elements?
-                phase.capabilities(caps, rng)?;
-                Ok(Provisioner::KeyExchange(phase.try_into()?))
+                match addresses.allocate(caps.number_of_elements) {
+                    Some(unicast_address) => {
+                        phase.capabilities(caps, unicast_address, rng)?;
+                        let phase: Phase<KeyExchange> = phase.try_into()?;
+                        match phase.state.peer_key {
+                            // the public key of the device is known, so authenticate right away.
+                            Some(peer_key) => {
+                                let start = phase.response.clone();
+                                match Provisioner::exchange_keys(phase, &peer_key, rng)? {
+                                    Provisioner::Authentication(mut phase) => {
+                                        phase.response = start.followed_by(phase.response)?;
+                                        Ok(Provisioner::Authentication(phase))
+                                    }
+                                    failure => Ok(failure),
+                                }
+                            }
+                            None => Ok(Provisioner::KeyExchange(phase)),
+                        }
+                    }
+                    None => Provisioner::fail(ErrorCode::CannotAssignAddresses),
+                }
             }
             // PUBLIC KEY
-            (Provisioner::KeyExchange(mut phase), ProvisioningPDU::PublicKey(peer_key)) => {
+            (Provisioner::KeyExchange(phase), ProvisioningPDU::PublicKey(peer_key)) => {
                 // TODO: OOB capabilities should determine whether we
                 // return a Confirmation here or wait for the device
                 // to send us an InputComplete
-
-                match phase.calculate_ecdh(peer_key, rng) {
-                    Ok(_) => Ok(Provisioner::Authentication(phase.try_into()?)),
-                    Err(DriverError::InvalidFormat) => Provisioner::fail(ErrorCode::InvalidFormat),
-                    Err(_) => Provisioner::fail(ErrorCode::UnexpectedError),
-                }
+                Provisioner::exchange_keys(phase, peer_key, rng)
             }
             // CONFIRMATION
             (Provisioner::Authentication(mut phase), ProvisioningPDU::Confirmation(value)) => {
+                if value.confirmation.len() != phase.transcript.algorithm().value_size() {
+                    return Provisioner::fail(ErrorCode::InvalidFormat);
+                }
                 phase.confirmation(value)?;
                 Ok(Provisioner::Authentication(phase))
             }
@@ -86,8 +177,12 @@ impl Provisioner {
                 }
             }
             // COMPLETE
-            (Provisioner::DataDistribution(_), ProvisioningPDU::Complete) => {
-                Ok(Provisioner::Success)
+            (Provisioner::DataDistribution(phase), ProvisioningPDU::Complete) => {
+                Ok(Provisioner::Success(phase.provisioned_node()?))
+            }
+            // FAILED
+            (current, ProvisioningPDU::Failed(_)) if current.in_progress() => {
+                Ok(Provisioner::Failure(ResponsePDU::One(pdu.clone())))
             }
             (current, _) => {
                 // if it's an invalid PDU, assume it's just a wayward PDU and ignore, don't break.
@@ -95,6 +190,18 @@ impl Provisioner {
             }
         }
     }
+    fn exchange_keys<RNG: RngCore + CryptoRng>(
+        mut phase: Phase<KeyExchange>,
+        peer_key: &PublicKey,
+        rng: &mut RNG,
+    ) -> Result<Self, DriverError> {
+        match phase.calculate_ecdh(peer_key, rng) {
+            Ok(_) => Ok(Provisioner::Authentication(phase.try_into()?)),
+            Err(DriverError::InvalidFormat) => Provisioner::fail(ErrorCode::InvalidFormat),
+            Err(_) => Provisioner::fail(ErrorCode::UnexpectedError),
+        }
+    }
+
     fn fail(error_code: ErrorCode) -> Result<Provisioner, DriverError> {
         Ok(Provisioner::Failure(ResponsePDU::One(
             ProvisioningPDU::Failed(Failed { error_code }),
@@ -104,6 +211,7 @@ impl Provisioner {
 
 #[derive(Default, Clone)]
 pub enum ResponsePDU {
+    Three([ProvisioningPDU; 3]),
     Two([ProvisioningPDU; 2]),
     One(ProvisioningPDU),
     #[default]
@@ -119,48 +227,83 @@ impl<'a> IntoIterator for &'a ResponsePDU {
             ResponsePDU::None => &[],
             ResponsePDU::One(single) => core::slice::from_ref(single),
             ResponsePDU::Two(array) => array.as_slice(),
+            ResponsePDU::Three(array) => array.as_slice(),
         };
         slice.iter()
     }
 }
 
+impl ResponsePDU {
+    /// These PDUs, followed by the single PDU of `next`.
+    fn followed_by(self, next: ResponsePDU) -> Result<Self, DriverError> {
+        match (self, next) {
+            (ResponsePDU::None, next) => Ok(next),
+            (ResponsePDU::One(first), ResponsePDU::One(second)) => {
+                Ok(ResponsePDU::Two([first, second]))
+            }
+            (ResponsePDU::Two([first, second]), ResponsePDU::One(third)) => {
+                Ok(ResponsePDU::Three([first, second, third]))
+            }
+            _ => Err(DriverError::InvalidState),
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct Phase<S> {
     pub response: ResponsePDU,
     transcript: Transcript,
+    uuid: Option<Uuid>,
     data: Option<ProvisioningData>,
+    number_of_elements: u8,
     state: S,
 }
 #[derive(Default)]
 pub struct Invitation {
     auth_value: AuthValue,
+    static_oob: Option<StaticOob>,
+    public_key: Option<PublicKey>,
     private: Option<SecretKey>,
 }
 #[derive(Default)]
 pub struct KeyExchange {
     auth_value: AuthValue,
+    /// The public key of the device, when known out of band.
+    peer_key: Option<PublicKey>,
     private: Option<SecretKey>,
     shared_secret: Option<[u8; 32]>,
-    random_provisioner: [u8; 16],
+    random_provisioner: Vec<u8, 32>,
 }
 #[derive(Default)]
 pub struct Authentication {
     auth_value: AuthValue,
     shared_secret: [u8; 32],
-    confirmation: Option<[u8; 16]>,
-    random_device: [u8; 16],
-    random_provisioner: [u8; 16],
+    confirmation: Option<Vec<u8, 32>>,
+    random_device: Vec<u8, 32>,
+    random_provisioner: Vec<u8, 32>,
 }
 pub struct DataDistribution {
     shared_secret: [u8; 32],
-    random_device: [u8; 16],
-    random_provisioner: [u8; 16],
+    random_device: Vec<u8, 32>,
+    random_provisioner: Vec<u8, 32>,
 }
 
 impl Phase<Invitation> {
-    pub fn new(data: ProvisioningData, attention_duration: u8) -> Result<Self, DriverError> {
+    pub fn new(
+        uuid: Uuid,
+        data: ProvisioningData,
+        attention_duration: u8,
+        static_oob: Option<StaticOob>,
+        public_key: Option<PublicKey>,
+    ) -> Result<Self, DriverError> {
         let mut result = Self {
+            uuid: Some(uuid),
             data: Some(data),
+            state: Invitation {
+                static_oob,
+                public_key,
+                ..Default::default()
+            },
             ..Default::default()
         };
         let invitation = Invite { attention_duration };
@@ -171,13 +314,39 @@ impl Phase<Invitation> {
     pub fn capabilities<RNG: RngCore + CryptoRng>(
         &mut self,
         capabilities: &Capabilities,
+        unicast_address: UnicastAddress,
         rng: &mut RNG,
     ) -> Result<(), DriverError> {
         self.transcript.add_capabilities(capabilities)?;
-        // TODO: derive Start from Capabilities
-        let start = Start::default();
+        self.data
+            .as_mut()
+            .ok_or(DriverError::InvalidState)?
+            .unicast_address = unicast_address;
+        self.number_of_elements = capabilities.number_of_elements;
+        // TODO: output and input OOB authentication
+        let mut start = match self.state.static_oob {
+            Some(secret) if capabilities.static_oob_type.available => {
+                self.state.auth_value = AuthValue::Static(secret);
+                Start {
+                    authentication_method: AuthenticationMethod::Static,
+                    ..Default::default()
+                }
+            }
+            _ => {
+                let start = Start::default();
+                self.state.auth_value = determine_auth_value(rng, &start)?;
+                start
+            }
+        };
+        if capabilities.algorithms.contains(Algorithm::P256HmacSha256) {
+            start.algorithm = Algorithm::P256HmacSha256;
+        }
+        if capabilities.public_key_type.available && self.state.public_key.is_some() {
+            start.public_key = PublicKeySelected::OOBPublicKey;
+        } else {
+            self.state.public_key.take();
+        }
         self.transcript.add_start(&start)?;
-        self.state.auth_value = determine_auth_value(rng, &start)?;
         let private = SecretKey::random(rng);
         let public = private.public_key().try_into()?;
         self.state.private = Some(private);
@@ -194,7 +363,7 @@ impl Phase<KeyExchange> {
         &mut self,
         key: &PublicKey,
         rng: &mut RNG,
-    ) -> Result<[u8; 16], DriverError> {
+    ) -> Result<Vec<u8, 32>, DriverError> {
         let public: p256::PublicKey = match key.try_into() {
             Ok(v) => Ok(v),
             Err(_) => Err(DriverError::InvalidFormat),
@@ -206,8 +375,8 @@ impl Phase<KeyExchange> {
                 let pk = private.public_key().try_into()?;
                 self.transcript.add_pubkey_provisioner(&pk)?;
                 self.transcript.add_pubkey_device(key)?;
-                rng.fill_bytes(&mut self.state.random_provisioner);
-                Ok(self.state.random_provisioner)
+                self.state.random_provisioner = self.transcript.random(rng);
+                Ok(self.state.random_provisioner.clone())
             }
             None => Err(DriverError::InvalidState),
         }
@@ -216,38 +385,35 @@ impl Phase<KeyExchange> {
 
 impl Phase<Authentication> {
     pub fn confirmation(&mut self, value: &Confirmation) -> Result<(), DriverError> {
-        self.state.confirmation = Some(value.confirmation);
+        self.state.confirmation = Some(value.confirmation.clone());
         self.response = ResponsePDU::One(ProvisioningPDU::Random(Random {
-            random: self.state.random_provisioner,
+            random: self.state.random_provisioner.clone(),
         }));
         Ok(())
     }
     pub fn check(&mut self, value: &Random) -> Result<(), DriverError> {
         let confirmation = self.confirm(&value.random)?;
-        match self.state.confirmation {
-            Some(v) if v == confirmation => (),
+        match &self.state.confirmation {
+            Some(v) if *v == confirmation => (),
             _ => return Err(DriverError::CryptoError),
         }
-        self.state.random_device = value.random;
+        self.state.random_device = value.random.clone();
         Ok(())
     }
-    fn confirm(&self, random: &[u8]) -> Result<[u8; 16], DriverError> {
-        let salt = self.transcript.confirmation_salt()?;
-        let key = prck(&self.state.shared_secret, &salt.into_bytes())?;
-        let mut bytes: Vec<u8, 32> = Vec::new();
-        bytes.extend_from_slice(random)?;
-        bytes.extend_from_slice(&self.state.auth_value.get_bytes())?;
-        Ok(aes_cmac(&key.into_bytes(), &bytes)?.into_bytes().into())
+    fn confirm(&self, random: &[u8]) -> Result<Vec<u8, 32>, DriverError> {
+        self.transcript
+            .confirmation(&self.state.shared_secret, random, &self.state.auth_value)
     }
 }
 
 impl Phase<DataDistribution> {
+    fn provisioning_salt(&self) -> Result<[u8; 16], DriverError> {
+        self.transcript
+            .provisioning_salt(&self.state.random_provisioner, &self.state.random_device)
+    }
+
     pub fn encrypt(&self) -> Result<Data, DriverError> {
-        let mut salt = [0; 48];
-        salt[0..16].copy_from_slice(&self.transcript.confirmation_salt()?.into_bytes());
-        salt[16..32].copy_from_slice(&self.state.random_provisioner);
-        salt[32..48].copy_from_slice(&self.state.random_device);
-        let salt = &s1(&salt)?.into_bytes()[0..];
+        let salt = &self.provisioning_salt()?;
         let session_key = &prsk(&self.state.shared_secret, salt)?.into_bytes()[0..];
         let nonce = &prsn(&self.state.shared_secret, salt)?.into_bytes()[3..];
 
@@ -266,6 +432,18 @@ impl Phase<DataDistribution> {
             Ok(Data { encrypted, mic })
         }
     }
+
+    /// The device key derived alongside the provisionee, and its addresses.
+    pub fn provisioned_node(&self) -> Result<ProvisionedNode, DriverError> {
+        let device_key =
+            &*prdk(&self.state.shared_secret, &self.provisioning_salt()?)?.into_bytes();
+        Ok(ProvisionedNode {
+            uuid: self.uuid.ok_or(DriverError::InvalidState)?,
+            device_key: device_key.try_into()?,
+            unicast_address: self.data.ok_or(DriverError::InvalidState)?.unicast_address,
+            number_of_elements: self.number_of_elements,
+        })
+    }
 }
 
 impl TryFrom<Phase<Invitation>> for Phase<KeyExchange> {
@@ -273,10 +451,13 @@ impl TryFrom<Phase<Invitation>> for Phase<KeyExchange> {
     fn try_from(p: Phase<Invitation>) -> Result<Self, Self::Error> {
         Ok(Phase {
             transcript: p.transcript,
+            uuid: p.uuid,
             data: p.data,
+            number_of_elements: p.number_of_elements,
             response: p.response,
             state: KeyExchange {
                 auth_value: p.state.auth_value,
+                peer_key: p.state.public_key,
                 private: p.state.private,
                 ..Default::default()
             },
@@ -289,12 +470,14 @@ impl TryFrom<Phase<KeyExchange>> for Phase<Authentication> {
     fn try_from(p: Phase<KeyExchange>) -> Result<Self, Self::Error> {
         let mut phase = Phase {
             transcript: p.transcript,
+            uuid: p.uuid,
             data: p.data,
+            number_of_elements: p.number_of_elements,
             response: p.response,
             state: Authentication {
                 auth_value: p.state.auth_value,
                 shared_secret: p.state.shared_secret.unwrap(),
-                random_provisioner: p.state.random_provisioner,
+                random_provisioner: p.state.random_provisioner.clone(),
                 ..Default::default()
             },
         };
@@ -310,7 +493,9 @@ impl TryFrom<Phase<Authentication>> for Phase<DataDistribution> {
     fn try_from(p: Phase<Authentication>) -> Result<Self, Self::Error> {
         let mut phase = Phase {
             transcript: p.transcript,
+            uuid: p.uuid,
             data: p.data,
+            number_of_elements: p.number_of_elements,
             response: p.response,
             state: DataDistribution {
                 shared_secret: p.state.shared_secret,
@@ -329,31 +514,257 @@ mod tests {
 
     use super::*;
     use crate::stack::unprovisioned::provisionee::Provisionee;
-    use btmesh_common::{address::UnicastAddress, KeyRefreshFlag};
-    use btmesh_pdu::provisioning::{Capabilities, ProvisioningPDU::Failed};
+    use btmesh_common::KeyRefreshFlag;
+    use btmesh_pdu::provisioning::{
+        Algorithms, Capabilities, ProvisioningPDU::Failed, PublicKeyType, StaticOOBType,
+    };
     use rand_core::OsRng;
 
     #[test]
     fn provision_device() {
-        let rng = &mut OsRng;
-
         let fixture = ProvisioningData {
             unicast_address: UnicastAddress::new(0x00_0A).unwrap(),
             key_refresh_flag: KeyRefreshFlag(true),
             ..Default::default()
         };
-        let mut provisioner = Provisioner::new(fixture, 60).unwrap();
-        let mut device = Provisionee::new(Capabilities {
+        let uuid = Uuid::new([0x42; 16]);
+        let mut addresses = UnicastAddressAllocator::new(
+            UnicastAddress::new(0x00_0A).unwrap(),
+            UnicastAddress::new(0x00_FF).unwrap(),
+        );
+        let provisioner = Provisioner::new(
+            uuid,
+            ProvisioningData {
+                unicast_address: UnicastAddress::new(0x00_01).unwrap(),
+                ..fixture
+            },
+            60,
+            None,
+            None,
+        )
+        .unwrap();
+        let device = Provisionee::new(
+            Capabilities {
+                number_of_elements: 2,
+                ..Default::default()
+            },
+            None,
+            None,
+        );
+        match provision(provisioner, device, &mut addresses) {
+            (Provisionee::Complete(key, result), Provisioner::Success(node)) => {
+                assert_ne!(&[0; 16], key.deref());
+                assert_eq!(key.deref(), node.device_key.deref());
+                assert_eq!(fixture, result);
+                assert_eq!(uuid, node.uuid);
+                assert_eq!(fixture.unicast_address, node.unicast_address);
+                assert_eq!(2, node.number_of_elements);
+            }
+            _ => panic!("wrong ending state"),
+        }
+        assert_eq!(UnicastAddress::new(0x00_0C).ok(), addresses.next());
+    }
+
+    #[test]
+    fn provision_device_with_static_oob() {
+        let secret = [0x5A; 16].into();
+        let capabilities = Capabilities {
+            number_of_elements: 1,
+            static_oob_type: StaticOOBType { available: true },
+            ..Default::default()
+        };
+        let mut addresses = UnicastAddressAllocator::new(
+            UnicastAddress::new(0x00_0A).unwrap(),
+            UnicastAddress::new(0x00_FF).unwrap(),
+        );
+
+        let provisioner = Provisioner::new(
+            Uuid::new([0x42; 16]),
+            Default::default(),
+            60,
+            Some(secret),
+            None,
+        )
+        .unwrap();
+        let device = Provisionee::new(capabilities.clone(), Some(secret), None);
+        assert!(matches!(
+            provision(provisioner, device, &mut addresses),
+            (Provisionee::Complete(..), Provisioner::Success(..))
+        ));
+
+        // a provisioner without the right secret fails to confirm.
+        let provisioner = Provisioner::new(
+            Uuid::new([0x42; 16]),
+            Default::default(),
+            60,
+            Some([0; 16].into()),
+            None,
+        )
+        .unwrap();
+        let device = Provisionee::new(capabilities, Some(secret), None);
+        assert!(matches!(
+            provision(provisioner, device, &mut addresses),
+            (
+                Provisionee::Failure(ErrorCode::ConfirmationFailed),
+                Provisioner::Failure(..)
+            )
+        ));
+    }
+
+    #[test]
+    fn provision_device_with_oob_public_key() {
+        let private_key = SecretKey::random(OsRng);
+        let public_key: PublicKey = private_key.public_key().try_into().unwrap();
+        let capabilities = Capabilities {
             number_of_elements: 1,
+            public_key_type: PublicKeyType { available: true },
             ..Default::default()
-        });
+        };
+        let mut addresses = UnicastAddressAllocator::new(
+            UnicastAddress::new(0x00_0A).unwrap(),
+            UnicastAddress::new(0x00_FF).unwrap(),
+        );
+
+        let mut provisioner = Provisioner::new(
+            Uuid::new([0x42; 16]),
+            Default::default(),
+            60,
+            None,
+            Some(public_key),
+        )
+        .unwrap();
+        let mut device = Provisionee::new(capabilities.clone(), None, Some(private_key.clone()));
+
+        // the public key of the device is not exchanged in-band.
+        let rng = &mut OsRng;
+        device = device
+            .next(provisioner.response().into_iter().next().unwrap(), rng)
+            .unwrap();
+        provisioner = provisioner
+            .next(&device.response().unwrap(), rng, &mut addresses)
+            .unwrap();
+        match provisioner.response() {
+            ResponsePDU::Three(
+                [ProvisioningPDU::Start(start), ProvisioningPDU::PublicKey(_), ProvisioningPDU::Confirmation(_)],
+            ) => assert!(matches!(start.public_key, PublicKeySelected::OOBPublicKey)),
+            _ => panic!("wrong pdus returned for capabilities"),
+        }
+        assert!(matches!(
+            provision(provisioner, device, &mut addresses),
+            (Provisionee::Complete(..), Provisioner::Success(..))
+        ));
+
+        // a provisioner with another key fails to confirm.
+        let other: PublicKey = SecretKey::random(OsRng).public_key().try_into().unwrap();
+        let provisioner = Provisioner::new(
+            Uuid::new([0x42; 16]),
+            Default::default(),
+            60,
+            None,
+            Some(other),
+        )
+        .unwrap();
+        let device = Provisionee::new(capabilities, None, Some(private_key));
+        assert!(matches!(
+            provision(provisioner, device, &mut addresses),
+            (
+                Provisionee::Failure(ErrorCode::ConfirmationFailed),
+                Provisioner::Failure(..)
+            )
+        ));
+    }
+
+    #[test]
+    fn provision_device_with_hmac_sha256() {
+        let secret = [0x5A; 32].into();
+        let mut algorithms = Algorithms::default();
+        algorithms.push(Algorithm::P256HmacSha256).unwrap();
+        let capabilities = Capabilities {
+            number_of_elements: 1,
+            algorithms,
+            static_oob_type: StaticOOBType { available: true },
+            ..Default::default()
+        };
+        let mut addresses = UnicastAddressAllocator::new(
+            UnicastAddress::new(0x00_0A).unwrap(),
+            UnicastAddress::new(0x00_FF).unwrap(),
+        );
+
+        let mut provisioner = Provisioner::new(
+            Uuid::new([0x42; 16]),
+            Default::default(),
+            60,
+            Some(secret),
+            None,
+        )
+        .unwrap();
+        let mut device = Provisionee::new(capabilities.clone(), Some(secret), None);
+
+        // the strongest algorithm offered by the device is selected.
+        let rng = &mut OsRng;
+        device = device
+            .next(provisioner.response().into_iter().next().unwrap(), rng)
+            .unwrap();
+        provisioner = provisioner
+            .next(&device.response().unwrap(), rng, &mut addresses)
+            .unwrap();
+        for pdu in provisioner.response().into_iter() {
+            if let ProvisioningPDU::Start(start) = pdu {
+                assert_eq!(Algorithm::P256HmacSha256, start.algorithm);
+            }
+            device = device.next(pdu, rng).unwrap();
+        }
+        provisioner = provisioner
+            .next(&device.response().unwrap(), rng, &mut addresses)
+            .unwrap();
+        match provisioner.response() {
+            ResponsePDU::One(ProvisioningPDU::Confirmation(value)) => {
+                assert_eq!(32, value.confirmation.len())
+            }
+            _ => panic!("wrong pdu returned for public key"),
+        }
+        match provision(provisioner, device, &mut addresses) {
+            (Provisionee::Complete(key, _), Provisioner::Success(node)) => {
+                assert_eq!(key.deref(), node.device_key.deref());
+            }
+            _ => panic!("wrong ending state"),
+        }
+
+        // the whole 32-octet AuthValue is confirmed, not only its first 16 octets.
+        let mut other = [0x5A; 32];
+        other[31] = 0;
+        let provisioner = Provisioner::new(
+            Uuid::new([0x42; 16]),
+            Default::default(),
+            60,
+            Some(other.into()),
+            None,
+        )
+        .unwrap();
+        let device = Provisionee::new(capabilities, Some(secret), None);
+        assert!(matches!(
+            provision(provisioner, device, &mut addresses),
+            (
+                Provisionee::Failure(ErrorCode::ConfirmationFailed),
+                Provisioner::Failure(..)
+            )
+        ));
+    }
+
+    /// Run `provisioner` against `device` until the device is done.
+    fn provision(
+        mut provisioner: Provisioner,
+        mut device: Provisionee,
+        addresses: &mut UnicastAddressAllocator,
+    ) -> (Provisionee, Provisioner) {
+        let rng = &mut OsRng;
         loop {
             for pdu in provisioner.response().into_iter() {
                 assert!(!matches!(pdu, Failed(_)), "Unexpected PDU: {:?}", pdu);
                 device = match device.next(pdu, rng) {
                     Ok(provisionee) => {
                         if let Some(pdu) = provisionee.response() {
-                            provisioner = match provisioner.next(&pdu, rng) {
+                            provisioner = match provisioner.next(&pdu, rng, addresses) {
                                 Ok(p) => p,
                                 Err(e) => panic!("provisoner error: {:?}", e),
                             }
@@ -369,12 +780,20 @@ mod tests {
                 break;
             }
         }
-        match device {
-            Provisionee::Complete(key, result) => {
-                assert_ne!(&[0; 16], key.deref());
-                assert_eq!(fixture, result);
-            }
-            _ => panic!("wrong ending state"),
-        }
+        (device, provisioner)
+    }
+
+    #[test]
+    fn allocate_addresses() {
+        let mut addresses = UnicastAddressAllocator::new(
+            UnicastAddress::new(0x7FFC).unwrap(),
+            UnicastAddress::new(0x7FFF).unwrap(),
+        );
+        assert_eq!(None, addresses.allocate(0));
+        assert_eq!(UnicastAddress::new(0x7FFC).ok(), addresses.allocate(3));
+        assert_eq!(None, addresses.allocate(2));
+        assert_eq!(UnicastAddress::new(0x7FFF).ok(), addresses.allocate(1));
+        assert_eq!(None, addresses.next());
+        assert_eq!(None, addresses.allocate(1));
     }
 }
@@ -1,13 +1,16 @@
-use aes::Aes128;
-use btmesh_common::crypto::s1;
+use super::auth_value::AuthValue;
+use crate::DriverError;
+use btmesh_common::crypto::provisioning::{prck, prck256};
+use btmesh_common::crypto::{aes_cmac, hmac_sha256, s1, s2};
 use btmesh_common::InsufficientBuffer;
-use btmesh_pdu::provisioning::{Capabilities, Invite, PublicKey, Start};
-use cmac::crypto_mac::{InvalidKeyLength, Output};
-use cmac::Cmac;
+use btmesh_pdu::provisioning::{Algorithm, Capabilities, Invite, PublicKey, Start};
+use cmac::crypto_mac::InvalidKeyLength;
 use heapless::Vec;
+use rand_core::RngCore;
 
 pub struct Transcript {
     confirmation_inputs: Vec<u8, 256>,
+    algorithm: Algorithm,
 }
 
 impl Default for Transcript {
@@ -20,6 +23,7 @@ impl Transcript {
     pub fn new() -> Self {
         Self {
             confirmation_inputs: Vec::new(),
+            algorithm: Algorithm::default(),
         }
     }
 
@@ -47,6 +51,7 @@ impl Transcript {
     }
 
     pub(crate) fn add_start(&mut self, start: &Start) -> Result<(), InsufficientBuffer> {
+        self.algorithm = start.algorithm;
         let mut vec: Vec<u8, 32> = Vec::new();
         start.emit(&mut vec)?;
         self.confirmation_inputs
@@ -77,7 +82,290 @@ impl Transcript {
         &self.confirmation_inputs
     }
 
-    pub(crate) fn confirmation_salt(&self) -> Result<Output<Cmac<Aes128>>, InvalidKeyLength> {
-        s1(self.confirmation_inputs())
+    /// The algorithm selected by the provisioner.
+    pub(crate) fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    pub(crate) fn confirmation_salt(&self) -> Result<Vec<u8, 32>, InvalidKeyLength> {
+        match self.algorithm {
+            Algorithm::P256 => Vec::from_slice(&s1(self.confirmation_inputs())?.into_bytes()),
+            Algorithm::P256HmacSha256 => Vec::from_slice(&s2(self.confirmation_inputs())?),
+        }
+        .map_err(|_| InvalidKeyLength)
+    }
+
+    /// Random value of the size used by the selected algorithm.
+    pub(crate) fn random<RNG: RngCore>(&self, rng: &mut RNG) -> Vec<u8, 32> {
+        let mut random = Vec::new();
+        // infallible, at most 32 octets.
+        random.resize(self.algorithm.value_size(), 0).ok();
+        rng.fill_bytes(&mut random);
+        random
+    }
+
+    /// Confirmation value of `random`, authenticated with `auth_value` and the
+    /// ECDH `shared_secret`, as computed by the selected algorithm.
+    pub(crate) fn confirmation(
+        &self,
+        shared_secret: &[u8],
+        random: &[u8],
+        auth_value: &AuthValue,
+    ) -> Result<Vec<u8, 32>, DriverError> {
+        if random.len() != self.algorithm.value_size() {
+            return Err(DriverError::InvalidFormat);
+        }
+        let salt = self.confirmation_salt()?;
+        let confirmation = match self.algorithm {
+            Algorithm::P256 => {
+                let key = prck(shared_secret, &salt)?;
+                let mut bytes: Vec<u8, 32> = Vec::new();
+                bytes.extend_from_slice(random)?;
+                bytes.extend_from_slice(&auth_value.get_bytes())?;
+                Vec::from_slice(&aes_cmac(&key.into_bytes(), &bytes)?.into_bytes())?
+            }
+            Algorithm::P256HmacSha256 => {
+                let mut secret: Vec<u8, 64> = Vec::new();
+                secret.extend_from_slice(shared_secret)?;
+                secret.extend_from_slice(&auth_value.get_bytes_256())?;
+                let key = prck256(&secret, &salt)?;
+                Vec::from_slice(&hmac_sha256(&key, random)?)?
+            }
+        };
+        Ok(confirmation)
+    }
+
+    /// Salt of the session key, nonce and device key, once confirmed.
+    pub(crate) fn provisioning_salt(
+        &self,
+        random_provisioner: &[u8],
+        random_device: &[u8],
+    ) -> Result<[u8; 16], DriverError> {
+        let mut salt: Vec<u8, 96> = Vec::new();
+        salt.extend_from_slice(&self.confirmation_salt()?)?;
+        salt.extend_from_slice(random_provisioner)?;
+        salt.extend_from_slice(random_device)?;
+        Ok(s1(&salt)?.into_bytes().into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stack::unprovisioned::oob::StaticOob;
+    use btmesh_common::crypto::provisioning::prsk;
+    use btmesh_pdu::provisioning::ProvisioningPDU;
+
+    // provisioning sample data, shared by both algorithms.
+    const PROVISIONER_PUBLIC_KEY: PublicKey = PublicKey {
+        x: [
+            0x2c, 0x31, 0xa4, 0x7b, 0x57, 0x79, 0x80, 0x9e, 0xf4, 0x4c, 0xb5, 0xea, 0xaf, 0x5c,
+            0x3e, 0x43, 0xd5, 0xf8, 0xfa, 0xad, 0x4a, 0x87, 0x94, 0xcb, 0x98, 0x7e, 0x9b, 0x03,
+            0x74, 0x5c, 0x78, 0xdd,
+        ],
+        y: [
+            0x91, 0x95, 0x12, 0x18, 0x38, 0x98, 0xdf, 0xbe, 0xcd, 0x52, 0xe2, 0x40, 0x8e, 0x43,
+            0x87, 0x1f, 0xd0, 0x21, 0x10, 0x91, 0x17, 0xbd, 0x3e, 0xd4, 0xea, 0xf8, 0x43, 0x77,
+            0x43, 0x71, 0x5d, 0x4f,
+        ],
+    };
+
+    const DEVICE_PUBLIC_KEY: PublicKey = PublicKey {
+        x: [
+            0xf4, 0x65, 0xe4, 0x3f, 0xf2, 0x3d, 0x3f, 0x1b, 0x9d, 0xc7, 0xdf, 0xc0, 0x4d, 0xa8,
+            0x75, 0x81, 0x84, 0xdb, 0xc9, 0x66, 0x20, 0x47, 0x96, 0xec, 0xcf, 0x0d, 0x6c, 0xf5,
+            0xe1, 0x65, 0x00, 0xcc,
+        ],
+        y: [
+            0x02, 0x01, 0xd0, 0x48, 0xbc, 0xbb, 0xd8, 0x99, 0xee, 0xef, 0xc4, 0x24, 0x16, 0x4e,
+            0x33, 0xc2, 0x01, 0xc2, 0xb0, 0x10, 0xca, 0x6b, 0x4d, 0x43, 0xa8, 0xa1, 0x55, 0xca,
+            0xd8, 0xec, 0xb2, 0x79,
+        ],
+    };
+
+    const ECDH_SECRET: [u8; 32] = [
+        0xab, 0x85, 0x84, 0x3a, 0x2f, 0x6d, 0x88, 0x3f, 0x62, 0xe5, 0x68, 0x4b, 0x38, 0xe3, 0x07,
+        0x33, 0x5f, 0xe6, 0xe1, 0x94, 0x5e, 0xcd, 0x19, 0x60, 0x41, 0x05, 0xc6, 0xf2, 0x32, 0x21,
+        0xeb, 0x69,
+    ];
+
+    fn transcript(capabilities: &[u8], start: &[u8]) -> Transcript {
+        let mut transcript = Transcript::new();
+        transcript
+            .add_invite(&Invite::parse(&[0x00, 0x00]).unwrap())
+            .unwrap();
+        match ProvisioningPDU::parse(capabilities).unwrap() {
+            ProvisioningPDU::Capabilities(capabilities) => {
+                transcript.add_capabilities(&capabilities).unwrap()
+            }
+            _ => panic!("not capabilities"),
+        }
+        match ProvisioningPDU::parse(start).unwrap() {
+            ProvisioningPDU::Start(start) => transcript.add_start(&start).unwrap(),
+            _ => panic!("not start"),
+        }
+        transcript
+            .add_pubkey_provisioner(&PROVISIONER_PUBLIC_KEY)
+            .unwrap();
+        transcript.add_pubkey_device(&DEVICE_PUBLIC_KEY).unwrap();
+        transcript
+    }
+
+    #[test]
+    fn cmac_provisioning_sample_data() {
+        // Mesh Profile sample data, no OOB authentication.
+        let transcript = transcript(
+            &[
+                0x01, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            ],
+            &[0x02, 0x00, 0x00, 0x00, 0x00, 0x00],
+        );
+        let random_provisioner = [
+            0x8b, 0x19, 0xac, 0x31, 0xd5, 0x8b, 0x12, 0x4c, 0x94, 0x62, 0x09, 0xb5, 0xdb, 0x10,
+            0x21, 0xb9,
+        ];
+        let random_device = [
+            0x55, 0xa2, 0xa2, 0xbc, 0xa0, 0x4c, 0xd3, 0x2f, 0xf6, 0xf3, 0x46, 0xbd, 0x0a, 0x0c,
+            0x1a, 0x3a,
+        ];
+
+        let salt = transcript.confirmation_salt().unwrap();
+        assert_eq!(
+            salt,
+            [
+                0x5f, 0xaa, 0xbe, 0x18, 0x73, 0x37, 0xc7, 0x1c, 0xc6, 0xc9, 0x73, 0x36, 0x9d, 0xca,
+                0xa7, 0x9a
+            ]
+        );
+        assert_eq!(
+            &*prck(&ECDH_SECRET, &salt).unwrap().into_bytes(),
+            [
+                0xe3, 0x1f, 0xe0, 0x46, 0xc6, 0x8e, 0xc3, 0x39, 0xc4, 0x25, 0xfc, 0x66, 0x29, 0xf0,
+                0x33, 0x6f
+            ]
+        );
+        assert_eq!(
+            transcript
+                .confirmation(&ECDH_SECRET, &random_provisioner, &AuthValue::None)
+                .unwrap(),
+            [
+                0xb3, 0x8a, 0x11, 0x4d, 0xfd, 0xca, 0x1f, 0xe1, 0x53, 0xbd, 0x2c, 0x1e, 0x0d, 0xc4,
+                0x6a, 0xc2
+            ]
+        );
+        assert_eq!(
+            transcript
+                .confirmation(&ECDH_SECRET, &random_device, &AuthValue::None)
+                .unwrap(),
+            [
+                0xee, 0xba, 0x52, 0x1c, 0x19, 0x6b, 0x52, 0xcc, 0x2e, 0x37, 0xaa, 0x40, 0x32, 0x9f,
+                0x55, 0x4e
+            ]
+        );
+
+        let salt = transcript
+            .provisioning_salt(&random_provisioner, &random_device)
+            .unwrap();
+        assert_eq!(
+            salt,
+            [
+                0xa2, 0x1c, 0x7d, 0x45, 0xf2, 0x01, 0xcf, 0x94, 0x89, 0xa2, 0xfb, 0x57, 0x14, 0x50,
+                0x15, 0xb4
+            ]
+        );
+        assert_eq!(
+            &*prsk(&ECDH_SECRET, &salt).unwrap().into_bytes(),
+            [
+                0xc8, 0x02, 0x53, 0xaf, 0x86, 0xb3, 0x3d, 0xfa, 0x45, 0x0b, 0xbd, 0xb2, 0xa1, 0x91,
+                0xfe, 0xa3
+            ]
+        );
+    }
+
+    #[test]
+    fn hmac_sha256_provisioning_sample_data() {
+        // Mesh Protocol 1.1 sample data, with 32-octet static OOB.
+        let transcript = transcript(
+            &[
+                0x01, 0x01, 0x00, 0x03, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            ],
+            &[0x02, 0x01, 0x00, 0x01, 0x00, 0x00],
+        );
+        let auth_value = AuthValue::Static(StaticOob::Octets32([
+            0x90, 0x6d, 0x73, 0xa3, 0xc7, 0xa7, 0xcb, 0x3f, 0xf7, 0x30, 0xdc, 0xa6, 0x8a, 0x46,
+            0xb9, 0xc1, 0x8d, 0x67, 0x3f, 0x50, 0xe0, 0x78, 0x20, 0x23, 0x11, 0x47, 0x3e, 0xbb,
+            0xe2, 0x53, 0x66, 0x9f,
+        ]));
+        let random_provisioner = [
+            0x36, 0xf9, 0x68, 0xb9, 0x4a, 0x13, 0x00, 0x0e, 0x64, 0xb2, 0x23, 0x57, 0x63, 0x90,
+            0xdb, 0x6b, 0xcc, 0x6d, 0x62, 0xf0, 0x26, 0x17, 0xc3, 0x69, 0xee, 0x3f, 0x5b, 0x3e,
+            0x89, 0xdf, 0x7e, 0x1f,
+        ];
+        let random_device = [
+            0x5b, 0x9b, 0x1f, 0xc6, 0xa6, 0x4b, 0x2d, 0xe8, 0xbe, 0xce, 0x53, 0x18, 0x7e, 0xe9,
+            0x89, 0xc6, 0x56, 0x6d, 0xb1, 0xfc, 0x7d, 0xc8, 0x58, 0x0a, 0x73, 0xda, 0xfd, 0xd6,
+            0x21, 0x1d, 0x56, 0xa5,
+        ];
+
+        let salt = transcript.confirmation_salt().unwrap();
+        assert_eq!(
+            salt,
+            [
+                0xa7, 0x11, 0x41, 0xba, 0x8c, 0xb6, 0xb4, 0x0f, 0x4f, 0x52, 0xb6, 0x22, 0xe1, 0xc0,
+                0x91, 0x61, 0x4c, 0x73, 0xfc, 0x30, 0x8f, 0x87, 0x1b, 0x78, 0xca, 0x77, 0x5e, 0x76,
+                0x9b, 0xc3, 0xae, 0x69
+            ]
+        );
+        let mut secret: Vec<u8, 64> = Vec::new();
+        secret.extend_from_slice(&ECDH_SECRET).unwrap();
+        secret
+            .extend_from_slice(&auth_value.get_bytes_256())
+            .unwrap();
+        assert_eq!(
+            prck256(&secret, &salt).unwrap(),
+            [
+                0x21, 0x0c, 0x3c, 0x44, 0x81, 0x52, 0xe8, 0xd5, 0x9e, 0xf7, 0x42, 0xaa, 0x7d, 0x22,
+                0xee, 0x5b, 0xa5, 0x9a, 0x38, 0x64, 0x8b, 0xda, 0x6b, 0xf0, 0x5c, 0x74, 0xf3, 0xe4,
+                0x6f, 0xc2, 0xc0, 0xbb
+            ]
+        );
+        assert_eq!(
+            transcript
+                .confirmation(&ECDH_SECRET, &random_provisioner, &auth_value)
+                .unwrap(),
+            [
+                0xc9, 0x9b, 0x54, 0x61, 0x7a, 0xe6, 0x46, 0xf5, 0xf3, 0x2c, 0xf7, 0xe1, 0xea, 0x6f,
+                0xcc, 0x49, 0xfd, 0x69, 0x06, 0x60, 0x78, 0xeb, 0xa9, 0x58, 0x0f, 0xa6, 0xc7, 0x03,
+                0x18, 0x33, 0xe6, 0xc8
+            ]
+        );
+        assert_eq!(
+            transcript
+                .confirmation(&ECDH_SECRET, &random_device, &auth_value)
+                .unwrap(),
+            [
+                0x56, 0xe3, 0x72, 0x2d, 0x29, 0x13, 0x73, 0xd3, 0x8c, 0x99, 0x5d, 0x6f, 0x94, 0x2c,
+                0x02, 0x92, 0x8c, 0x96, 0xab, 0xb0, 0x15, 0xc2, 0x33, 0x55, 0x7d, 0x79, 0x74, 0xb6,
+                0xe2, 0xdf, 0x66, 0x2b
+            ]
+        );
+
+        let salt = transcript
+            .provisioning_salt(&random_provisioner, &random_device)
+            .unwrap();
+        assert_eq!(
+            salt,
+            [
+                0xd1, 0xcb, 0x10, 0xad, 0x8d, 0x51, 0x28, 0x60, 0x67, 0xe3, 0x48, 0xfc, 0x4b, 0x69,
+                0x21, 0x22
+            ]
+        );
+        assert_eq!(
+            &*prsk(&ECDH_SECRET, &salt).unwrap().into_bytes(),
+            [
+                0xdf, 0x4a, 0x49, 0x4d, 0xa3, 0xd4, 0x54, 0x05, 0xe4, 0x02, 0xf1, 0xd6, 0xa6, 0xce,
+                0xa3, 0x38
+            ]
+        );
     }
 }
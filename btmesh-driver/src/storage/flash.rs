@@ -1,4 +1,4 @@
-use crate::storage::{BackingStore, StorageError};
+use crate::storage::{BackingStore, StorageError, STORAGE_FORMAT_VERSION};
 use crate::util::hash::hash_of;
 use crate::ProvisionedConfiguration;
 use core::future::Future;
@@ -46,8 +46,21 @@ impl<F: NorFlash, const PAGE_SIZE: u32> FlashBackingStore<F, PAGE_SIZE> {
             .await
             .map_err(|_| StorageError::Load)?;
 
+        let (header, data) = self.buffer.0.split_at(HEADER_SIZE);
+        if header[..MAGIC.len()] != MAGIC {
+            return if header.iter().all(|b| *b == 0xFF) {
+                // erased, nothing has been stored yet.
+                Err(StorageError::Deserialization)
+            } else {
+                Err(StorageError::Incompatible)
+            };
+        }
+        if header[MAGIC.len()] != STORAGE_FORMAT_VERSION {
+            return Err(StorageError::Incompatible);
+        }
+
         let config: ProvisionedConfiguration =
-            from_bytes(&self.buffer.0).map_err(|_| StorageError::Serialization)?;
+            from_bytes(data).map_err(|_| StorageError::Serialization)?;
 
         Ok(config)
     }
@@ -77,6 +90,10 @@ impl<F: NorFlash, const PAGE_SIZE: u32> FlashBackingStore<F, PAGE_SIZE> {
 
 const USEFUL_BUFFER_SIZE: usize = 2048;
 
+/// Prefix of every stored configuration, followed by the `STORAGE_FORMAT_VERSION` byte.
+const MAGIC: [u8; 3] = *b"BTM";
+const HEADER_SIZE: usize = 4;
+
 impl<F: NorFlash, const PAGE_SIZE: u32> BackingStore for FlashBackingStore<F, PAGE_SIZE> {
     type LoadFuture<'m> =  impl Future<Output = Result<ProvisionedConfiguration, StorageError>> + 'm
         where
@@ -107,6 +124,10 @@ impl<F: NorFlash, const PAGE_SIZE: u32> BackingStore for FlashBackingStore<F, PA
                 }
                 (Ok(c1), _) => c1,
                 (_, Ok(c2)) => c2,
+                (Err(StorageError::Incompatible), Err(_))
+                | (Err(_), Err(StorageError::Incompatible)) => {
+                    return Err(StorageError::Incompatible);
+                }
                 (Err(e1), Err(_)) => {
                     return Err(e1);
                 }
@@ -123,7 +144,10 @@ impl<F: NorFlash, const PAGE_SIZE: u32> BackingStore for FlashBackingStore<F, PA
     fn store<'f>(&'f mut self, config: &'f ProvisionedConfiguration) -> Self::StoreFuture<'f> {
         async move {
             if should_writeback(self.latest_load, config, self.sequence_threshold) {
-                to_slice(config, &mut self.buffer.0).map_err(|_| StorageError::Serialization)?;
+                let (header, data) = self.buffer.0.split_at_mut(HEADER_SIZE);
+                header[..MAGIC.len()].copy_from_slice(&MAGIC);
+                header[MAGIC.len()] = STORAGE_FORMAT_VERSION;
+                to_slice(config, data).map_err(|_| StorageError::Serialization)?;
                 Self::store(self, self.base_address, config).await?;
                 if let Some(base_address) = self.extra_base_address {
                     Self::store(self, base_address, config).await?;
@@ -181,16 +205,145 @@ pub fn should_writeback(
 
 #[cfg(test)]
 mod test {
-    use crate::stack::provisioned::secrets::application::ApplicationKeys;
-    use crate::stack::provisioned::secrets::network::NetworkKeys;
-    use crate::storage::flash::{should_writeback, LatestLoad};
-    use crate::storage::provisioned::ProvisionedConfiguration;
+    use crate::stack::provisioned::network::replay_protection::ReplayProtection;
+    use crate::stack::provisioned::system::NetworkMetadata;
+    use crate::storage::flash::{should_writeback, FlashBackingStore, LatestLoad, MAGIC};
+    use crate::storage::provisioned::fixture::config;
     use crate::storage::unprovisioned::UnprovisionedConfiguration;
+    use crate::storage::{BackingStore, Storage, StorageError, STORAGE_FORMAT_VERSION};
     use crate::util::hash::hash_of;
-    use crate::{Configuration, DeviceInfo, NetworkState, Secrets};
-    use btmesh_common::address::UnicastAddress;
-    use btmesh_common::crypto::device::DeviceKey;
-    use btmesh_common::{IvIndex, IvUpdateFlag, Uuid};
+    use crate::Configuration;
+    use btmesh_common::address::{Address, UnicastAddress};
+    use btmesh_common::crypto::network::{NetworkKey, Nid};
+    use btmesh_common::{Ctl, IvIndex, Ivi, Seq, Ttl, Uuid};
+    use btmesh_device::NetworkKeyHandle;
+    use btmesh_models::foundation::configuration::NetKeyIndex;
+    use btmesh_pdu::provisioned::network::CleartextNetworkPDU;
+    use core::convert::Infallible;
+    use embassy_futures::block_on;
+    use embedded_storage::nor_flash::ErrorType;
+    use embedded_storage_async::nor_flash::{NorFlash, ReadNorFlash};
+
+    const FLASH_SIZE: usize = 4096;
+
+    struct RamFlash([u8; FLASH_SIZE]);
+
+    impl ErrorType for RamFlash {
+        type Error = Infallible;
+    }
+
+    impl ReadNorFlash for RamFlash {
+        const READ_SIZE: usize = 1;
+
+        async fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+            let offset = offset as usize;
+            bytes.copy_from_slice(&self.0[offset..offset + bytes.len()]);
+            Ok(())
+        }
+
+        fn capacity(&self) -> usize {
+            FLASH_SIZE
+        }
+    }
+
+    impl NorFlash for RamFlash {
+        const WRITE_SIZE: usize = 4;
+        const ERASE_SIZE: usize = FLASH_SIZE;
+
+        async fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+            self.0[from as usize..to as usize].fill(0xFF);
+            Ok(())
+        }
+
+        async fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+            let offset = offset as usize;
+            self.0[offset..offset + bytes.len()].copy_from_slice(bytes);
+            Ok(())
+        }
+    }
+
+    fn is_replay(replay_protection: &mut ReplayProtection, src: u16, seq: u32) -> bool {
+        let mut pdu = CleartextNetworkPDU::new(
+            Ivi::Zero,
+            Nid::new(0x68),
+            Ctl::Access,
+            Ttl::new(5),
+            Seq::new(seq),
+            UnicastAddress::new(src).unwrap(),
+            Address::Unicast(UnicastAddress::new(0x00A1).unwrap()),
+            &[0; 8],
+            NetworkMetadata::new(
+                IvIndex::new(100),
+                None,
+                NetworkKeyHandle::new(NetKeyIndex::new(0), Nid::new(0x68)),
+            ),
+        )
+        .unwrap();
+        replay_protection.check_network_pdu(&mut pdu);
+        pdu.meta().is_replay_protected()
+    }
+
+    #[test]
+    pub fn replay_protection_restored_from_flash() {
+        let mut config = config(NetworkKey::new([0x01; 16]).unwrap());
+        let mut replay_protection = ReplayProtection::default();
+        assert!(!is_replay(&mut replay_protection, 0x0001, 10));
+        assert!(!is_replay(&mut replay_protection, 0x0002, 20));
+        *config.replay_protection_mut() = replay_protection.list();
+
+        let mut backing_store =
+            FlashBackingStore::<_, 4096>::new(RamFlash([0xFF; FLASH_SIZE]), 0, None, 100);
+        block_on(BackingStore::store(&mut backing_store, &config)).unwrap();
+
+        // after a reboot.
+        let mut backing_store =
+            FlashBackingStore::<_, 4096>::new(backing_store.flash, 0, None, 100);
+        let restored = block_on(BackingStore::load(&mut backing_store)).unwrap();
+        let mut replay_protection = ReplayProtection::default();
+        replay_protection.restore(restored.replay_protection());
+
+        assert!(is_replay(&mut replay_protection, 0x0001, 10));
+        assert!(is_replay(&mut replay_protection, 0x0002, 19));
+        assert!(!is_replay(&mut replay_protection, 0x0002, 21));
+        assert!(!is_replay(&mut replay_protection, 0x0003, 1));
+    }
+
+    #[test]
+    pub fn incompatible_format_is_reset() {
+        let config = config(NetworkKey::new([0x01; 16]).unwrap());
+
+        let mut backing_store =
+            FlashBackingStore::<_, 4096>::new(RamFlash([0xFF; FLASH_SIZE]), 0, None, 100);
+        assert_eq!(
+            Err(StorageError::Deserialization),
+            block_on(BackingStore::load(&mut backing_store)).map(|_| ())
+        );
+        block_on(BackingStore::store(&mut backing_store, &config)).unwrap();
+        assert!(block_on(BackingStore::load(&mut backing_store)).is_ok());
+
+        // written by an earlier version.
+        backing_store.flash.0[MAGIC.len()] = STORAGE_FORMAT_VERSION - 1;
+        assert_eq!(
+            Err(StorageError::Incompatible),
+            block_on(BackingStore::load(&mut backing_store)).map(|_| ())
+        );
+
+        let storage = Storage::new(
+            backing_store,
+            UnprovisionedConfiguration {
+                uuid: Uuid::new([0; 16]),
+            },
+        );
+        block_on(storage.init()).unwrap();
+        assert!(block_on(storage.is_unprovisioned()).unwrap());
+        assert!(storage
+            .backing_store
+            .borrow()
+            .flash
+            .0
+            .iter()
+            .all(|b| *b == 0xFF));
+    }
 
     #[test]
     pub fn hashing() {
@@ -206,40 +359,15 @@ mod test {
     }
 
     pub fn should_writeback_from_none() {
-        let provisioned_config = ProvisionedConfiguration::new(
-            0,
-            NetworkState::new(IvIndex::new(100), IvUpdateFlag::Normal),
-            Secrets::new(
-                DeviceKey::new([
-                    0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xAA, 0xBB, 0xCC, 0xDD,
-                    0xEE, 0xFF, 0x00,
-                ]),
-                NetworkKeys::default(),
-                ApplicationKeys::default(),
-            ),
-            DeviceInfo::new(UnicastAddress::new(0x00A1).unwrap(), 1),
-            Default::default(),
-        );
+        let provisioned_config = config(NetworkKey::new([0x01; 16]).unwrap());
 
         assert!(should_writeback(LatestLoad::None, &provisioned_config, 100))
     }
 
     #[test]
     pub fn should_writeback_provisioned_sequence_unchanged() {
-        let provisioned_config = ProvisionedConfiguration::new(
-            100,
-            NetworkState::new(IvIndex::new(100), IvUpdateFlag::Normal),
-            Secrets::new(
-                DeviceKey::new([
-                    0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xAA, 0xBB, 0xCC, 0xDD,
-                    0xEE, 0xFF, 0x00,
-                ]),
-                NetworkKeys::default(),
-                ApplicationKeys::default(),
-            ),
-            DeviceInfo::new(UnicastAddress::new(0x00A1).unwrap(), 1),
-            Default::default(),
-        );
+        let mut provisioned_config = config(NetworkKey::new([0x01; 16]).unwrap());
+        *provisioned_config.sequence_mut() = 100;
 
         let hash = hash_of(&provisioned_config);
 
@@ -255,20 +383,8 @@ mod test {
 
     #[test]
     pub fn should_writeback_provisioned_sequence_changed_threshold_not_met() {
-        let provisioned_config = ProvisionedConfiguration::new(
-            199,
-            NetworkState::new(IvIndex::new(100), IvUpdateFlag::Normal),
-            Secrets::new(
-                DeviceKey::new([
-                    0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xAA, 0xBB, 0xCC, 0xDD,
-                    0xEE, 0xFF, 0x00,
-                ]),
-                NetworkKeys::default(),
-                ApplicationKeys::default(),
-            ),
-            DeviceInfo::new(UnicastAddress::new(0x00A1).unwrap(), 1),
-            Default::default(),
-        );
+        let mut provisioned_config = config(NetworkKey::new([0x01; 16]).unwrap());
+        *provisioned_config.sequence_mut() = 199;
 
         assert!(!should_writeback(
             LatestLoad::Provisioned {
@@ -282,20 +398,8 @@ mod test {
 
     #[test]
     pub fn should_writeback_provisioned_sequence_changed_threshold_is_met() {
-        let provisioned_config = ProvisionedConfiguration::new(
-            200,
-            NetworkState::new(IvIndex::new(100), IvUpdateFlag::Normal),
-            Secrets::new(
-                DeviceKey::new([
-                    0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xAA, 0xBB, 0xCC, 0xDD,
-                    0xEE, 0xFF, 0x00,
-                ]),
-                NetworkKeys::default(),
-                ApplicationKeys::default(),
-            ),
-            DeviceInfo::new(UnicastAddress::new(0x00A1).unwrap(), 1),
-            Default::default(),
-        );
+        let mut provisioned_config = config(NetworkKey::new([0x01; 16]).unwrap());
+        *provisioned_config.sequence_mut() = 200;
 
         assert!(should_writeback(
             LatestLoad::Provisioned {
@@ -309,20 +413,8 @@ mod test {
 
     #[test]
     pub fn should_writeback_provisioned_sequence_changed_threshold_is_met_skippingly() {
-        let provisioned_config = ProvisionedConfiguration::new(
-            205,
-            NetworkState::new(IvIndex::new(100), IvUpdateFlag::Normal),
-            Secrets::new(
-                DeviceKey::new([
-                    0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xAA, 0xBB, 0xCC, 0xDD,
-                    0xEE, 0xFF, 0x00,
-                ]),
-                NetworkKeys::default(),
-                ApplicationKeys::default(),
-            ),
-            DeviceInfo::new(UnicastAddress::new(0x00A1).unwrap(), 1),
-            Default::default(),
-        );
+        let mut provisioned_config = config(NetworkKey::new([0x01; 16]).unwrap());
+        *provisioned_config.sequence_mut() = 205;
 
         assert!(should_writeback(
             LatestLoad::Provisioned {
@@ -20,6 +20,12 @@ pub mod flash;
 #[cfg(feature = "memory")]
 pub mod memory;
 
+/// Version of the layout of the persisted configuration.
+///
+/// Bumped whenever the serialized form of `ProvisionedConfiguration` changes,
+/// see the "Upgrading" section of the README for the history.
+pub const STORAGE_FORMAT_VERSION: u8 = 8;
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum StorageError {
@@ -27,6 +33,8 @@ pub enum StorageError {
     Store,
     Serialization,
     Deserialization,
+    /// The stored configuration was written with another `STORAGE_FORMAT_VERSION`.
+    Incompatible,
 }
 
 pub trait BackingStore {
@@ -88,20 +96,28 @@ impl<B: BackingStore> Storage<B> {
     pub async fn init(&self) -> Result<(), StorageError> {
         let mut locked_config = self.config.lock().await;
         let mut backing_store = self.backing_store.borrow_mut();
-        if let Ok(mut config) = backing_store.load().await {
-            let seq = config.sequence();
-
-            let mut extra = seq % 100;
-            if extra == 100 {
-                extra = 0;
+        match backing_store.load().await {
+            Ok(mut config) => {
+                let seq = config.sequence();
+
+                let mut extra = seq % 100;
+                if extra == 100 {
+                    extra = 0;
+                }
+                let seq = (seq - extra) + 100;
+
+                *config.sequence_mut() = seq;
+                backing_store.store(&config).await?;
+                locked_config.replace(Configuration::Provisioned(config));
+            }
+            Err(StorageError::Incompatible) => {
+                warn!("stored configuration has an incompatible format, resetting");
+                backing_store.clear().await?;
+                locked_config.replace(Configuration::Unprovisioned(self.default_config.clone()));
+            }
+            Err(_) => {
+                locked_config.replace(Configuration::Unprovisioned(self.default_config.clone()));
             }
-            let seq = (seq - extra) + 100;
-
-            *config.sequence_mut() = seq;
-            backing_store.store(&config).await?;
-            locked_config.replace(Configuration::Provisioned(config));
-        } else {
-            locked_config.replace(Configuration::Unprovisioned(self.default_config.clone()));
         }
         Ok(())
     }
@@ -1,5 +1,9 @@
-use btmesh_common::Ttl;
-use btmesh_models::foundation::configuration::relay::RelayConfig;
+use btmesh_common::{Features, Ttl};
+use btmesh_models::foundation::configuration::friend::Friend;
+use btmesh_models::foundation::configuration::gatt_proxy::GattProxy;
+use btmesh_models::foundation::configuration::heartbeat_publication::HeartbeatPublication;
+use btmesh_models::foundation::configuration::network_transmit::NetworkTransmitConfig;
+use btmesh_models::foundation::configuration::relay::{Relay, RelayConfig};
 
 #[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 #[cfg_attr(feature = "defmt", derive(::defmt::Format))]
@@ -7,14 +11,22 @@ use btmesh_models::foundation::configuration::relay::RelayConfig;
 pub struct Configuration {
     beacon: bool,
     relay: RelayConfig,
+    friend: Friend,
+    network_transmit: NetworkTransmitConfig,
+    gatt_proxy: GattProxy,
     default_ttl: Ttl,
+    heartbeat_publication: HeartbeatPublication,
 }
 
 impl Configuration {
     pub fn display(&self) {
         info!("  beacon: {}", self.beacon);
         info!("  relay: {}", self.relay);
+        info!("  friend: {}", self.friend);
+        info!("  network_transmit: {}", self.network_transmit);
+        info!("  gatt_proxy: {}", self.gatt_proxy);
         info!("  default_ttl: {}", self.default_ttl);
+        info!("  heartbeat_publication: {}", self.heartbeat_publication);
     }
 
     pub fn beacon(&self) -> bool {
@@ -33,6 +45,30 @@ impl Configuration {
         &mut self.relay
     }
 
+    pub fn friend(&self) -> Friend {
+        self.friend
+    }
+
+    pub fn friend_mut(&mut self) -> &mut Friend {
+        &mut self.friend
+    }
+
+    pub fn network_transmit(&self) -> &NetworkTransmitConfig {
+        &self.network_transmit
+    }
+
+    pub fn network_transmit_mut(&mut self) -> &mut NetworkTransmitConfig {
+        &mut self.network_transmit
+    }
+
+    pub fn gatt_proxy(&self) -> GattProxy {
+        self.gatt_proxy
+    }
+
+    pub fn gatt_proxy_mut(&mut self) -> &mut GattProxy {
+        &mut self.gatt_proxy
+    }
+
     pub fn default_ttl(&self) -> Ttl {
         self.default_ttl
     }
@@ -40,6 +76,24 @@ impl Configuration {
     pub fn default_ttl_mut(&mut self) -> &mut Ttl {
         &mut self.default_ttl
     }
+
+    pub fn heartbeat_publication(&self) -> &HeartbeatPublication {
+        &self.heartbeat_publication
+    }
+
+    pub fn heartbeat_publication_mut(&mut self) -> &mut HeartbeatPublication {
+        &mut self.heartbeat_publication
+    }
+
+    /// Features currently enabled on the node.
+    pub fn features(&self) -> Features {
+        Features {
+            relay: matches!(self.relay.relay(), Relay::SupportedEnabled),
+            proxy: matches!(self.gatt_proxy, GattProxy::Enabled),
+            friend: matches!(self.friend, Friend::Enabled),
+            low_power: cfg!(feature = "low_power"),
+        }
+    }
 }
 
 impl Default for Configuration {
@@ -47,10 +101,20 @@ impl Default for Configuration {
         Self {
             beacon: true,
             default_ttl: Ttl::new(127),
+            network_transmit: Default::default(),
+            #[cfg(feature = "proxy")]
+            gatt_proxy: GattProxy::Enabled,
+            #[cfg(not(feature = "proxy"))]
+            gatt_proxy: GattProxy::NotSupported,
+            heartbeat_publication: Default::default(),
             #[cfg(feature = "relay")]
             relay: Default::default(),
             #[cfg(not(feature = "relay"))]
             relay: RelayConfig::not_supported(),
+            #[cfg(feature = "friend")]
+            friend: Friend::Enabled,
+            #[cfg(not(feature = "friend"))]
+            friend: Friend::NotSupported,
         }
     }
 }
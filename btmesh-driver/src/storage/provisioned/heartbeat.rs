@@ -0,0 +1,240 @@
+use btmesh_common::address::{Address, UnicastAddress};
+use btmesh_models::foundation::configuration::heartbeat_publication::{
+    log_to_value, value_to_log, HeartbeatPublication,
+};
+use core::hash::{Hash, Hasher};
+use embassy_time::{Duration, Instant};
+
+/// Heartbeat counters which change with every heartbeat sent or received.
+///
+/// They are neither persisted nor hashed, so updating them never causes
+/// the configuration to be written back to flash.
+#[derive(Clone, Debug, Default)]
+pub struct Heartbeat {
+    publication_count: u16,
+    subscription: HeartbeatSubscription,
+}
+
+impl Hash for Heartbeat {
+    fn hash<H: Hasher>(&self, _state: &mut H) {}
+}
+
+#[cfg(feature = "defmt")]
+impl ::defmt::Format for Heartbeat {
+    fn format(&self, fmt: ::defmt::Formatter) {
+        ::defmt::write!(
+            fmt,
+            "publication_count: {}, subscription: {}",
+            self.publication_count,
+            self.subscription
+        );
+    }
+}
+
+impl Heartbeat {
+    pub fn publication_count(&self) -> u16 {
+        self.publication_count
+    }
+
+    /// Restart the publication count as configured.
+    pub fn reset_publication(&mut self, publication: &HeartbeatPublication) {
+        self.publication_count = publication.count();
+    }
+
+    /// Account for a periodic heartbeat about to be sent.
+    ///
+    /// Returns `false` if the publication count has been exhausted.
+    pub fn consume_publication(&mut self) -> bool {
+        match self.publication_count {
+            0 => false,
+            0xFFFF => true,
+            _ => {
+                self.publication_count -= 1;
+                true
+            }
+        }
+    }
+
+    pub fn subscription(&self) -> &HeartbeatSubscription {
+        &self.subscription
+    }
+
+    pub fn subscription_mut(&mut self) -> &mut HeartbeatSubscription {
+        &mut self.subscription
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct HeartbeatSubscription {
+    source: Address,
+    destination: Address,
+    expiry: Option<Instant>,
+    count: u16,
+    min_hops: u8,
+    max_hops: u8,
+}
+
+impl Default for HeartbeatSubscription {
+    fn default() -> Self {
+        Self {
+            source: Address::Unassigned,
+            destination: Address::Unassigned,
+            expiry: None,
+            count: 0,
+            min_hops: 0,
+            max_hops: 0,
+        }
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl ::defmt::Format for HeartbeatSubscription {
+    fn format(&self, fmt: ::defmt::Formatter) {
+        ::defmt::write!(
+            fmt,
+            "{}->{} count: {} hops: {}..{}",
+            self.source,
+            self.destination,
+            self.count,
+            self.min_hops,
+            self.max_hops
+        );
+    }
+}
+
+impl HeartbeatSubscription {
+    /// Apply a Config Heartbeat Subscription Set.
+    ///
+    /// Disabling the subscription leaves the gathered statistics untouched,
+    /// while a new subscription starts them afresh.
+    pub fn set(&mut self, source: Address, destination: Address, period_log: u8) {
+        if source == Address::Unassigned || destination == Address::Unassigned || period_log == 0 {
+            self.source = Address::Unassigned;
+            self.destination = Address::Unassigned;
+            self.expiry.take();
+        } else {
+            self.source = source;
+            self.destination = destination;
+            self.expiry
+                .replace(Instant::now() + Duration::from_secs(log_to_value(period_log) as u64));
+            self.count = 0;
+            self.min_hops = 0x7F;
+            self.max_hops = 0;
+        }
+    }
+
+    /// Account for a received heartbeat, if it matches this subscription.
+    pub fn receive(&mut self, src: UnicastAddress, dst: Address, hops: u8) -> bool {
+        if self.remaining_period() == 0
+            || self.source != Address::Unicast(src)
+            || self.destination != dst
+        {
+            return false;
+        }
+
+        self.count = self.count.saturating_add(1);
+        self.min_hops = self.min_hops.min(hops);
+        self.max_hops = self.max_hops.max(hops);
+        true
+    }
+
+    /// Remaining subscription period, in seconds.
+    pub fn remaining_period(&self) -> u16 {
+        match self.expiry {
+            Some(expiry) => {
+                let now = Instant::now();
+                if expiry > now {
+                    // round up, so a running period never reports as zero.
+                    let remaining = ((expiry - now).as_millis() + 999) / 1000;
+                    remaining.min(0xFFFF) as u16
+                } else {
+                    0
+                }
+            }
+            None => 0,
+        }
+    }
+
+    pub fn source(&self) -> Address {
+        self.source
+    }
+
+    pub fn destination(&self) -> Address {
+        self.destination
+    }
+
+    pub fn period_log(&self) -> u8 {
+        match self.remaining_period() {
+            // a full 0x11 period is 0xFFFF seconds, which is not a count.
+            0xFFFF => 0x11,
+            remaining => value_to_log(remaining),
+        }
+    }
+
+    pub fn count_log(&self) -> u8 {
+        value_to_log(self.count)
+    }
+
+    pub fn min_hops(&self) -> u8 {
+        self.min_hops
+    }
+
+    pub fn max_hops(&self) -> u8 {
+        self.max_hops
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use btmesh_common::address::GroupAddress;
+
+    #[test]
+    fn subscription_statistics() {
+        let src = UnicastAddress::new(0x0042).unwrap();
+        let dst = Address::Group(GroupAddress::AllNodes);
+
+        let mut subscription = HeartbeatSubscription::default();
+        assert!(!subscription.receive(src, dst, 1));
+
+        subscription.set(Address::Unicast(src), dst, 0x05);
+        assert_eq!(0x05, subscription.period_log());
+        assert!(subscription.receive(src, dst, 3));
+        assert!(subscription.receive(src, dst, 1));
+        assert!(subscription.receive(src, dst, 2));
+        assert!(!subscription.receive(UnicastAddress::new(0x0043).unwrap(), dst, 1));
+        assert_eq!(3, subscription.count);
+        assert_eq!(0x02, subscription.count_log());
+        assert_eq!(1, subscription.min_hops());
+        assert_eq!(3, subscription.max_hops());
+
+        // disabling keeps the statistics around.
+        subscription.set(Address::Unassigned, dst, 0x05);
+        assert_eq!(Address::Unassigned, subscription.source());
+        assert_eq!(0, subscription.period_log());
+        assert!(!subscription.receive(src, dst, 1));
+        assert_eq!(3, subscription.count);
+        assert_eq!(1, subscription.min_hops());
+    }
+
+    #[test]
+    fn publication_count() {
+        let mut heartbeat = Heartbeat::default();
+        assert!(!heartbeat.consume_publication());
+
+        heartbeat.reset_publication(&HeartbeatPublication {
+            count_log: 0x02,
+            ..Default::default()
+        });
+        assert!(heartbeat.consume_publication());
+        assert!(heartbeat.consume_publication());
+        assert!(!heartbeat.consume_publication());
+
+        heartbeat.reset_publication(&HeartbeatPublication {
+            count_log: 0xFF,
+            ..Default::default()
+        });
+        assert!(heartbeat.consume_publication());
+        assert_eq!(0xFFFF, heartbeat.publication_count());
+    }
+}
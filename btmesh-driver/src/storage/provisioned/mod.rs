@@ -1,6 +1,9 @@
 use crate::storage::provisioned::bindings::Bindings;
 use crate::storage::provisioned::foundation::Foundation;
+use crate::storage::provisioned::heartbeat::Heartbeat;
+use crate::storage::provisioned::node_identity::NodeIdentities;
 use crate::storage::provisioned::publications::Publications;
+use crate::storage::provisioned::replay_protection::ReplayProtectionList;
 use crate::storage::provisioned::subscriptions::Subscriptions;
 use crate::{Configuration, DeviceInfo, NetworkState, Secrets};
 use btmesh_common::{Composition, IvIndex};
@@ -8,7 +11,10 @@ use core::hash::{Hash, Hasher};
 
 pub(crate) mod bindings;
 pub(crate) mod foundation;
+pub(crate) mod heartbeat;
+pub(crate) mod node_identity;
 pub(crate) mod publications;
+pub(crate) mod replay_protection;
 pub(crate) mod subscriptions;
 
 #[derive(Clone, Debug)]
@@ -38,6 +44,11 @@ pub struct ProvisionedConfiguration {
     subscriptions: Subscriptions,
     publications: Publications,
     foundation: Foundation,
+    replay_protection: ReplayProtectionList,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    heartbeat: Heartbeat,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    node_identities: NodeIdentities,
 }
 
 impl ProvisionedConfiguration {
@@ -57,6 +68,9 @@ impl ProvisionedConfiguration {
             bindings: Default::default(),
             subscriptions: Default::default(),
             publications: Default::default(),
+            replay_protection: Default::default(),
+            heartbeat: Default::default(),
+            node_identities: Default::default(),
         }
     }
 
@@ -74,6 +88,7 @@ impl ProvisionedConfiguration {
         self.subscriptions.display(composition);
         self.publications.display(composition);
         self.foundation.display();
+        self.replay_protection.display();
         info!("========================================================================");
     }
 
@@ -81,6 +96,10 @@ impl ProvisionedConfiguration {
         &self.network_state
     }
 
+    pub(crate) fn network_state_mut(&mut self) -> &mut NetworkState {
+        &mut self.network_state
+    }
+
     pub(crate) fn secrets(&self) -> &Secrets {
         &self.secrets
     }
@@ -136,6 +155,30 @@ impl ProvisionedConfiguration {
     pub(crate) fn foundation_mut(&mut self) -> &mut Foundation {
         &mut self.foundation
     }
+
+    pub(crate) fn replay_protection(&self) -> &ReplayProtectionList {
+        &self.replay_protection
+    }
+
+    pub(crate) fn replay_protection_mut(&mut self) -> &mut ReplayProtectionList {
+        &mut self.replay_protection
+    }
+
+    pub(crate) fn heartbeat(&self) -> &Heartbeat {
+        &self.heartbeat
+    }
+
+    pub(crate) fn heartbeat_mut(&mut self) -> &mut Heartbeat {
+        &mut self.heartbeat
+    }
+
+    pub(crate) fn node_identities(&self) -> &NodeIdentities {
+        &self.node_identities
+    }
+
+    pub(crate) fn node_identities_mut(&mut self) -> &mut NodeIdentities {
+        &mut self.node_identities
+    }
 }
 
 impl From<(DeviceInfo, Secrets, NetworkState)> for ProvisionedConfiguration {
@@ -149,6 +192,9 @@ impl From<(DeviceInfo, Secrets, NetworkState)> for ProvisionedConfiguration {
             bindings: Default::default(),
             subscriptions: Default::default(),
             publications: Default::default(),
+            replay_protection: Default::default(),
+            heartbeat: Default::default(),
+            node_identities: Default::default(),
         }
     }
 }
@@ -171,3 +217,33 @@ impl From<ProvisionedConfiguration> for Configuration {
         Configuration::Provisioned(inner)
     }
 }
+
+#[cfg(test)]
+pub(crate) mod fixture {
+    use super::ProvisionedConfiguration;
+    use crate::{DeviceInfo, NetworkState, Secrets};
+    use btmesh_common::address::UnicastAddress;
+    use btmesh_common::crypto::device::DeviceKey;
+    use btmesh_common::crypto::network::NetworkKey;
+    use btmesh_common::{IvIndex, IvUpdateFlag};
+    use btmesh_models::foundation::configuration::NetKeyIndex;
+
+    /// A single-element node at `0x00A1`, holding `network_key` as its primary NetKey.
+    pub(crate) fn config(network_key: NetworkKey) -> ProvisionedConfiguration {
+        let mut secrets = Secrets::new(
+            DeviceKey::new([0x11; 16]),
+            Default::default(),
+            Default::default(),
+        );
+        secrets
+            .add_network_key(NetKeyIndex::new(0), network_key)
+            .unwrap();
+        ProvisionedConfiguration::new(
+            0,
+            NetworkState::new(IvIndex::new(0), IvUpdateFlag::Normal),
+            secrets,
+            DeviceInfo::new(UnicastAddress::new(0x00A1).unwrap(), 1),
+            Default::default(),
+        )
+    }
+}
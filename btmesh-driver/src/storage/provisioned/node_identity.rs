@@ -0,0 +1,68 @@
+use btmesh_models::foundation::configuration::NetKeyIndex;
+use core::hash::{Hash, Hasher};
+use embassy_time::{Duration, Instant};
+use heapless::Vec;
+
+/// Node Identity advertising stops on its own after 60 seconds.
+const NODE_IDENTITY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Subnets on which Node Identity is currently advertised, never persisted
+/// since a restart ends any advertising in progress.
+#[derive(Clone, Debug, Default)]
+pub struct NodeIdentities<const N: usize = 4> {
+    running: Vec<(NetKeyIndex, Instant), N>,
+}
+
+impl<const N: usize> Hash for NodeIdentities<N> {
+    fn hash<H: Hasher>(&self, _state: &mut H) {}
+}
+
+#[cfg(feature = "defmt")]
+impl<const N: usize> ::defmt::Format for NodeIdentities<N> {
+    fn format(&self, fmt: ::defmt::Formatter) {
+        ::defmt::write!(fmt, "running: {}", self.running.len());
+    }
+}
+
+impl<const N: usize> NodeIdentities<N> {
+    /// Start advertising Node Identity on a subnet, for the next 60 seconds.
+    pub fn start(&mut self, net_key_index: NetKeyIndex) {
+        self.stop(net_key_index);
+        self.running
+            .push((net_key_index, Instant::now() + NODE_IDENTITY_TIMEOUT))
+            .ok();
+    }
+
+    pub fn stop(&mut self, net_key_index: NetKeyIndex) {
+        self.running.retain(|(index, _)| *index != net_key_index);
+    }
+
+    pub fn is_running(&self, net_key_index: NetKeyIndex) -> bool {
+        let now = Instant::now();
+        self.running
+            .iter()
+            .any(|(index, expiry)| *index == net_key_index && *expiry > now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn start_and_stop() {
+        let mut identities = NodeIdentities::<2>::default();
+        let primary = NetKeyIndex::new(0);
+        let secondary = NetKeyIndex::new(1);
+
+        identities.start(primary);
+        identities.start(primary);
+        identities.start(secondary);
+        assert!(identities.is_running(primary));
+        assert!(identities.is_running(secondary));
+
+        identities.stop(primary);
+        assert!(!identities.is_running(primary));
+        assert!(identities.is_running(secondary));
+    }
+}
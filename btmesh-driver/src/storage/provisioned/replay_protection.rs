@@ -0,0 +1,49 @@
+use crate::stack::provisioned::network::replay_protection::MAX_REPLAY_PROTECTION_LIST_SIZE;
+use btmesh_common::address::UnicastAddress;
+use btmesh_common::IvIndex;
+use heapless::Vec;
+
+/// Highest sequence number received from a source, along with its IV Index.
+#[cfg_attr(feature = "defmt", derive(::defmt::Format))]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub struct ReplayProtectionEntry {
+    pub src: UnicastAddress,
+    pub seq: u32,
+    pub iv_index: IvIndex,
+}
+
+/// Replay protection list, persisted so replayed messages are still
+/// rejected after a reboot.
+///
+/// Changes are written back along with the sequence number, once every
+/// persist interval, rather than wearing out the flash on every message.
+#[cfg_attr(feature = "defmt", derive(::defmt::Format))]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[derive(Clone, Debug, Default, Hash)]
+pub struct ReplayProtectionList {
+    entries: Vec<ReplayProtectionEntry, MAX_REPLAY_PROTECTION_LIST_SIZE>,
+}
+
+impl ReplayProtectionList {
+    pub fn display(&self) {
+        info!("replay protection: {} sources", self.entries.len());
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ReplayProtectionEntry> + '_ {
+        self.entries.iter()
+    }
+
+    /// Replace the entries, kept ordered by source so the hash only changes
+    /// along with their content.
+    pub(crate) fn replace<I: Iterator<Item = ReplayProtectionEntry>>(&mut self, entries: I) {
+        self.entries.clear();
+        for entry in entries {
+            if self.entries.push(entry).is_err() {
+                break;
+            }
+        }
+        self.entries
+            .sort_unstable_by_key(|entry| entry.src.as_bytes());
+    }
+}
@@ -166,6 +166,24 @@ impl<const N: usize> Subscriptions<N> {
         }
     }
 
+    /// Group and virtual addresses subscribed to by any model, each listed once.
+    pub fn addresses<const S: usize>(&self) -> Vec<Address, S> {
+        let mut addresses: Vec<Address, S> = Vec::new();
+        for slot in self.entries.iter().flatten() {
+            let address = match slot.address {
+                SubscriptionAddress::Group(group) => Address::Group(group),
+                SubscriptionAddress::Label(label_uuid) => {
+                    Address::Virtual(label_uuid.virtual_address())
+                }
+                _ => continue,
+            };
+            if !addresses.contains(&address) {
+                addresses.push(address).ok();
+            }
+        }
+        addresses
+    }
+
     pub fn subscriptions_for(
         &self,
         dst: Address,
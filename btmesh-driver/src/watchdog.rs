@@ -1,45 +1,39 @@
+use crate::stack::provisioned::lower::CONCURRENT_REASSEMBLIES;
+use btmesh_common::address::UnicastAddress;
 use btmesh_common::SeqZero;
-use core::cell::Cell;
+use core::cell::{Cell, RefCell};
 use core::future::pending;
 use embassy_time::{Instant, Timer};
+use heapless::Vec;
 
 #[derive(Copy, Clone)]
 pub enum WatchdogEvent {
     LinkOpenTimeout,
-    OutboundExpiration(SeqZero),
-    InboundExpiration(SeqZero),
+    InboundExpiration(UnicastAddress, SeqZero),
 }
 
 #[derive(Default)]
 pub struct Watchdog {
     link_opening_timeout: Cell<Option<(Instant, WatchdogEvent)>>,
-    outbound_expiration: Cell<Option<(Instant, WatchdogEvent)>>,
-    inbound_expiration: Cell<Option<(Instant, WatchdogEvent)>>,
+    /// One expiration per inbound reassembly, keyed by source and `SeqZero`.
+    inbound_expirations: RefCell<Vec<(Instant, UnicastAddress, SeqZero), CONCURRENT_REASSEMBLIES>>,
 }
 
 impl Watchdog {
-    fn earliest(
-        left: Option<(Instant, WatchdogEvent)>,
-        right: Option<(Instant, WatchdogEvent)>,
-    ) -> Option<(Instant, WatchdogEvent)> {
-        match (left, right) {
-            (None, Some(_)) => right,
-            (Some(_), None) => left,
-            (Some(inner_left), Some(inner_right)) if inner_left.0 < inner_right.0 => left,
-            (Some(inner_left), Some(inner_right)) if inner_right.0 < inner_left.0 => right,
-            _ => None,
-        }
-    }
-
     #[allow(clippy::let_unit_value)]
     pub async fn next(&self) -> Option<Expiration<'_>> {
-        let next = Self::earliest(
-            Self::earliest(None, self.link_opening_timeout.get()),
-            Self::earliest(
-                self.outbound_expiration.get(),
-                self.inbound_expiration.get(),
-            ),
-        );
+        let next = self
+            .inbound_expirations
+            .borrow()
+            .iter()
+            .map(|(expiration, src, seq_zero)| {
+                (
+                    *expiration,
+                    WatchdogEvent::InboundExpiration(*src, *seq_zero),
+                )
+            })
+            .chain(self.link_opening_timeout.get())
+            .min_by_key(|(expiration, _)| *expiration);
 
         if let Some(next) = next {
             Timer::at(next.0).await;
@@ -59,56 +53,22 @@ impl Watchdog {
         self.link_opening_timeout.take();
     }
 
-    pub fn outbound_expiration(&self, expiration: (Instant, SeqZero)) {
-        if let Some(current) = self.outbound_expiration.get() {
-            if current.0 < expiration.0 {
-                return;
-            }
-            self.outbound_expiration.replace(Some((
-                expiration.0,
-                WatchdogEvent::OutboundExpiration(expiration.1),
-            )));
-        } else {
-            self.outbound_expiration.replace(Some((
-                expiration.0,
-                WatchdogEvent::OutboundExpiration(expiration.1),
-            )));
-        }
-    }
-
-    pub fn clear_outbound_expiration(&self, seq_zero: SeqZero) {
-        if let Some((_, WatchdogEvent::OutboundExpiration(current))) =
-            self.outbound_expiration.get()
-        {
-            if current == seq_zero {
-                self.outbound_expiration.take();
-            }
-        }
+    pub fn clear_inbound_expiration(&self, src: UnicastAddress, seq_zero: SeqZero) {
+        self.inbound_expirations
+            .borrow_mut()
+            .retain(|(_, current_src, current)| !(*current_src == src && *current == seq_zero));
     }
 
-    pub fn clear_inbound_expiration(&self, seq_zero: SeqZero) {
-        if let Some((_, WatchdogEvent::InboundExpiration(current))) = self.inbound_expiration.get()
+    /// Set, or move, the expiration of the reassembly of `SeqZero` from the source.
+    pub fn inbound_expiration(&self, expiration: (Instant, UnicastAddress, SeqZero)) {
+        let mut expirations = self.inbound_expirations.borrow_mut();
+        if let Some(current) = expirations
+            .iter_mut()
+            .find(|(_, src, seq_zero)| *src == expiration.1 && *seq_zero == expiration.2)
         {
-            if current == seq_zero {
-                self.inbound_expiration.take();
-            }
-        }
-    }
-
-    pub fn inbound_expiration(&self, expiration: (Instant, SeqZero)) {
-        if let Some(current) = self.inbound_expiration.get() {
-            if current.0 < expiration.0 {
-                return;
-            }
-            self.inbound_expiration.replace(Some((
-                expiration.0,
-                WatchdogEvent::InboundExpiration(expiration.1),
-            )));
-        } else {
-            self.inbound_expiration.replace(Some((
-                expiration.0,
-                WatchdogEvent::InboundExpiration(expiration.1),
-            )));
+            *current = expiration;
+        } else if expirations.push(expiration).is_err() {
+            warn!("no space for inbound expiration");
         }
     }
 }
@@ -128,11 +88,8 @@ impl<'w> Expiration<'w> {
             WatchdogEvent::LinkOpenTimeout => {
                 self.watchdog.clear_link_open_timeout();
             }
-            WatchdogEvent::OutboundExpiration(seq_zero) => {
-                self.watchdog.clear_outbound_expiration(seq_zero);
-            }
-            WatchdogEvent::InboundExpiration(seq_zero) => {
-                self.watchdog.clear_inbound_expiration(seq_zero);
+            WatchdogEvent::InboundExpiration(src, seq_zero) => {
+                self.watchdog.clear_inbound_expiration(src, seq_zero);
             }
         }
 
@@ -55,6 +55,21 @@ impl AppKeyMessage {
         }
     }
 
+    /// Parses byte array into AppKey Update message.
+    pub fn parse_update(parameters: &[u8]) -> Result<Self, ParseError> {
+        if parameters.len() == 19 {
+            let indexes = NetKeyAppKeyIndexesPair::parse(&parameters[0..=2])?;
+            let app_key = ApplicationKey::new(
+                parameters[3..]
+                    .try_into()
+                    .map_err(|_| ParseError::InvalidLength)?,
+            )?;
+            Ok(Self::Update(AppKeyUpdateMessage { indexes, app_key }))
+        } else {
+            Err(ParseError::InvalidLength)
+        }
+    }
+
     /// Parses byte array into AppKey Delete message.
     pub fn parse_delete(parameters: &[u8]) -> Result<Self, ParseError> {
         if parameters.len() == 3 {
@@ -89,7 +104,7 @@ impl Message for AppKeyMessage {
             Self::Get(_) => CONFIG_APPKEY_GET,
             Self::List(_) => CONFIG_APPKEY_LIST,
             Self::Status(_) => CONFIG_APPKEY_STATUS,
-            Self::Update(_) => CONFIG_APPKEY_STATUS,
+            Self::Update(_) => CONFIG_APPKEY_UPDATE,
         }
     }
 
@@ -266,16 +281,35 @@ impl AppKeyStatusMessage {
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[derive(Debug)]
 pub struct AppKeyUpdateMessage {
-    pub(crate) net_key_index: NetKeyIndex,
-    pub(crate) app_key_index: AppKeyIndex,
-    pub(crate) app_key: [u8; 16],
+    /// Index of the NetKey and index of the AppKey.
+    pub indexes: NetKeyAppKeyIndexesPair,
+    /// New AppKey value.
+    pub app_key: ApplicationKey,
 }
 
 impl AppKeyUpdateMessage {
     fn emit_parameters<const N: usize>(
         &self,
-        _xmit: &mut Vec<u8, N>,
+        xmit: &mut Vec<u8, N>,
     ) -> Result<(), InsufficientBuffer> {
-        todo!()
+        self.indexes.emit(xmit)?;
+        xmit.extend_from_slice(&*self.app_key)
+            .map_err(|_| InsufficientBuffer)?;
+        Ok(())
+    }
+
+    /// Returns network key index.
+    pub fn net_key_index(&self) -> NetKeyIndex {
+        self.indexes.0
+    }
+
+    /// Returns app key index.
+    pub fn app_key_index(&self) -> AppKeyIndex {
+        self.indexes.1
+    }
+
+    /// Returns app key.
+    pub fn app_key(&self) -> ApplicationKey {
+        self.app_key
     }
 }
@@ -0,0 +1,132 @@
+use crate::foundation::configuration::ConfigurationMessage;
+use crate::Message;
+use btmesh_common::opcode::Opcode;
+use btmesh_common::{opcode, InsufficientBuffer, ParseError};
+use heapless::Vec;
+
+opcode!( CONFIG_FRIEND_GET 0x80, 0x0F );
+opcode!( CONFIG_FRIEND_SET 0x80, 0x10 );
+opcode!( CONFIG_FRIEND_STATUS 0x80, 0x11 );
+
+/// The Friend state indicates if the Friend feature is supported, and if supported, enabled.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Friend {
+    /// The Friend feature is supported and disabled.
+    Disabled = 0x00,
+    /// The Friend feature is supported and enabled.
+    Enabled = 0x01,
+    /// The Friend feature is not supported.
+    NotSupported = 0x02,
+}
+
+impl Friend {
+    /// Parses parameters into Friend state.
+    pub fn parse(data: u8) -> Result<Self, ParseError> {
+        match data {
+            0x00 => Ok(Self::Disabled),
+            0x01 => Ok(Self::Enabled),
+            0x02 => Ok(Self::NotSupported),
+            _ => Err(ParseError::InvalidValue),
+        }
+    }
+
+    /// Emits Friend state into array of bytes.
+    pub fn emit<const N: usize>(&self, xmit: &mut Vec<u8, N>) -> Result<(), InsufficientBuffer> {
+        xmit.push(*self as u8).map_err(|_| InsufficientBuffer)?;
+        Ok(())
+    }
+}
+
+/// Friend message.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug)]
+pub enum FriendMessage {
+    /// Friend Get is an acknowledged message used to get the Friend state of a node.
+    Get,
+    /// Friend Set is an acknowledged message used to set the Friend state of a node.
+    Set(Friend),
+    /// Friend Status is an unacknowledged message used to report the Friend state of a node.
+    Status(Friend),
+}
+
+impl From<FriendMessage> for ConfigurationMessage {
+    fn from(inner: FriendMessage) -> Self {
+        ConfigurationMessage::Friend(inner)
+    }
+}
+
+impl Message for FriendMessage {
+    fn opcode(&self) -> Opcode {
+        match self {
+            Self::Get => CONFIG_FRIEND_GET,
+            Self::Set(_) => CONFIG_FRIEND_SET,
+            Self::Status(_) => CONFIG_FRIEND_STATUS,
+        }
+    }
+
+    fn emit_parameters<const N: usize>(
+        &self,
+        xmit: &mut Vec<u8, N>,
+    ) -> Result<(), InsufficientBuffer> {
+        match self {
+            Self::Get => {}
+            Self::Set(inner) => inner.emit(xmit)?,
+            Self::Status(inner) => inner.emit(xmit)?,
+        }
+        Ok(())
+    }
+}
+
+impl FriendMessage {
+    /// Parses parameters into Friend Get message.
+    pub fn parse_get(parameters: &[u8]) -> Result<Self, ParseError> {
+        if parameters.is_empty() {
+            Ok(Self::Get)
+        } else {
+            Err(ParseError::InvalidLength)
+        }
+    }
+
+    /// Parses parameters into Friend Set message.
+    pub fn parse_set(parameters: &[u8]) -> Result<Self, ParseError> {
+        if parameters.len() != 1 {
+            return Err(ParseError::InvalidLength);
+        }
+        match Friend::parse(parameters[0])? {
+            // only a node can tell whether it supports the feature.
+            Friend::NotSupported => Err(ParseError::InvalidValue),
+            state => Ok(Self::Set(state)),
+        }
+    }
+
+    /// Parses parameters into Friend Status message.
+    pub fn parse_status(parameters: &[u8]) -> Result<Self, ParseError> {
+        if parameters.len() == 1 {
+            Ok(Self::Status(Friend::parse(parameters[0])?))
+        } else {
+            Err(ParseError::InvalidLength)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_set() {
+        assert!(matches!(
+            FriendMessage::parse_set(&[0x01]),
+            Ok(FriendMessage::Set(Friend::Enabled))
+        ));
+        assert!(matches!(
+            FriendMessage::parse_set(&[0x00]),
+            Ok(FriendMessage::Set(Friend::Disabled))
+        ));
+        assert!(FriendMessage::parse_set(&[0x02]).is_err());
+        assert!(FriendMessage::parse_set(&[0x03]).is_err());
+        assert!(FriendMessage::parse_set(&[]).is_err());
+    }
+}
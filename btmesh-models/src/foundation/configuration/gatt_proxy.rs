@@ -0,0 +1,132 @@
+use crate::foundation::configuration::ConfigurationMessage;
+use crate::Message;
+use btmesh_common::opcode::Opcode;
+use btmesh_common::{opcode, InsufficientBuffer, ParseError};
+use heapless::Vec;
+
+opcode!( CONFIG_GATT_PROXY_GET 0x80, 0x12 );
+opcode!( CONFIG_GATT_PROXY_SET 0x80, 0x13 );
+opcode!( CONFIG_GATT_PROXY_STATUS 0x80, 0x14 );
+
+/// The GATT Proxy state indicates if the Proxy feature of a GATT Bearer Server is supported, and if supported, enabled.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GattProxy {
+    /// The Proxy feature is supported and disabled.
+    Disabled = 0x00,
+    /// The Proxy feature is supported and enabled.
+    Enabled = 0x01,
+    /// The Proxy feature is not supported.
+    NotSupported = 0x02,
+}
+
+impl GattProxy {
+    /// Parses parameters into GATT Proxy state.
+    pub fn parse(data: u8) -> Result<Self, ParseError> {
+        match data {
+            0x00 => Ok(Self::Disabled),
+            0x01 => Ok(Self::Enabled),
+            0x02 => Ok(Self::NotSupported),
+            _ => Err(ParseError::InvalidValue),
+        }
+    }
+
+    /// Emits GATT Proxy state into array of bytes.
+    pub fn emit<const N: usize>(&self, xmit: &mut Vec<u8, N>) -> Result<(), InsufficientBuffer> {
+        xmit.push(*self as u8).map_err(|_| InsufficientBuffer)?;
+        Ok(())
+    }
+}
+
+/// GATT Proxy message.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug)]
+pub enum GattProxyMessage {
+    /// GATT Proxy Get is an acknowledged message used to get the GATT Proxy state of a node.
+    Get,
+    /// GATT Proxy Set is an acknowledged message used to set the GATT Proxy state of a node.
+    Set(GattProxy),
+    /// GATT Proxy Status is an unacknowledged message used to report the GATT Proxy state of a node.
+    Status(GattProxy),
+}
+
+impl From<GattProxyMessage> for ConfigurationMessage {
+    fn from(inner: GattProxyMessage) -> Self {
+        ConfigurationMessage::GattProxy(inner)
+    }
+}
+
+impl Message for GattProxyMessage {
+    fn opcode(&self) -> Opcode {
+        match self {
+            Self::Get => CONFIG_GATT_PROXY_GET,
+            Self::Set(_) => CONFIG_GATT_PROXY_SET,
+            Self::Status(_) => CONFIG_GATT_PROXY_STATUS,
+        }
+    }
+
+    fn emit_parameters<const N: usize>(
+        &self,
+        xmit: &mut Vec<u8, N>,
+    ) -> Result<(), InsufficientBuffer> {
+        match self {
+            Self::Get => {}
+            Self::Set(inner) => inner.emit(xmit)?,
+            Self::Status(inner) => inner.emit(xmit)?,
+        }
+        Ok(())
+    }
+}
+
+impl GattProxyMessage {
+    /// Parses parameters into GATT Proxy Get message.
+    pub fn parse_get(parameters: &[u8]) -> Result<Self, ParseError> {
+        if parameters.is_empty() {
+            Ok(Self::Get)
+        } else {
+            Err(ParseError::InvalidLength)
+        }
+    }
+
+    /// Parses parameters into GATT Proxy Set message.
+    pub fn parse_set(parameters: &[u8]) -> Result<Self, ParseError> {
+        if parameters.len() != 1 {
+            return Err(ParseError::InvalidLength);
+        }
+        match GattProxy::parse(parameters[0])? {
+            // only a node can tell whether it supports the feature.
+            GattProxy::NotSupported => Err(ParseError::InvalidValue),
+            state => Ok(Self::Set(state)),
+        }
+    }
+
+    /// Parses parameters into GATT Proxy Status message.
+    pub fn parse_status(parameters: &[u8]) -> Result<Self, ParseError> {
+        if parameters.len() == 1 {
+            Ok(Self::Status(GattProxy::parse(parameters[0])?))
+        } else {
+            Err(ParseError::InvalidLength)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_set() {
+        assert!(matches!(
+            GattProxyMessage::parse_set(&[0x01]),
+            Ok(GattProxyMessage::Set(GattProxy::Enabled))
+        ));
+        assert!(matches!(
+            GattProxyMessage::parse_set(&[0x00]),
+            Ok(GattProxyMessage::Set(GattProxy::Disabled))
+        ));
+        assert!(GattProxyMessage::parse_set(&[0x02]).is_err());
+        assert!(GattProxyMessage::parse_set(&[0x03]).is_err());
+        assert!(GattProxyMessage::parse_set(&[]).is_err());
+    }
+}
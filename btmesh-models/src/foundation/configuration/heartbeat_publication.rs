@@ -0,0 +1,273 @@
+use crate::foundation::configuration::{ConfigurationMessage, KeyIndex, NetKeyIndex};
+use crate::{Message, Status};
+use btmesh_common::address::Address;
+use btmesh_common::opcode::Opcode;
+use btmesh_common::{opcode, Features, InsufficientBuffer, ParseError, Ttl};
+use core::convert::TryInto;
+use heapless::Vec;
+
+opcode!( CONFIG_HEARTBEAT_PUBLICATION_GET 0x80, 0x38 );
+opcode!( CONFIG_HEARTBEAT_PUBLICATION_SET 0x80, 0x39 );
+opcode!( CONFIG_HEARTBEAT_PUBLICATION_STATUS 0x06 );
+
+/// Converts a logarithmic Count Log or Period Log field into its value, `2^(n-1)`.
+///
+/// `0x00` is zero, while `0x11` and `0xFF` saturate at `0xFFFF`.
+pub fn log_to_value(log: u8) -> u16 {
+    match log {
+        0x00 => 0x0000,
+        0x01..=0x10 => 1 << (log - 1),
+        _ => 0xFFFF,
+    }
+}
+
+/// Converts a count or period value into its logarithmic representation, `1 + floor(log2(n))`.
+///
+/// `0xFFFF` is reported as `0xFF`.
+pub fn value_to_log(value: u16) -> u8 {
+    match value {
+        0x0000 => 0x00,
+        0xFFFF => 0xFF,
+        _ => (16 - value.leading_zeros()) as u8,
+    }
+}
+
+/// The Heartbeat Publication state controls sending of periodic Heartbeat transport control messages.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HeartbeatPublication {
+    /// Destination address for Heartbeat messages.
+    pub destination: Address,
+    /// Number of Heartbeat messages to be sent, as a logarithm.
+    pub count_log: u8,
+    /// Period between the publication of two consecutive Heartbeat messages, as a logarithm.
+    pub period_log: u8,
+    /// TTL to be used when sending Heartbeat messages.
+    pub ttl: Ttl,
+    /// Features that trigger sending Heartbeat messages when changed.
+    pub features: Features,
+    /// Index of the NetKey used when sending Heartbeat messages.
+    pub net_key_index: NetKeyIndex,
+}
+
+impl Default for HeartbeatPublication {
+    fn default() -> Self {
+        Self {
+            destination: Address::Unassigned,
+            count_log: 0,
+            period_log: 0,
+            ttl: Ttl::new(0),
+            features: Features::none(),
+            net_key_index: NetKeyIndex::new(0),
+        }
+    }
+}
+
+impl HeartbeatPublication {
+    /// Parses parameters into Heartbeat Publication state.
+    pub fn parse(parameters: &[u8]) -> Result<Self, ParseError> {
+        if parameters.len() != 9 {
+            return Err(ParseError::InvalidLength);
+        }
+
+        let destination = Address::parse([parameters[1], parameters[0]]);
+        if let Address::Virtual(_) = destination {
+            return Err(ParseError::InvalidValue);
+        }
+
+        let count_log = parameters[2];
+        if !matches!(count_log, 0x00..=0x11 | 0xFF) {
+            return Err(ParseError::InvalidValue);
+        }
+
+        let period_log = parameters[3];
+        if period_log > 0x11 {
+            return Err(ParseError::InvalidValue);
+        }
+
+        if parameters[4] > 0x7F {
+            return Err(ParseError::InvalidValue);
+        }
+        let ttl = Ttl::new(parameters[4]);
+
+        let features = Features::from_bits(u16::from_le_bytes([parameters[5], parameters[6]]));
+        let net_key_index = NetKeyIndex(KeyIndex::parse_one(&parameters[7..=8])?);
+
+        Ok(Self {
+            destination,
+            count_log,
+            period_log,
+            ttl,
+            features,
+            net_key_index,
+        })
+    }
+
+    /// Emits Heartbeat Publication state into array of bytes.
+    pub fn emit<const N: usize>(&self, xmit: &mut Vec<u8, N>) -> Result<(), InsufficientBuffer> {
+        let destination = self.destination.as_bytes();
+        xmit.push(destination[1]).map_err(|_| InsufficientBuffer)?;
+        xmit.push(destination[0]).map_err(|_| InsufficientBuffer)?;
+        xmit.push(self.count_log).map_err(|_| InsufficientBuffer)?;
+        xmit.push(self.period_log).map_err(|_| InsufficientBuffer)?;
+        xmit.push(self.ttl.value())
+            .map_err(|_| InsufficientBuffer)?;
+        self.features.emit(xmit)?;
+        self.net_key_index.emit(xmit)?;
+        Ok(())
+    }
+
+    /// Returns the number of Heartbeat messages to be sent.
+    pub fn count(&self) -> u16 {
+        log_to_value(self.count_log)
+    }
+
+    /// Returns the period between Heartbeat messages, in seconds.
+    pub fn period(&self) -> u16 {
+        log_to_value(self.period_log)
+    }
+}
+
+/// Heartbeat Publication message.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug)]
+pub enum HeartbeatPublicationMessage {
+    /// Heartbeat Publication Get is an acknowledged message used to get the current Heartbeat Publication state of an element.
+    Get,
+    /// Heartbeat Publication Set is an acknowledged message used to set the current Heartbeat Publication state of an element.
+    Set(HeartbeatPublication),
+    /// Heartbeat Publication Status is an unacknowledged message used to report the Heartbeat Publication state of a node.
+    Status(HeartbeatPublicationStatusMessage),
+}
+
+impl From<HeartbeatPublicationMessage> for ConfigurationMessage {
+    fn from(inner: HeartbeatPublicationMessage) -> Self {
+        ConfigurationMessage::HeartbeatPublication(inner)
+    }
+}
+
+impl Message for HeartbeatPublicationMessage {
+    fn opcode(&self) -> Opcode {
+        match self {
+            Self::Get => CONFIG_HEARTBEAT_PUBLICATION_GET,
+            Self::Set(_) => CONFIG_HEARTBEAT_PUBLICATION_SET,
+            Self::Status(_) => CONFIG_HEARTBEAT_PUBLICATION_STATUS,
+        }
+    }
+
+    fn emit_parameters<const N: usize>(
+        &self,
+        xmit: &mut Vec<u8, N>,
+    ) -> Result<(), InsufficientBuffer> {
+        match self {
+            Self::Get => Ok(()),
+            Self::Set(inner) => inner.emit(xmit),
+            Self::Status(inner) => {
+                xmit.push(inner.status as u8)
+                    .map_err(|_| InsufficientBuffer)?;
+                inner.publication.emit(xmit)
+            }
+        }
+    }
+}
+
+impl HeartbeatPublicationMessage {
+    /// Parses byte array into Heartbeat Publication Get message.
+    pub fn parse_get(parameters: &[u8]) -> Result<Self, ParseError> {
+        if parameters.is_empty() {
+            Ok(Self::Get)
+        } else {
+            Err(ParseError::InvalidLength)
+        }
+    }
+
+    /// Parses byte array into Heartbeat Publication Set message.
+    pub fn parse_set(parameters: &[u8]) -> Result<Self, ParseError> {
+        Ok(Self::Set(HeartbeatPublication::parse(parameters)?))
+    }
+
+    /// Parses byte array into Heartbeat Publication Status message.
+    pub fn parse_status(parameters: &[u8]) -> Result<Self, ParseError> {
+        if parameters.len() == 10 {
+            Ok(Self::Status(HeartbeatPublicationStatusMessage {
+                status: parameters[0].try_into()?,
+                publication: HeartbeatPublication::parse(&parameters[1..])?,
+            }))
+        } else {
+            Err(ParseError::InvalidLength)
+        }
+    }
+}
+
+/// Heartbeat Publication Status message.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug)]
+pub struct HeartbeatPublicationStatusMessage {
+    /// Status Code for the requesting message.
+    pub status: Status,
+    /// Heartbeat Publication state.
+    pub publication: HeartbeatPublication,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use btmesh_common::address::GroupAddress;
+
+    #[test]
+    fn log_conversions() {
+        assert_eq!(0, log_to_value(0x00));
+        assert_eq!(1, log_to_value(0x01));
+        assert_eq!(0x8000, log_to_value(0x10));
+        assert_eq!(0xFFFF, log_to_value(0x11));
+        assert_eq!(0xFFFF, log_to_value(0xFF));
+
+        assert_eq!(0x00, value_to_log(0));
+        assert_eq!(0x01, value_to_log(1));
+        assert_eq!(0x02, value_to_log(3));
+        assert_eq!(0x03, value_to_log(4));
+        assert_eq!(0x10, value_to_log(0xFFFE));
+        assert_eq!(0xFF, value_to_log(0xFFFF));
+    }
+
+    #[test]
+    fn parse_and_emit_set() {
+        let parameters = [0xFF, 0xFF, 0x04, 0x02, 0x05, 0x03, 0x00, 0x23, 0x01];
+        let publication = HeartbeatPublication::parse(&parameters).unwrap();
+        assert_eq!(
+            Address::Group(GroupAddress::AllNodes),
+            publication.destination
+        );
+        assert_eq!(8, publication.count());
+        assert_eq!(2, publication.period());
+        assert_eq!(Ttl::new(5), publication.ttl);
+        assert!(publication.features.relay);
+        assert!(publication.features.proxy);
+        assert!(!publication.features.friend);
+        assert_eq!(NetKeyIndex::new(0x123), publication.net_key_index);
+
+        let mut xmit: Vec<u8, 9> = Vec::new();
+        publication.emit(&mut xmit).unwrap();
+        assert_eq!(&parameters, &*xmit);
+    }
+
+    #[test]
+    fn reject_prohibited_values() {
+        // virtual destination
+        assert!(HeartbeatPublication::parse(&[
+            0x00, 0x80, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x00
+        ])
+        .is_err());
+        // count log
+        assert!(HeartbeatPublication::parse(&[
+            0x01, 0x00, 0x12, 0x00, 0x05, 0x00, 0x00, 0x00, 0x00
+        ])
+        .is_err());
+        // ttl
+        assert!(HeartbeatPublication::parse(&[
+            0x01, 0x00, 0x00, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00
+        ])
+        .is_err());
+    }
+}
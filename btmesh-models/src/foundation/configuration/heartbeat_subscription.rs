@@ -0,0 +1,166 @@
+use crate::foundation::configuration::ConfigurationMessage;
+use crate::{Message, Status};
+use btmesh_common::address::Address;
+use btmesh_common::opcode::Opcode;
+use btmesh_common::{opcode, InsufficientBuffer, ParseError};
+use core::convert::TryInto;
+use heapless::Vec;
+
+opcode!( CONFIG_HEARTBEAT_SUBSCRIPTION_GET 0x80, 0x3A );
+opcode!( CONFIG_HEARTBEAT_SUBSCRIPTION_SET 0x80, 0x3B );
+opcode!( CONFIG_HEARTBEAT_SUBSCRIPTION_STATUS 0x80, 0x3C );
+
+fn parse_address(parameters: &[u8]) -> Address {
+    Address::parse([parameters[1], parameters[0]])
+}
+
+fn emit_address<const N: usize>(
+    address: &Address,
+    xmit: &mut Vec<u8, N>,
+) -> Result<(), InsufficientBuffer> {
+    let bytes = address.as_bytes();
+    xmit.push(bytes[1]).map_err(|_| InsufficientBuffer)?;
+    xmit.push(bytes[0]).map_err(|_| InsufficientBuffer)?;
+    Ok(())
+}
+
+/// Heartbeat Subscription message.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug)]
+pub enum HeartbeatSubscriptionMessage {
+    /// Heartbeat Subscription Get is an acknowledged message used to get the Heartbeat Subscription state of an element.
+    Get,
+    /// Heartbeat Subscription Set is an acknowledged message used to set the Heartbeat Subscription state of an element.
+    Set(HeartbeatSubscriptionSetMessage),
+    /// Heartbeat Subscription Status is an unacknowledged message used to report the Heartbeat Subscription state of a node.
+    Status(HeartbeatSubscriptionStatusMessage),
+}
+
+impl From<HeartbeatSubscriptionMessage> for ConfigurationMessage {
+    fn from(inner: HeartbeatSubscriptionMessage) -> Self {
+        ConfigurationMessage::HeartbeatSubscription(inner)
+    }
+}
+
+impl Message for HeartbeatSubscriptionMessage {
+    fn opcode(&self) -> Opcode {
+        match self {
+            Self::Get => CONFIG_HEARTBEAT_SUBSCRIPTION_GET,
+            Self::Set(_) => CONFIG_HEARTBEAT_SUBSCRIPTION_SET,
+            Self::Status(_) => CONFIG_HEARTBEAT_SUBSCRIPTION_STATUS,
+        }
+    }
+
+    fn emit_parameters<const N: usize>(
+        &self,
+        xmit: &mut Vec<u8, N>,
+    ) -> Result<(), InsufficientBuffer> {
+        match self {
+            Self::Get => Ok(()),
+            Self::Set(inner) => {
+                emit_address(&inner.source, xmit)?;
+                emit_address(&inner.destination, xmit)?;
+                xmit.push(inner.period_log).map_err(|_| InsufficientBuffer)
+            }
+            Self::Status(inner) => {
+                xmit.push(inner.status as u8)
+                    .map_err(|_| InsufficientBuffer)?;
+                emit_address(&inner.source, xmit)?;
+                emit_address(&inner.destination, xmit)?;
+                xmit.push(inner.period_log)
+                    .map_err(|_| InsufficientBuffer)?;
+                xmit.push(inner.count_log).map_err(|_| InsufficientBuffer)?;
+                xmit.push(inner.min_hops).map_err(|_| InsufficientBuffer)?;
+                xmit.push(inner.max_hops).map_err(|_| InsufficientBuffer)
+            }
+        }
+    }
+}
+
+impl HeartbeatSubscriptionMessage {
+    /// Parses byte array into Heartbeat Subscription Get message.
+    pub fn parse_get(parameters: &[u8]) -> Result<Self, ParseError> {
+        if parameters.is_empty() {
+            Ok(Self::Get)
+        } else {
+            Err(ParseError::InvalidLength)
+        }
+    }
+
+    /// Parses byte array into Heartbeat Subscription Set message.
+    pub fn parse_set(parameters: &[u8]) -> Result<Self, ParseError> {
+        if parameters.len() != 5 {
+            return Err(ParseError::InvalidLength);
+        }
+
+        let source = parse_address(&parameters[0..=1]);
+        if !matches!(source, Address::Unassigned | Address::Unicast(_)) {
+            return Err(ParseError::InvalidValue);
+        }
+
+        let destination = parse_address(&parameters[2..=3]);
+        if let Address::Virtual(_) = destination {
+            return Err(ParseError::InvalidValue);
+        }
+
+        let period_log = parameters[4];
+        if period_log > 0x11 {
+            return Err(ParseError::InvalidValue);
+        }
+
+        Ok(Self::Set(HeartbeatSubscriptionSetMessage {
+            source,
+            destination,
+            period_log,
+        }))
+    }
+
+    /// Parses byte array into Heartbeat Subscription Status message.
+    pub fn parse_status(parameters: &[u8]) -> Result<Self, ParseError> {
+        if parameters.len() == 9 {
+            Ok(Self::Status(HeartbeatSubscriptionStatusMessage {
+                status: parameters[0].try_into()?,
+                source: parse_address(&parameters[1..=2]),
+                destination: parse_address(&parameters[3..=4]),
+                period_log: parameters[5],
+                count_log: parameters[6],
+                min_hops: parameters[7],
+                max_hops: parameters[8],
+            }))
+        } else {
+            Err(ParseError::InvalidLength)
+        }
+    }
+}
+
+/// Heartbeat Subscription Set message.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug)]
+pub struct HeartbeatSubscriptionSetMessage {
+    /// Source address for Heartbeat messages.
+    pub source: Address,
+    /// Destination address for Heartbeat messages.
+    pub destination: Address,
+    /// Period for receiving Heartbeat messages, as a logarithm.
+    pub period_log: u8,
+}
+
+/// Heartbeat Subscription Status message.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug)]
+pub struct HeartbeatSubscriptionStatusMessage {
+    /// Status Code for the requesting message.
+    pub status: Status,
+    /// Source address for Heartbeat messages.
+    pub source: Address,
+    /// Destination address for Heartbeat messages.
+    pub destination: Address,
+    /// Remaining period for receiving Heartbeat messages, as a logarithm.
+    pub period_log: u8,
+    /// Number of Heartbeat messages received, as a logarithm.
+    pub count_log: u8,
+    /// Minimum hops when receiving Heartbeat messages.
+    pub min_hops: u8,
+    /// Maximum hops when receiving Heartbeat messages.
+    pub max_hops: u8,
+}
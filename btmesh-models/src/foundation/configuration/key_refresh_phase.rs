@@ -0,0 +1,163 @@
+use crate::foundation::configuration::{ConfigurationMessage, KeyIndex, NetKeyIndex};
+use crate::{Message, Status};
+use btmesh_common::opcode::Opcode;
+use btmesh_common::{opcode, InsufficientBuffer, ParseError};
+use core::convert::TryInto;
+use heapless::Vec;
+
+opcode!( CONFIG_KEY_REFRESH_PHASE_GET 0x80, 0x15 );
+opcode!( CONFIG_KEY_REFRESH_PHASE_SET 0x80, 0x16 );
+opcode!( CONFIG_KEY_REFRESH_PHASE_STATUS 0x80, 0x17 );
+
+/// The Key Refresh Phase state indicates and controls the Key Refresh procedure for each NetKey.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KeyRefreshPhase {
+    /// Normal operation; Key Refresh procedure is not active.
+    #[default]
+    Normal = 0x00,
+    /// First phase of Key Refresh procedure; new keys are distributed.
+    Phase1 = 0x01,
+    /// Second phase of Key Refresh procedure; new keys are used for transmission.
+    Phase2 = 0x02,
+}
+
+impl KeyRefreshPhase {
+    /// Parses byte into Key Refresh Phase state.
+    pub fn parse(data: u8) -> Result<Self, ParseError> {
+        match data {
+            0x00 => Ok(Self::Normal),
+            0x01 => Ok(Self::Phase1),
+            0x02 => Ok(Self::Phase2),
+            _ => Err(ParseError::InvalidValue),
+        }
+    }
+}
+
+/// Transition requested by a Config Key Refresh Phase Set message.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum KeyRefreshTransition {
+    /// Start using the new keys for transmission.
+    Phase2 = 0x02,
+    /// Revoke the old keys and return to normal operation.
+    Phase3 = 0x03,
+}
+
+impl KeyRefreshTransition {
+    /// Parses byte into Key Refresh Phase transition.
+    pub fn parse(data: u8) -> Result<Self, ParseError> {
+        match data {
+            0x02 => Ok(Self::Phase2),
+            0x03 => Ok(Self::Phase3),
+            _ => Err(ParseError::InvalidValue),
+        }
+    }
+}
+
+/// Key Refresh Phase message.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug)]
+pub enum KeyRefreshPhaseMessage {
+    /// Key Refresh Phase Get is an acknowledged message used to get the current Key Refresh Phase state of the identified network key.
+    Get(NetKeyIndex),
+    /// Key Refresh Phase Set is an acknowledged message used to set the Key Refresh Phase state of the identified network key.
+    Set(KeyRefreshPhaseSetMessage),
+    /// Key Refresh Phase Status is an unacknowledged message used to report the current Key Refresh Phase state of the identified network key.
+    Status(KeyRefreshPhaseStatusMessage),
+}
+
+impl From<KeyRefreshPhaseMessage> for ConfigurationMessage {
+    fn from(inner: KeyRefreshPhaseMessage) -> Self {
+        ConfigurationMessage::KeyRefreshPhase(inner)
+    }
+}
+
+impl Message for KeyRefreshPhaseMessage {
+    fn opcode(&self) -> Opcode {
+        match self {
+            Self::Get(_) => CONFIG_KEY_REFRESH_PHASE_GET,
+            Self::Set(_) => CONFIG_KEY_REFRESH_PHASE_SET,
+            Self::Status(_) => CONFIG_KEY_REFRESH_PHASE_STATUS,
+        }
+    }
+
+    fn emit_parameters<const N: usize>(
+        &self,
+        xmit: &mut Vec<u8, N>,
+    ) -> Result<(), InsufficientBuffer> {
+        match self {
+            Self::Get(net_key_index) => net_key_index.emit(xmit),
+            Self::Set(inner) => {
+                inner.net_key_index.emit(xmit)?;
+                xmit.push(inner.transition as u8)
+                    .map_err(|_| InsufficientBuffer)
+            }
+            Self::Status(inner) => {
+                xmit.push(inner.status as u8)
+                    .map_err(|_| InsufficientBuffer)?;
+                inner.net_key_index.emit(xmit)?;
+                xmit.push(inner.phase as u8).map_err(|_| InsufficientBuffer)
+            }
+        }
+    }
+}
+
+impl KeyRefreshPhaseMessage {
+    /// Parses byte array into Key Refresh Phase Get message.
+    pub fn parse_get(parameters: &[u8]) -> Result<Self, ParseError> {
+        if parameters.len() == 2 {
+            Ok(Self::Get(NetKeyIndex(KeyIndex::parse_one(parameters)?)))
+        } else {
+            Err(ParseError::InvalidLength)
+        }
+    }
+
+    /// Parses byte array into Key Refresh Phase Set message.
+    pub fn parse_set(parameters: &[u8]) -> Result<Self, ParseError> {
+        if parameters.len() == 3 {
+            Ok(Self::Set(KeyRefreshPhaseSetMessage {
+                net_key_index: NetKeyIndex(KeyIndex::parse_one(&parameters[0..=1])?),
+                transition: KeyRefreshTransition::parse(parameters[2])?,
+            }))
+        } else {
+            Err(ParseError::InvalidLength)
+        }
+    }
+
+    /// Parses byte array into Key Refresh Phase Status message.
+    pub fn parse_status(parameters: &[u8]) -> Result<Self, ParseError> {
+        if parameters.len() == 4 {
+            Ok(Self::Status(KeyRefreshPhaseStatusMessage {
+                status: parameters[0].try_into()?,
+                net_key_index: NetKeyIndex(KeyIndex::parse_one(&parameters[1..=2])?),
+                phase: KeyRefreshPhase::parse(parameters[3])?,
+            }))
+        } else {
+            Err(ParseError::InvalidLength)
+        }
+    }
+}
+
+/// Key Refresh Phase Set message.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug)]
+pub struct KeyRefreshPhaseSetMessage {
+    /// Index of the NetKey.
+    pub net_key_index: NetKeyIndex,
+    /// New Key Refresh Phase transition.
+    pub transition: KeyRefreshTransition,
+}
+
+/// Key Refresh Phase Status message.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug)]
+pub struct KeyRefreshPhaseStatusMessage {
+    /// Status Code for the requesting message.
+    pub status: Status,
+    /// Index of the NetKey.
+    pub net_key_index: NetKeyIndex,
+    /// Key Refresh Phase state.
+    pub phase: KeyRefreshPhase,
+}
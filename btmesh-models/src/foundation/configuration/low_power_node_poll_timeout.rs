@@ -0,0 +1,139 @@
+use crate::foundation::configuration::ConfigurationMessage;
+use crate::Message;
+use btmesh_common::address::UnicastAddress;
+use btmesh_common::opcode::Opcode;
+use btmesh_common::{opcode, InsufficientBuffer, ParseError};
+use heapless::Vec;
+
+opcode!( CONFIG_LOW_POWER_NODE_POLLTIMEOUT_GET 0x80, 0x2D );
+opcode!( CONFIG_LOW_POWER_NODE_POLLTIMEOUT_STATUS 0x80, 0x2E );
+
+/// Largest PollTimeout, in units of 100 milliseconds.
+pub const POLL_TIMEOUT_MAX: u32 = 0x34BBFF;
+
+/// Low Power node PollTimeout message.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug)]
+pub enum LowPowerNodePollTimeoutMessage {
+    /// Low Power node PollTimeout Get is an acknowledged message used to get the current value
+    /// of the PollTimeout timer of a Low Power node within a Friend node.
+    Get(UnicastAddress),
+    /// Low Power node PollTimeout Status is an unacknowledged message used to report the current
+    /// value of the PollTimeout timer of a Low Power node within a Friend node.
+    Status(LowPowerNodePollTimeoutStatusMessage),
+}
+
+/// Low Power node PollTimeout Status message.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug)]
+pub struct LowPowerNodePollTimeoutStatusMessage {
+    /// The unicast address of the Low Power node.
+    pub lpn_address: UnicastAddress,
+    /// The current value of the PollTimeout timer, in units of 100 milliseconds,
+    /// zero when the node is not a friend of the Low Power node.
+    pub poll_timeout: u32,
+}
+
+impl From<LowPowerNodePollTimeoutMessage> for ConfigurationMessage {
+    fn from(inner: LowPowerNodePollTimeoutMessage) -> Self {
+        ConfigurationMessage::LowPowerNodePollTimeout(inner)
+    }
+}
+
+impl Message for LowPowerNodePollTimeoutMessage {
+    fn opcode(&self) -> Opcode {
+        match self {
+            Self::Get(_) => CONFIG_LOW_POWER_NODE_POLLTIMEOUT_GET,
+            Self::Status(_) => CONFIG_LOW_POWER_NODE_POLLTIMEOUT_STATUS,
+        }
+    }
+
+    fn emit_parameters<const N: usize>(
+        &self,
+        xmit: &mut Vec<u8, N>,
+    ) -> Result<(), InsufficientBuffer> {
+        match self {
+            Self::Get(lpn_address) => emit_address(lpn_address, xmit),
+            Self::Status(inner) => inner.emit_parameters(xmit),
+        }
+    }
+}
+
+impl LowPowerNodePollTimeoutMessage {
+    /// Parses parameters into Low Power node PollTimeout Get message.
+    pub fn parse_get(parameters: &[u8]) -> Result<Self, ParseError> {
+        if parameters.len() == 2 {
+            Ok(Self::Get(UnicastAddress::parse([
+                parameters[1],
+                parameters[0],
+            ])?))
+        } else {
+            Err(ParseError::InvalidLength)
+        }
+    }
+
+    /// Parses parameters into Low Power node PollTimeout Status message.
+    pub fn parse_status(parameters: &[u8]) -> Result<Self, ParseError> {
+        if parameters.len() == 5 {
+            let poll_timeout = u32::from_le_bytes([parameters[2], parameters[3], parameters[4], 0]);
+            if poll_timeout > POLL_TIMEOUT_MAX {
+                return Err(ParseError::InvalidValue);
+            }
+            Ok(Self::Status(LowPowerNodePollTimeoutStatusMessage {
+                lpn_address: UnicastAddress::parse([parameters[1], parameters[0]])?,
+                poll_timeout,
+            }))
+        } else {
+            Err(ParseError::InvalidLength)
+        }
+    }
+}
+
+impl LowPowerNodePollTimeoutStatusMessage {
+    fn emit_parameters<const N: usize>(
+        &self,
+        xmit: &mut Vec<u8, N>,
+    ) -> Result<(), InsufficientBuffer> {
+        emit_address(&self.lpn_address, xmit)?;
+        xmit.extend_from_slice(&self.poll_timeout.to_le_bytes()[0..3])
+            .map_err(|_| InsufficientBuffer)?;
+        Ok(())
+    }
+}
+
+fn emit_address<const N: usize>(
+    address: &UnicastAddress,
+    xmit: &mut Vec<u8, N>,
+) -> Result<(), InsufficientBuffer> {
+    let bytes = address.as_bytes();
+    xmit.push(bytes[1]).map_err(|_| InsufficientBuffer)?;
+    xmit.push(bytes[0]).map_err(|_| InsufficientBuffer)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_round_trip() {
+        let status = LowPowerNodePollTimeoutMessage::Status(LowPowerNodePollTimeoutStatusMessage {
+            lpn_address: UnicastAddress::new(0x0102).unwrap(),
+            poll_timeout: 0x034567,
+        });
+        let mut xmit: Vec<u8, 8> = Vec::new();
+        status.emit_parameters(&mut xmit).unwrap();
+        assert_eq!(&[0x02, 0x01, 0x67, 0x45, 0x03], &*xmit);
+
+        match LowPowerNodePollTimeoutMessage::parse_status(&xmit).unwrap() {
+            LowPowerNodePollTimeoutMessage::Status(parsed) => {
+                assert_eq!(UnicastAddress::new(0x0102).unwrap(), parsed.lpn_address);
+                assert_eq!(0x034567, parsed.poll_timeout);
+            }
+            _ => panic!("expected status"),
+        }
+
+        assert!(LowPowerNodePollTimeoutMessage::parse_get(&[0x00, 0x80]).is_err());
+        assert!(LowPowerNodePollTimeoutMessage::parse_get(&[0x02]).is_err());
+    }
+}
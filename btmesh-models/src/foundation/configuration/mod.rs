@@ -1,6 +1,7 @@
 //! Implementation of the Configuration models.
 use crate::foundation::configuration::app_key::{
-    AppKeyMessage, CONFIG_APPKEY_ADD, CONFIG_APPKEY_DELETE, CONFIG_APPKEY_GET, CONFIG_APPKEY_STATUS,
+    AppKeyMessage, CONFIG_APPKEY_ADD, CONFIG_APPKEY_DELETE, CONFIG_APPKEY_GET,
+    CONFIG_APPKEY_STATUS, CONFIG_APPKEY_UPDATE,
 };
 use crate::foundation::configuration::beacon::{
     BeaconMessage, CONFIG_BEACON_GET, CONFIG_BEACON_SET,
@@ -11,6 +12,28 @@ use crate::foundation::configuration::composition_data::{
 use crate::foundation::configuration::default_ttl::{
     DefaultTTLMessage, CONFIG_DEFAULT_TTL_GET, CONFIG_DEFAULT_TTL_SET,
 };
+use crate::foundation::configuration::friend::{
+    FriendMessage, CONFIG_FRIEND_GET, CONFIG_FRIEND_SET, CONFIG_FRIEND_STATUS,
+};
+use crate::foundation::configuration::gatt_proxy::{
+    GattProxyMessage, CONFIG_GATT_PROXY_GET, CONFIG_GATT_PROXY_SET, CONFIG_GATT_PROXY_STATUS,
+};
+use crate::foundation::configuration::heartbeat_publication::{
+    HeartbeatPublicationMessage, CONFIG_HEARTBEAT_PUBLICATION_GET,
+    CONFIG_HEARTBEAT_PUBLICATION_SET, CONFIG_HEARTBEAT_PUBLICATION_STATUS,
+};
+use crate::foundation::configuration::heartbeat_subscription::{
+    HeartbeatSubscriptionMessage, CONFIG_HEARTBEAT_SUBSCRIPTION_GET,
+    CONFIG_HEARTBEAT_SUBSCRIPTION_SET, CONFIG_HEARTBEAT_SUBSCRIPTION_STATUS,
+};
+use crate::foundation::configuration::key_refresh_phase::{
+    KeyRefreshPhaseMessage, CONFIG_KEY_REFRESH_PHASE_GET, CONFIG_KEY_REFRESH_PHASE_SET,
+    CONFIG_KEY_REFRESH_PHASE_STATUS,
+};
+use crate::foundation::configuration::low_power_node_poll_timeout::{
+    LowPowerNodePollTimeoutMessage, CONFIG_LOW_POWER_NODE_POLLTIMEOUT_GET,
+    CONFIG_LOW_POWER_NODE_POLLTIMEOUT_STATUS,
+};
 use crate::foundation::configuration::model_app::{
     ModelAppMessage, CONFIG_MODEL_APP_BIND, CONFIG_MODEL_APP_STATUS, CONFIG_MODEL_APP_UNBIND,
 };
@@ -28,6 +51,18 @@ use crate::foundation::configuration::model_subscription::{
     CONFIG_VENDOR_MODEL_SUBSCRIPTION_GET,
 };
 
+use crate::foundation::configuration::net_key::{
+    NetKeyMessage, CONFIG_NETKEY_ADD, CONFIG_NETKEY_DELETE, CONFIG_NETKEY_GET, CONFIG_NETKEY_LIST,
+    CONFIG_NETKEY_STATUS, CONFIG_NETKEY_UPDATE,
+};
+use crate::foundation::configuration::network_transmit::{
+    NetworkTransmitMessage, CONFIG_NETWORK_TRANSMIT_GET, CONFIG_NETWORK_TRANSMIT_SET,
+    CONFIG_NETWORK_TRANSMIT_STATUS,
+};
+use crate::foundation::configuration::node_identity::{
+    NodeIdentityMessage, CONFIG_NODE_IDENTITY_GET, CONFIG_NODE_IDENTITY_SET,
+    CONFIG_NODE_IDENTITY_STATUS,
+};
 use crate::foundation::configuration::node_reset::{
     NodeResetMessage, CONFIG_NODE_RESET, CONFIG_NODE_RESET_STATUS,
 };
@@ -50,14 +85,30 @@ pub mod beacon;
 pub mod composition_data;
 /// Default TTL message.
 pub mod default_ttl;
+/// Friend messages.
+pub mod friend;
+/// GATT proxy messages.
+pub mod gatt_proxy;
+/// Heartbeat publication messages.
+pub mod heartbeat_publication;
+/// Heartbeat subscription messages.
+pub mod heartbeat_subscription;
+/// Key refresh phase messages.
+pub mod key_refresh_phase;
+/// Low Power node PollTimeout messages.
+pub mod low_power_node_poll_timeout;
 /// Model app message.
 pub mod model_app;
 /// Model publication messages.
 pub mod model_publication;
 /// Model subscription messages.
 pub mod model_subscription;
+/// Network key messages.
+pub mod net_key;
 /// Network transmit messages.
 pub mod network_transmit;
+/// Node identity messages.
+pub mod node_identity;
 /// Node reset messages.
 pub mod node_reset;
 /// Relay messages.
@@ -82,6 +133,10 @@ pub enum ConfigurationMessage {
     CompositionData(CompositionDataMessage),
     /// App key message
     AppKey(AppKeyMessage),
+    /// Net key message.
+    NetKey(NetKeyMessage),
+    /// Key refresh phase message.
+    KeyRefreshPhase(KeyRefreshPhaseMessage),
     /// Model app message.
     ModelApp(ModelAppMessage),
     /// Model publication message.
@@ -90,6 +145,20 @@ pub enum ConfigurationMessage {
     ModelSubscription(ModelSubscriptionMessage),
     /// Relay message.
     Relay(RelayMessage),
+    /// Network transmit message.
+    NetworkTransmit(NetworkTransmitMessage),
+    /// GATT proxy message.
+    GattProxy(GattProxyMessage),
+    /// Node identity message.
+    NodeIdentity(NodeIdentityMessage),
+    /// Friend message.
+    Friend(FriendMessage),
+    /// Low Power node PollTimeout message.
+    LowPowerNodePollTimeout(LowPowerNodePollTimeoutMessage),
+    /// Heartbeat publication message.
+    HeartbeatPublication(HeartbeatPublicationMessage),
+    /// Heartbeat subscription message.
+    HeartbeatSubscription(HeartbeatSubscriptionMessage),
 }
 
 impl Message for ConfigurationMessage {
@@ -100,10 +169,19 @@ impl Message for ConfigurationMessage {
             ConfigurationMessage::NodeReset(inner) => inner.opcode(),
             ConfigurationMessage::CompositionData(inner) => inner.opcode(),
             ConfigurationMessage::AppKey(inner) => inner.opcode(),
+            ConfigurationMessage::NetKey(inner) => inner.opcode(),
+            ConfigurationMessage::KeyRefreshPhase(inner) => inner.opcode(),
             ConfigurationMessage::ModelApp(inner) => inner.opcode(),
             ConfigurationMessage::ModelPublication(inner) => inner.opcode(),
             ConfigurationMessage::ModelSubscription(inner) => inner.opcode(),
             ConfigurationMessage::Relay(inner) => inner.opcode(),
+            ConfigurationMessage::NetworkTransmit(inner) => inner.opcode(),
+            ConfigurationMessage::GattProxy(inner) => inner.opcode(),
+            ConfigurationMessage::NodeIdentity(inner) => inner.opcode(),
+            ConfigurationMessage::Friend(inner) => inner.opcode(),
+            ConfigurationMessage::LowPowerNodePollTimeout(inner) => inner.opcode(),
+            ConfigurationMessage::HeartbeatPublication(inner) => inner.opcode(),
+            ConfigurationMessage::HeartbeatSubscription(inner) => inner.opcode(),
         }
     }
 
@@ -117,10 +195,19 @@ impl Message for ConfigurationMessage {
             ConfigurationMessage::NodeReset(inner) => inner.emit_parameters(xmit),
             ConfigurationMessage::CompositionData(inner) => inner.emit_parameters(xmit),
             ConfigurationMessage::AppKey(inner) => inner.emit_parameters(xmit),
+            ConfigurationMessage::NetKey(inner) => inner.emit_parameters(xmit),
+            ConfigurationMessage::KeyRefreshPhase(inner) => inner.emit_parameters(xmit),
             ConfigurationMessage::ModelApp(inner) => inner.emit_parameters(xmit),
             ConfigurationMessage::ModelPublication(inner) => inner.emit_parameters(xmit),
             ConfigurationMessage::ModelSubscription(inner) => inner.emit_parameters(xmit),
             ConfigurationMessage::Relay(inner) => inner.emit_parameters(xmit),
+            ConfigurationMessage::NetworkTransmit(inner) => inner.emit_parameters(xmit),
+            ConfigurationMessage::GattProxy(inner) => inner.emit_parameters(xmit),
+            ConfigurationMessage::NodeIdentity(inner) => inner.emit_parameters(xmit),
+            ConfigurationMessage::Friend(inner) => inner.emit_parameters(xmit),
+            ConfigurationMessage::LowPowerNodePollTimeout(inner) => inner.emit_parameters(xmit),
+            ConfigurationMessage::HeartbeatPublication(inner) => inner.emit_parameters(xmit),
+            ConfigurationMessage::HeartbeatSubscription(inner) => inner.emit_parameters(xmit),
         }
     }
 }
@@ -165,6 +252,29 @@ impl Model for ConfigurationServer {
             CONFIG_APPKEY_GET => Ok(Some(ConfigurationMessage::AppKey(
                 AppKeyMessage::parse_get(parameters)?,
             ))),
+            CONFIG_APPKEY_UPDATE => Ok(Some(ConfigurationMessage::AppKey(
+                AppKeyMessage::parse_update(parameters)?,
+            ))),
+            // Net Key
+            CONFIG_NETKEY_ADD => Ok(Some(ConfigurationMessage::NetKey(
+                NetKeyMessage::parse_add(parameters)?,
+            ))),
+            CONFIG_NETKEY_DELETE => Ok(Some(ConfigurationMessage::NetKey(
+                NetKeyMessage::parse_delete(parameters)?,
+            ))),
+            CONFIG_NETKEY_GET => Ok(Some(ConfigurationMessage::NetKey(
+                NetKeyMessage::parse_get(parameters)?,
+            ))),
+            CONFIG_NETKEY_UPDATE => Ok(Some(ConfigurationMessage::NetKey(
+                NetKeyMessage::parse_update(parameters)?,
+            ))),
+            // Key Refresh Phase
+            CONFIG_KEY_REFRESH_PHASE_GET => Ok(Some(ConfigurationMessage::KeyRefreshPhase(
+                KeyRefreshPhaseMessage::parse_get(parameters)?,
+            ))),
+            CONFIG_KEY_REFRESH_PHASE_SET => Ok(Some(ConfigurationMessage::KeyRefreshPhase(
+                KeyRefreshPhaseMessage::parse_set(parameters)?,
+            ))),
             // Model App
             CONFIG_MODEL_APP_BIND => Ok(Some(ConfigurationMessage::ModelApp(
                 ModelAppMessage::parse_bind(parameters)?,
@@ -232,6 +342,61 @@ impl Model for ConfigurationServer {
             CONFIG_RELAY_SET => Ok(Some(ConfigurationMessage::Relay(RelayMessage::parse_set(
                 parameters,
             )?))),
+            // Network transmit
+            CONFIG_NETWORK_TRANSMIT_GET => Ok(Some(ConfigurationMessage::NetworkTransmit(
+                NetworkTransmitMessage::parse_get(parameters)?,
+            ))),
+            CONFIG_NETWORK_TRANSMIT_SET => Ok(Some(ConfigurationMessage::NetworkTransmit(
+                NetworkTransmitMessage::parse_set(parameters)?,
+            ))),
+            // GATT proxy
+            CONFIG_GATT_PROXY_GET => Ok(Some(ConfigurationMessage::GattProxy(
+                GattProxyMessage::parse_get(parameters)?,
+            ))),
+            CONFIG_GATT_PROXY_SET => Ok(Some(ConfigurationMessage::GattProxy(
+                GattProxyMessage::parse_set(parameters)?,
+            ))),
+            // Node identity
+            CONFIG_NODE_IDENTITY_GET => Ok(Some(ConfigurationMessage::NodeIdentity(
+                NodeIdentityMessage::parse_get(parameters)?,
+            ))),
+            CONFIG_NODE_IDENTITY_SET => Ok(Some(ConfigurationMessage::NodeIdentity(
+                NodeIdentityMessage::parse_set(parameters)?,
+            ))),
+            // Friend
+            CONFIG_FRIEND_GET => Ok(Some(ConfigurationMessage::Friend(
+                FriendMessage::parse_get(parameters)?,
+            ))),
+            CONFIG_FRIEND_SET => Ok(Some(ConfigurationMessage::Friend(
+                FriendMessage::parse_set(parameters)?,
+            ))),
+            // Low Power node PollTimeout
+            CONFIG_LOW_POWER_NODE_POLLTIMEOUT_GET => {
+                Ok(Some(ConfigurationMessage::LowPowerNodePollTimeout(
+                    LowPowerNodePollTimeoutMessage::parse_get(parameters)?,
+                )))
+            }
+            // Heartbeat
+            CONFIG_HEARTBEAT_PUBLICATION_GET => {
+                Ok(Some(ConfigurationMessage::HeartbeatPublication(
+                    HeartbeatPublicationMessage::parse_get(parameters)?,
+                )))
+            }
+            CONFIG_HEARTBEAT_PUBLICATION_SET => {
+                Ok(Some(ConfigurationMessage::HeartbeatPublication(
+                    HeartbeatPublicationMessage::parse_set(parameters)?,
+                )))
+            }
+            CONFIG_HEARTBEAT_SUBSCRIPTION_GET => {
+                Ok(Some(ConfigurationMessage::HeartbeatSubscription(
+                    HeartbeatSubscriptionMessage::parse_get(parameters)?,
+                )))
+            }
+            CONFIG_HEARTBEAT_SUBSCRIPTION_SET => {
+                Ok(Some(ConfigurationMessage::HeartbeatSubscription(
+                    HeartbeatSubscriptionMessage::parse_set(parameters)?,
+                )))
+            }
             _ => Ok(None),
         }
     }
@@ -255,6 +420,15 @@ impl Model for ConfigurationClient {
             CONFIG_APPKEY_STATUS => Ok(Some(ConfigurationMessage::AppKey(
                 AppKeyMessage::parse_status(parameters)?,
             ))),
+            CONFIG_NETKEY_LIST => Ok(Some(ConfigurationMessage::NetKey(
+                NetKeyMessage::parse_list(parameters)?,
+            ))),
+            CONFIG_NETKEY_STATUS => Ok(Some(ConfigurationMessage::NetKey(
+                NetKeyMessage::parse_status(parameters)?,
+            ))),
+            CONFIG_KEY_REFRESH_PHASE_STATUS => Ok(Some(ConfigurationMessage::KeyRefreshPhase(
+                KeyRefreshPhaseMessage::parse_status(parameters)?,
+            ))),
             CONFIG_MODEL_APP_STATUS => Ok(Some(ConfigurationMessage::ModelApp(
                 ModelAppMessage::parse_status(parameters)?,
             ))),
@@ -267,6 +441,33 @@ impl Model for ConfigurationClient {
             CONFIG_RELAY_STATUS => Ok(Some(ConfigurationMessage::Relay(
                 RelayMessage::parse_status(parameters)?,
             ))),
+            CONFIG_NETWORK_TRANSMIT_STATUS => Ok(Some(ConfigurationMessage::NetworkTransmit(
+                NetworkTransmitMessage::parse_status(parameters)?,
+            ))),
+            CONFIG_GATT_PROXY_STATUS => Ok(Some(ConfigurationMessage::GattProxy(
+                GattProxyMessage::parse_status(parameters)?,
+            ))),
+            CONFIG_NODE_IDENTITY_STATUS => Ok(Some(ConfigurationMessage::NodeIdentity(
+                NodeIdentityMessage::parse_status(parameters)?,
+            ))),
+            CONFIG_FRIEND_STATUS => Ok(Some(ConfigurationMessage::Friend(
+                FriendMessage::parse_status(parameters)?,
+            ))),
+            CONFIG_LOW_POWER_NODE_POLLTIMEOUT_STATUS => {
+                Ok(Some(ConfigurationMessage::LowPowerNodePollTimeout(
+                    LowPowerNodePollTimeoutMessage::parse_status(parameters)?,
+                )))
+            }
+            CONFIG_HEARTBEAT_PUBLICATION_STATUS => {
+                Ok(Some(ConfigurationMessage::HeartbeatPublication(
+                    HeartbeatPublicationMessage::parse_status(parameters)?,
+                )))
+            }
+            CONFIG_HEARTBEAT_SUBSCRIPTION_STATUS => {
+                Ok(Some(ConfigurationMessage::HeartbeatSubscription(
+                    HeartbeatSubscriptionMessage::parse_status(parameters)?,
+                )))
+            }
             _ => Ok(None),
         }
     }
@@ -295,9 +496,7 @@ impl KeyIndex {
 
     fn parse_one(parameters: &[u8]) -> Result<Self, ParseError> {
         if parameters.len() >= 2 {
-            let byte1 = parameters[0];
-            let byte2 = parameters[1] & 0b11110000 >> 4;
-            let val = u16::from_be_bytes([byte2, byte1]);
+            let val = u16::from_le_bytes([parameters[0], parameters[1] & 0b00001111]);
             Ok(Self(val))
         } else {
             Err(ParseError::InvalidLength)
@@ -308,26 +507,21 @@ impl KeyIndex {
         index: &KeyIndex,
         xmit: &mut Vec<u8, N>,
     ) -> Result<(), InsufficientBuffer> {
-        let bytes = index.0.to_be_bytes();
-        let byte1 = bytes[1];
-        let byte2 = bytes[0] << 4;
-        xmit.push(byte1).map_err(|_| InsufficientBuffer)?;
-        xmit.push(byte2).map_err(|_| InsufficientBuffer)?;
+        let bytes = index.0.to_le_bytes();
+        xmit.push(bytes[0]).map_err(|_| InsufficientBuffer)?;
+        xmit.push(bytes[1] & 0b00001111)
+            .map_err(|_| InsufficientBuffer)?;
         Ok(())
     }
 
+    /// Two key indexes are packed into 3 octets, little-endian, with the
+    /// first index occupying the 12 least-significant bits.
     fn parse_two(parameters: &[u8]) -> Result<(Self, Self), ParseError> {
         if parameters.len() >= 3 {
-            let byte1 = parameters[0];
-            let byte2 = (parameters[1] & 0b11110000) >> 4;
-
-            let index1 = u16::from_be_bytes([byte1, byte2]);
-
-            let byte1 = parameters[1] & 0b00001111;
-            let byte2 = parameters[2];
-
-            let index2 = u16::from_be_bytes([byte1, byte2]);
-            Ok((Self(index2), Self(index1)))
+            let packed = u32::from_le_bytes([parameters[0], parameters[1], parameters[2], 0]);
+            let index1 = (packed & 0x0FFF) as u16;
+            let index2 = ((packed >> 12) & 0x0FFF) as u16;
+            Ok((Self(index1), Self(index2)))
         } else {
             Err(ParseError::InvalidLength)
         }
@@ -337,19 +531,9 @@ impl KeyIndex {
         indexes: (&KeyIndex, &KeyIndex),
         xmit: &mut Vec<u8, N>,
     ) -> Result<(), InsufficientBuffer> {
-        let bytes = indexes.1 .0.to_be_bytes();
-        let byte1 = bytes[0];
-        xmit.push(byte1).map_err(|_| InsufficientBuffer)?;
-
-        let byte2 = bytes[1] << 4;
-        let bytes = indexes.0 .0.to_be_bytes();
-        let byte1 = byte2 | bytes[0];
-
-        xmit.push(byte1).map_err(|_| InsufficientBuffer)?;
-
-        let byte2 = bytes[1];
-        xmit.push(byte2).map_err(|_| InsufficientBuffer)?;
-
+        let packed = (indexes.0 .0 as u32 & 0x0FFF) | ((indexes.1 .0 as u32 & 0x0FFF) << 12);
+        xmit.extend_from_slice(&packed.to_le_bytes()[0..3])
+            .map_err(|_| InsufficientBuffer)?;
         Ok(())
     }
 }
@@ -446,3 +630,26 @@ impl NetKeyAppKeyIndexesPair {
 
 // ------------------------------------------------------------------------
 // ------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_index_packing() {
+        let pair = NetKeyAppKeyIndexesPair::parse(&[0x56, 0x34, 0x12]).unwrap();
+        assert_eq!(NetKeyIndex::new(0x456), pair.net_key());
+        assert_eq!(AppKeyIndex::new(0x123), pair.app_key());
+
+        let mut xmit: Vec<u8, 3> = Vec::new();
+        pair.emit(&mut xmit).unwrap();
+        assert_eq!(&[0x56, 0x34, 0x12], &*xmit);
+
+        let single = KeyIndex::parse_one(&[0x23, 0x01]).unwrap();
+        assert_eq!(KeyIndex::new(0x123), single);
+
+        let mut xmit: Vec<u8, 2> = Vec::new();
+        KeyIndex::emit_one(&single, &mut xmit).unwrap();
+        assert_eq!(&[0x23, 0x01], &*xmit);
+    }
+}
@@ -0,0 +1,303 @@
+use crate::foundation::configuration::{ConfigurationMessage, KeyIndex, NetKeyIndex};
+use crate::{Message, Status};
+use btmesh_common::crypto::network::NetworkKey;
+use btmesh_common::opcode::Opcode;
+use btmesh_common::{opcode, InsufficientBuffer, ParseError};
+use core::convert::TryInto;
+use heapless::Vec;
+
+opcode!( CONFIG_NETKEY_ADD 0x80, 0x40 );
+opcode!( CONFIG_NETKEY_DELETE 0x80, 0x41 );
+opcode!( CONFIG_NETKEY_GET 0x80, 0x42 );
+opcode!( CONFIG_NETKEY_LIST 0x80, 0x43 );
+opcode!( CONFIG_NETKEY_STATUS 0x80, 0x44 );
+opcode!( CONFIG_NETKEY_UPDATE 0x80, 0x45 );
+
+/// Network key message.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug)]
+pub enum NetKeyMessage {
+    /// NetKey Add message.
+    Add(NetKeyAddMessage),
+    /// NetKey Delete message.
+    Delete(NetKeyDeleteMessage),
+    /// NetKey Get message.
+    Get,
+    /// NetKey List message.
+    List(NetKeyListMessage),
+    /// NetKey Status message.
+    Status(NetKeyStatusMessage),
+    /// NetKey Update message.
+    Update(NetKeyUpdateMessage),
+}
+
+impl From<NetKeyMessage> for ConfigurationMessage {
+    fn from(inner: NetKeyMessage) -> Self {
+        Self::NetKey(inner)
+    }
+}
+
+impl NetKeyMessage {
+    /// Parses byte array into NetKey Add message.
+    pub fn parse_add(parameters: &[u8]) -> Result<Self, ParseError> {
+        let (net_key_index, net_key) = Self::parse_index_and_key(parameters)?;
+        Ok(Self::Add(NetKeyAddMessage {
+            net_key_index,
+            net_key,
+        }))
+    }
+
+    /// Parses byte array into NetKey Update message.
+    pub fn parse_update(parameters: &[u8]) -> Result<Self, ParseError> {
+        let (net_key_index, net_key) = Self::parse_index_and_key(parameters)?;
+        Ok(Self::Update(NetKeyUpdateMessage {
+            net_key_index,
+            net_key,
+        }))
+    }
+
+    /// Parses byte array into NetKey Delete message.
+    pub fn parse_delete(parameters: &[u8]) -> Result<Self, ParseError> {
+        if parameters.len() == 2 {
+            let net_key_index = NetKeyIndex(KeyIndex::parse_one(parameters)?);
+            Ok(Self::Delete(NetKeyDeleteMessage { net_key_index }))
+        } else {
+            Err(ParseError::InvalidLength)
+        }
+    }
+
+    /// Parses byte array into NetKey Get message.
+    pub fn parse_get(parameters: &[u8]) -> Result<Self, ParseError> {
+        if parameters.is_empty() {
+            Ok(Self::Get)
+        } else {
+            Err(ParseError::InvalidLength)
+        }
+    }
+
+    /// Parses byte array into NetKey List message.
+    pub fn parse_list(parameters: &[u8]) -> Result<Self, ParseError> {
+        Ok(Self::List(NetKeyListMessage::parse(parameters)?))
+    }
+
+    /// Parses byte array into NetKey Status message.
+    pub fn parse_status(parameters: &[u8]) -> Result<Self, ParseError> {
+        Ok(Self::Status(NetKeyStatusMessage::parse(parameters)?))
+    }
+
+    fn parse_index_and_key(parameters: &[u8]) -> Result<(NetKeyIndex, NetworkKey), ParseError> {
+        if parameters.len() == 18 {
+            let net_key_index = NetKeyIndex(KeyIndex::parse_one(&parameters[0..=1])?);
+            let net_key = NetworkKey::new(
+                parameters[2..]
+                    .try_into()
+                    .map_err(|_| ParseError::InvalidLength)?,
+            )?;
+            Ok((net_key_index, net_key))
+        } else {
+            Err(ParseError::InvalidLength)
+        }
+    }
+}
+
+impl Message for NetKeyMessage {
+    fn opcode(&self) -> Opcode {
+        match self {
+            Self::Add(_) => CONFIG_NETKEY_ADD,
+            Self::Delete(_) => CONFIG_NETKEY_DELETE,
+            Self::Get => CONFIG_NETKEY_GET,
+            Self::List(_) => CONFIG_NETKEY_LIST,
+            Self::Status(_) => CONFIG_NETKEY_STATUS,
+            Self::Update(_) => CONFIG_NETKEY_UPDATE,
+        }
+    }
+
+    fn emit_parameters<const N: usize>(
+        &self,
+        xmit: &mut Vec<u8, N>,
+    ) -> Result<(), InsufficientBuffer> {
+        match self {
+            NetKeyMessage::Add(inner) => inner.emit_parameters(xmit),
+            NetKeyMessage::Delete(inner) => inner.emit_parameters(xmit),
+            NetKeyMessage::Get => Ok(()),
+            NetKeyMessage::List(inner) => inner.emit_parameters(xmit),
+            NetKeyMessage::Status(inner) => inner.emit_parameters(xmit),
+            NetKeyMessage::Update(inner) => inner.emit_parameters(xmit),
+        }
+    }
+}
+
+/// NetKey Add is an acknowledged message used to add a NetKey to a NetKey List on a node.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug)]
+pub struct NetKeyAddMessage {
+    /// Index of the NetKey.
+    pub net_key_index: NetKeyIndex,
+    /// NetKey value.
+    pub net_key: NetworkKey,
+}
+
+impl NetKeyAddMessage {
+    fn emit_parameters<const N: usize>(
+        &self,
+        xmit: &mut Vec<u8, N>,
+    ) -> Result<(), InsufficientBuffer> {
+        self.net_key_index.emit(xmit)?;
+        xmit.extend_from_slice(&self.net_key.to_bytes())
+            .map_err(|_| InsufficientBuffer)?;
+        Ok(())
+    }
+
+    /// Returns network key index.
+    pub fn net_key_index(&self) -> NetKeyIndex {
+        self.net_key_index
+    }
+
+    /// Returns network key.
+    pub fn net_key(&self) -> NetworkKey {
+        self.net_key
+    }
+}
+
+/// NetKey Update is an acknowledged message used to update a NetKey on a node.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug)]
+pub struct NetKeyUpdateMessage {
+    /// Index of the NetKey.
+    pub net_key_index: NetKeyIndex,
+    /// NetKey value.
+    pub net_key: NetworkKey,
+}
+
+impl NetKeyUpdateMessage {
+    fn emit_parameters<const N: usize>(
+        &self,
+        xmit: &mut Vec<u8, N>,
+    ) -> Result<(), InsufficientBuffer> {
+        self.net_key_index.emit(xmit)?;
+        xmit.extend_from_slice(&self.net_key.to_bytes())
+            .map_err(|_| InsufficientBuffer)?;
+        Ok(())
+    }
+
+    /// Returns network key index.
+    pub fn net_key_index(&self) -> NetKeyIndex {
+        self.net_key_index
+    }
+
+    /// Returns network key.
+    pub fn net_key(&self) -> NetworkKey {
+        self.net_key
+    }
+}
+
+/// NetKey Delete is an acknowledged message used to delete a NetKey on a NetKey List from a node.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug)]
+pub struct NetKeyDeleteMessage {
+    /// Index of the NetKey.
+    pub net_key_index: NetKeyIndex,
+}
+
+impl NetKeyDeleteMessage {
+    fn emit_parameters<const N: usize>(
+        &self,
+        xmit: &mut Vec<u8, N>,
+    ) -> Result<(), InsufficientBuffer> {
+        self.net_key_index.emit(xmit)
+    }
+
+    /// Returns network key index.
+    pub fn net_key_index(&self) -> NetKeyIndex {
+        self.net_key_index
+    }
+}
+
+/// NetKey List is an unacknowledged message reporting all NetKeys known to the node.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug)]
+pub struct NetKeyListMessage {
+    /// Indexes of the NetKeys.
+    pub net_key_indexes: Vec<NetKeyIndex, 10>,
+}
+
+impl NetKeyListMessage {
+    fn emit_parameters<const N: usize>(
+        &self,
+        xmit: &mut Vec<u8, N>,
+    ) -> Result<(), InsufficientBuffer> {
+        for chunk in self.net_key_indexes.chunks(2) {
+            if chunk.len() == 2 {
+                KeyIndex::emit_two((&chunk[0].0, &chunk[1].0), xmit)?;
+            } else {
+                KeyIndex::emit_one(&chunk[0].0, xmit)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn parse(parameters: &[u8]) -> Result<Self, ParseError> {
+        let mut net_key_indexes = Vec::new();
+        let mut chunks = parameters.chunks_exact(3);
+        for chunk in &mut chunks {
+            let (first, second) = KeyIndex::parse_two(chunk)?;
+            net_key_indexes
+                .push(NetKeyIndex(first))
+                .map_err(|_| ParseError::InsufficientBuffer)?;
+            net_key_indexes
+                .push(NetKeyIndex(second))
+                .map_err(|_| ParseError::InsufficientBuffer)?;
+        }
+        match chunks.remainder().len() {
+            0 => {}
+            2 => {
+                net_key_indexes
+                    .push(NetKeyIndex(KeyIndex::parse_one(chunks.remainder())?))
+                    .map_err(|_| ParseError::InsufficientBuffer)?;
+            }
+            _ => return Err(ParseError::InvalidLength),
+        }
+        Ok(Self { net_key_indexes })
+    }
+}
+
+/// NetKey Status is an unacknowledged message used to report the status of the operation on the NetKey List.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug)]
+pub struct NetKeyStatusMessage {
+    /// Status Code for the requesting message.
+    pub status: Status,
+    /// Index of the NetKey.
+    pub net_key_index: NetKeyIndex,
+}
+
+impl From<NetKeyStatusMessage> for NetKeyMessage {
+    fn from(inner: NetKeyStatusMessage) -> Self {
+        Self::Status(inner)
+    }
+}
+
+impl NetKeyStatusMessage {
+    fn emit_parameters<const N: usize>(
+        &self,
+        xmit: &mut Vec<u8, N>,
+    ) -> Result<(), InsufficientBuffer> {
+        xmit.push(self.status as u8)
+            .map_err(|_| InsufficientBuffer)?;
+        self.net_key_index.emit(xmit)?;
+        Ok(())
+    }
+
+    fn parse(parameters: &[u8]) -> Result<Self, ParseError> {
+        if parameters.len() == 3 {
+            let status: Status = parameters[0].try_into()?;
+            let net_key_index = NetKeyIndex(KeyIndex::parse_one(&parameters[1..=2])?);
+            Ok(Self {
+                status,
+                net_key_index,
+            })
+        } else {
+            Err(ParseError::InvalidLength)
+        }
+    }
+}
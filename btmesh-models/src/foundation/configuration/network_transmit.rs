@@ -1,3 +1,4 @@
+use crate::foundation::configuration::ConfigurationMessage;
 use crate::Message;
 use btmesh_common::opcode::Opcode;
 use btmesh_common::{opcode, InsufficientBuffer, ParseError};
@@ -8,6 +9,7 @@ opcode!( CONFIG_NETWORK_TRANSMIT_SET 0x80, 0x24);
 opcode!( CONFIG_NETWORK_TRANSMIT_STATUS 0x80, 0x25);
 
 /// The Network Transmit state is a composite state that controls the number and timing of the transmissions of Network PDU originating from a node.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct NetworkTransmitConfig {
@@ -30,10 +32,10 @@ impl Default for NetworkTransmitConfig {
 impl NetworkTransmitConfig {
     /// Parses parameters into Network Transmit.
     pub fn parse(parameters: &[u8]) -> Result<Self, ParseError> {
-        if parameters.len() < 2 {
+        if parameters.len() != 1 {
             Err(ParseError::InvalidLength)
         } else {
-            let network_retransmit_count = parameters[0] & 0b11100000 >> 5;
+            let network_retransmit_count = (parameters[0] & 0b11100000) >> 5;
             let network_retransmit_interval_steps = parameters[0] & 0b00011111;
 
             Ok(Self {
@@ -46,17 +48,23 @@ impl NetworkTransmitConfig {
     /// Emits Network Transmit into array of bytes.
     pub fn emit<const N: usize>(&self, xmit: &mut Vec<u8, N>) -> Result<(), InsufficientBuffer> {
         xmit.push(
-            self.network_retransmit_count & 0b111 << 5
+            (self.network_retransmit_count & 0b111) << 5
                 | self.network_retransmit_interval_steps & 0b11111,
         )
         .map_err(|_| InsufficientBuffer)?;
 
         Ok(())
     }
+
+    /// Returns the interval between transmissions, in milliseconds.
+    pub fn interval_millis(&self) -> u16 {
+        (self.network_retransmit_interval_steps as u16 + 1) * 10
+    }
 }
 
 /// Network Transmit message.
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug)]
 pub enum NetworkTransmitMessage {
     /// Network Transmit Get is an acknowledged message used to get the current Network Transmit state of a node.
     Get,
@@ -66,6 +74,12 @@ pub enum NetworkTransmitMessage {
     Status(NetworkTransmitConfig),
 }
 
+impl From<NetworkTransmitMessage> for ConfigurationMessage {
+    fn from(inner: NetworkTransmitMessage) -> Self {
+        ConfigurationMessage::NetworkTransmit(inner)
+    }
+}
+
 impl Message for NetworkTransmitMessage {
     fn opcode(&self) -> Opcode {
         match self {
@@ -106,6 +120,25 @@ impl NetworkTransmitMessage {
 
     /// Parses parameters into Network Transmit Status message.
     pub fn parse_status(parameters: &[u8]) -> Result<Self, ParseError> {
-        Ok(Self::Set(NetworkTransmitConfig::parse(parameters)?))
+        Ok(Self::Status(NetworkTransmitConfig::parse(parameters)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_emit() {
+        let config = NetworkTransmitConfig::parse(&[0b01000101]).unwrap();
+        assert_eq!(2, config.network_retransmit_count);
+        assert_eq!(5, config.network_retransmit_interval_steps);
+        assert_eq!(60, config.interval_millis());
+
+        let mut xmit: Vec<u8, 1> = Vec::new();
+        config.emit(&mut xmit).unwrap();
+        assert_eq!(&[0b01000101], &*xmit);
+
+        assert!(NetworkTransmitConfig::parse(&[0x00, 0x00]).is_err());
     }
 }
@@ -0,0 +1,175 @@
+use crate::foundation::configuration::{ConfigurationMessage, KeyIndex, NetKeyIndex};
+use crate::{Message, Status};
+use btmesh_common::opcode::Opcode;
+use btmesh_common::{opcode, InsufficientBuffer, ParseError};
+use core::convert::TryInto;
+use heapless::Vec;
+
+opcode!( CONFIG_NODE_IDENTITY_GET 0x80, 0x46 );
+opcode!( CONFIG_NODE_IDENTITY_SET 0x80, 0x47 );
+opcode!( CONFIG_NODE_IDENTITY_STATUS 0x80, 0x48 );
+
+/// The Node Identity state indicates whether a node advertises with Node Identity on a subnet.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum NodeIdentity {
+    /// Node Identity for a subnet is stopped.
+    Stopped = 0x00,
+    /// Node Identity for a subnet is running.
+    Running = 0x01,
+    /// Node Identity is not supported.
+    NotSupported = 0x02,
+}
+
+impl NodeIdentity {
+    /// Parses byte into Node Identity state.
+    pub fn parse(data: u8) -> Result<Self, ParseError> {
+        match data {
+            0x00 => Ok(Self::Stopped),
+            0x01 => Ok(Self::Running),
+            0x02 => Ok(Self::NotSupported),
+            _ => Err(ParseError::InvalidValue),
+        }
+    }
+}
+
+/// Node Identity message.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug)]
+pub enum NodeIdentityMessage {
+    /// Node Identity Get is an acknowledged message used to get the current Node Identity state for a subnet.
+    Get(NetKeyIndex),
+    /// Node Identity Set is an acknowledged message used to set the current Node Identity state for a subnet.
+    Set(NodeIdentitySetMessage),
+    /// Node Identity Status is an unacknowledged message used to report the current Node Identity state for a subnet.
+    Status(NodeIdentityStatusMessage),
+}
+
+impl From<NodeIdentityMessage> for ConfigurationMessage {
+    fn from(inner: NodeIdentityMessage) -> Self {
+        ConfigurationMessage::NodeIdentity(inner)
+    }
+}
+
+impl Message for NodeIdentityMessage {
+    fn opcode(&self) -> Opcode {
+        match self {
+            Self::Get(_) => CONFIG_NODE_IDENTITY_GET,
+            Self::Set(_) => CONFIG_NODE_IDENTITY_SET,
+            Self::Status(_) => CONFIG_NODE_IDENTITY_STATUS,
+        }
+    }
+
+    fn emit_parameters<const N: usize>(
+        &self,
+        xmit: &mut Vec<u8, N>,
+    ) -> Result<(), InsufficientBuffer> {
+        match self {
+            Self::Get(net_key_index) => net_key_index.emit(xmit),
+            Self::Set(inner) => {
+                inner.net_key_index.emit(xmit)?;
+                xmit.push(inner.identity as u8)
+                    .map_err(|_| InsufficientBuffer)
+            }
+            Self::Status(inner) => {
+                xmit.push(inner.status as u8)
+                    .map_err(|_| InsufficientBuffer)?;
+                inner.net_key_index.emit(xmit)?;
+                xmit.push(inner.identity as u8)
+                    .map_err(|_| InsufficientBuffer)
+            }
+        }
+    }
+}
+
+impl NodeIdentityMessage {
+    /// Parses byte array into Node Identity Get message.
+    pub fn parse_get(parameters: &[u8]) -> Result<Self, ParseError> {
+        if parameters.len() == 2 {
+            Ok(Self::Get(NetKeyIndex(KeyIndex::parse_one(parameters)?)))
+        } else {
+            Err(ParseError::InvalidLength)
+        }
+    }
+
+    /// Parses byte array into Node Identity Set message.
+    pub fn parse_set(parameters: &[u8]) -> Result<Self, ParseError> {
+        if parameters.len() != 3 {
+            return Err(ParseError::InvalidLength);
+        }
+        let net_key_index = NetKeyIndex(KeyIndex::parse_one(&parameters[0..=1])?);
+        match NodeIdentity::parse(parameters[2])? {
+            // only a node can tell whether it supports the feature.
+            NodeIdentity::NotSupported => Err(ParseError::InvalidValue),
+            identity => Ok(Self::Set(NodeIdentitySetMessage {
+                net_key_index,
+                identity,
+            })),
+        }
+    }
+
+    /// Parses byte array into Node Identity Status message.
+    pub fn parse_status(parameters: &[u8]) -> Result<Self, ParseError> {
+        if parameters.len() == 4 {
+            Ok(Self::Status(NodeIdentityStatusMessage {
+                status: parameters[0].try_into()?,
+                net_key_index: NetKeyIndex(KeyIndex::parse_one(&parameters[1..=2])?),
+                identity: NodeIdentity::parse(parameters[3])?,
+            }))
+        } else {
+            Err(ParseError::InvalidLength)
+        }
+    }
+}
+
+/// Node Identity Set message.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug)]
+pub struct NodeIdentitySetMessage {
+    /// Index of the NetKey.
+    pub net_key_index: NetKeyIndex,
+    /// New Node Identity state.
+    pub identity: NodeIdentity,
+}
+
+/// Node Identity Status message.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug)]
+pub struct NodeIdentityStatusMessage {
+    /// Status Code for the requesting message.
+    pub status: Status,
+    /// Index of the NetKey.
+    pub net_key_index: NetKeyIndex,
+    /// Node Identity state.
+    pub identity: NodeIdentity,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_set() {
+        assert!(matches!(
+            NodeIdentityMessage::parse_set(&[0x23, 0x01, 0x01]),
+            Ok(NodeIdentityMessage::Set(NodeIdentitySetMessage {
+                identity: NodeIdentity::Running,
+                ..
+            }))
+        ));
+        assert!(NodeIdentityMessage::parse_set(&[0x23, 0x01, 0x02]).is_err());
+        assert!(NodeIdentityMessage::parse_set(&[0x23, 0x01]).is_err());
+    }
+
+    #[test]
+    fn emit_status() {
+        let message = NodeIdentityMessage::Status(NodeIdentityStatusMessage {
+            status: Status::Success,
+            net_key_index: NetKeyIndex::new(0x123),
+            identity: NodeIdentity::Running,
+        });
+        let mut xmit: Vec<u8, 4> = Vec::new();
+        message.emit_parameters(&mut xmit).unwrap();
+        assert_eq!(&[0x00, 0x23, 0x01, 0x01], &*xmit);
+    }
+}
@@ -74,12 +74,12 @@ impl RelayConfig {
 
     /// Parses parameters into Relay configuration.
     pub fn parse(parameters: &[u8]) -> Result<Self, ParseError> {
-        if parameters.len() < 2 {
+        if parameters.len() != 2 {
             Err(ParseError::InvalidLength)
         } else {
             let relay = Relay::parse(parameters[0])?;
-            let relay_retransmit_count = parameters[0] & 0b11100000 >> 5;
-            let relay_retransmit_interval_steps = parameters[0] & 0b00011111;
+            let relay_retransmit_count = (parameters[1] & 0b11100000) >> 5;
+            let relay_retransmit_interval_steps = parameters[1] & 0b00011111;
 
             Ok(Self {
                 relay,
@@ -94,7 +94,7 @@ impl RelayConfig {
         self.relay.emit(xmit)?;
 
         xmit.push(
-            self.relay_retransmit_count & 0b111 << 5
+            (self.relay_retransmit_count & 0b111) << 5
                 | self.relay_retransmit_interval_steps & 0b11111,
         )
         .map_err(|_| InsufficientBuffer)?;
@@ -116,6 +116,11 @@ impl RelayConfig {
     pub fn retransmit_interval_steps(&self) -> u8 {
         self.relay_retransmit_interval_steps
     }
+
+    /// Returns the interval between retransmissions, in milliseconds.
+    pub fn retransmit_interval_millis(&self) -> u16 {
+        (self.relay_retransmit_interval_steps as u16 + 1) * 10
+    }
 }
 
 /// Relay Message.
@@ -176,6 +181,27 @@ impl RelayMessage {
 
     /// Parses parameters into Relay Status message.
     pub fn parse_status(parameters: &[u8]) -> Result<Self, ParseError> {
-        Ok(Self::Set(RelayConfig::parse(parameters)?))
+        Ok(Self::Status(RelayConfig::parse(parameters)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_emit() {
+        let config = RelayConfig::parse(&[0x01, 0b01100011]).unwrap();
+        assert!(matches!(config.relay(), Relay::SupportedEnabled));
+        assert_eq!(3, config.retransmit_count());
+        assert_eq!(3, config.retransmit_interval_steps());
+        assert_eq!(40, config.retransmit_interval_millis());
+
+        let mut xmit: Vec<u8, 2> = Vec::new();
+        config.emit(&mut xmit).unwrap();
+        assert_eq!(&[0x01, 0b01100011], &*xmit);
+
+        assert!(RelayConfig::parse(&[0x03, 0x00]).is_err());
+        assert!(RelayConfig::parse(&[0x01]).is_err());
     }
 }
@@ -1,4 +1,4 @@
-use atomic_polyfill::AtomicBool;
+use atomic_polyfill::{AtomicBool, AtomicU32};
 use btmesh_bearer::{BearerError, GattBearer};
 use btmesh_device::Signal;
 use core::cell::RefCell;
@@ -26,6 +26,7 @@ pub struct SoftdeviceGattBearer {
     connection_channel: RefCell<Option<ConnectionChannel>>,
     server: MeshGattServer,
     connected: AtomicBool,
+    connection_count: AtomicU32,
     outbound: Channel<ThreadModeRawMutex, Vec<u8, 66>, 5>,
     inbound: Channel<ThreadModeRawMutex, Vec<u8, 66>, 5>,
 }
@@ -37,6 +38,7 @@ impl SoftdeviceGattBearer {
             server,
             connection: Signal::new(),
             connected: AtomicBool::new(false),
+            connection_count: AtomicU32::new(0),
             current_connection: RefCell::new(None),
             connection_channel: RefCell::new(None),
             outbound: Channel::new(),
@@ -49,6 +51,7 @@ impl SoftdeviceGattBearer {
         loop {
             let connection = self.connection.wait().await;
             self.current_connection.borrow_mut().replace(connection);
+            self.connection_count.fetch_add(1, Ordering::Relaxed);
 
             let server_fut = async move {
                 gatt_server::run(
@@ -107,6 +110,21 @@ impl GattBearer<66> for SoftdeviceGattBearer {
         RESET_SIGNAL.signal(())
     }
 
+    fn connection_id(&self) -> Option<u32> {
+        if self.current_connection.borrow().is_some() {
+            Some(self.connection_count.load(Ordering::Relaxed))
+        } else {
+            None
+        }
+    }
+
+    fn mtu(&self) -> usize {
+        match &*self.current_connection.borrow() {
+            Some(connection) => connection.att_mtu().min(ATT_MTU as u16) as usize - 3,
+            None => 20,
+        }
+    }
+
     type RunFuture<'m> = impl Future<Output=Result<(), BearerError>> + 'm
     where
     Self: 'm;
@@ -1,8 +1,10 @@
 #![cfg_attr(not(test), no_std)]
 #![allow(dead_code)]
 
+use crate::provisioned::beacon::SecureNetworkBeacon;
 use crate::provisioned::network::NetworkPDU;
 use crate::provisioning::ProvisioningPDU;
+use btmesh_common::Uuid;
 
 pub const PB_ADV: u8 = 0x29;
 pub const MESH_MESSAGE: u8 = 0x2A;
@@ -15,4 +17,9 @@ pub mod provisioning;
 pub enum PDU {
     Provisioning(ProvisioningPDU),
     Network(NetworkPDU),
+    /// Network PDU carrying a proxy configuration message, only exchanged over GATT.
+    ProxyConfiguration(NetworkPDU),
+    SecureNetworkBeacon(SecureNetworkBeacon),
+    /// Beacon of an unprovisioned device, carrying its UUID, only of interest to provisioners.
+    UnprovisionedBeacon(Uuid),
 }
@@ -0,0 +1,136 @@
+use btmesh_common::crypto::network::BeaconKey;
+use btmesh_common::{
+    InsufficientBuffer, IvIndex, IvUpdateFlag, KeyRefreshFlag, NetworkId, ParseError,
+};
+use heapless::Vec;
+
+pub const UNPROVISIONED_DEVICE_BEACON: u8 = 0x00;
+pub const SECURE_NETWORK_BEACON: u8 = 0x01;
+
+/// Secure network beacon, advertising the IV Index and Key Refresh state of a subnet.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct SecureNetworkBeacon {
+    pub key_refresh_flag: KeyRefreshFlag,
+    pub iv_update_flag: IvUpdateFlag,
+    pub network_id: NetworkId,
+    pub iv_index: IvIndex,
+    pub authentication_value: [u8; 8],
+}
+
+impl SecureNetworkBeacon {
+    /// Construct a beacon authenticated with the subnet's beacon key.
+    pub fn new(
+        key_refresh_flag: KeyRefreshFlag,
+        iv_update_flag: IvUpdateFlag,
+        network_id: NetworkId,
+        iv_index: IvIndex,
+        beacon_key: &BeaconKey,
+    ) -> Result<Self, ParseError> {
+        let mut beacon = Self {
+            key_refresh_flag,
+            iv_update_flag,
+            network_id,
+            iv_index,
+            authentication_value: [0; 8],
+        };
+        beacon.authentication_value =
+            beacon_key.authentication_value(beacon.flags(), &network_id, iv_index)?;
+        Ok(beacon)
+    }
+
+    /// Whether the beacon was authenticated with the given beacon key.
+    pub fn authenticate(&self, beacon_key: &BeaconKey) -> bool {
+        matches!(
+            beacon_key.authentication_value(self.flags(), &self.network_id, self.iv_index),
+            Ok(authentication_value) if authentication_value == self.authentication_value
+        )
+    }
+
+    fn flags(&self) -> u8 {
+        let mut flags = 0;
+        self.key_refresh_flag.emit(&mut flags);
+        self.iv_update_flag.emit(&mut flags);
+        flags
+    }
+
+    /// Parse a secure network beacon, including its beacon type.
+    pub fn parse(data: &[u8]) -> Result<Self, ParseError> {
+        if data.len() != 22 {
+            return Err(ParseError::InvalidLength);
+        }
+        if data[0] != SECURE_NETWORK_BEACON {
+            return Err(ParseError::InvalidValue);
+        }
+
+        let flags = data[1];
+        let network_id = NetworkId::new(data[2..10].try_into()?);
+        let iv_index = IvIndex::parse(&data[10..14])?;
+        let authentication_value = data[14..22].try_into()?;
+
+        Ok(Self {
+            key_refresh_flag: KeyRefreshFlag::parse(flags & 0b00000001),
+            iv_update_flag: IvUpdateFlag::parse(flags & 0b00000010),
+            network_id,
+            iv_index,
+            authentication_value,
+        })
+    }
+
+    pub fn emit<const N: usize>(&self, xmit: &mut Vec<u8, N>) -> Result<(), InsufficientBuffer> {
+        xmit.push(SECURE_NETWORK_BEACON)
+            .map_err(|_| InsufficientBuffer)?;
+        xmit.push(self.flags()).map_err(|_| InsufficientBuffer)?;
+        xmit.extend_from_slice(&self.network_id)?;
+        xmit.extend_from_slice(&self.iv_index.to_be_bytes())?;
+        xmit.extend_from_slice(&self.authentication_value)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_emit() {
+        // 8.4.1 Secure Network beacon
+        let data = [
+            0x01, 0x00, 0x3e, 0xca, 0xff, 0x67, 0x2f, 0x67, 0x33, 0x70, 0x12, 0x34, 0x56, 0x78,
+            0x8e, 0xa2, 0x61, 0x58, 0x2f, 0x36, 0x4f, 0x6f,
+        ];
+        let beacon_key = BeaconKey::new([
+            0x54, 0x23, 0xd9, 0x67, 0xda, 0x63, 0x9a, 0x99, 0xcb, 0x02, 0x23, 0x1a, 0x83, 0xf7,
+            0xd2, 0x54,
+        ]);
+
+        let beacon = SecureNetworkBeacon::parse(&data).unwrap();
+        assert_eq!(KeyRefreshFlag(false), beacon.key_refresh_flag);
+        assert_eq!(IvUpdateFlag::Normal, beacon.iv_update_flag);
+        assert_eq!(IvIndex::new(0x12345678), beacon.iv_index);
+        assert!(beacon.authenticate(&beacon_key));
+        assert!(!beacon.authenticate(&BeaconKey::new([0; 16])));
+
+        assert_eq!(
+            beacon,
+            SecureNetworkBeacon::new(
+                KeyRefreshFlag(false),
+                IvUpdateFlag::Normal,
+                beacon.network_id,
+                beacon.iv_index,
+                &beacon_key
+            )
+            .unwrap()
+        );
+
+        let mut xmit: Vec<u8, 22> = Vec::new();
+        beacon.emit(&mut xmit).unwrap();
+        assert_eq!(&data, &*xmit);
+
+        let tampered = SecureNetworkBeacon {
+            iv_update_flag: IvUpdateFlag::InProgress,
+            ..beacon
+        };
+        assert!(!tampered.authenticate(&beacon_key));
+    }
+}
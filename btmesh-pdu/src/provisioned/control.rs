@@ -1,7 +1,7 @@
 use crate::provisioned::lower::BlockAck;
 use crate::provisioned::upper::control::ControlOpcode;
 use crate::provisioned::{Message, System};
-use btmesh_common::{InsufficientBuffer, ParseError};
+use btmesh_common::{Features, InsufficientBuffer, ParseError, Ttl};
 use heapless::Vec;
 
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -31,6 +31,10 @@ impl<S: System> ControlMessage<S> {
     pub fn parameters(&self) -> &[u8] {
         &self.parameters
     }
+
+    pub fn meta(&self) -> &S::ControlMetadata {
+        &self.meta
+    }
 }
 
 impl<S: System> From<ControlMessage<S>> for Message<S> {
@@ -50,3 +54,83 @@ impl<S: System> TryFrom<&ControlMessage<S>> for BlockAck {
         }
     }
 }
+
+/// Heartbeat transport control message, used to monitor nodes
+/// and determine how far away they are.
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Heartbeat {
+    init_ttl: Ttl,
+    features: Features,
+}
+
+impl Heartbeat {
+    pub fn new(init_ttl: Ttl, features: Features) -> Self {
+        Self { init_ttl, features }
+    }
+
+    pub fn parse(parameters: &[u8]) -> Result<Self, ParseError> {
+        if parameters.len() != 3 {
+            Err(ParseError::InvalidLength)
+        } else {
+            let init_ttl = Ttl::new(parameters[0] & 0b01111111);
+            let features = Features::from_bits(u16::from_be_bytes([parameters[1], parameters[2]]));
+            Ok(Self { init_ttl, features })
+        }
+    }
+
+    pub fn emit<const N: usize>(&self, xmit: &mut Vec<u8, N>) -> Result<(), InsufficientBuffer> {
+        xmit.push(self.init_ttl.value() & 0b01111111)
+            .map_err(|_| InsufficientBuffer)?;
+        xmit.extend_from_slice(&self.features.bits().to_be_bytes())
+            .map_err(|_| InsufficientBuffer)?;
+        Ok(())
+    }
+
+    pub fn init_ttl(&self) -> Ttl {
+        self.init_ttl
+    }
+
+    pub fn features(&self) -> Features {
+        self.features
+    }
+
+    /// Number of hops the heartbeat travelled, given the TTL it was received with.
+    pub fn hops(&self, received_ttl: Ttl) -> u8 {
+        self.init_ttl
+            .value()
+            .saturating_sub(received_ttl.value())
+            .saturating_add(1)
+    }
+}
+
+impl<S: System> TryFrom<&ControlMessage<S>> for Heartbeat {
+    type Error = ParseError;
+
+    fn try_from(value: &ControlMessage<S>) -> Result<Self, Self::Error> {
+        if let ControlOpcode::Heartbeat = value.opcode {
+            Ok(Heartbeat::parse(&value.parameters)?)
+        } else {
+            Err(ParseError::InvalidPDUFormat)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heartbeat_round_trip() {
+        let heartbeat = Heartbeat::parse(&[0x85, 0x00, 0x03]).unwrap();
+        assert_eq!(Ttl::new(5), heartbeat.init_ttl());
+        assert!(heartbeat.features().relay);
+        assert!(heartbeat.features().proxy);
+        assert!(!heartbeat.features().low_power);
+        assert_eq!(3, heartbeat.hops(Ttl::new(3)));
+
+        let mut xmit: Vec<u8, 3> = Vec::new();
+        heartbeat.emit(&mut xmit).unwrap();
+        assert_eq!(&[0x05, 0x00, 0x03], &*xmit);
+    }
+}
@@ -0,0 +1,403 @@
+//! Transport control messages establishing and maintaining a friendship
+//! between a Low Power node and a Friend node.
+
+use crate::provisioned::control::ControlMessage;
+use crate::provisioned::upper::control::ControlOpcode;
+use crate::provisioned::System;
+use btmesh_common::address::{Address, UnicastAddress};
+use btmesh_common::{InsufficientBuffer, IvIndex, IvUpdateFlag, KeyRefreshFlag, ParseError};
+use heapless::Vec;
+
+/// Number of addresses fitting into an unsegmented subscription list message.
+pub const SUBSCRIPTION_LIST_ADDRESSES: usize = 5;
+
+/// Requests the next message from the friend queue.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FriendPoll {
+    /// Friend Sequence Number, toggled once a message has been received.
+    pub fsn: bool,
+}
+
+impl FriendPoll {
+    pub fn parse(parameters: &[u8]) -> Result<Self, ParseError> {
+        if parameters.len() != 1 {
+            return Err(ParseError::InvalidLength);
+        }
+        if parameters[0] & 0b11111110 != 0 {
+            return Err(ParseError::InvalidValue);
+        }
+        Ok(Self {
+            fsn: parameters[0] & 0b00000001 != 0,
+        })
+    }
+
+    pub fn emit<const N: usize>(&self, xmit: &mut Vec<u8, N>) -> Result<(), InsufficientBuffer> {
+        xmit.push(self.fsn as u8).map_err(|_| InsufficientBuffer)
+    }
+}
+
+/// Security parameters of the network, along with whether more messages are queued.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FriendUpdate {
+    pub key_refresh_flag: KeyRefreshFlag,
+    pub iv_update_flag: IvUpdateFlag,
+    pub iv_index: IvIndex,
+    /// More Data, set while the friend queue is not empty.
+    pub md: bool,
+}
+
+impl FriendUpdate {
+    pub fn parse(parameters: &[u8]) -> Result<Self, ParseError> {
+        if parameters.len() != 6 {
+            return Err(ParseError::InvalidLength);
+        }
+        Ok(Self {
+            key_refresh_flag: KeyRefreshFlag::parse(parameters[0] & 0b00000001),
+            iv_update_flag: IvUpdateFlag::parse(parameters[0] & 0b00000010),
+            iv_index: IvIndex::parse(&parameters[1..5])?,
+            md: parameters[5] != 0,
+        })
+    }
+
+    pub fn emit<const N: usize>(&self, xmit: &mut Vec<u8, N>) -> Result<(), InsufficientBuffer> {
+        let mut flags = 0;
+        self.key_refresh_flag.emit(&mut flags);
+        self.iv_update_flag.emit(&mut flags);
+        xmit.push(flags).map_err(|_| InsufficientBuffer)?;
+        xmit.extend_from_slice(&self.iv_index.to_be_bytes())?;
+        xmit.push(self.md as u8).map_err(|_| InsufficientBuffer)?;
+        Ok(())
+    }
+}
+
+/// Requirements a Low Power node places upon its future friend.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FriendCriteria {
+    rssi_factor: u8,
+    receive_window_factor: u8,
+    min_queue_size_log: u8,
+}
+
+impl FriendCriteria {
+    /// Construct from the 2-bit factor fields and the 3-bit minimum queue size log.
+    pub fn new(
+        rssi_factor: u8,
+        receive_window_factor: u8,
+        min_queue_size_log: u8,
+    ) -> Result<Self, ParseError> {
+        if rssi_factor > 0b11 || receive_window_factor > 0b11 {
+            return Err(ParseError::InvalidValue);
+        }
+        if min_queue_size_log == 0 || min_queue_size_log > 0b111 {
+            return Err(ParseError::InvalidValue);
+        }
+        Ok(Self {
+            rssi_factor,
+            receive_window_factor,
+            min_queue_size_log,
+        })
+    }
+
+    pub fn parse(data: u8) -> Result<Self, ParseError> {
+        Self::new(
+            (data & 0b01100000) >> 5,
+            (data & 0b00011000) >> 3,
+            data & 0b00000111,
+        )
+    }
+
+    pub fn emit(&self) -> u8 {
+        self.rssi_factor << 5 | self.receive_window_factor << 3 | self.min_queue_size_log
+    }
+
+    /// Weight of the RSSI when computing the offer delay, in tenths.
+    pub fn rssi_factor(&self) -> u32 {
+        10 + 5 * self.rssi_factor as u32
+    }
+
+    /// Weight of the receive window when computing the offer delay, in tenths.
+    pub fn receive_window_factor(&self) -> u32 {
+        10 + 5 * self.receive_window_factor as u32
+    }
+
+    /// Minimum number of messages the friend must be able to queue.
+    pub fn min_queue_size(&self) -> u8 {
+        1 << self.min_queue_size_log
+    }
+}
+
+/// Sent by a Low Power node looking for a friend.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FriendRequest {
+    pub criteria: FriendCriteria,
+    /// Delay between a poll and the friend's response, in milliseconds.
+    pub receive_delay: u8,
+    /// Maximum time between two polls, in units of 100 milliseconds.
+    pub poll_timeout: u32,
+    /// Friend of a previous friendship, to be cleared once established.
+    pub previous_address: Option<UnicastAddress>,
+    pub num_elements: u8,
+    pub lpn_counter: u16,
+}
+
+impl FriendRequest {
+    pub fn parse(parameters: &[u8]) -> Result<Self, ParseError> {
+        if parameters.len() != 10 {
+            return Err(ParseError::InvalidLength);
+        }
+        let receive_delay = parameters[1];
+        let poll_timeout = u32::from_be_bytes([0, parameters[2], parameters[3], parameters[4]]);
+        let num_elements = parameters[7];
+        if receive_delay < 0x0A
+            || !(0x00000A..=0x34BBFF).contains(&poll_timeout)
+            || num_elements == 0
+        {
+            return Err(ParseError::InvalidValue);
+        }
+
+        Ok(Self {
+            criteria: FriendCriteria::parse(parameters[0])?,
+            receive_delay,
+            poll_timeout,
+            previous_address: match Address::parse([parameters[5], parameters[6]]) {
+                Address::Unassigned => None,
+                Address::Unicast(address) => Some(address),
+                _ => return Err(ParseError::InvalidValue),
+            },
+            num_elements,
+            lpn_counter: u16::from_be_bytes([parameters[8], parameters[9]]),
+        })
+    }
+
+    pub fn emit<const N: usize>(&self, xmit: &mut Vec<u8, N>) -> Result<(), InsufficientBuffer> {
+        xmit.push(self.criteria.emit())
+            .map_err(|_| InsufficientBuffer)?;
+        xmit.push(self.receive_delay)
+            .map_err(|_| InsufficientBuffer)?;
+        xmit.extend_from_slice(&self.poll_timeout.to_be_bytes()[1..])?;
+        match self.previous_address {
+            Some(address) => xmit.extend_from_slice(&address.as_bytes())?,
+            None => xmit.extend_from_slice(&[0, 0])?,
+        }
+        xmit.push(self.num_elements)
+            .map_err(|_| InsufficientBuffer)?;
+        xmit.extend_from_slice(&self.lpn_counter.to_be_bytes())?;
+        Ok(())
+    }
+}
+
+/// Sent by a Friend node willing to befriend a Low Power node.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FriendOffer {
+    /// Time the Low Power node listens for a response, in milliseconds.
+    pub receive_window: u8,
+    pub queue_size: u8,
+    pub subscription_list_size: u8,
+    /// Signal strength of the Friend Request, `0x7F` if not available.
+    pub rssi: i8,
+    pub friend_counter: u16,
+}
+
+impl FriendOffer {
+    pub fn parse(parameters: &[u8]) -> Result<Self, ParseError> {
+        if parameters.len() != 6 {
+            return Err(ParseError::InvalidLength);
+        }
+        if parameters[0] == 0 {
+            return Err(ParseError::InvalidValue);
+        }
+        Ok(Self {
+            receive_window: parameters[0],
+            queue_size: parameters[1],
+            subscription_list_size: parameters[2],
+            rssi: parameters[3] as i8,
+            friend_counter: u16::from_be_bytes([parameters[4], parameters[5]]),
+        })
+    }
+
+    pub fn emit<const N: usize>(&self, xmit: &mut Vec<u8, N>) -> Result<(), InsufficientBuffer> {
+        xmit.extend_from_slice(&[
+            self.receive_window,
+            self.queue_size,
+            self.subscription_list_size,
+            self.rssi as u8,
+        ])?;
+        xmit.extend_from_slice(&self.friend_counter.to_be_bytes())?;
+        Ok(())
+    }
+}
+
+/// Terminates a friendship, or confirms its termination.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FriendClear {
+    pub lpn_address: UnicastAddress,
+    pub lpn_counter: u16,
+}
+
+impl FriendClear {
+    pub fn parse(parameters: &[u8]) -> Result<Self, ParseError> {
+        if parameters.len() != 4 {
+            return Err(ParseError::InvalidLength);
+        }
+        Ok(Self {
+            lpn_address: UnicastAddress::parse([parameters[0], parameters[1]])?,
+            lpn_counter: u16::from_be_bytes([parameters[2], parameters[3]]),
+        })
+    }
+
+    pub fn emit<const N: usize>(&self, xmit: &mut Vec<u8, N>) -> Result<(), InsufficientBuffer> {
+        xmit.extend_from_slice(&self.lpn_address.as_bytes())?;
+        xmit.extend_from_slice(&self.lpn_counter.to_be_bytes())?;
+        Ok(())
+    }
+}
+
+/// Addresses to be added to, or removed from, the friend subscription list.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FriendSubscriptionList {
+    pub transaction_number: u8,
+    pub addresses: Vec<Address, SUBSCRIPTION_LIST_ADDRESSES>,
+}
+
+impl FriendSubscriptionList {
+    pub fn parse(parameters: &[u8]) -> Result<Self, ParseError> {
+        if parameters.len() < 3 || parameters.len() % 2 != 1 {
+            return Err(ParseError::InvalidLength);
+        }
+        let mut addresses = Vec::new();
+        for address in parameters[1..].chunks_exact(2) {
+            addresses
+                .push(Address::parse([address[0], address[1]]))
+                .map_err(|_| ParseError::InsufficientBuffer)?;
+        }
+        Ok(Self {
+            transaction_number: parameters[0],
+            addresses,
+        })
+    }
+
+    pub fn emit<const N: usize>(&self, xmit: &mut Vec<u8, N>) -> Result<(), InsufficientBuffer> {
+        xmit.push(self.transaction_number)
+            .map_err(|_| InsufficientBuffer)?;
+        for address in &self.addresses {
+            xmit.extend_from_slice(&address.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+/// Confirms a change to the friend subscription list.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FriendSubscriptionListConfirm {
+    pub transaction_number: u8,
+}
+
+impl FriendSubscriptionListConfirm {
+    pub fn parse(parameters: &[u8]) -> Result<Self, ParseError> {
+        if parameters.len() != 1 {
+            return Err(ParseError::InvalidLength);
+        }
+        Ok(Self {
+            transaction_number: parameters[0],
+        })
+    }
+
+    pub fn emit<const N: usize>(&self, xmit: &mut Vec<u8, N>) -> Result<(), InsufficientBuffer> {
+        xmit.push(self.transaction_number)
+            .map_err(|_| InsufficientBuffer)
+    }
+}
+
+macro_rules! control_message {
+    ($message:ty, $($opcode:ident)|+) => {
+        impl<S: System> TryFrom<&ControlMessage<S>> for $message {
+            type Error = ParseError;
+
+            fn try_from(value: &ControlMessage<S>) -> Result<Self, Self::Error> {
+                match value.opcode() {
+                    $(ControlOpcode::$opcode)|+ => Self::parse(value.parameters()),
+                    _ => Err(ParseError::InvalidPDUFormat),
+                }
+            }
+        }
+    };
+}
+
+control_message!(FriendPoll, FriendPoll);
+control_message!(FriendUpdate, FriendUpdate);
+control_message!(FriendRequest, FriendRequest);
+control_message!(FriendOffer, FriendOffer);
+control_message!(FriendClear, FriendClear | FriendClearConfirm);
+control_message!(
+    FriendSubscriptionList,
+    FriendSubscriptionListAdd | FriendSubscriptionListRemove
+);
+control_message!(FriendSubscriptionListConfirm, FriendSubscriptionListConfirm);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use btmesh_common::address::GroupAddress;
+
+    #[test]
+    fn friend_request_round_trip() {
+        let parameters = [0x4b, 0x64, 0x00, 0x03, 0xe8, 0x00, 0x00, 0x02, 0x00, 0x07];
+        let request = FriendRequest::parse(&parameters).unwrap();
+        assert_eq!(20, request.criteria.rssi_factor());
+        assert_eq!(15, request.criteria.receive_window_factor());
+        assert_eq!(8, request.criteria.min_queue_size());
+        assert_eq!(100, request.receive_delay);
+        assert_eq!(1000, request.poll_timeout);
+        assert_eq!(None, request.previous_address);
+        assert_eq!(2, request.num_elements);
+        assert_eq!(7, request.lpn_counter);
+
+        let mut xmit: Vec<u8, 10> = Vec::new();
+        request.emit(&mut xmit).unwrap();
+        assert_eq!(&parameters, &*xmit);
+
+        // receive delay below 10ms.
+        assert!(FriendRequest::parse(&[
+            0x4b, 0x09, 0x00, 0x03, 0xe8, 0x00, 0x00, 0x02, 0x00, 0x07
+        ])
+        .is_err());
+        // minimum queue size log of 0 is prohibited.
+        assert!(FriendRequest::parse(&[
+            0x48, 0x64, 0x00, 0x03, 0xe8, 0x00, 0x00, 0x02, 0x00, 0x07
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn friend_update_and_subscription_list() {
+        let update = FriendUpdate {
+            key_refresh_flag: KeyRefreshFlag(false),
+            iv_update_flag: IvUpdateFlag::InProgress,
+            iv_index: IvIndex::new(0x12345678),
+            md: true,
+        };
+        let mut xmit: Vec<u8, 6> = Vec::new();
+        update.emit(&mut xmit).unwrap();
+        assert_eq!(&[0x02, 0x12, 0x34, 0x56, 0x78, 0x01], &*xmit);
+        assert_eq!(update, FriendUpdate::parse(&xmit).unwrap());
+
+        let list = FriendSubscriptionList::parse(&[0x03, 0xc0, 0x01, 0xff, 0xff]).unwrap();
+        assert_eq!(3, list.transaction_number);
+        assert_eq!(
+            &[
+                Address::Group(GroupAddress::Normal(0xc001)),
+                Address::Group(GroupAddress::AllNodes)
+            ],
+            &*list.addresses
+        );
+        assert!(FriendSubscriptionList::parse(&[0x03, 0xc0]).is_err());
+    }
+}
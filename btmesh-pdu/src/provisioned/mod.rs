@@ -2,7 +2,9 @@ use crate::provisioned::access::AccessMessage;
 use crate::provisioned::control::ControlMessage;
 
 pub mod access;
+pub mod beacon;
 pub mod control;
+pub mod friend;
 pub mod lower;
 pub mod network;
 pub mod proxy;
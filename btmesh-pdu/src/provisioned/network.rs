@@ -95,7 +95,6 @@ impl NetworkPDU {
     }
 }
 
-#[derive(Clone)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[allow(dead_code)]
 pub struct CleartextNetworkPDU<S: System = ()> {
@@ -115,6 +114,23 @@ pub struct CleartextNetworkPDU<S: System = ()> {
     meta: S::NetworkMetadata,
 }
 
+// derived, `Clone` would be required of the system itself.
+impl<S: System> Clone for CleartextNetworkPDU<S> {
+    fn clone(&self) -> Self {
+        Self {
+            ivi: self.ivi,
+            nid: self.nid,
+            ctl: self.ctl,
+            ttl: self.ttl,
+            seq: self.seq,
+            src: self.src,
+            dst: self.dst,
+            transport_pdu: self.transport_pdu.clone(),
+            meta: self.meta.clone(),
+        }
+    }
+}
+
 impl<S: System> CleartextNetworkPDU<S> {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
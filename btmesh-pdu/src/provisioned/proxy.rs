@@ -1,3 +1,4 @@
+use btmesh_common::address::Address;
 use btmesh_common::{InsufficientBuffer, ParseError};
 use heapless::Vec;
 
@@ -34,7 +35,7 @@ impl From<SAR> for u8 {
 }
 
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Eq)]
 pub enum MessageType {
     NetworkPDU,
     MeshBeacon,
@@ -99,3 +100,148 @@ impl ProxyPDU {
         })
     }
 }
+
+/// Filter applied by a proxy server to the network PDUs it forwards to a proxy client.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum FilterType {
+    /// Only forward PDUs addressed to the listed addresses.
+    AcceptList = 0x00,
+    /// Forward all PDUs except those addressed to the listed addresses.
+    RejectList = 0x01,
+}
+
+impl FilterType {
+    pub fn parse(data: u8) -> Result<Self, ParseError> {
+        match data {
+            0x00 => Ok(Self::AcceptList),
+            0x01 => Ok(Self::RejectList),
+            _ => Err(ParseError::InvalidValue),
+        }
+    }
+}
+
+/// Proxy configuration message, exchanged between a proxy client and
+/// a proxy server to configure the proxy filter of their connection.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Debug)]
+pub enum ProxyConfigurationMessage {
+    SetFilterType(FilterType),
+    AddAddresses(Vec<Address, 8>),
+    RemoveAddresses(Vec<Address, 8>),
+    FilterStatus {
+        filter_type: FilterType,
+        list_size: u16,
+    },
+}
+
+impl ProxyConfigurationMessage {
+    pub fn parse(data: &[u8]) -> Result<Self, ParseError> {
+        if data.is_empty() {
+            return Err(ParseError::InvalidLength);
+        }
+
+        let parameters = &data[1..];
+        match data[0] {
+            0x00 if parameters.len() == 1 => {
+                Ok(Self::SetFilterType(FilterType::parse(parameters[0])?))
+            }
+            0x01 => Ok(Self::AddAddresses(Self::parse_addresses(parameters)?)),
+            0x02 => Ok(Self::RemoveAddresses(Self::parse_addresses(parameters)?)),
+            0x03 if parameters.len() == 3 => Ok(Self::FilterStatus {
+                filter_type: FilterType::parse(parameters[0])?,
+                list_size: u16::from_be_bytes([parameters[1], parameters[2]]),
+            }),
+            0x00 | 0x03 => Err(ParseError::InvalidLength),
+            _ => Err(ParseError::InvalidValue),
+        }
+    }
+
+    fn parse_addresses(parameters: &[u8]) -> Result<Vec<Address, 8>, ParseError> {
+        if parameters.len() % 2 != 0 {
+            return Err(ParseError::InvalidLength);
+        }
+
+        let mut addresses = Vec::new();
+        for address in parameters.chunks_exact(2) {
+            addresses
+                .push(Address::parse([address[0], address[1]]))
+                .map_err(|_| ParseError::InsufficientBuffer)?;
+        }
+        Ok(addresses)
+    }
+
+    pub fn emit<const N: usize>(&self, xmit: &mut Vec<u8, N>) -> Result<(), InsufficientBuffer> {
+        match self {
+            Self::SetFilterType(filter_type) => {
+                xmit.push(0x00).map_err(|_| InsufficientBuffer)?;
+                xmit.push(*filter_type as u8)
+                    .map_err(|_| InsufficientBuffer)?;
+            }
+            Self::AddAddresses(addresses) => {
+                xmit.push(0x01).map_err(|_| InsufficientBuffer)?;
+                for address in addresses {
+                    xmit.extend_from_slice(&address.as_bytes())?;
+                }
+            }
+            Self::RemoveAddresses(addresses) => {
+                xmit.push(0x02).map_err(|_| InsufficientBuffer)?;
+                for address in addresses {
+                    xmit.extend_from_slice(&address.as_bytes())?;
+                }
+            }
+            Self::FilterStatus {
+                filter_type,
+                list_size,
+            } => {
+                xmit.push(0x03).map_err(|_| InsufficientBuffer)?;
+                xmit.push(*filter_type as u8)
+                    .map_err(|_| InsufficientBuffer)?;
+                xmit.extend_from_slice(&list_size.to_be_bytes())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use btmesh_common::address::UnicastAddress;
+
+    #[test]
+    fn parse_proxy_configuration() {
+        assert!(matches!(
+            ProxyConfigurationMessage::parse(&[0x00, 0x01]),
+            Ok(ProxyConfigurationMessage::SetFilterType(
+                FilterType::RejectList
+            ))
+        ));
+        assert!(ProxyConfigurationMessage::parse(&[0x00, 0x02]).is_err());
+
+        if let Ok(ProxyConfigurationMessage::AddAddresses(addresses)) =
+            ProxyConfigurationMessage::parse(&[0x01, 0x12, 0x01, 0xFF, 0xFF])
+        {
+            assert_eq!(
+                Address::Unicast(UnicastAddress::new(0x1201).unwrap()),
+                addresses[0]
+            );
+            assert_eq!(2, addresses.len());
+        } else {
+            panic!("expected add addresses");
+        }
+        assert!(ProxyConfigurationMessage::parse(&[0x02, 0x12]).is_err());
+    }
+
+    #[test]
+    fn emit_filter_status() {
+        let mut xmit: Vec<u8, 4> = Vec::new();
+        ProxyConfigurationMessage::FilterStatus {
+            filter_type: FilterType::AcceptList,
+            list_size: 3,
+        }
+        .emit(&mut xmit)
+        .unwrap();
+        assert_eq!(&[0x03, 0x00, 0x00, 0x03], &*xmit);
+    }
+}
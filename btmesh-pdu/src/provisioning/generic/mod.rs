@@ -157,11 +157,17 @@ impl ProvisioningBearerControl {
 
     pub fn emit<const N: usize>(&self, xmit: &mut Vec<u8, N>) -> Result<(), InsufficientBuffer> {
         match self {
-            ProvisioningBearerControl::LinkOpen(_) => {}
+            ProvisioningBearerControl::LinkOpen(uuid) => {
+                xmit.push(0b11)?;
+                xmit.extend_from_slice(uuid)?;
+            }
             ProvisioningBearerControl::LinkAck => {
                 xmit.push(0x01 << 2 | 0b11)?;
             }
-            ProvisioningBearerControl::LinkClose(_) => {}
+            ProvisioningBearerControl::LinkClose(reason) => {
+                xmit.push(0x02 << 2 | 0b11)?;
+                xmit.push(*reason as u8)?;
+            }
         }
 
         Ok(())
@@ -214,3 +220,32 @@ impl Reason {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bearer_control_round_trip() {
+        let uuid = Uuid::new([0x42; 16]);
+        let mut xmit: Vec<u8, 17> = Vec::new();
+        ProvisioningBearerControl::LinkOpen(uuid)
+            .emit(&mut xmit)
+            .unwrap();
+        assert_eq!(0x03, xmit[0]);
+        assert!(matches!(
+            ProvisioningBearerControl::parse(&xmit),
+            Ok(ProvisioningBearerControl::LinkOpen(parsed)) if parsed == uuid
+        ));
+
+        let mut xmit: Vec<u8, 17> = Vec::new();
+        ProvisioningBearerControl::LinkClose(Reason::Timeout)
+            .emit(&mut xmit)
+            .unwrap();
+        assert_eq!(&[0x0B, 0x01], &*xmit);
+        assert!(matches!(
+            ProvisioningBearerControl::parse(&xmit),
+            Ok(ProvisioningBearerControl::LinkClose(Reason::Timeout))
+        ));
+    }
+}
@@ -233,18 +233,17 @@ impl TryFrom<&PublicKey> for p256::PublicKey {
 #[derive(Clone, Hash, Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct Confirmation {
-    pub confirmation: [u8; 16],
+    /// 16 or 32 octets, depending on the algorithm.
+    pub confirmation: Vec<u8, 32>,
 }
 
 impl Confirmation {
     fn parse(data: &[u8]) -> Result<Self, ParseError> {
-        if data.len() != 17 && data[0] != ProvisioningPDU::CONFIRMATION {
+        if !matches!(data.len(), 17 | 33) || data[0] != ProvisioningPDU::CONFIRMATION {
             Err(ParseError::InvalidPDUFormat)
         } else {
             Ok(Self {
-                confirmation: data[1..]
-                    .try_into()
-                    .map_err(|_| ParseError::InvalidLength)?,
+                confirmation: Vec::from_slice(&data[1..]).map_err(|_| ParseError::InvalidLength)?,
             })
         }
     }
@@ -259,18 +258,17 @@ impl Confirmation {
 #[derive(Clone, Hash, Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct Random {
-    pub random: [u8; 16],
+    /// 16 or 32 octets, depending on the algorithm.
+    pub random: Vec<u8, 32>,
 }
 
 impl Random {
     fn parse(data: &[u8]) -> Result<Self, ParseError> {
-        if data.len() != 17 && data[0] != ProvisioningPDU::RANDOM {
+        if !matches!(data.len(), 17 | 33) || data[0] != ProvisioningPDU::RANDOM {
             Err(ParseError::InvalidPDUFormat)
         } else {
             Ok(Self {
-                random: data[1..]
-                    .try_into()
-                    .map_err(|_| ParseError::InvalidLength)?,
+                random: Vec::from_slice(&data[1..]).map_err(|_| ParseError::InvalidLength)?,
             })
         }
     }
@@ -316,7 +314,7 @@ impl Data {
 /// The decrypted provisioning data wrapped in `Data` above.
 pub struct ProvisioningData {
     pub network_key: [u8; 16],
-    // TODO: pub key_index: NetKeyIndex,
+    pub key_index: u16,
     pub key_refresh_flag: KeyRefreshFlag,
     pub iv_update_flag: IvUpdateFlag,
     pub iv_index: u32,
@@ -329,7 +327,7 @@ impl ProvisioningData {
             Err(ParseError::InvalidLength)
         } else {
             let network_key = &data[0..16];
-            // TODO: let key_index = NetKeyIndex::new(u16::from_be_bytes([data[16], data[17]]));
+            let key_index = u16::from_be_bytes([data[16], data[17]]);
             let flags = data[18];
             let iv_index = u32::from_be_bytes([data[19], data[20], data[21], data[22]]);
             let unicast_address = UnicastAddress::parse([data[23], data[24]])?;
@@ -338,7 +336,7 @@ impl ProvisioningData {
                 network_key: network_key
                     .try_into()
                     .map_err(|_| ParseError::InvalidLength)?,
-                // TODO: key_index,
+                key_index,
                 key_refresh_flag: KeyRefreshFlag::parse(flags & 0b00000001),
                 iv_update_flag: IvUpdateFlag::parse(flags & 0b00000010),
                 iv_index,
@@ -348,7 +346,7 @@ impl ProvisioningData {
     }
     pub fn emit<const N: usize>(&self, xmit: &mut Vec<u8, N>) -> Result<(), InsufficientBuffer> {
         xmit.extend_from_slice(&self.network_key)?;
-        xmit.extend_from_slice(&[0; 2])?; // TODO: key_index
+        xmit.extend_from_slice(&self.key_index.to_be_bytes())?;
         let mut flags = 0;
         self.key_refresh_flag.emit(&mut flags);
         self.iv_update_flag.emit(&mut flags);
@@ -364,9 +362,9 @@ impl defmt::Format for ProvisioningData {
     fn format(&self, fmt: defmt::Formatter) {
         defmt::write!(
             fmt,
-            "ProvisioningData( network_key={:x}, flags={}:{}, iv_index={}, unicast_address={:x}",
+            "ProvisioningData( network_key={:x}, key_index={}, flags={}:{}, iv_index={}, unicast_address={:x}",
             self.network_key,
-            //self.key_index,
+            self.key_index,
             self.key_refresh_flag,
             self.iv_update_flag,
             self.iv_index,
@@ -462,30 +460,42 @@ impl ProvisioningPDU {
     }
 }
 
-#[derive(Clone, Default, Hash, Debug)]
+#[derive(Copy, Clone, Default, Hash, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Algorithm {
+    /// BTM_ECDH_P256_CMAC_AES128_AES_CCM
     #[default]
     P256,
+    /// BTM_ECDH_P256_HMAC_SHA256_AES_CCM
+    P256HmacSha256,
 }
 
 impl Algorithm {
     pub fn parse(octet: u8) -> Result<Self, ParseError> {
-        if octet == 0x00 {
-            Ok(Self::P256)
-        } else {
-            Err(ParseError::InvalidValue)
+        match octet {
+            0x00 => Ok(Self::P256),
+            0x01 => Ok(Self::P256HmacSha256),
+            _ => Err(ParseError::InvalidValue),
         }
     }
 
     pub fn emit<const N: usize>(&self, xmit: &mut Vec<u8, N>) -> Result<(), InsufficientBuffer> {
         match self {
             Algorithm::P256 => xmit.push(0x00)?,
+            Algorithm::P256HmacSha256 => xmit.push(0x01)?,
         }
 
         Ok(())
     }
+
+    /// Size of the confirmation, random and AuthValue of this algorithm.
+    pub fn value_size(&self) -> usize {
+        match self {
+            Algorithm::P256 => 16,
+            Algorithm::P256HmacSha256 => 32,
+        }
+    }
 }
 
 #[derive(Clone, Hash, Debug)]
@@ -498,23 +508,33 @@ impl Algorithms {
         Self(Vec::new())
     }
 
-    fn push(&mut self, algo: Algorithm) -> Result<(), Algorithm> {
+    pub fn push(&mut self, algo: Algorithm) -> Result<(), Algorithm> {
         self.0.push(algo)
     }
 
+    pub fn contains(&self, algo: Algorithm) -> bool {
+        self.0.contains(&algo)
+    }
+
     pub fn parse(bits: u16) -> Result<Self, ParseError> {
-        if bits & 0b1111111111111110 != 0 {
+        if bits & 0b1111111111111100 != 0 {
             return Err(ParseError::InvalidValue);
         }
 
         let mut algos = Algorithms::new();
 
-        if bits & 0b1 == 1 {
+        if bits & 0b01 != 0 {
             algos
                 .push(Algorithm::P256)
                 .map_err(|_| ParseError::InsufficientBuffer)?;
         }
 
+        if bits & 0b10 != 0 {
+            algos
+                .push(Algorithm::P256HmacSha256)
+                .map_err(|_| ParseError::InsufficientBuffer)?;
+        }
+
         Ok(algos)
     }
 
@@ -522,10 +542,9 @@ impl Algorithms {
         let bits: Option<u16> = self
             .0
             .iter()
-            .map(|e| {
-                match e {
-                    Algorithm::P256 => 0b0000000000000001, // room for growth
-                }
+            .map(|e| match e {
+                Algorithm::P256 => 0b0000000000000001,
+                Algorithm::P256HmacSha256 => 0b0000000000000010,
             })
             .reduce(|accum, e| accum | e);
 
@@ -640,7 +659,7 @@ impl OOBSize {
     pub fn parse(octet: u8) -> Result<Self, ParseError> {
         if octet == 0 {
             Ok(Self::NotSupported)
-        } else if octet < 8 {
+        } else if octet <= 8 {
             Ok(Self::MaximumSize(octet))
         } else {
             Err(ParseError::InvalidValue)
@@ -656,7 +675,7 @@ impl OOBSize {
     }
 }
 
-#[derive(Copy, Clone, Hash, Debug)]
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum OutputOOBAction {
@@ -680,7 +699,14 @@ impl OutputOOBAction {
     }
 
     pub fn emit<const N: usize>(&self, xmit: &mut Vec<u8, N>) -> Result<(), InsufficientBuffer> {
-        Ok(xmit.extend_from_slice(&(*self as u16).to_be_bytes())?)
+        match self {
+            Self::Blink => xmit.push(0x00)?,
+            Self::Beep => xmit.push(0x01)?,
+            Self::Vibrate => xmit.push(0x02)?,
+            Self::OutputNumeric => xmit.push(0x03)?,
+            Self::OutputAlphanumeric => xmit.push(0x04)?,
+        }
+        Ok(())
     }
 }
 
@@ -704,6 +730,10 @@ impl OutputOOBActions {
         self.0.push(action)
     }
 
+    pub fn contains(&self, action: OutputOOBAction) -> bool {
+        self.0.contains(&action)
+    }
+
     pub fn parse(bits: u16) -> Result<Self, ParseError> {
         if bits & 0b1111111111100000 != 0 {
             return Err(ParseError::InvalidValue);
@@ -752,7 +782,7 @@ impl Default for OutputOOBActions {
     }
 }
 
-#[derive(Copy, Clone, Hash, Debug)]
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum InputOOBAction {
@@ -774,7 +804,13 @@ impl InputOOBAction {
     }
 
     pub fn emit<const N: usize>(&self, xmit: &mut Vec<u8, N>) -> Result<(), InsufficientBuffer> {
-        Ok(xmit.extend_from_slice(&(*self as u16).to_be_bytes())?)
+        match self {
+            Self::Push => xmit.push(0x00)?,
+            Self::Twist => xmit.push(0x01)?,
+            Self::InputNumeric => xmit.push(0x02)?,
+            Self::InputAlphanumeric => xmit.push(0x03)?,
+        }
+        Ok(())
     }
 }
 
@@ -798,6 +834,10 @@ impl InputOOBActions {
         self.0.push(action)
     }
 
+    pub fn contains(&self, action: InputOOBAction) -> bool {
+        self.0.contains(&action)
+    }
+
     pub fn parse(bits: u16) -> Result<Self, ParseError> {
         if bits & 0b1111111111110000 != 0 {
             return Err(ParseError::InvalidValue);
@@ -957,3 +997,55 @@ impl ErrorCode {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn start_round_trip() {
+        let start = Start {
+            authentication_method: AuthenticationMethod::Output,
+            authentication_action: OOBAction::Output(OutputOOBAction::OutputNumeric),
+            authentication_size: OOBSize::MaximumSize(8),
+            ..Default::default()
+        };
+        let mut xmit: Vec<u8, 6> = Vec::new();
+        start.emit(&mut xmit).unwrap();
+        assert_eq!(
+            &xmit[..],
+            &[ProvisioningPDU::START, 0x00, 0x00, 0x02, 0x03, 0x08]
+        );
+
+        let parsed = Start::parse(&xmit).unwrap();
+        assert!(matches!(
+            parsed.authentication_action,
+            OOBAction::Output(OutputOOBAction::OutputNumeric)
+        ));
+        assert!(matches!(
+            parsed.authentication_size,
+            OOBSize::MaximumSize(8)
+        ));
+    }
+
+    #[test]
+    fn hmac_sha256_algorithm() {
+        let algorithms = Algorithms::parse(0b11).unwrap();
+        assert!(algorithms.contains(Algorithm::P256));
+        assert!(algorithms.contains(Algorithm::P256HmacSha256));
+        let mut xmit: Vec<u8, 2> = Vec::new();
+        algorithms.emit(&mut xmit).unwrap();
+        assert_eq!(&xmit[..], &[0x00, 0b11]);
+
+        let random = ProvisioningPDU::Random(Random {
+            random: Vec::from_slice(&[0x42; 32]).unwrap(),
+        });
+        let mut xmit: Vec<u8, 33> = Vec::new();
+        random.emit(&mut xmit).unwrap();
+        match ProvisioningPDU::parse(&xmit).unwrap() {
+            ProvisioningPDU::Random(parsed) => assert_eq!(&parsed.random[..], &[0x42; 32]),
+            _ => panic!("wrong pdu parsed"),
+        }
+        assert!(ProvisioningPDU::parse(&xmit[..20]).is_err());
+    }
+}